@@ -2,6 +2,10 @@ use anyhow::{bail, Result};
 use std::fmt::Write;
 use wasmparser::*;
 
+mod structured;
+
+pub use structured::*;
+
 pub fn dump_wasm(bytes: &[u8]) -> Result<String> {
     let mut d = Dump::new(bytes);
     d.run()?;
@@ -0,0 +1,362 @@
+//! A structured, serializable counterpart to [`dump_wasm`](crate::dump_wasm).
+
+use super::Indices;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use wasmparser::*;
+
+/// A structured dump of a module, see [`dump_wasm_structured`].
+#[derive(Debug, Serialize)]
+pub struct ModuleDump<'a> {
+    /// The byte range of the whole module.
+    pub range: Range,
+    /// The sections of the module, in order.
+    pub sections: Vec<SectionDump<'a>>,
+}
+
+/// A single section of a [`ModuleDump`].
+#[derive(Debug, Serialize)]
+pub struct SectionDump<'a> {
+    /// The kind of section, such as `"type"`, `"code"`, or `"custom"`.
+    pub kind: &'static str,
+    /// The name of a custom section, or the id of an unknown one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The byte range of the section's contents, excluding its id and size.
+    pub range: Range,
+    /// Each item in the section.
+    pub items: Vec<ItemDump<'a>>,
+    /// The modules nested within a module section.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<ModuleDump<'a>>,
+}
+
+/// A single item of a [`SectionDump`], such as an import or a function body.
+#[derive(Debug, Serialize)]
+pub struct ItemDump<'a> {
+    /// The byte range of the item.
+    pub range: Range,
+    /// The index of the item in its index space, if it defines one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    /// A description of the item, in the same format as the text dump.
+    pub description: String,
+    /// The locals of a function body, as `(count, type)` pairs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub locals: Vec<(u32, Type)>,
+    /// The decoded operators of a function body or constant expression.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub operators: Vec<OperatorDump<'a>>,
+}
+
+/// A decoded operator along with where it was found.
+#[derive(Debug, Serialize)]
+pub struct OperatorDump<'a> {
+    /// The offset of the operator's opcode.
+    pub offset: usize,
+    /// The operator and its immediates, or `None` if it couldn't be decoded.
+    pub operator: Option<Operator<'a>>,
+    /// Why the operator couldn't be decoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Parses `bytes` into a [`ModuleDump`] which records the byte range of
+/// every section and item, and the offset and immediates of every operator.
+///
+/// This carries the same information as [`dump_wasm`](crate::dump_wasm) but
+/// is meant to be consumed by tools, for example by serializing it as JSON.
+pub fn dump_wasm_structured(bytes: &[u8]) -> Result<ModuleDump<'_>> {
+    let mut stack = Vec::new();
+    let mut module = ModuleDump {
+        range: Range {
+            start: 0,
+            end: bytes.len(),
+        },
+        sections: Vec::new(),
+    };
+    let mut i = Indices::default();
+
+    for item in Parser::new(0).parse_all(bytes) {
+        let section = match item? {
+            Payload::Version { .. } => continue,
+            Payload::TypeSection(s) => items(s, "type", |t| {
+                Ok((next(&mut i.types), format!("{:?}", t), Vec::new()))
+            })?,
+            Payload::ImportSection(s) => items(s, "import", |imp| {
+                let index = match imp.ty {
+                    ImportSectionEntryType::Function(_) => next(&mut i.funcs),
+                    ImportSectionEntryType::Memory(_) => next(&mut i.memories),
+                    ImportSectionEntryType::Tag(_) => next(&mut i.tags),
+                    ImportSectionEntryType::Table(_) => next(&mut i.tables),
+                    ImportSectionEntryType::Global(_) => next(&mut i.globals),
+                    ImportSectionEntryType::Instance(_) => next(&mut i.instances),
+                    ImportSectionEntryType::Module(_) => next(&mut i.modules),
+                };
+                Ok((index, format!("{:?}", imp), Vec::new()))
+            })?,
+            Payload::FunctionSection(s) => {
+                let mut funcs = i.funcs;
+                items(s, "func", |f| {
+                    Ok((next(&mut funcs), format!("type {}", f), Vec::new()))
+                })?
+            }
+            Payload::TableSection(s) => items(s, "table", |t| {
+                Ok((next(&mut i.tables), format!("{:?}", t), Vec::new()))
+            })?,
+            Payload::MemorySection(s) => items(s, "memory", |m| {
+                Ok((next(&mut i.memories), format!("{:?}", m), Vec::new()))
+            })?,
+            Payload::TagSection(s) => items(s, "tag", |t| {
+                Ok((next(&mut i.tags), format!("{:?}", t), Vec::new()))
+            })?,
+            Payload::ExportSection(s) => {
+                items(s, "export", |e| Ok((None, format!("{:?}", e), Vec::new())))?
+            }
+            Payload::GlobalSection(s) => items(s, "global", |g| {
+                let ops = operators(g.init_expr.get_operators_reader())?;
+                Ok((next(&mut i.globals), format!("{:?}", g.ty), ops))
+            })?,
+            Payload::AliasSection(s) => items(s, "alias", |a| {
+                let index = match a {
+                    Alias::InstanceExport { kind, .. } => match kind {
+                        ExternalKind::Function => next(&mut i.funcs),
+                        ExternalKind::Global => next(&mut i.globals),
+                        ExternalKind::Module => next(&mut i.modules),
+                        ExternalKind::Table => next(&mut i.tables),
+                        ExternalKind::Instance => next(&mut i.instances),
+                        ExternalKind::Memory => next(&mut i.memories),
+                        ExternalKind::Tag => next(&mut i.tags),
+                        ExternalKind::Type => next(&mut i.types),
+                    },
+                    Alias::OuterType { .. } => next(&mut i.types),
+                    Alias::OuterModule { .. } => next(&mut i.modules),
+                };
+                Ok((index, format!("{:?}", a), Vec::new()))
+            })?,
+            Payload::InstanceSection(s) => items(s, "instance", |instance| {
+                let args = instance
+                    .args()?
+                    .into_iter()
+                    .collect::<Result<Vec<_>, _>>()?;
+                let description = format!("instantiate module:{} {:?}", instance.module(), args);
+                Ok((next(&mut i.instances), description, Vec::new()))
+            })?,
+            Payload::StartSection { func, range } => single("start", range, func),
+            Payload::DataCountSection { count, range } => single("data count", range, count),
+            Payload::ElementSection(s) => {
+                let mut index = 0;
+                items(s, "element", |e| {
+                    let ops = match &e.kind {
+                        ElementKind::Active { init_expr, .. } => {
+                            operators(init_expr.get_operators_reader())?
+                        }
+                        _ => Vec::new(),
+                    };
+                    let mut reader = e.items.get_items_reader()?;
+                    let items = (0..reader.get_count())
+                        .map(|_| reader.read())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let description = match e.kind {
+                        ElementKind::Passive => format!("element {:?} passive {:?}", e.ty, items),
+                        ElementKind::Active { table_index, .. } => {
+                            format!("element {:?} table[{}] {:?}", e.ty, table_index, items)
+                        }
+                        ElementKind::Declared => {
+                            format!("element {:?} declared {:?}", e.ty, items)
+                        }
+                    };
+                    Ok((next(&mut index), description, ops))
+                })?
+            }
+            Payload::DataSection(s) => {
+                let mut index = 0;
+                items(s, "data", |d| {
+                    let (description, ops) = match d.kind {
+                        DataKind::Passive => ("data passive".to_string(), Vec::new()),
+                        DataKind::Active {
+                            memory_index,
+                            init_expr,
+                        } => (
+                            format!("data memory[{}]", memory_index),
+                            operators(init_expr.get_operators_reader())?,
+                        ),
+                    };
+                    let description = format!("{}, {} bytes of data", description, d.data.len());
+                    Ok((next(&mut index), description, ops))
+                })?
+            }
+            Payload::CodeSectionStart { range, .. } => SectionDump {
+                kind: "code",
+                name: None,
+                range,
+                items: Vec::new(),
+                modules: Vec::new(),
+            },
+            Payload::CodeSectionEntry(body) => {
+                let locals = body
+                    .get_locals_reader()?
+                    .into_iter()
+                    .collect::<Result<Vec<_>, _>>()?;
+                let item = ItemDump {
+                    range: body.range(),
+                    index: next(&mut i.funcs),
+                    description: format!("{} local blocks", locals.len()),
+                    locals,
+                    operators: operators(body.get_operators_reader()?)?,
+                };
+                current_section(&mut module, "code")?.items.push(item);
+                continue;
+            }
+            Payload::ModuleSectionStart { range, .. } => SectionDump {
+                kind: "module",
+                name: None,
+                range,
+                items: Vec::new(),
+                modules: Vec::new(),
+            },
+            Payload::ModuleSectionEntry { range, .. } => {
+                stack.push((module, i));
+                module = ModuleDump {
+                    range,
+                    sections: Vec::new(),
+                };
+                i = Indices::default();
+                continue;
+            }
+            Payload::CustomSection {
+                name,
+                data_offset,
+                data,
+                range,
+            } => SectionDump {
+                kind: "custom",
+                name: Some(name.to_string()),
+                range,
+                items: vec![ItemDump {
+                    range: Range {
+                        start: data_offset,
+                        end: data_offset + data.len(),
+                    },
+                    index: None,
+                    description: format!("{} bytes of data", data.len()),
+                    locals: Vec::new(),
+                    operators: Vec::new(),
+                }],
+                modules: Vec::new(),
+            },
+            Payload::UnknownSection { id, range, .. } => SectionDump {
+                kind: "unknown",
+                name: Some(id.to_string()),
+                range,
+                items: Vec::new(),
+                modules: Vec::new(),
+            },
+            Payload::End => match stack.pop() {
+                Some((mut parent, parent_indices)) => {
+                    current_section(&mut parent, "module")?.modules.push(module);
+                    module = parent;
+                    i = parent_indices;
+                    continue;
+                }
+                None => break,
+            },
+        };
+        module.sections.push(section);
+    }
+
+    Ok(module)
+}
+
+/// Returns the next index from the `counter` and increments it.
+fn next(counter: &mut u32) -> Option<u32> {
+    let index = *counter;
+    *counter += 1;
+    Some(index)
+}
+
+/// Dumps each item of the section `s` with `item`, which returns an item's
+/// index, its description, and any operators it contains.
+fn items<'a, T>(
+    mut s: T,
+    kind: &'static str,
+    mut item: impl FnMut(T::Item) -> Result<(Option<u32>, String, Vec<OperatorDump<'a>>)>,
+) -> Result<SectionDump<'a>>
+where
+    T: SectionReader + SectionWithLimitedItems,
+{
+    let range = s.range();
+    let mut items = Vec::new();
+    for _ in 0..s.get_count() {
+        let start = s.original_position();
+        let (index, description, operators) = item(s.read()?)?;
+        items.push(ItemDump {
+            range: Range {
+                start,
+                end: s.original_position(),
+            },
+            index,
+            description,
+            locals: Vec::new(),
+            operators,
+        });
+    }
+    if !s.eof() {
+        bail!("too many bytes in section");
+    }
+    Ok(SectionDump {
+        kind,
+        name: None,
+        range,
+        items,
+        modules: Vec::new(),
+    })
+}
+
+/// Dumps a section which consists of a single value.
+fn single<'a>(kind: &'static str, range: Range, value: u32) -> SectionDump<'a> {
+    SectionDump {
+        kind,
+        name: None,
+        range,
+        items: vec![ItemDump {
+            range,
+            index: None,
+            description: value.to_string(),
+            locals: Vec::new(),
+            operators: Vec::new(),
+        }],
+        modules: Vec::new(),
+    }
+}
+
+fn operators(mut reader: OperatorsReader<'_>) -> Result<Vec<OperatorDump<'_>>> {
+    let mut ops = Vec::new();
+    while !reader.eof() {
+        let offset = reader.original_position();
+        // Like the text dump, keep going past operators which fail to decode
+        // as long as the reader makes progress.
+        let (operator, error) = match reader.read() {
+            Ok(op) => (Some(op), None),
+            Err(e) if reader.original_position() > offset => (None, Some(e.message().to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        ops.push(OperatorDump {
+            offset,
+            operator,
+            error,
+        });
+    }
+    Ok(ops)
+}
+
+/// Returns the most recent section of `module`, which must be of `kind`.
+fn current_section<'a, 'b>(
+    module: &'b mut ModuleDump<'a>,
+    kind: &str,
+) -> Result<&'b mut SectionDump<'a>> {
+    match module.sections.last_mut() {
+        Some(section) if section.kind == kind => Ok(section),
+        _ => bail!("{} section entry outside of a {} section", kind, kind),
+    }
+}
@@ -0,0 +1,396 @@
+//! Call graphs and function reachability.
+//!
+//! [`CallGraph::new`] records, for every function in a module, which
+//! functions it calls directly, which functions its `call_indirect`s may
+//! reach, and which functions it references with `ref.func`. It also records
+//! the roots of the module: the functions which can be entered from outside
+//! the module or which are placed in tables by element segments.
+//!
+//! The targets of a `call_indirect` are the functions of the right type in
+//! the active element segments of the table it calls through. If the table is
+//! imported, exported, or modified by an instruction such as `table.set`,
+//! then any function whose reference can escape (imported, exported, or
+//! referenced by an element segment, global, or `ref.func`) of the right type
+//! is considered a target instead.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_analysis::CallGraph;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let wasm = wat::parse_str(r#"
+//!     (module
+//!         (func $a (export "a") call $b)
+//!         (func $b)
+//!         (func $c call $b))
+//! "#)?;
+//! let graph = CallGraph::new(&wasm)?;
+//! assert!(graph.functions()[0].calls.contains(&1));
+//! assert_eq!(graph.reachable().into_iter().collect::<Vec<_>>(), [0, 1]);
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+use wasmparser::{
+    ElementItem, ElementKind, ExternalKind, FuncType, ImportSectionEntryType, InitExpr, Name,
+    NameSectionReader, Operator, Parser, Payload, TypeDef,
+};
+
+/// The call graph of a module.
+///
+/// Functions are identified by their index in the module's function index
+/// space, so imported functions come first.
+#[derive(Clone, Debug)]
+pub struct CallGraph {
+    functions: Vec<Function>,
+    roots: Roots,
+}
+
+/// A function in a [`CallGraph`].
+#[derive(Clone, Debug)]
+pub struct Function {
+    /// The index of this function's type.
+    pub ty: u32,
+    /// Whether this function is imported.
+    pub imported: bool,
+    /// This function's name from the `name` section, if any.
+    pub name: Option<String>,
+    /// The functions called with `call` or `return_call`.
+    pub calls: BTreeSet<u32>,
+    /// The functions which may be called with `call_indirect` or
+    /// `return_call_indirect`.
+    pub indirect_calls: BTreeSet<u32>,
+    /// The functions referenced with `ref.func`.
+    pub references: BTreeSet<u32>,
+}
+
+impl Function {
+    fn new(ty: u32, imported: bool) -> Function {
+        Function {
+            ty,
+            imported,
+            name: None,
+            calls: BTreeSet::new(),
+            indirect_calls: BTreeSet::new(),
+            references: BTreeSet::new(),
+        }
+    }
+
+    /// Returns every function this one calls or references, possibly with
+    /// duplicates.
+    pub fn successors(&self) -> impl Iterator<Item = u32> + '_ {
+        self.calls
+            .iter()
+            .chain(&self.indirect_calls)
+            .chain(&self.references)
+            .copied()
+    }
+}
+
+/// The functions which are live regardless of what calls them.
+#[derive(Clone, Debug, Default)]
+pub struct Roots {
+    /// Exported functions.
+    pub exports: BTreeSet<u32>,
+    /// The start function.
+    pub start: Option<u32>,
+    /// Functions in element segments.
+    pub elements: BTreeSet<u32>,
+    /// Functions referenced with `ref.func` in global initializers.
+    pub globals: BTreeSet<u32>,
+}
+
+impl Roots {
+    /// Returns all root functions, possibly with duplicates.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.exports
+            .iter()
+            .copied()
+            .chain(self.start)
+            .chain(self.elements.iter().copied())
+            .chain(self.globals.iter().copied())
+    }
+}
+
+#[derive(Default)]
+struct Table {
+    /// Functions placed in this table by active element segments.
+    contents: BTreeSet<u32>,
+    /// Whether anything other than active element segments may place
+    /// functions in this table.
+    open: bool,
+}
+
+impl CallGraph {
+    /// Computes the call graph of the module `wasm`.
+    ///
+    /// Returns an error if `wasm` can't be parsed or uses module linking.
+    pub fn new(wasm: &[u8]) -> Result<CallGraph> {
+        let mut types = Vec::new();
+        let mut functions = Vec::new();
+        let mut tables = Vec::new();
+        let mut roots = Roots::default();
+        let mut indirect_sites = Vec::new();
+        let mut next_body = None;
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::TypeSection(s) => {
+                    for ty in s {
+                        types.push(match ty? {
+                            TypeDef::Func(ty) => Some(ty),
+                            _ => None,
+                        });
+                    }
+                }
+                Payload::ImportSection(s) => {
+                    for import in s {
+                        match import?.ty {
+                            ImportSectionEntryType::Function(ty) => {
+                                functions.push(Function::new(ty, true));
+                            }
+                            ImportSectionEntryType::Table(_) => tables.push(Table {
+                                open: true,
+                                ..Table::default()
+                            }),
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::FunctionSection(s) => {
+                    for ty in s {
+                        functions.push(Function::new(ty?, false));
+                    }
+                }
+                Payload::TableSection(s) => {
+                    for table in s {
+                        table?;
+                        tables.push(Table::default());
+                    }
+                }
+                Payload::GlobalSection(s) => {
+                    for global in s {
+                        roots.globals.extend(ref_func(&global?.init_expr)?);
+                    }
+                }
+                Payload::ExportSection(s) => {
+                    for export in s {
+                        let export = export?;
+                        match export.kind {
+                            ExternalKind::Function => {
+                                roots.exports.insert(export.index);
+                            }
+                            ExternalKind::Table => table(&mut tables, export.index)?.open = true,
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::StartSection { func, .. } => roots.start = Some(func),
+                Payload::ElementSection(s) => {
+                    for element in s {
+                        let element = element?;
+                        let mut funcs = Vec::new();
+                        let mut items = element.items.get_items_reader()?;
+                        for _ in 0..items.get_count() {
+                            match items.read()? {
+                                ElementItem::Func(f) => funcs.push(f),
+                                ElementItem::Expr(expr) => funcs.extend(ref_func(&expr)?),
+                            }
+                        }
+                        if let ElementKind::Active { table_index, .. } = element.kind {
+                            table(&mut tables, table_index)?
+                                .contents
+                                .extend(funcs.iter().copied());
+                        }
+                        roots.elements.extend(funcs);
+                    }
+                }
+                Payload::CodeSectionStart { .. } => {
+                    next_body = Some(functions.iter().filter(|f| f.imported).count() as u32);
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let index = next_body.unwrap();
+                    next_body = Some(index + 1);
+                    let func = match functions.get_mut(index as usize) {
+                        Some(func) => func,
+                        None => bail!("function body {} has no declared type", index),
+                    };
+                    let mut reader = body.get_operators_reader()?;
+                    reader.allow_memarg64(true);
+                    while !reader.eof() {
+                        match reader.read()? {
+                            Operator::Call { function_index }
+                            | Operator::ReturnCall { function_index } => {
+                                func.calls.insert(function_index);
+                            }
+                            Operator::CallIndirect {
+                                index: ty,
+                                table_index,
+                            }
+                            | Operator::ReturnCallIndirect {
+                                index: ty,
+                                table_index,
+                            } => indirect_sites.push((index, ty, table_index)),
+                            Operator::RefFunc { function_index } => {
+                                func.references.insert(function_index);
+                            }
+                            Operator::TableSet { table: t }
+                            | Operator::TableGrow { table: t }
+                            | Operator::TableFill { table: t }
+                            | Operator::TableInit { table: t, .. }
+                            | Operator::TableCopy { dst_table: t, .. } => {
+                                table(&mut tables, t)?.open = true;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::CustomSection {
+                    name: "name",
+                    data,
+                    data_offset,
+                    ..
+                } => {
+                    // Names are informational only, so a malformed `name`
+                    // section is ignored rather than rejected.
+                    if let Ok(names) = function_names(data, data_offset) {
+                        for (index, name) in names {
+                            if let Some(func) = functions.get_mut(index as usize) {
+                                func.name = Some(name);
+                            }
+                        }
+                    }
+                }
+                Payload::AliasSection(_)
+                | Payload::InstanceSection(_)
+                | Payload::ModuleSectionStart { .. } => {
+                    bail!("module-linking modules are not supported")
+                }
+                _ => {}
+            }
+        }
+
+        // Everything whose reference may escape the module, and so may be
+        // placed in a table by something other than an element segment.
+        let escaping = functions
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.imported)
+            .map(|(i, _)| i as u32)
+            .chain(roots.iter())
+            .chain(functions.iter().flat_map(|f| f.references.iter().copied()))
+            .collect::<BTreeSet<_>>();
+        for (caller, ty, t) in indirect_sites {
+            let table = table(&mut tables, t)?;
+            let candidates = if table.open {
+                &escaping
+            } else {
+                &table.contents
+            };
+            let targets = candidates
+                .iter()
+                .copied()
+                .filter(|f| match functions.get(*f as usize) {
+                    Some(f) => same_type(&types, f.ty, ty),
+                    None => false,
+                })
+                .collect::<Vec<_>>();
+            functions[caller as usize].indirect_calls.extend(targets);
+        }
+
+        Ok(CallGraph { functions, roots })
+    }
+
+    /// Returns every function in the module, indexed by function index.
+    pub fn functions(&self) -> &[Function] {
+        &self.functions
+    }
+
+    /// Returns the number of imported functions.
+    pub fn num_imported_functions(&self) -> u32 {
+        self.functions.iter().filter(|f| f.imported).count() as u32
+    }
+
+    /// Returns the roots of the module.
+    pub fn roots(&self) -> &Roots {
+        &self.roots
+    }
+
+    /// Returns the functions which call or reference `func`.
+    pub fn callers(&self, func: u32) -> BTreeSet<u32> {
+        self.functions
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.successors().any(|s| s == func))
+            .map(|(i, _)| i as u32)
+            .collect()
+    }
+
+    /// Returns the functions reachable from the module's [roots](Roots),
+    /// including the roots themselves.
+    pub fn reachable(&self) -> BTreeSet<u32> {
+        self.reachable_from(self.roots.iter())
+    }
+
+    /// Returns the functions reachable from `roots`, including the roots
+    /// themselves.
+    ///
+    /// Indices in `roots` which are out of bounds are ignored.
+    pub fn reachable_from(&self, roots: impl IntoIterator<Item = u32>) -> BTreeSet<u32> {
+        let mut reachable = BTreeSet::new();
+        let mut worklist = roots.into_iter().collect::<Vec<_>>();
+        while let Some(index) = worklist.pop() {
+            let func = match self.functions.get(index as usize) {
+                Some(func) => func,
+                None => continue,
+            };
+            if reachable.insert(index) {
+                worklist.extend(func.successors());
+            }
+        }
+        reachable
+    }
+}
+
+fn table(tables: &mut [Table], index: u32) -> Result<&mut Table> {
+    match tables.get_mut(index as usize) {
+        Some(table) => Ok(table),
+        None => bail!("table index {} out of bounds", index),
+    }
+}
+
+fn same_type(types: &[Option<FuncType>], a: u32, b: u32) -> bool {
+    match (types.get(a as usize), types.get(b as usize)) {
+        (Some(Some(a)), Some(Some(b))) => a.params == b.params && a.returns == b.returns,
+        _ => false,
+    }
+}
+
+/// Returns the function referenced by a constant expression, if any.
+fn ref_func(expr: &InitExpr<'_>) -> Result<Option<u32>> {
+    let mut reader = expr.get_operators_reader();
+    while !reader.eof() {
+        if let Operator::RefFunc { function_index } = reader.read()? {
+            return Ok(Some(function_index));
+        }
+    }
+    Ok(None)
+}
+
+fn function_names(data: &[u8], offset: usize) -> Result<Vec<(u32, String)>> {
+    let mut names = Vec::new();
+    let mut reader = NameSectionReader::new(data, offset)?;
+    while !reader.eof() {
+        if let Name::Function(map) = reader.read()? {
+            let mut map = map.get_map()?;
+            for _ in 0..map.get_count() {
+                let naming = map.read()?;
+                names.push((naming.index, naming.name.to_string()));
+            }
+        }
+    }
+    Ok(names)
+}
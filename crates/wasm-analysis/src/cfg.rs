@@ -0,0 +1,419 @@
+//! Control-flow graphs of function bodies.
+//!
+//! [`Cfg::new`] splits a function body into basic blocks, maximal runs of
+//! operators which are always executed in sequence, and connects them with
+//! the edges of WebAssembly's structured control flow. Operators are
+//! identified by their position in the body, counting from zero, and blocks
+//! by their index in [`Cfg::blocks`].
+//!
+//! Blocks are numbered in the order their first operator appears in the
+//! body, so the entry block is always block 0. Every `return`, tail call, and
+//! the fall-through at the end of the body leads to the exit block, which
+//! holds the body's final `end` and is always the last block. Operators which
+//! leave the function by trapping or throwing, such as `unreachable` and
+//! `throw`, end a block which has no successors.
+//!
+//! Any operator inside a `try` may throw, so every block of a `try` body has
+//! an edge to each of its `catch` and `catch_all` handlers. These edges are
+//! also listed in [`BasicBlock::handlers`], since control may leave the block
+//! along them before reaching its end.
+//!
+//! Code following an unconditional branch is unreachable, and is placed in
+//! blocks without predecessors.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_analysis::Cfg;
+//! use wasmparser::{Parser, Payload};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let wasm = wat::parse_str(r#"
+//!     (module
+//!         (func (param i32)
+//!             local.get 0
+//!             if
+//!                 nop
+//!             end))
+//! "#)?;
+//! for payload in Parser::new(0).parse_all(&wasm) {
+//!     if let Payload::CodeSectionEntry(body) = payload? {
+//!         let cfg = Cfg::new(&body)?;
+//!         // The condition, the `then` branch, the `end` of the `if`, and
+//!         // the final `end` of the body.
+//!         assert_eq!(cfg.blocks().len(), 4);
+//!         assert_eq!(cfg.blocks()[0].successors, [1, 2]);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{bail, Result};
+use std::ops::Range;
+use wasmparser::{FunctionBody, Operator};
+
+/// The control-flow graph of a function body.
+#[derive(Clone, Debug)]
+pub struct Cfg {
+    blocks: Vec<BasicBlock>,
+    offsets: Vec<usize>,
+}
+
+/// A basic block in a [`Cfg`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The operators in this block.
+    ///
+    /// This may be empty, for example for the body of an empty `loop`.
+    pub ops: Range<usize>,
+    /// The blocks control may flow to from this one, without duplicates.
+    pub successors: Vec<usize>,
+    /// The blocks control may flow to this one from, without duplicates.
+    pub predecessors: Vec<usize>,
+    /// The `catch` and `catch_all` handlers which any operator in this block
+    /// may throw to, without duplicates. These are also in `successors`.
+    pub handlers: Vec<usize>,
+}
+
+#[derive(PartialEq)]
+enum FrameKind {
+    Function,
+    Block,
+    Loop,
+    If { has_else: bool },
+    Try { body: Option<Range<usize>> },
+}
+
+/// An enclosing `block`, `loop`, `if`, or `try`, or the function body.
+struct Frame {
+    kind: FrameKind,
+    /// Where a branch to this frame's label goes.
+    target: usize,
+    /// The block which starts with this frame's `end`.
+    end: usize,
+    /// For an `if`, the block which ends with its condition. For a `try`, the
+    /// first block of its body.
+    start: usize,
+}
+
+/// Blocks are given temporary ids as they are created, which are replaced
+/// with their position in the body once it has been fully read.
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<BasicBlock>,
+    frames: Vec<Frame>,
+    /// The order in which blocks were started.
+    order: Vec<usize>,
+    /// Whether each block has been started.
+    started: Vec<bool>,
+    /// The block operators are added to, or `None` after a branch.
+    current: Option<usize>,
+    /// When there's no current block, a block which falls through to the
+    /// next one to be started.
+    fallthrough: Option<usize>,
+}
+
+impl Cfg {
+    /// Builds the control-flow graph of `body`.
+    ///
+    /// Returns an error if `body` can't be parsed or its control
+    /// instructions aren't properly nested.
+    pub fn new(body: &FunctionBody<'_>) -> Result<Cfg> {
+        let mut builder = Builder::default();
+        let entry = builder.new_block();
+        let exit = builder.new_block();
+        builder.frames.push(Frame {
+            kind: FrameKind::Function,
+            target: exit,
+            end: exit,
+            start: entry,
+        });
+        builder.start(entry, 0);
+
+        let mut offsets = Vec::new();
+        let mut reader = body.get_operators_reader()?;
+        reader.allow_memarg64(true);
+        while !reader.eof() {
+            if builder.frames.is_empty() {
+                bail!("operators after the end of the function");
+            }
+            let (op, offset) = reader.read_with_offset()?;
+            builder.operator(offsets.len(), &op)?;
+            offsets.push(offset);
+        }
+        if !builder.frames.is_empty() {
+            bail!("control frames remain at the end of the function");
+        }
+
+        Ok(Cfg {
+            blocks: builder.finish(),
+            offsets,
+        })
+    }
+
+    /// Returns the basic blocks of the function, in the order in which they
+    /// appear in the body.
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    /// Returns the entry block, which is always block 0.
+    pub fn entry(&self) -> usize {
+        0
+    }
+
+    /// Returns the exit block, which holds the final `end` of the body.
+    pub fn exit(&self) -> usize {
+        self.blocks.len() - 1
+    }
+
+    /// Returns the number of operators in the body.
+    pub fn num_operators(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns the offset in the original module of the operator `op`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op` is out of bounds.
+    pub fn offset(&self, op: usize) -> usize {
+        self.offsets[op]
+    }
+
+    /// Returns the block which contains the operator `op`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op` is out of bounds.
+    pub fn block_of(&self, op: usize) -> usize {
+        assert!(op < self.offsets.len(), "operator {} out of bounds", op);
+        self.blocks.partition_point(|b| b.ops.start <= op) - 1
+    }
+}
+
+impl Builder {
+    fn new_block(&mut self) -> usize {
+        self.blocks.push(BasicBlock::default());
+        self.started.push(false);
+        self.blocks.len() - 1
+    }
+
+    /// Makes `block` the current block, starting at the operator `op`.
+    fn start(&mut self, block: usize, op: usize) {
+        self.blocks[block].ops = op..op;
+        self.order.push(block);
+        self.started[block] = true;
+        self.current = Some(block);
+        self.fallthrough = None;
+    }
+
+    /// Ends the current block, which falls through to the next one.
+    fn fall_through(&mut self) {
+        self.fallthrough = self.current.take();
+    }
+
+    /// Starts a new block at the operator `op` which control falls through
+    /// to from the current one.
+    fn split(&mut self, op: usize) -> usize {
+        let block = self.new_block();
+        self.edge_from_current(block);
+        self.start(block, op);
+        block
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        let successors = &mut self.blocks[from].successors;
+        if !successors.contains(&to) {
+            successors.push(to);
+        }
+    }
+
+    fn edge_from_current(&mut self, to: usize) {
+        if let Some(from) = self.current.or(self.fallthrough) {
+            self.edge(from, to);
+        }
+    }
+
+    fn frame(&self, relative_depth: u32) -> Result<&Frame> {
+        match self.frames.len().checked_sub(relative_depth as usize + 1) {
+            Some(i) => Ok(&self.frames[i]),
+            None => bail!("branch depth {} out of bounds", relative_depth),
+        }
+    }
+
+    fn branch(&mut self, relative_depth: u32) -> Result<()> {
+        let target = self.frame(relative_depth)?.target;
+        self.edge_from_current(target);
+        Ok(())
+    }
+
+    fn push_frame(&mut self, kind: FrameKind, start: usize, target: Option<usize>) {
+        let end = self.new_block();
+        self.frames.push(Frame {
+            kind,
+            target: target.unwrap_or(end),
+            end,
+            start,
+        });
+    }
+
+    /// Closes the innermost frame at the operator `op`, which starts the
+    /// block after it.
+    fn end(&mut self, op: usize) -> Result<()> {
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => bail!("`end` without a matching control frame"),
+        };
+        if frame.kind == (FrameKind::If { has_else: false }) {
+            self.edge(frame.start, frame.end);
+        }
+        self.edge_from_current(frame.end);
+        self.start(frame.end, op);
+        Ok(())
+    }
+
+    /// Starts a `catch` or `catch_all` handler of the innermost `try` at the
+    /// operator `op`.
+    fn catch(&mut self, op: usize) -> Result<()> {
+        let first_handler = self.blocks.len();
+        let frame = match self.frames.last_mut() {
+            Some(frame) => frame,
+            None => bail!("`catch` outside of a `try`"),
+        };
+        let body = match &mut frame.kind {
+            FrameKind::Try { body } => body
+                .get_or_insert_with(|| frame.start..first_handler)
+                .clone(),
+            _ => bail!("`catch` outside of a `try`"),
+        };
+        let end = frame.end;
+        self.edge_from_current(end);
+        let handler = self.new_block();
+        for block in body {
+            // Skip the `end` blocks of this `try` and of any enclosing
+            // frames, which haven't been started yet.
+            if self.started[block] {
+                self.edge(block, handler);
+                self.blocks[block].handlers.push(handler);
+            }
+        }
+        self.start(handler, op);
+        Ok(())
+    }
+
+    fn operator(&mut self, i: usize, op: &Operator<'_>) -> Result<()> {
+        // Operators which close a region start the block after it.
+        match op {
+            Operator::End | Operator::Delegate { .. } => {
+                self.end(i)?;
+                self.append(i);
+                return Ok(());
+            }
+            Operator::Catch { .. } | Operator::CatchAll => {
+                self.catch(i)?;
+                self.append(i);
+                return Ok(());
+            }
+            Operator::Else => {
+                let frame = match self.frames.last_mut() {
+                    Some(frame) if frame.kind == (FrameKind::If { has_else: false }) => frame,
+                    _ => bail!("`else` outside of an `if`"),
+                };
+                frame.kind = FrameKind::If { has_else: true };
+                let (cond, end) = (frame.start, frame.end);
+                self.edge_from_current(end);
+                let block = self.new_block();
+                self.edge(cond, block);
+                self.start(block, i);
+                self.append(i);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if self.current.is_none() {
+            self.split(i);
+        }
+        self.append(i);
+
+        match op {
+            Operator::Block { .. } => {
+                let current = self.current.unwrap();
+                self.push_frame(FrameKind::Block, current, None);
+            }
+            Operator::Loop { .. } => {
+                let header = self.split(i + 1);
+                self.push_frame(FrameKind::Loop, header, Some(header));
+            }
+            Operator::If { .. } => {
+                let cond = self.current.unwrap();
+                self.push_frame(FrameKind::If { has_else: false }, cond, None);
+                self.fall_through();
+            }
+            Operator::Try { .. } => {
+                let body = self.split(i + 1);
+                self.push_frame(FrameKind::Try { body: None }, body, None);
+            }
+            Operator::Br { relative_depth } => {
+                self.branch(*relative_depth)?;
+                self.current = None;
+            }
+            Operator::BrIf { relative_depth } => {
+                self.branch(*relative_depth)?;
+                self.fall_through();
+            }
+            Operator::BrTable { table } => {
+                for depth in table.targets() {
+                    self.branch(depth?)?;
+                }
+                self.branch(table.default())?;
+                self.current = None;
+            }
+            Operator::Return
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. } => {
+                let exit = self.frames[0].end;
+                self.edge_from_current(exit);
+                self.current = None;
+            }
+            Operator::Unreachable | Operator::Throw { .. } | Operator::Rethrow { .. } => {
+                self.current = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Adds the operator `op` to the current block.
+    fn append(&mut self, op: usize) {
+        let block = self.current.unwrap();
+        self.blocks[block].ops.end = op + 1;
+    }
+
+    /// Renumbers the blocks in the order they were started and fills in
+    /// their predecessors.
+    fn finish(self) -> Vec<BasicBlock> {
+        let mut ids = vec![usize::MAX; self.blocks.len()];
+        for (new, old) in self.order.iter().enumerate() {
+            ids[*old] = new;
+        }
+        let mut blocks = vec![BasicBlock::default(); self.order.len()];
+        for (old, block) in self.blocks.into_iter().enumerate() {
+            let new = ids[old];
+            if new == usize::MAX {
+                continue;
+            }
+            blocks[new].ops = block.ops;
+            blocks[new].successors = block.successors.iter().map(|s| ids[*s]).collect();
+            blocks[new].handlers = block.handlers.iter().map(|h| ids[*h]).collect();
+        }
+        for i in 0..blocks.len() {
+            for s in blocks[i].successors.clone() {
+                blocks[s].predecessors.push(i);
+            }
+        }
+        blocks
+    }
+}
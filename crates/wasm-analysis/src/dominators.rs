@@ -0,0 +1,149 @@
+//! Dominator trees of control-flow graphs.
+//!
+//! A block `a` dominates a block `b` if every path from the entry of the
+//! [`Cfg`] to `b` goes through `a`. [`Dominators::new`] computes the
+//! immediate dominator of every block with the algorithm from "A Simple,
+//! Fast Dominance Algorithm" by Cooper, Harvey, and Kennedy.
+//!
+//! Blocks which can't be reached from the entry have no dominators, and
+//! dominate nothing but themselves.
+
+use crate::cfg::Cfg;
+
+/// The dominator tree of a [`Cfg`].
+#[derive(Clone, Debug)]
+pub struct Dominators {
+    idoms: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    reverse_postorder: Vec<usize>,
+}
+
+impl Dominators {
+    /// Computes the dominator tree of `cfg`.
+    pub fn new(cfg: &Cfg) -> Dominators {
+        let blocks = cfg.blocks();
+        let reverse_postorder = reverse_postorder(cfg);
+        let mut order = vec![usize::MAX; blocks.len()];
+        for (i, block) in reverse_postorder.iter().enumerate() {
+            order[*block] = i;
+        }
+
+        let entry = cfg.entry();
+        let mut idoms = vec![None; blocks.len()];
+        idoms[entry] = Some(entry);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in reverse_postorder.iter().skip(1) {
+                let mut new_idom = None;
+                for &pred in &blocks[block].predecessors {
+                    if idoms[pred].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(idom) => intersect(&idoms, &order, pred, idom),
+                    });
+                }
+                if new_idom.is_some() && idoms[block] != new_idom {
+                    idoms[block] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        // The entry is its own immediate dominator while iterating, but has
+        // none as far as callers are concerned.
+        idoms[entry] = None;
+        let mut children = vec![Vec::new(); blocks.len()];
+        for (block, idom) in idoms.iter().enumerate() {
+            if let Some(idom) = idom {
+                children[*idom].push(block);
+            }
+        }
+
+        Dominators {
+            idoms,
+            children,
+            reverse_postorder,
+        }
+    }
+
+    /// Returns the immediate dominator of `block`, or `None` for the entry
+    /// block and unreachable blocks.
+    pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+        self.idoms[block]
+    }
+
+    /// Returns the blocks immediately dominated by `block`, which are its
+    /// children in the dominator tree.
+    pub fn children(&self, block: usize) -> &[usize] {
+        &self.children[block]
+    }
+
+    /// Returns whether `a` dominates `b`.
+    ///
+    /// Every block dominates itself.
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        let mut block = b;
+        loop {
+            if block == a {
+                return true;
+            }
+            match self.idoms[block] {
+                Some(idom) => block = idom,
+                None => return false,
+            }
+        }
+    }
+
+    /// Returns whether `block` can be reached from the entry block.
+    pub fn is_reachable(&self, block: usize) -> bool {
+        self.idoms[block].is_some() || self.reverse_postorder.first() == Some(&block)
+    }
+
+    /// Returns the blocks reachable from the entry in reverse postorder, in
+    /// which every block comes before its successors except along back
+    /// edges.
+    pub fn reverse_postorder(&self) -> &[usize] {
+        &self.reverse_postorder
+    }
+}
+
+fn reverse_postorder(cfg: &Cfg) -> Vec<usize> {
+    let blocks = cfg.blocks();
+    let mut visited = vec![false; blocks.len()];
+    let mut postorder = Vec::new();
+    let mut stack = vec![(cfg.entry(), 0)];
+    visited[cfg.entry()] = true;
+    while let Some((block, next)) = stack.last_mut() {
+        let block = *block;
+        match blocks[block].successors.get(*next) {
+            Some(&succ) => {
+                *next += 1;
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            }
+            None => {
+                postorder.push(block);
+                stack.pop();
+            }
+        }
+    }
+    postorder.reverse();
+    postorder
+}
+
+fn intersect(idoms: &[Option<usize>], order: &[usize], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while order[a] > order[b] {
+            a = idoms[a].unwrap();
+        }
+        while order[b] > order[a] {
+            b = idoms[b].unwrap();
+        }
+    }
+    a
+}
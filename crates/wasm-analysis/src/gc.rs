@@ -0,0 +1,929 @@
+//! Removal of unreachable items from a module.
+//!
+//! [`Gc`] finds every item reachable from a module's exports, its start
+//! function, and any items explicitly [retained](Gc::retain), and emits a
+//! copy of the module without everything else. Functions, tables, memories,
+//! globals, types, imports, and element and data segments may all be removed.
+//! Every index space is then renumbered, and all references to the remaining
+//! items, including those in the `name` section, are rewritten to match.
+//!
+//! An item is reachable if a reachable item refers to it, for example through
+//! a function's type and body, a global's initializer, or an import's type.
+//! Active element and data segments are reachable when the table or memory
+//! they initialize is. Those initializing an imported table or memory are
+//! always kept, along with the import, since the host can see what they
+//! write, and those initializing an exported one are kept with the export.
+//! Removing the remaining active segments may remove a trap on instantiation
+//! but has no other observable effect. Declared element
+//! segments are kept only for the reachable functions they declare, and a new
+//! one is appended if a reachable `ref.func` would otherwise be undeclared.
+//!
+//! Tags are always kept. Custom sections other than `name` are copied
+//! verbatim, so any indices they contain may become stale. Module-linking
+//! modules are not supported.
+//!
+//! The output only depends on the input module and the options, so running
+//! the same [`Gc`] on the same module always produces the same bytes.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_analysis::gc::Gc;
+//! use wasm_analysis::CallGraph;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let wasm = wat::parse_str(r#"
+//!     (module
+//!         (import "env" "unused" (func))
+//!         (func (export "a") call $b)
+//!         (func $b)
+//!         (func $c)
+//!         (global $g i32 (i32.const 0)))
+//! "#)?;
+//! let wasm = Gc::new().run(&wasm)?;
+//! wasmparser::validate(&wasm)?;
+//!
+//! // The unused import, `$c`, and `$g` are gone, and `$b` is now function 1.
+//! let graph = CallGraph::new(&wasm)?;
+//! assert_eq!(graph.functions().len(), 2);
+//! assert!(graph.functions()[0].calls.contains(&1));
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+use std::fmt;
+use wasm_encoder::reencode::{self, Reencode};
+use wasm_encoder::{
+    CodeSection, CustomSection, DataCountSection, DataSection, ElementMode, ElementSection,
+    ElementSegment, Elements, ExportSection, FunctionSection, GlobalSection, ImportSection,
+    IndirectNameMap, MemorySection, Module, NameMap, NameSection, StartSection, TableSection,
+    TagSection, TypeSection, ValType,
+};
+use wasmparser::{
+    ElementItem, ElementKind, ExternalKind, ImportSectionEntryType, Name, NameSectionReader,
+    Operator, Parser, Payload,
+};
+
+/// A kind of item that [`Gc`] may keep or remove.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Item {
+    /// A function.
+    Function,
+    /// A table.
+    Table,
+    /// A memory.
+    Memory,
+    /// A global.
+    Global,
+    /// An exception-handling tag.
+    Tag,
+    /// A type.
+    Type,
+    /// An element segment.
+    Element,
+    /// A data segment.
+    Data,
+}
+
+const KINDS: usize = 8;
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Item::Function => "function",
+            Item::Table => "table",
+            Item::Memory => "memory",
+            Item::Global => "global",
+            Item::Tag => "tag",
+            Item::Type => "type",
+            Item::Element => "element segment",
+            Item::Data => "data segment",
+        })
+    }
+}
+
+/// Removes unreachable items from a module.
+///
+/// See the [module documentation](self) for more details.
+#[derive(Clone, Debug, Default)]
+pub struct Gc {
+    retained: Vec<(Item, u32)>,
+    keep_imports: bool,
+}
+
+impl Gc {
+    /// Creates a new `Gc` which only keeps what's reachable from the exports
+    /// and the start function.
+    pub fn new() -> Gc {
+        Gc::default()
+    }
+
+    /// Keeps the `item` with the given `index`, and everything it refers to,
+    /// even if it isn't otherwise reachable.
+    ///
+    /// Indices refer to the input module.
+    pub fn retain(&mut self, item: Item, index: u32) -> &mut Gc {
+        self.retained.push((item, index));
+        self
+    }
+
+    /// Whether to keep every import, so that the module's imports don't
+    /// change. Defaults to `false`.
+    pub fn keep_imports(&mut self, keep: bool) -> &mut Gc {
+        self.keep_imports = keep;
+        self
+    }
+
+    /// Removes the unreachable items from `wasm` and returns the new module.
+    ///
+    /// Returns an error if `wasm` can't be parsed, uses module linking, or if
+    /// a retained item is out of bounds.
+    pub fn run(&self, wasm: &[u8]) -> Result<Vec<u8>> {
+        let module = ModuleInfo::new(wasm)?;
+        let live = module.live(self)?;
+        module.emit(wasm, &live)
+    }
+}
+
+/// Everything [`Gc`] needs to know about a module to decide what to keep.
+#[derive(Default)]
+struct ModuleInfo {
+    /// The items each item refers to, by kind and then by index.
+    uses: [Vec<Vec<(Item, u32)>>; KINDS],
+    /// Every import, in order.
+    imports: Vec<(Item, u32)>,
+    /// Exported items and the start function.
+    roots: Vec<(Item, u32)>,
+    /// The exported functions.
+    exported_functions: Vec<u32>,
+    /// The table and index of each active element segment.
+    active_elements: Vec<(u32, u32)>,
+    /// The memory and index of each active data segment.
+    active_data: Vec<(u32, u32)>,
+    /// Whether each element segment is declared.
+    declared: Vec<bool>,
+    /// The functions referenced by each element segment.
+    element_functions: Vec<Vec<u32>>,
+    /// The functions referenced by `ref.func` in each function body.
+    ref_funcs: Vec<Vec<u32>>,
+}
+
+impl ModuleInfo {
+    fn new(wasm: &[u8]) -> Result<ModuleInfo> {
+        let mut info = ModuleInfo::default();
+        let mut next_body = 0;
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::TypeSection(s) => {
+                    for ty in s {
+                        ty?;
+                        info.add(Item::Type, Vec::new());
+                    }
+                }
+                Payload::ImportSection(s) => {
+                    for import in s {
+                        let import = import?;
+                        let item = match import.ty {
+                            ImportSectionEntryType::Function(_) => Item::Function,
+                            ImportSectionEntryType::Table(_) => Item::Table,
+                            ImportSectionEntryType::Memory(_) => Item::Memory,
+                            ImportSectionEntryType::Global(_) => Item::Global,
+                            ImportSectionEntryType::Tag(_) => Item::Tag,
+                            ImportSectionEntryType::Module(_)
+                            | ImportSectionEntryType::Instance(_) => {
+                                bail!("module-linking modules are not supported")
+                            }
+                        };
+                        let mut uses = Uses::default();
+                        uses.parse_import(&mut ImportSection::new(), import)?;
+                        let index = info.add(item, uses.0);
+                        info.imports.push((item, index));
+                    }
+                    next_body = info.uses[Item::Function as usize].len() as u32;
+                }
+                Payload::FunctionSection(s) => {
+                    for ty in s {
+                        info.add(Item::Function, vec![(Item::Type, ty?)]);
+                    }
+                }
+                Payload::TableSection(s) => {
+                    for table in s {
+                        table?;
+                        info.add(Item::Table, Vec::new());
+                    }
+                }
+                Payload::MemorySection(s) => {
+                    for memory in s {
+                        memory?;
+                        info.add(Item::Memory, Vec::new());
+                    }
+                }
+                Payload::TagSection(s) => {
+                    for tag in s {
+                        let mut uses = Uses::default();
+                        uses.parse_tag(&mut TagSection::new(), tag?)?;
+                        info.add(Item::Tag, uses.0);
+                    }
+                }
+                Payload::GlobalSection(s) => {
+                    for global in s {
+                        let mut uses = Uses::default();
+                        uses.parse_global(&mut GlobalSection::new(), global?)?;
+                        info.add(Item::Global, uses.0);
+                    }
+                }
+                Payload::ExportSection(s) => {
+                    for export in s {
+                        let export = export?;
+                        let item = match export.kind {
+                            ExternalKind::Function => {
+                                info.exported_functions.push(export.index);
+                                Item::Function
+                            }
+                            ExternalKind::Table => Item::Table,
+                            ExternalKind::Memory => Item::Memory,
+                            ExternalKind::Global => Item::Global,
+                            ExternalKind::Tag => Item::Tag,
+                            ExternalKind::Type | ExternalKind::Module | ExternalKind::Instance => {
+                                bail!("module-linking modules are not supported")
+                            }
+                        };
+                        info.roots.push((item, export.index));
+                    }
+                }
+                Payload::StartSection { func, .. } => info.roots.push((Item::Function, func)),
+                Payload::ElementSection(s) => {
+                    for element in s {
+                        let element = element?;
+                        let index = info.uses[Item::Element as usize].len() as u32;
+                        let declared = matches!(element.kind, ElementKind::Declared);
+                        if let ElementKind::Active { table_index, .. } = element.kind {
+                            info.active_elements.push((table_index, index));
+                        }
+                        // A declared segment only makes references to its
+                        // functions valid, it doesn't keep them alive.
+                        let (uses, functions) = if declared {
+                            (Vec::new(), declared_functions(&element)?)
+                        } else {
+                            let mut uses = Uses::default();
+                            uses.parse_element(&mut ElementSection::new(), element)?;
+                            let functions = uses.functions().collect();
+                            (uses.0, functions)
+                        };
+                        info.add(Item::Element, uses);
+                        info.declared.push(declared);
+                        info.element_functions.push(functions);
+                    }
+                }
+                Payload::DataSection(s) => {
+                    for data in s {
+                        let data = data?;
+                        let index = info.uses[Item::Data as usize].len() as u32;
+                        if let wasmparser::DataKind::Active { memory_index, .. } = data.kind {
+                            info.active_data.push((memory_index, index));
+                        }
+                        let mut uses = Uses::default();
+                        uses.parse_data(&mut DataSection::new(), data)?;
+                        info.add(Item::Data, uses.0);
+                    }
+                }
+                Payload::CodeSectionEntry(mut body) => {
+                    body.allow_memarg64(true);
+                    let index = next_body as usize;
+                    next_body += 1;
+                    let mut refs = Vec::new();
+                    let mut reader = body.get_operators_reader()?;
+                    while !reader.eof() {
+                        if let Operator::RefFunc { function_index } = reader.read()? {
+                            refs.push(function_index);
+                        }
+                    }
+                    let mut uses = Uses::default();
+                    uses.parse_function_body(&mut CodeSection::new(), body)?;
+                    match info.uses[Item::Function as usize].get_mut(index) {
+                        Some(func) => func.extend(uses.0),
+                        None => bail!("function body {} has no declared type", index),
+                    }
+                    info.ref_funcs.resize(index + 1, Vec::new());
+                    info.ref_funcs[index] = refs;
+                }
+                Payload::AliasSection(_)
+                | Payload::InstanceSection(_)
+                | Payload::ModuleSectionStart { .. } => {
+                    bail!("module-linking modules are not supported")
+                }
+                Payload::UnknownSection { id, .. } => bail!("unknown section with id {}", id),
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    /// Adds an item of kind `item` which refers to `uses` and returns its
+    /// index.
+    fn add(&mut self, item: Item, uses: Vec<(Item, u32)>) -> u32 {
+        let items = &mut self.uses[item as usize];
+        items.push(uses);
+        items.len() as u32 - 1
+    }
+
+    fn len(&self, item: Item) -> u32 {
+        self.uses[item as usize].len() as u32
+    }
+
+    /// Returns whether each item is kept, by kind and then by index.
+    fn live(&self, gc: &Gc) -> Result<[Vec<bool>; KINDS]> {
+        let mut live: [Vec<bool>; KINDS] = Default::default();
+        for (live, uses) in live.iter_mut().zip(&self.uses) {
+            *live = vec![false; uses.len()];
+        }
+
+        let mut worklist = self.roots.clone();
+        for &(item, index) in &gc.retained {
+            if index >= self.len(item) {
+                bail!("cannot retain {} {}: index out of bounds", item, index);
+            }
+            worklist.push((item, index));
+        }
+        if gc.keep_imports {
+            worklist.extend(self.imports.iter().copied());
+        }
+        worklist.extend((0..self.len(Item::Tag)).map(|i| (Item::Tag, i)));
+        // Writes to an imported table or memory are visible to the host.
+        let imported_tables = self.imported(Item::Table) as u32;
+        let imported_memories = self.imported(Item::Memory) as u32;
+        worklist.extend(
+            self.active_elements
+                .iter()
+                .filter(|(table, _)| *table < imported_tables)
+                .map(|(_, segment)| (Item::Element, *segment)),
+        );
+        worklist.extend(
+            self.active_data
+                .iter()
+                .filter(|(memory, _)| *memory < imported_memories)
+                .map(|(_, segment)| (Item::Data, *segment)),
+        );
+
+        while let Some((item, index)) = worklist.pop() {
+            match live[item as usize].get_mut(index as usize) {
+                Some(true) => continue,
+                Some(live) => *live = true,
+                None => bail!("{} index {} out of bounds", item, index),
+            }
+            worklist.extend(self.uses[item as usize][index as usize].iter().copied());
+            match item {
+                Item::Table => worklist.extend(
+                    self.active_elements
+                        .iter()
+                        .filter(|(table, _)| *table == index)
+                        .map(|(_, segment)| (Item::Element, *segment)),
+                ),
+                Item::Memory => worklist.extend(
+                    self.active_data
+                        .iter()
+                        .filter(|(memory, _)| *memory == index)
+                        .map(|(_, segment)| (Item::Data, *segment)),
+                ),
+                _ => {}
+            }
+        }
+
+        // Declared segments which aren't referenced by `elem.drop` or
+        // `table.init` are kept as long as they declare a live function.
+        let functions = &live[Item::Function as usize];
+        let declared = (0..self.declared.len())
+            .filter(|&i| {
+                self.declared[i]
+                    && self.element_functions[i]
+                        .iter()
+                        .any(|f| functions.get(*f as usize) == Some(&true))
+            })
+            .collect::<Vec<_>>();
+        for i in declared {
+            live[Item::Element as usize][i] = true;
+        }
+
+        Ok(live)
+    }
+
+    /// Returns the functions referenced by `ref.func` in live code which
+    /// won't be declared by anything else once dead items are removed.
+    fn undeclared_functions(&self, live: &[Vec<bool>; KINDS]) -> BTreeSet<u32> {
+        let functions = &live[Item::Function as usize];
+        let is_live = |f: &u32| functions.get(*f as usize) == Some(&true);
+
+        let mut declared = self
+            .exported_functions
+            .iter()
+            .copied()
+            .collect::<BTreeSet<_>>();
+        for (i, funcs) in self.element_functions.iter().enumerate() {
+            if live[Item::Element as usize][i] {
+                declared.extend(funcs.iter().copied().filter(is_live));
+            }
+        }
+        for (i, uses) in self.uses[Item::Global as usize].iter().enumerate() {
+            if live[Item::Global as usize][i] {
+                declared.extend(
+                    uses.iter()
+                        .filter(|(item, _)| *item == Item::Function)
+                        .map(|(_, f)| *f),
+                );
+            }
+        }
+
+        self.ref_funcs
+            .iter()
+            .enumerate()
+            .filter(|(f, _)| functions[*f])
+            .flat_map(|(_, refs)| refs.iter().copied())
+            .filter(|f| !declared.contains(f))
+            .collect()
+    }
+
+    /// Emits a copy of `wasm` with only the `live` items.
+    fn emit(&self, wasm: &[u8], live: &[Vec<bool>; KINDS]) -> Result<Vec<u8>> {
+        let mut renumber = Renumber::new(live);
+        let mut undeclared = self
+            .undeclared_functions(live)
+            .into_iter()
+            .map(|f| renumber.get(Item::Function, f).unwrap())
+            .collect::<Vec<_>>();
+        let imported_functions = self
+            .imports
+            .iter()
+            .filter(|(item, _)| *item == Item::Function)
+            .count() as u32;
+
+        let mut module = Module::new();
+        let mut code = CodeSection::new();
+        let mut code_len = 0;
+        let mut next_body = imported_functions;
+        let mut remaining_bodies = 0;
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::TypeSection(s) => {
+                    let mut types = TypeSection::new();
+                    let mut len = 0;
+                    for (i, ty) in s.into_iter().enumerate() {
+                        let ty = ty?;
+                        if live[Item::Type as usize][i] {
+                            renumber.parse_type(&mut types, ty)?;
+                            len += 1;
+                        }
+                    }
+                    section(&mut module, len, &types);
+                }
+                Payload::ImportSection(s) => {
+                    let mut imports = ImportSection::new();
+                    let mut len = 0;
+                    for (import, (item, index)) in s.into_iter().zip(&self.imports) {
+                        let import = import?;
+                        if live[*item as usize][*index as usize] {
+                            renumber.parse_import(&mut imports, import)?;
+                            len += 1;
+                        }
+                    }
+                    section(&mut module, len, &imports);
+                }
+                Payload::FunctionSection(s) => {
+                    let mut functions = FunctionSection::new();
+                    let mut len = 0;
+                    for (i, ty) in s.into_iter().enumerate() {
+                        let ty = ty?;
+                        if live[Item::Function as usize][imported_functions as usize + i] {
+                            renumber.parse_function(&mut functions, ty)?;
+                            len += 1;
+                        }
+                    }
+                    section(&mut module, len, &functions);
+                }
+                Payload::TableSection(s) => {
+                    let mut tables = TableSection::new();
+                    let imported = self.imported(Item::Table);
+                    let mut len = 0;
+                    for (i, table) in s.into_iter().enumerate() {
+                        let table = table?;
+                        if live[Item::Table as usize][imported + i] {
+                            renumber.parse_table(&mut tables, table)?;
+                            len += 1;
+                        }
+                    }
+                    section(&mut module, len, &tables);
+                }
+                Payload::MemorySection(s) => {
+                    let mut memories = MemorySection::new();
+                    let imported = self.imported(Item::Memory);
+                    let mut len = 0;
+                    for (i, memory) in s.into_iter().enumerate() {
+                        let memory = memory?;
+                        if live[Item::Memory as usize][imported + i] {
+                            renumber.parse_memory(&mut memories, memory)?;
+                            len += 1;
+                        }
+                    }
+                    section(&mut module, len, &memories);
+                }
+                Payload::TagSection(s) => {
+                    let mut tags = TagSection::new();
+                    let mut len = 0;
+                    for tag in s {
+                        renumber.parse_tag(&mut tags, tag?)?;
+                        len += 1;
+                    }
+                    section(&mut module, len, &tags);
+                }
+                Payload::GlobalSection(s) => {
+                    let mut globals = GlobalSection::new();
+                    let imported = self.imported(Item::Global);
+                    let mut len = 0;
+                    for (i, global) in s.into_iter().enumerate() {
+                        let global = global?;
+                        if live[Item::Global as usize][imported + i] {
+                            renumber.parse_global(&mut globals, global)?;
+                            len += 1;
+                        }
+                    }
+                    section(&mut module, len, &globals);
+                }
+                Payload::ExportSection(s) => {
+                    let mut exports = ExportSection::new();
+                    renumber.parse_export_section(&mut exports, s)?;
+                    section(&mut module, exports.len(), &exports);
+                }
+                Payload::StartSection { func, .. } => {
+                    module.section(&StartSection {
+                        function_index: renumber.function_index(func)?,
+                    });
+                }
+                Payload::ElementSection(s) => {
+                    let mut elements = ElementSection::new();
+                    let mut len = 0;
+                    for (i, element) in s.into_iter().enumerate() {
+                        let element = element?;
+                        if !live[Item::Element as usize][i] {
+                            continue;
+                        }
+                        if let ElementKind::Declared = element.kind {
+                            renumber.parse_declared_element(&mut elements, &element)?;
+                        } else {
+                            renumber.parse_element(&mut elements, element)?;
+                        }
+                        len += 1;
+                    }
+                    len += declare(&mut elements, &mut undeclared);
+                    section(&mut module, len, &elements);
+                }
+                Payload::DataCountSection { .. } => {
+                    declare_in_new_section(&mut module, &mut undeclared);
+                    module.section(&DataCountSection {
+                        count: renumber.len(Item::Data),
+                    });
+                }
+                Payload::CodeSectionStart { count, .. } => {
+                    declare_in_new_section(&mut module, &mut undeclared);
+                    remaining_bodies = count;
+                }
+                Payload::CodeSectionEntry(body) => {
+                    if live[Item::Function as usize][next_body as usize] {
+                        renumber.parse_function_body(&mut code, body)?;
+                        code_len += 1;
+                    }
+                    next_body += 1;
+                    remaining_bodies -= 1;
+                    if remaining_bodies == 0 {
+                        section(&mut module, code_len, &code);
+                    }
+                }
+                Payload::DataSection(s) => {
+                    let mut data = DataSection::new();
+                    for (i, segment) in s.into_iter().enumerate() {
+                        let segment = segment?;
+                        if live[Item::Data as usize][i] {
+                            renumber.parse_data(&mut data, segment)?;
+                        }
+                    }
+                    section(&mut module, data.len(), &data);
+                }
+                Payload::CustomSection {
+                    name: "name",
+                    data,
+                    data_offset,
+                    ..
+                } => {
+                    // Names are informational only, so a malformed `name`
+                    // section is dropped rather than rejected.
+                    if let Ok(names) = renumber.names(data, data_offset) {
+                        module.section(&names);
+                    }
+                }
+                Payload::CustomSection { name, data, .. } => {
+                    module.section(&CustomSection { name, data });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(module.finish())
+    }
+
+    /// Returns the number of imported items of kind `item`.
+    fn imported(&self, item: Item) -> usize {
+        self.imports.iter().filter(|(i, _)| *i == item).count()
+    }
+}
+
+/// Returns the functions in a declared element segment.
+fn declared_functions(element: &wasmparser::Element<'_>) -> Result<Vec<u32>> {
+    let mut functions = Vec::new();
+    let mut items = element.items.get_items_reader()?;
+    for _ in 0..items.get_count() {
+        match items.read()? {
+            ElementItem::Func(f) => functions.push(f),
+            ElementItem::Expr(expr) => {
+                if let Operator::RefFunc { function_index } = expr.get_operators_reader().read()? {
+                    functions.push(function_index);
+                }
+            }
+        }
+    }
+    Ok(functions)
+}
+
+/// Emits `section` unless it has no items.
+fn section(module: &mut Module, len: u32, section: &impl wasm_encoder::Section) {
+    if len > 0 {
+        module.section(section);
+    }
+}
+
+/// Adds a declared segment for the `undeclared` functions, if there are any,
+/// and returns the number of segments added.
+fn declare(elements: &mut ElementSection, undeclared: &mut Vec<u32>) -> u32 {
+    if undeclared.is_empty() {
+        return 0;
+    }
+    elements.segment(ElementSegment {
+        mode: ElementMode::Declared,
+        element_type: ValType::FuncRef,
+        elements: Elements::Functions(undeclared),
+    });
+    undeclared.clear();
+    1
+}
+
+/// Declares the `undeclared` functions in a new element section, for modules
+/// which didn't have one.
+fn declare_in_new_section(module: &mut Module, undeclared: &mut Vec<u32>) {
+    let mut elements = ElementSection::new();
+    let len = declare(&mut elements, undeclared);
+    section(module, len, &elements);
+}
+
+/// A [`Reencode`] which records every index it's given.
+#[derive(Default)]
+struct Uses(Vec<(Item, u32)>);
+
+impl Uses {
+    fn record(&mut self, item: Item, index: u32) -> Result<u32, reencode::Error> {
+        self.0.push((item, index));
+        Ok(index)
+    }
+
+    fn functions(&self) -> impl Iterator<Item = u32> + '_ {
+        self.0
+            .iter()
+            .filter(|(item, _)| *item == Item::Function)
+            .map(|(_, f)| *f)
+    }
+}
+
+impl Reencode for Uses {
+    type Error = std::convert::Infallible;
+
+    fn type_index(&mut self, ty: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Type, ty)
+    }
+
+    fn function_index(&mut self, func: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Function, func)
+    }
+
+    fn table_index(&mut self, table: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Table, table)
+    }
+
+    fn memory_index(&mut self, memory: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Memory, memory)
+    }
+
+    fn global_index(&mut self, global: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Global, global)
+    }
+
+    fn tag_index(&mut self, tag: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Tag, tag)
+    }
+
+    fn data_index(&mut self, data: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Data, data)
+    }
+
+    fn element_index(&mut self, element: u32) -> Result<u32, reencode::Error> {
+        self.record(Item::Element, element)
+    }
+}
+
+/// The error reported when a removed item is still referenced, which would
+/// indicate a bug in the liveness analysis.
+#[derive(Debug)]
+struct Removed {
+    item: Item,
+    index: u32,
+}
+
+impl fmt::Display for Removed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} was removed but is still referenced",
+            self.item, self.index
+        )
+    }
+}
+
+impl std::error::Error for Removed {}
+
+/// A [`Reencode`] which maps the indices of kept items to their new indices.
+struct Renumber {
+    maps: [Vec<Option<u32>>; KINDS],
+}
+
+impl Renumber {
+    fn new(live: &[Vec<bool>; KINDS]) -> Renumber {
+        let mut maps: [Vec<Option<u32>>; KINDS] = Default::default();
+        for (map, live) in maps.iter_mut().zip(live) {
+            let mut next = 0;
+            *map = live
+                .iter()
+                .map(|live| {
+                    if !live {
+                        return None;
+                    }
+                    next += 1;
+                    Some(next - 1)
+                })
+                .collect();
+        }
+        Renumber { maps }
+    }
+
+    /// Returns the new index of an item, or `None` if it was removed.
+    fn get(&self, item: Item, index: u32) -> Option<u32> {
+        self.maps[item as usize]
+            .get(index as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Returns the number of items of kind `item` which are kept.
+    fn len(&self, item: Item) -> u32 {
+        self.maps[item as usize].iter().flatten().count() as u32
+    }
+
+    fn map(&self, item: Item, index: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.get(item, index)
+            .ok_or(reencode::Error::UserError(Removed { item, index }))
+    }
+
+    /// Emits a declared element segment with only the kept functions.
+    fn parse_declared_element(
+        &mut self,
+        elements: &mut ElementSection,
+        element: &wasmparser::Element<'_>,
+    ) -> Result<(), reencode::Error<Removed>> {
+        let element_type = self.val_type(element.ty)?;
+        let mut functions = Vec::new();
+        let mut exprs = Vec::new();
+        let mut items = element.items.get_items_reader()?;
+        for _ in 0..items.get_count() {
+            match items.read()? {
+                ElementItem::Func(f) => functions.extend(self.get(Item::Function, f)),
+                ElementItem::Expr(expr) => match expr.get_operators_reader().read()? {
+                    Operator::RefFunc { function_index } => exprs.extend(
+                        self.get(Item::Function, function_index)
+                            .map(wasm_encoder::Element::Func),
+                    ),
+                    Operator::RefNull { .. } => exprs.push(wasm_encoder::Element::Null),
+                    _ => return Err(reencode::Error::InvalidConstExpr),
+                },
+            }
+        }
+        elements.segment(ElementSegment {
+            mode: ElementMode::Declared,
+            element_type,
+            elements: if items.uses_exprs() {
+                Elements::Expressions(&exprs)
+            } else {
+                Elements::Functions(&functions)
+            },
+        });
+        Ok(())
+    }
+
+    /// Rewrites a `name` section, dropping the names of removed items.
+    fn names(&self, data: &[u8], offset: usize) -> Result<NameSection> {
+        let mut names = NameSection::new();
+        let mut reader = NameSectionReader::new(data, offset)?;
+        while !reader.eof() {
+            match reader.read()? {
+                Name::Module(name) => names.module(name.get_name()?),
+                Name::Function(map) => names.functions(&self.name_map(Item::Function, map)?),
+                Name::Local(map) => names.locals(&self.indirect_name_map(map)?),
+                Name::Label(map) => names.labels(&self.indirect_name_map(map)?),
+                Name::Type(map) => names.types(&self.name_map(Item::Type, map)?),
+                Name::Table(map) => names.tables(&self.name_map(Item::Table, map)?),
+                Name::Memory(map) => names.memories(&self.name_map(Item::Memory, map)?),
+                Name::Global(map) => names.globals(&self.name_map(Item::Global, map)?),
+                Name::Element(map) => names.elements(&self.name_map(Item::Element, map)?),
+                Name::Data(map) => names.data(&self.name_map(Item::Data, map)?),
+                Name::Unknown { .. } => {}
+            }
+        }
+        Ok(names)
+    }
+
+    fn name_map(&self, item: Item, map: wasmparser::NameMap<'_>) -> Result<NameMap> {
+        let mut names = NameMap::new();
+        let mut map = map.get_map()?;
+        for _ in 0..map.get_count() {
+            let naming = map.read()?;
+            if let Some(index) = self.get(item, naming.index) {
+                names.append(index, naming.name);
+            }
+        }
+        Ok(names)
+    }
+
+    /// Renumbers the functions of a map of local or label names.
+    fn indirect_name_map(&self, map: wasmparser::IndirectNameMap<'_>) -> Result<IndirectNameMap> {
+        let mut names = IndirectNameMap::new();
+        let mut map = map.get_indirect_map()?;
+        for _ in 0..map.get_indirect_count() {
+            let indirect = map.read()?;
+            let func = match self.get(Item::Function, indirect.indirect_index) {
+                Some(func) => func,
+                None => continue,
+            };
+            let mut inner = NameMap::new();
+            let mut naming = indirect.get_map()?;
+            for _ in 0..naming.get_count() {
+                let naming = naming.read()?;
+                inner.append(naming.index, naming.name);
+            }
+            names.append(func, &inner);
+        }
+        Ok(names)
+    }
+}
+
+impl Reencode for Renumber {
+    type Error = Removed;
+
+    fn type_index(&mut self, ty: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Type, ty)
+    }
+
+    fn function_index(&mut self, func: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Function, func)
+    }
+
+    fn table_index(&mut self, table: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Table, table)
+    }
+
+    fn memory_index(&mut self, memory: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Memory, memory)
+    }
+
+    fn global_index(&mut self, global: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Global, global)
+    }
+
+    fn tag_index(&mut self, tag: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Tag, tag)
+    }
+
+    fn data_index(&mut self, data: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Data, data)
+    }
+
+    fn element_index(&mut self, element: u32) -> Result<u32, reencode::Error<Removed>> {
+        self.map(Item::Element, element)
+    }
+}
@@ -0,0 +1,23 @@
+//! Static analyses of WebAssembly modules.
+//!
+//! The analyses in this crate work on the binary format directly, through
+//! [`wasmparser`], and don't validate their input. They expect a valid module
+//! and may report errors or nonsensical results for invalid ones.
+
+#![deny(missing_docs)]
+
+pub mod callgraph;
+pub mod cfg;
+pub mod dominators;
+pub mod gc;
+pub mod liveness;
+pub mod loops;
+pub mod nans;
+
+pub use callgraph::CallGraph;
+pub use cfg::Cfg;
+pub use dominators::Dominators;
+pub use gc::Gc;
+pub use liveness::Liveness;
+pub use loops::LoopNest;
+pub use nans::canonicalize_nans;
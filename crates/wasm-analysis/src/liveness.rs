@@ -0,0 +1,293 @@
+//! Definitions, uses, and liveness of locals.
+//!
+//! [`Liveness::new`] records where each local of a function is defined, by
+//! `local.set` or `local.tee`, and used, by `local.get`. It then computes
+//! which locals are live on entry to and exit from each block of the
+//! function's [`Cfg`], meaning that their current value may be read later,
+//! and which definitions may reach each use.
+//!
+//! Parameters and locals start out with the function's arguments or zero.
+//! This initial value is treated as a definition at the entry of the
+//! function, and is represented by `None` where a definition is expected.
+//!
+//! Operators are identified by their position in the function body, as in
+//! [`Cfg`].
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_analysis::{Cfg, Liveness};
+//! use wasmparser::{Parser, Payload};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let wasm = wat::parse_str(r#"
+//!     (module
+//!         (func (param i32) (result i32) (local i32)
+//!             local.get 0
+//!             local.set 1
+//!             local.get 1))
+//! "#)?;
+//! for payload in Parser::new(0).parse_all(&wasm) {
+//!     if let Payload::CodeSectionEntry(body) = payload? {
+//!         let cfg = Cfg::new(&body)?;
+//!         let liveness = Liveness::new(&body, &cfg)?;
+//!         assert_eq!(liveness.defs(1), [1]);
+//!         assert_eq!(liveness.uses(1), [2]);
+//!         assert!(liveness.live_in(cfg.entry()).contains(&0));
+//!         assert!(!liveness.live_in(cfg.entry()).contains(&1));
+//!         assert_eq!(
+//!             liveness.reaching_definitions(2).into_iter().collect::<Vec<_>>(),
+//!             [Some(1)],
+//!         );
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cfg::Cfg;
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use wasmparser::{FunctionBody, Operator};
+
+/// The definitions, uses, and liveness of the locals of a function.
+#[derive(Clone, Debug)]
+pub struct Liveness {
+    accesses: Vec<Option<Access>>,
+    block_of: Vec<usize>,
+    handlers: Vec<Vec<usize>>,
+    defs: BTreeMap<u32, Vec<usize>>,
+    uses: BTreeMap<u32, Vec<usize>>,
+    live_in: Vec<BTreeSet<u32>>,
+    live_out: Vec<BTreeSet<u32>>,
+    reaching_in: Vec<BTreeSet<(u32, Option<usize>)>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Access {
+    Def(u32),
+    Use(u32),
+}
+
+impl Liveness {
+    /// Computes the definitions, uses, and liveness of the locals of `body`,
+    /// whose control-flow graph is `cfg`.
+    ///
+    /// Returns an error if `body` can't be parsed or doesn't match `cfg`.
+    pub fn new(body: &FunctionBody<'_>, cfg: &Cfg) -> Result<Liveness> {
+        let mut accesses = Vec::with_capacity(cfg.num_operators());
+        let mut defs = BTreeMap::<u32, Vec<usize>>::new();
+        let mut uses = BTreeMap::<u32, Vec<usize>>::new();
+        let mut reader = body.get_operators_reader()?;
+        reader.allow_memarg64(true);
+        while !reader.eof() {
+            let i = accesses.len();
+            let access = match reader.read()? {
+                Operator::LocalGet { local_index } => {
+                    uses.entry(local_index).or_default().push(i);
+                    Some(Access::Use(local_index))
+                }
+                Operator::LocalSet { local_index } | Operator::LocalTee { local_index } => {
+                    defs.entry(local_index).or_default().push(i);
+                    Some(Access::Def(local_index))
+                }
+                _ => None,
+            };
+            accesses.push(access);
+        }
+        if accesses.len() != cfg.num_operators() {
+            bail!("function body doesn't match its control-flow graph");
+        }
+
+        let blocks = cfg.blocks();
+        let mut block_of = vec![0; accesses.len()];
+        for (b, block) in blocks.iter().enumerate() {
+            for op in block.ops.clone() {
+                block_of[op] = b;
+            }
+        }
+
+        // Backwards dataflow for liveness: a local is live on entry to a
+        // block if the block uses it before defining it, if it's live on exit
+        // and the block doesn't define it, or if it's live on entry to a
+        // handler the block may throw to before defining it.
+        let mut upward_uses = vec![BTreeSet::new(); blocks.len()];
+        let mut killed = vec![BTreeSet::new(); blocks.len()];
+        for (b, block) in blocks.iter().enumerate() {
+            for access in accesses[block.ops.clone()].iter().flatten() {
+                match *access {
+                    Access::Use(local) if !killed[b].contains(&local) => {
+                        upward_uses[b].insert(local);
+                    }
+                    Access::Use(_) => {}
+                    Access::Def(local) => {
+                        killed[b].insert(local);
+                    }
+                }
+            }
+        }
+        let mut live_in = upward_uses.clone();
+        let mut live_out = vec![BTreeSet::new(); blocks.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for b in (0..blocks.len()).rev() {
+                let out = blocks[b]
+                    .successors
+                    .iter()
+                    .flat_map(|s| live_in[*s].iter().copied())
+                    .collect::<BTreeSet<_>>();
+                let mut inn = upward_uses[b].clone();
+                inn.extend(out.difference(&killed[b]).copied());
+                for h in &blocks[b].handlers {
+                    inn.extend(live_in[*h].iter().copied());
+                }
+                if inn != live_in[b] {
+                    live_in[b] = inn;
+                    changed = true;
+                }
+                live_out[b] = out;
+            }
+        }
+
+        // Forwards dataflow for reaching definitions, starting with the
+        // implicit definition of every local at the entry. A throw may leave
+        // a block at any point, so everything reaching it and every
+        // definition in it reaches its handlers.
+        let mut generated = vec![BTreeMap::new(); blocks.len()];
+        for (b, block) in blocks.iter().enumerate() {
+            for op in block.ops.clone() {
+                if let Some(Access::Def(local)) = accesses[op] {
+                    generated[b].insert(local, op);
+                }
+            }
+        }
+        let mut reaching_in = vec![BTreeSet::new(); blocks.len()];
+        reaching_in[cfg.entry()] = defs
+            .keys()
+            .chain(uses.keys())
+            .map(|local| (*local, None))
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for b in 0..blocks.len() {
+                let out = reaching_out(&reaching_in[b], &generated[b], &killed[b]);
+                for &s in &blocks[b].successors {
+                    for def in &out {
+                        changed |= reaching_in[s].insert(*def);
+                    }
+                }
+                for &h in &blocks[b].handlers {
+                    let defs = reaching_in[b]
+                        .iter()
+                        .copied()
+                        .chain(blocks[b].ops.clone().filter_map(|op| match accesses[op] {
+                            Some(Access::Def(local)) => Some((local, Some(op))),
+                            _ => None,
+                        }))
+                        .collect::<Vec<_>>();
+                    for def in defs {
+                        changed |= reaching_in[h].insert(def);
+                    }
+                }
+            }
+        }
+
+        Ok(Liveness {
+            accesses,
+            block_of,
+            handlers: blocks.iter().map(|b| b.handlers.clone()).collect(),
+            defs,
+            uses,
+            live_in,
+            live_out,
+            reaching_in,
+        })
+    }
+
+    /// Returns the operators which define `local`, in order.
+    pub fn defs(&self, local: u32) -> &[usize] {
+        self.defs.get(&local).map_or(&[], |d| d)
+    }
+
+    /// Returns the operators which use `local`, in order.
+    pub fn uses(&self, local: u32) -> &[usize] {
+        self.uses.get(&local).map_or(&[], |u| u)
+    }
+
+    /// Returns the locals which are live on entry to `block`.
+    pub fn live_in(&self, block: usize) -> &BTreeSet<u32> {
+        &self.live_in[block]
+    }
+
+    /// Returns the locals which are live on exit from `block`.
+    pub fn live_out(&self, block: usize) -> &BTreeSet<u32> {
+        &self.live_out[block]
+    }
+
+    /// Returns whether `local` is live right after the operator `op`.
+    pub fn is_live_after(&self, op: usize, local: u32) -> bool {
+        let block = self.block_of[op];
+        if self.handlers[block]
+            .iter()
+            .any(|h| self.live_in[*h].contains(&local))
+        {
+            return true;
+        }
+        let end = self.block_end(block);
+        for access in self.accesses[op + 1..end].iter().flatten() {
+            match *access {
+                Access::Use(l) if l == local => return true,
+                Access::Def(l) if l == local => return false,
+                _ => {}
+            }
+        }
+        self.live_out[block].contains(&local)
+    }
+
+    /// Returns the definitions which may reach the `local.get` at `op`.
+    ///
+    /// `None` stands for the initial value of the local. Returns an empty set
+    /// if `op` isn't a `local.get` or is unreachable.
+    pub fn reaching_definitions(&self, op: usize) -> BTreeSet<Option<usize>> {
+        let local = match self.accesses[op] {
+            Some(Access::Use(local)) => local,
+            _ => return BTreeSet::new(),
+        };
+        let block = self.block_of[op];
+        for def in (self.block_start(block)..op).rev() {
+            if self.accesses[def] == Some(Access::Def(local)) {
+                return [Some(def)].into_iter().collect();
+            }
+        }
+        self.reaching_in[block]
+            .iter()
+            .filter(|(l, _)| *l == local)
+            .map(|(_, def)| *def)
+            .collect()
+    }
+
+    // Blocks are numbered in the order of their operators, so `block_of` is
+    // sorted.
+    fn block_start(&self, block: usize) -> usize {
+        self.block_of.partition_point(|b| *b < block)
+    }
+
+    fn block_end(&self, block: usize) -> usize {
+        self.block_of.partition_point(|b| *b <= block)
+    }
+}
+
+fn reaching_out(
+    reaching_in: &BTreeSet<(u32, Option<usize>)>,
+    generated: &BTreeMap<u32, usize>,
+    killed: &BTreeSet<u32>,
+) -> BTreeSet<(u32, Option<usize>)> {
+    reaching_in
+        .iter()
+        .filter(|(local, _)| !killed.contains(local))
+        .copied()
+        .chain(generated.iter().map(|(local, op)| (*local, Some(*op))))
+        .collect()
+}
@@ -0,0 +1,127 @@
+//! Loop nesting of control-flow graphs.
+//!
+//! A back edge is an edge of the [`Cfg`] whose target dominates its source.
+//! Each back edge forms a natural loop: its target, the loop header, together
+//! with every block which can reach the edge's source without going through
+//! the header. Natural loops with the same header are merged into one.
+//!
+//! Since WebAssembly's control flow is structured, every loop found this way
+//! is headed by the first block of a `loop` instruction whose label is the
+//! target of a branch, and loops are properly nested.
+
+use crate::cfg::Cfg;
+use crate::dominators::Dominators;
+use std::collections::BTreeSet;
+
+/// The loops of a [`Cfg`] and how they nest.
+#[derive(Clone, Debug)]
+pub struct LoopNest {
+    loops: Vec<Loop>,
+    innermost: Vec<Option<usize>>,
+}
+
+/// A natural loop in a [`LoopNest`].
+#[derive(Clone, Debug)]
+pub struct Loop {
+    /// The block every iteration of the loop starts at.
+    pub header: usize,
+    /// The blocks in the loop, including its header and the blocks of any
+    /// nested loops.
+    pub blocks: BTreeSet<usize>,
+    /// The sources of the back edges to the header.
+    pub latches: BTreeSet<usize>,
+    /// The innermost loop this one is nested in, as an index into
+    /// [`LoopNest::loops`].
+    pub parent: Option<usize>,
+    /// How many loops this one is nested in, plus one.
+    pub depth: u32,
+}
+
+impl LoopNest {
+    /// Finds the loops of `cfg`, whose dominator tree is `dominators`.
+    pub fn new(cfg: &Cfg, dominators: &Dominators) -> LoopNest {
+        let blocks = cfg.blocks();
+        let mut loops: Vec<Loop> = Vec::new();
+        for (source, block) in blocks.iter().enumerate() {
+            if !dominators.is_reachable(source) {
+                continue;
+            }
+            for &header in &block.successors {
+                if !dominators.dominates(header, source) {
+                    continue;
+                }
+                let i = match loops.iter().position(|l| l.header == header) {
+                    Some(i) => i,
+                    None => {
+                        loops.push(Loop {
+                            header,
+                            blocks: [header].into_iter().collect(),
+                            latches: BTreeSet::new(),
+                            parent: None,
+                            depth: 0,
+                        });
+                        loops.len() - 1
+                    }
+                };
+                let l = &mut loops[i];
+                l.latches.insert(source);
+                let mut worklist = vec![source];
+                while let Some(block) = worklist.pop() {
+                    if l.blocks.insert(block) {
+                        worklist.extend(
+                            blocks[block]
+                                .predecessors
+                                .iter()
+                                .copied()
+                                .filter(|p| dominators.is_reachable(*p)),
+                        );
+                    }
+                }
+            }
+        }
+        loops.sort_by_key(|l| l.header);
+
+        // Loops are properly nested, so the parent of a loop is the smallest
+        // other loop containing its header.
+        for i in 0..loops.len() {
+            loops[i].parent = (0..loops.len())
+                .filter(|&j| j != i && loops[j].blocks.contains(&loops[i].header))
+                .min_by_key(|&j| loops[j].blocks.len());
+        }
+        // Parents have smaller headers, and so come first.
+        for i in 0..loops.len() {
+            loops[i].depth = match loops[i].parent {
+                Some(parent) => loops[parent].depth + 1,
+                None => 1,
+            };
+        }
+
+        let mut innermost: Vec<Option<usize>> = vec![None; blocks.len()];
+        for (i, l) in loops.iter().enumerate() {
+            for &block in &l.blocks {
+                match innermost[block] {
+                    Some(j) if loops[j].depth >= l.depth => {}
+                    _ => innermost[block] = Some(i),
+                }
+            }
+        }
+
+        LoopNest { loops, innermost }
+    }
+
+    /// Returns every loop, ordered by header.
+    pub fn loops(&self) -> &[Loop] {
+        &self.loops
+    }
+
+    /// Returns the innermost loop containing `block`, if any, as an index
+    /// into [`LoopNest::loops`].
+    pub fn innermost(&self, block: usize) -> Option<usize> {
+        self.innermost[block]
+    }
+
+    /// Returns how many loops `block` is in.
+    pub fn depth(&self, block: usize) -> u32 {
+        self.innermost[block].map_or(0, |i| self.loops[i].depth)
+    }
+}
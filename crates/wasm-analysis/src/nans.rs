@@ -0,0 +1,207 @@
+//! Canonicalization of NaNs produced by float arithmetic.
+//!
+//! The bits of a NaN produced by an operator such as `f32.add` or
+//! `f64.promote_f32` aren't fully specified, so they may differ between
+//! engines and hardware.
+//! [`canonicalize_nans`] rewrites every such operator to replace a NaN result
+//! with the canonical NaN, so that a module which reinterprets or stores
+//! floats behaves the same everywhere.
+//!
+//! The inserted sequences are the ones expected by
+//! [`wasmparser::DeterministicProfile`] with
+//! [`canonicalize_nans`](wasmparser::DeterministicProfile::canonicalize_nans)
+//! enabled. Operators which are already canonicalized are left alone, so the
+//! pass can be run on its own output. Module-linking modules are not
+//! supported.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_analysis::canonicalize_nans;
+//! use wasmparser::DeterministicProfile;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let wasm = wat::parse_str(r#"
+//!     (module
+//!         (func (param f64 f64) (result f64)
+//!             local.get 0
+//!             local.get 1
+//!             f64.div))
+//! "#)?;
+//! let profile = DeterministicProfile {
+//!     canonicalize_nans: true,
+//! };
+//! assert_eq!(profile.check_module(&wasm)?.len(), 1);
+//!
+//! let wasm = canonicalize_nans(&wasm)?;
+//! wasmparser::validate(&wasm)?;
+//! assert!(profile.check_module(&wasm)?.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use wasm_encoder::reencode::{self, Reencode};
+use wasm_encoder::{CodeSection, Function, Instruction, Module, ValType};
+use wasmparser::{
+    DeterministicProfile, FloatShape, FunctionBody, ImportSectionEntryType, Nondeterminism, Parser,
+    Payload, TypeDef,
+};
+
+/// Returns a copy of the module `wasm` in which every float operator whose
+/// NaN results aren't canonicalized is followed by a canonicalization.
+///
+/// Each rewritten function gets a new scratch local for each of `f32`,
+/// `f64`, and `v128` it needs.
+///
+/// Returns an error if `wasm` can't be parsed or uses module linking.
+pub fn canonicalize_nans(wasm: &[u8]) -> Result<Vec<u8>> {
+    let mut params = Vec::new();
+    let mut functions = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::TypeSection(s) => {
+                for ty in s {
+                    params.push(match ty? {
+                        TypeDef::Func(ty) => ty.params.len() as u32,
+                        _ => 0,
+                    });
+                }
+            }
+            Payload::ImportSection(s) => {
+                for import in s {
+                    if let ImportSectionEntryType::Function(_) = import?.ty {
+                        functions.push(None);
+                    }
+                }
+            }
+            Payload::FunctionSection(s) => {
+                for ty in s {
+                    functions.push(Some(ty?));
+                }
+            }
+            Payload::AliasSection(_)
+            | Payload::InstanceSection(_)
+            | Payload::ModuleSectionStart { .. } => {
+                bail!("module-linking modules are not supported")
+            }
+            _ => {}
+        }
+    }
+
+    let mut canonicalize = Canonicalize {
+        params: functions
+            .into_iter()
+            .flatten()
+            .map(|ty| params.get(ty as usize).copied().unwrap_or(0))
+            .collect(),
+        next_body: 0,
+    };
+    let mut module = Module::new();
+    canonicalize.parse_core_module(&mut module, Parser::new(0), wasm)?;
+    Ok(module.finish())
+}
+
+/// A [`Reencode`] implementation which canonicalizes NaNs in function bodies.
+struct Canonicalize {
+    /// The number of parameters of each function with a body, in order.
+    params: Vec<u32>,
+    /// The index into `params` of the next function body.
+    next_body: usize,
+}
+
+impl Reencode for Canonicalize {
+    type Error = std::convert::Infallible;
+
+    fn parse_function_body(
+        &mut self,
+        code: &mut CodeSection,
+        body: FunctionBody<'_>,
+    ) -> Result<(), reencode::Error> {
+        let params = self.params.get(self.next_body).copied().unwrap_or(0);
+        self.next_body += 1;
+
+        let profile = DeterministicProfile {
+            canonicalize_nans: true,
+        };
+        let nans = profile
+            .check_function_body(&body)?
+            .into_iter()
+            .filter_map(|v| match v.reason {
+                Nondeterminism::NonCanonicalNan(shape) => Some((v.offset, shape)),
+                _ => None,
+            })
+            .collect::<BTreeMap<_, _>>();
+        if nans.is_empty() {
+            return reencode::utils::parse_function_body(self, code, body);
+        }
+
+        let mut locals = Vec::new();
+        let mut next_local = params;
+        for local in body.get_locals_reader()? {
+            let (count, ty) = local?;
+            locals.push((count, self.val_type(ty)?));
+            next_local += count;
+        }
+        // Scratch locals are added in the order they're first needed.
+        let mut scratch = BTreeMap::new();
+        for shape in nans.values() {
+            let ty = scratch_type(*shape);
+            scratch.entry(ty).or_insert_with(|| {
+                locals.push((1, ty));
+                next_local += 1;
+                next_local - 1
+            });
+        }
+
+        let mut func = Function::new(locals);
+        let mut reader = body.get_operators_reader()?;
+        reader.allow_memarg64(true);
+        while !reader.eof() {
+            let (op, offset) = reader.read_with_offset()?;
+            func.instruction(&self.instruction(&op)?);
+            if let Some(shape) = nans.get(&offset) {
+                let local = scratch[&scratch_type(*shape)];
+                canonicalize_nan(&mut func, *shape, local);
+            }
+        }
+        code.function(&func);
+        Ok(())
+    }
+}
+
+/// Returns the type of the scratch local used to canonicalize `shape`.
+fn scratch_type(shape: FloatShape) -> ValType {
+    match shape {
+        FloatShape::F32 => ValType::F32,
+        FloatShape::F64 => ValType::F64,
+        FloatShape::F32x4 | FloatShape::F64x2 => ValType::V128,
+    }
+}
+
+/// Emits the canonicalization of the NaN of `shape` on top of the stack,
+/// through the scratch `local`.
+fn canonicalize_nan(func: &mut Function, shape: FloatShape, local: u32) {
+    // Select between the value and the canonical NaN based on whether the
+    // value is equal to itself, which it isn't only if it's a NaN.
+    func.instruction(&Instruction::LocalTee(local));
+    let nan = shape.canonical_nan();
+    func.instruction(&match shape {
+        FloatShape::F32 => Instruction::F32Const(f32::from_bits(nan as u32)),
+        FloatShape::F64 => Instruction::F64Const(f64::from_bits(nan as u64)),
+        FloatShape::F32x4 | FloatShape::F64x2 => Instruction::V128Const(nan),
+    });
+    func.instruction(&Instruction::LocalGet(local));
+    func.instruction(&Instruction::LocalGet(local));
+    func.instruction(&match shape {
+        FloatShape::F32 => Instruction::F32Eq,
+        FloatShape::F64 => Instruction::F64Eq,
+        FloatShape::F32x4 => Instruction::F32x4Eq,
+        FloatShape::F64x2 => Instruction::F64x2Eq,
+    });
+    func.instruction(&match shape {
+        FloatShape::F32 | FloatShape::F64 => Instruction::Select,
+        FloatShape::F32x4 | FloatShape::F64x2 => Instruction::V128Bitselect,
+    });
+}
@@ -0,0 +1,83 @@
+//! Tests which apply to every analysis in this crate.
+
+use wasm_analysis::{canonicalize_nans, CallGraph, Cfg, Dominators, Gc, Liveness, LoopNest};
+use wasmparser::{Parser, Payload};
+
+mod common;
+
+#[test]
+fn local_tests() {
+    for (test, wasm) in common::local_modules(common::features()) {
+        CallGraph::new(&wasm).unwrap_or_else(|e| panic!("failed on {}: {}", test.display(), e));
+
+        for payload in Parser::new(0).parse_all(&wasm) {
+            let body = match payload.unwrap() {
+                Payload::CodeSectionEntry(body) => body,
+                _ => continue,
+            };
+            let cfg = Cfg::new(&body).unwrap();
+            let dominators = Dominators::new(&cfg);
+            let loops = LoopNest::new(&cfg, &dominators);
+            let liveness = Liveness::new(&body, &cfg).unwrap();
+            let blocks = cfg.blocks();
+
+            // Blocks cover every operator, in order.
+            let mut next = 0;
+            for block in blocks {
+                assert_eq!(block.ops.start, next, "{}", test.display());
+                next = block.ops.end;
+            }
+            assert_eq!(next, cfg.num_operators(), "{}", test.display());
+
+            for (i, block) in blocks.iter().enumerate() {
+                for s in &block.successors {
+                    assert!(blocks[*s].predecessors.contains(&i));
+                }
+                if dominators.is_reachable(i) {
+                    assert!(dominators.dominates(cfg.entry(), i));
+                }
+            }
+            for l in loops.loops() {
+                for latch in &l.latches {
+                    assert!(dominators.dominates(l.header, *latch));
+                }
+            }
+            assert!(liveness.live_in(cfg.exit()).is_empty());
+        }
+
+        let output = Gc::new()
+            .run(&wasm)
+            .unwrap_or_else(|e| panic!("failed on {}: {}", test.display(), e));
+        assert!(
+            common::validate(&output),
+            "invalid output for {}",
+            test.display()
+        );
+        // Nothing unreachable is left, so a second run changes nothing.
+        let again = Gc::new().run(&output).unwrap();
+        assert!(output == again, "not idempotent for {}", test.display());
+
+        let output = canonicalize_nans(&wasm)
+            .unwrap_or_else(|e| panic!("failed on {}: {}", test.display(), e));
+        assert!(
+            common::validate(&output),
+            "invalid output for {}",
+            test.display()
+        );
+    }
+}
+
+#[test]
+fn module_linking_is_unsupported() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (module $m)
+            (instance (instantiate $m)))
+        "#,
+    )
+    .unwrap();
+    assert!(CallGraph::new(&wasm).is_err());
+    assert!(Gc::new().run(&wasm).is_err());
+    assert!(canonicalize_nans(&wasm).is_err());
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeSet;
+use wasm_analysis::CallGraph;
+
+fn graph(wat: &str) -> CallGraph {
+    CallGraph::new(&wat::parse_str(wat).unwrap()).unwrap()
+}
+
+fn set(items: &[u32]) -> BTreeSet<u32> {
+    items.iter().copied().collect()
+}
+
+#[test]
+fn direct_calls() {
+    let graph = graph(
+        r#"
+        (module
+            (import "" "f" (func $imported))
+            (func $a (export "a") call $b return_call $imported)
+            (func $b call $b)
+            (func $dead call $a))
+        "#,
+    );
+    let funcs = graph.functions();
+    assert_eq!(graph.num_imported_functions(), 1);
+    assert!(funcs[0].imported);
+    assert_eq!(funcs[1].calls, set(&[0, 2]));
+    assert_eq!(funcs[2].calls, set(&[2]));
+    assert_eq!(graph.callers(2), set(&[1, 2]));
+    assert_eq!(graph.roots().exports, set(&[1]));
+    assert_eq!(graph.reachable(), set(&[0, 1, 2]));
+    assert_eq!(graph.reachable_from([3]), set(&[0, 1, 2, 3]));
+}
+
+#[test]
+fn indirect_calls_use_element_segments_and_types() {
+    let graph = graph(
+        r#"
+        (module
+            (type $v (func))
+            (type $i (func (param i32)))
+            (table $t 3 funcref)
+            (elem (table $t) (i32.const 0) func $v1 $i1)
+            (func $v1)
+            (func $i1 (param i32))
+            (func $v2)
+            (func $caller (export "caller")
+                (call_indirect $t (type $v) (i32.const 0))))
+        "#,
+    );
+    let funcs = graph.functions();
+    assert_eq!(funcs[3].indirect_calls, set(&[0]));
+    assert_eq!(graph.roots().elements, set(&[0, 1]));
+    assert_eq!(graph.reachable(), set(&[0, 1, 3]));
+}
+
+#[test]
+fn open_tables_may_hold_escaping_functions() {
+    let graph = graph(
+        r#"
+        (module
+            (import "" "f" (func $imported))
+            (table $t (export "t") 1 funcref)
+            (func $exported (export "e"))
+            (func $referenced (drop (ref.func $referenced)))
+            (func $private)
+            (func $caller (export "caller")
+                (call_indirect $t (i32.const 0))))
+        "#,
+    );
+    assert_eq!(graph.functions()[4].indirect_calls, set(&[0, 1, 2, 4]));
+}
+
+#[test]
+fn start_and_global_roots() {
+    let graph = graph(
+        r#"
+        (module
+            (func $start call $helper)
+            (func $helper)
+            (func $global)
+            (func $dead)
+            (global funcref (ref.func $global))
+            (start $start))
+        "#,
+    );
+    assert_eq!(graph.roots().start, Some(0));
+    assert_eq!(graph.roots().globals, set(&[2]));
+    assert_eq!(graph.reachable(), set(&[0, 1, 2]));
+}
+
+#[test]
+fn names() {
+    let graph = graph(r#"(module (func $foo) (func))"#);
+    assert_eq!(graph.functions()[0].name.as_deref(), Some("foo"));
+    assert_eq!(graph.functions()[1].name, None);
+}
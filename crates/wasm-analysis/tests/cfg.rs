@@ -0,0 +1,228 @@
+use std::collections::BTreeSet;
+use wasm_analysis::{Cfg, Dominators, Liveness, LoopNest};
+use wasmparser::{Parser, Payload};
+
+struct Analysis {
+    cfg: Cfg,
+    dominators: Dominators,
+    loops: LoopNest,
+    liveness: Liveness,
+}
+
+/// Analyzes every function body in `wasm`.
+fn analyze_all(wasm: &[u8]) -> Vec<Analysis> {
+    let mut ret = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CodeSectionEntry(body) = payload.unwrap() {
+            let cfg = Cfg::new(&body).unwrap();
+            let dominators = Dominators::new(&cfg);
+            let loops = LoopNest::new(&cfg, &dominators);
+            let liveness = Liveness::new(&body, &cfg).unwrap();
+            ret.push(Analysis {
+                cfg,
+                dominators,
+                loops,
+                liveness,
+            });
+        }
+    }
+    ret
+}
+
+/// Analyzes the only function in the module `wat`.
+fn analyze(wat: &str) -> Analysis {
+    let mut all = analyze_all(&wat::parse_str(wat).unwrap());
+    assert_eq!(all.len(), 1);
+    all.pop().unwrap()
+}
+
+fn successors(cfg: &Cfg) -> Vec<Vec<usize>> {
+    cfg.blocks().iter().map(|b| b.successors.clone()).collect()
+}
+
+fn set<T: Ord + Copy>(items: &[T]) -> BTreeSet<T> {
+    items.iter().copied().collect()
+}
+
+#[test]
+fn straight_line() {
+    let a = analyze("(module (func nop nop))");
+    let blocks = a.cfg.blocks();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0].ops, 0..2);
+    assert_eq!(blocks[1].ops, 2..3);
+    assert_eq!(successors(&a.cfg), [vec![1], vec![]]);
+    assert_eq!(a.cfg.exit(), 1);
+    assert_eq!(a.dominators.immediate_dominator(0), None);
+    assert_eq!(a.dominators.immediate_dominator(1), Some(0));
+    assert!(a.loops.loops().is_empty());
+}
+
+#[test]
+fn if_else() {
+    let a = analyze(
+        r#"
+        (module
+            (func (param i32) (result i32) (local i32)
+                local.get 0     ;; 0
+                if              ;; 1
+                    i32.const 1 ;; 2
+                    local.set 1 ;; 3
+                else            ;; 4
+                    nop         ;; 5
+                end             ;; 6
+                local.get 1))   ;; 7
+        "#,
+    );
+    let blocks = a.cfg.blocks();
+    assert_eq!(blocks[0].ops, 0..2);
+    assert_eq!(blocks[1].ops, 2..4);
+    assert_eq!(blocks[2].ops, 4..6);
+    assert_eq!(blocks[3].ops, 6..8);
+    assert_eq!(
+        successors(&a.cfg),
+        [vec![1, 2], vec![3], vec![3], vec![4], vec![]]
+    );
+    assert_eq!(blocks[3].predecessors, [1, 2]);
+    assert_eq!(a.cfg.block_of(5), 2);
+    assert!(a.cfg.offset(0) < a.cfg.offset(1));
+
+    for block in 1..4 {
+        assert_eq!(a.dominators.immediate_dominator(block), Some(0));
+    }
+    assert_eq!(a.dominators.children(0), [1, 2, 3]);
+    assert!(a.dominators.dominates(0, 4));
+    assert!(!a.dominators.dominates(1, 3));
+
+    let l = &a.liveness;
+    assert_eq!(l.defs(1), [3]);
+    assert_eq!(l.uses(1), [7]);
+    assert_eq!(l.live_in(0), &set(&[0, 1]));
+    assert_eq!(l.live_in(1), &set(&[]));
+    assert_eq!(l.live_in(2), &set(&[1]));
+    assert_eq!(l.live_out(1), &set(&[1]));
+    assert!(l.is_live_after(3, 1));
+    assert!(!l.is_live_after(0, 0));
+    assert_eq!(l.reaching_definitions(7), set(&[None, Some(3)]));
+    assert_eq!(l.reaching_definitions(0), set(&[None]));
+    assert_eq!(l.reaching_definitions(1), set(&[]));
+}
+
+#[test]
+fn unreachable_code() {
+    let a = analyze(
+        r#"
+        (module
+            (func (result i32)
+                block           ;; 0
+                    br 0        ;; 1
+                    nop         ;; 2
+                end             ;; 3
+                i32.const 1     ;; 4
+                return          ;; 5
+                i32.const 2))   ;; 6
+        "#,
+    );
+    assert_eq!(
+        successors(&a.cfg),
+        [vec![2], vec![2], vec![4], vec![4], vec![]]
+    );
+    assert_eq!(a.cfg.blocks()[1].predecessors, []);
+    assert!(!a.dominators.is_reachable(1));
+    assert!(!a.dominators.is_reachable(3));
+    assert!(!a.dominators.dominates(0, 1));
+    assert_eq!(a.dominators.immediate_dominator(1), None);
+    assert_eq!(a.dominators.immediate_dominator(4), Some(2));
+    assert_eq!(a.dominators.reverse_postorder(), [0, 2, 4]);
+}
+
+#[test]
+fn nested_loops() {
+    let a = analyze(
+        r#"
+        (module
+            (func (param i32)
+                loop $outer             ;; 0
+                    loop $inner         ;; 1
+                        local.get 0     ;; 2
+                        br_if $inner    ;; 3
+                    end                 ;; 4
+                    local.get 0         ;; 5
+                    br_if $outer        ;; 6
+                end))                   ;; 7
+        "#,
+    );
+    assert_eq!(
+        successors(&a.cfg),
+        [vec![1], vec![2], vec![2, 3], vec![1, 4], vec![5], vec![]]
+    );
+    assert_eq!(a.dominators.immediate_dominator(3), Some(2));
+
+    let loops = a.loops.loops();
+    assert_eq!(loops.len(), 2);
+    assert_eq!(loops[0].header, 1);
+    assert_eq!(loops[0].blocks, set(&[1, 2, 3]));
+    assert_eq!(loops[0].latches, set(&[3]));
+    assert_eq!(loops[0].parent, None);
+    assert_eq!(loops[0].depth, 1);
+    assert_eq!(loops[1].header, 2);
+    assert_eq!(loops[1].blocks, set(&[2]));
+    assert_eq!(loops[1].parent, Some(0));
+    assert_eq!(loops[1].depth, 2);
+    assert_eq!(a.loops.innermost(2), Some(1));
+    assert_eq!(a.loops.innermost(3), Some(0));
+    assert_eq!(a.loops.innermost(4), None);
+    assert_eq!(a.loops.depth(2), 2);
+    assert_eq!(a.loops.depth(0), 0);
+
+    assert!(a.liveness.live_out(3).contains(&0));
+    assert!(!a.liveness.live_out(4).contains(&0));
+}
+
+#[test]
+fn br_table() {
+    let a = analyze(
+        r#"
+        (module
+            (func (param i32)
+                block               ;; 0
+                    block           ;; 1
+                        local.get 0 ;; 2
+                        br_table 0 1 0 ;; 3
+                    end             ;; 4
+                    nop             ;; 5
+                end))               ;; 6
+        "#,
+    );
+    assert_eq!(successors(&a.cfg), [vec![1, 2], vec![2], vec![3], vec![]]);
+}
+
+#[test]
+fn exception_handlers() {
+    let a = analyze(
+        r#"
+        (module
+            (tag $e)
+            (func (local i32)
+                try                 ;; 0
+                    i32.const 1     ;; 1
+                    local.set 0     ;; 2
+                    throw $e        ;; 3
+                catch $e            ;; 4
+                    local.get 0     ;; 5
+                    drop            ;; 6
+                end))               ;; 7
+        "#,
+    );
+    let blocks = a.cfg.blocks();
+    assert_eq!(blocks[1].ops, 1..4);
+    assert_eq!(blocks[1].successors, [2]);
+    assert_eq!(blocks[1].handlers, [2]);
+    assert_eq!(blocks[2].ops, 4..7);
+
+    // The throw may happen before `local.set`, so the initial value of the
+    // local is both live and reaching in the handler.
+    assert!(a.liveness.live_in(1).contains(&0));
+    assert!(a.liveness.is_live_after(2, 0));
+    assert_eq!(a.liveness.reaching_definitions(5), set(&[None, Some(2)]));
+}
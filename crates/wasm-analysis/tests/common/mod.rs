@@ -0,0 +1,28 @@
+//! Helpers shared by the integration tests, in addition to the ones shared
+//! with `wasm-encoder`.
+
+// Each test crate includes this module but only uses some of it.
+#![allow(dead_code)]
+
+#[path = "../../../wasm-encoder/tests/common/mod.rs"]
+mod shared;
+
+#[allow(unused_imports)]
+pub use shared::*;
+
+/// Runs `transform` on `input` and checks that the result is valid and prints
+/// the same as `expected`, returning the result.
+pub fn check(
+    input: &str,
+    expected: &str,
+    transform: impl FnOnce(&[u8]) -> anyhow::Result<Vec<u8>>,
+) -> Vec<u8> {
+    let output = transform(&wat::parse_str(input).unwrap()).unwrap();
+    wasmparser::validate(&output).unwrap();
+    let expected = wat::parse_str(expected).unwrap();
+    assert_eq!(
+        wasmprinter::print_bytes(&output).unwrap(),
+        wasmprinter::print_bytes(&expected).unwrap(),
+    );
+    output
+}
@@ -0,0 +1,232 @@
+use wasm_analysis::gc::{Gc, Item};
+
+mod common;
+
+/// Runs `gc` on `input` and checks that the result prints the same as
+/// `expected`.
+fn check(gc: &Gc, input: &str, expected: &str) {
+    common::check(input, expected, |wasm| gc.run(wasm));
+}
+
+#[test]
+fn removes_unreachable_items() {
+    check(
+        &Gc::new(),
+        r#"
+        (module
+            (type $unused (func (param i32)))
+            (import "env" "f" (func $f))
+            (import "env" "g" (func $g))
+            (memory $m 1)
+            (table $t 1 funcref)
+            (global $a i32 (i32.const 1))
+            (global $b i32 (i32.const 2))
+            (func $run (export "run") (result i32)
+                call $g
+                global.get $b)
+            (func $dead (param i32) call $f)
+            (elem (table $t) (i32.const 0) func $dead)
+            (data (memory $m) (i32.const 0) "dead"))
+        "#,
+        r#"
+        (module
+            (import "env" "g" (func $g))
+            (global $b i32 (i32.const 2))
+            (func $run (export "run") (result i32)
+                call $g
+                global.get $b))
+        "#,
+    );
+}
+
+#[test]
+fn tables_and_memories_keep_active_segments() {
+    check(
+        &Gc::new(),
+        r#"
+        (module
+            (memory $dead 1)
+            (memory $m (export "m") 1)
+            (table $t (export "t") 1 funcref)
+            (func $a)
+            (func $b)
+            (elem (table $t) (i32.const 0) func $b)
+            (elem $passive func $a)
+            (data (memory $dead) (i32.const 0) "x")
+            (data (memory $m) (i32.const 0) "y"))
+        "#,
+        r#"
+        (module
+            (memory $m (export "m") 1)
+            (table $t (export "t") 1 funcref)
+            (func $b)
+            (elem (table $t) (i32.const 0) func $b)
+            (data (memory $m) (i32.const 0) "y"))
+        "#,
+    );
+}
+
+#[test]
+fn imported_tables_and_memories_keep_active_segments() {
+    check(
+        &Gc::new(),
+        r#"
+        (module
+            (import "env" "mem" (memory 1))
+            (import "env" "tab" (table 1 funcref))
+            (func $f)
+            (elem (i32.const 0) $f)
+            (data (i32.const 0) "config")
+            (func (export "run")))
+        "#,
+        r#"
+        (module
+            (import "env" "mem" (memory 1))
+            (import "env" "tab" (table 1 funcref))
+            (func $f)
+            (elem (i32.const 0) $f)
+            (data (i32.const 0) "config")
+            (func (export "run")))
+        "#,
+    );
+}
+
+#[test]
+fn passive_segments_are_kept_when_referenced() {
+    check(
+        &Gc::new(),
+        r#"
+        (module
+            (memory 1)
+            (table 1 funcref)
+            (func $unused)
+            (func $f)
+            (func (export "run")
+                i32.const 0
+                i32.const 0
+                i32.const 1
+                table.init $used
+                elem.drop $unused
+                data.drop $d2)
+            (elem $unused func $unused)
+            (elem $used func $f)
+            (data $d1 "a")
+            (data $d2 "b"))
+        "#,
+        r#"
+        (module
+            (table 1 funcref)
+            (func $unused)
+            (func $f)
+            (func (export "run")
+                i32.const 0
+                i32.const 0
+                i32.const 1
+                table.init $used
+                elem.drop $unused
+                data.drop $d2)
+            (elem $unused func $unused)
+            (elem $used func $f)
+            (data $d2 "b"))
+        "#,
+    );
+}
+
+#[test]
+fn retained_items_and_imports() {
+    check(
+        Gc::new()
+            .retain(Item::Function, 2)
+            .retain(Item::Global, 0)
+            .keep_imports(true),
+        r#"
+        (module
+            (import "env" "f" (func $f))
+            (global $g i32 (i32.const 0))
+            (func $a)
+            (func $kept call $a))
+        "#,
+        r#"
+        (module
+            (import "env" "f" (func $f))
+            (global $g i32 (i32.const 0))
+            (func $a)
+            (func $kept call $a))
+        "#,
+    );
+    assert!(Gc::new()
+        .retain(Item::Function, 1)
+        .run(&wat::parse_str("(module (func))").unwrap())
+        .is_err());
+}
+
+#[test]
+fn ref_func_declarations() {
+    // `$a` is only declared by a passive segment which is removed, so a new
+    // declared segment is added for it, while the declared segment for `$b`
+    // is kept without the unreachable `$c`.
+    check(
+        &Gc::new(),
+        r#"
+        (module
+            (func $a)
+            (func $b)
+            (func $c)
+            (func (export "run")
+                ref.func $a
+                drop
+                ref.func $b
+                drop)
+            (func $dead
+                ref.func $c
+                drop)
+            (elem $passive funcref (ref.func $a))
+            (elem declare func $b $c))
+        "#,
+        r#"
+        (module
+            (func $a)
+            (func $b)
+            (func (export "run")
+                ref.func $a
+                drop
+                ref.func $b
+                drop)
+            (elem declare func $b)
+            (elem declare func $a))
+        "#,
+    );
+}
+
+#[test]
+fn rewrites_names() {
+    let output = Gc::new()
+        .run(
+            &wat::parse_str(
+                r#"
+                (module $m
+                    (type $dead (func (param i64)))
+                    (type $t (func (param i32)))
+                    (global $dead i32 (i32.const 0))
+                    (global $g (mut i32) (i32.const 0))
+                    (func $dead (type $dead) (param $x i64))
+                    (func $f (export "f") (type $t) (param $y i32) (local $z i32)
+                        local.get $y
+                        global.set $g))
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        wasmprinter::print_bytes(&output).unwrap(),
+        r#"(module $m
+  (type $t (func (param i32)))
+  (func $f (type $t) (param $y i32)
+    (local $z i32)
+    local.get $y
+    global.set $g)
+  (global $g (mut i32) i32.const 0)
+  (export "f" (func $f)))"#
+    );
+}
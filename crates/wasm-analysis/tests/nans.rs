@@ -0,0 +1,217 @@
+use arbitrary::Unstructured;
+use rand::{rngs::SmallRng, RngCore, SeedableRng};
+use wasm_analysis::canonicalize_nans;
+use wasm_smith::{Config, Module};
+use wasmparser::{DeterministicProfile, Nondeterminism};
+
+mod common;
+
+fn profile() -> DeterministicProfile {
+    DeterministicProfile {
+        canonicalize_nans: true,
+    }
+}
+
+/// Runs `canonicalize_nans` on `input` and checks that the result prints the
+/// same as `expected`.
+fn check(input: &str, expected: &str) {
+    let output = common::check(input, expected, canonicalize_nans);
+    assert!(profile().check_module(&output).unwrap().is_empty());
+    assert_eq!(canonicalize_nans(&output).unwrap(), output);
+}
+
+#[test]
+fn scalars() {
+    check(
+        r#"
+        (module
+            (import "env" "f" (func (param f64)))
+            (func (param f32 f64) (result f32) (local i32)
+                local.get 1
+                local.get 1
+                f64.mul
+                call 0
+                local.get 0
+                f32.sqrt
+                local.get 0
+                f32.add))
+        "#,
+        r#"
+        (module
+            (import "env" "f" (func (param f64)))
+            (func (param f32 f64) (result f32) (local i32 f64 f32)
+                local.get 1
+                local.get 1
+                f64.mul
+                local.tee 3
+                f64.const nan
+                local.get 3
+                local.get 3
+                f64.eq
+                select
+                call 0
+                local.get 0
+                f32.sqrt
+                local.tee 4
+                f32.const nan
+                local.get 4
+                local.get 4
+                f32.eq
+                select
+                local.get 0
+                f32.add
+                local.tee 4
+                f32.const nan
+                local.get 4
+                local.get 4
+                f32.eq
+                select))
+        "#,
+    );
+}
+
+#[test]
+fn vectors() {
+    check(
+        r#"
+        (module
+            (func (param v128) (result v128)
+                local.get 0
+                f32x4.nearest
+                local.get 0
+                f64x2.min))
+        "#,
+        r#"
+        (module
+            (func (param v128) (result v128) (local v128)
+                local.get 0
+                f32x4.nearest
+                local.tee 1
+                v128.const i32x4 0x7fc00000 0x7fc00000 0x7fc00000 0x7fc00000
+                local.get 1
+                local.get 1
+                f32x4.eq
+                v128.bitselect
+                local.get 0
+                f64x2.min
+                local.tee 1
+                v128.const i64x2 0x7ff8000000000000 0x7ff8000000000000
+                local.get 1
+                local.get 1
+                f64x2.eq
+                v128.bitselect))
+        "#,
+    );
+}
+
+#[test]
+fn conversions() {
+    check(
+        r#"
+        (module
+            (func (param f32) (result f32)
+                local.get 0
+                f64.promote_f32
+                f32.demote_f64))
+        "#,
+        r#"
+        (module
+            (func (param f32) (result f32) (local f64 f32)
+                local.get 0
+                f64.promote_f32
+                local.tee 1
+                f64.const nan
+                local.get 1
+                local.get 1
+                f64.eq
+                select
+                f32.demote_f64
+                local.tee 2
+                f32.const nan
+                local.get 2
+                local.get 2
+                f32.eq
+                select))
+        "#,
+    );
+}
+
+#[test]
+fn untouched_functions() {
+    let input = r#"
+        (module
+            (func (param f32) (result f32)
+                local.get 0
+                f32.neg)
+            (func (param f64) (result f64) (local f64)
+                local.get 0
+                f64.floor
+                local.tee 1
+                f64.const nan
+                local.get 1
+                local.get 1
+                f64.eq
+                select))
+    "#;
+    check(input, input);
+}
+
+#[derive(Debug)]
+struct SmithConfig {
+    canonicalize_nans: bool,
+}
+
+impl Config for SmithConfig {
+    fn simd_enabled(&self) -> bool {
+        true
+    }
+
+    fn canonicalize_nans(&self) -> bool {
+        self.canonicalize_nans
+    }
+}
+
+/// Generates modules with `wasm-smith`, calling `f` with each one.
+fn smith_modules(canonicalize_nans: bool, mut f: impl FnMut(Vec<u8>)) {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut buf = vec![0; 1024];
+    for _ in 0..256 {
+        rng.fill_bytes(&mut buf);
+        let config = SmithConfig { canonicalize_nans };
+        if let Ok(module) = Module::new(config, &mut Unstructured::new(&buf)) {
+            f(module.to_bytes());
+        }
+    }
+}
+
+#[test]
+fn smith_canonicalizations_are_recognized() {
+    smith_modules(true, |wasm| {
+        let violations = profile().check_module(&wasm).unwrap();
+        assert!(violations.is_empty(), "{}", violations[0]);
+    });
+}
+
+#[test]
+fn smith_modules_are_canonicalized() {
+    let mut rewritten = 0;
+    smith_modules(false, |wasm| {
+        let output = canonicalize_nans(&wasm).unwrap();
+        if let Err(e) = wasmparser::validate(&output) {
+            panic!(
+                "invalid output: {}\n{}",
+                e,
+                wasmprinter::print_bytes(&output).unwrap()
+            );
+        }
+        let before = profile().check_module(&wasm).unwrap();
+        assert!(profile().check_module(&output).unwrap().is_empty());
+        if !before.is_empty() {
+            assert!(before
+                .iter()
+                .all(|v| matches!(v.reason, Nondeterminism::NonCanonicalNan(_))));
+            rewritten += 1;
+        }
+    });
+    assert!(rewritten > 0);
+}
@@ -0,0 +1,436 @@
+//! A higher-level, index-space-aware module builder.
+//!
+//! The section encoders at the root of this crate require callers to compute
+//! raw indices themselves and to add sections to a [`Module`] in a valid
+//! order. The [`ModuleBuilder`] in this module instead hands out typed
+//! handles, like [`FuncId`] and [`GlobalId`], whose indices already account
+//! for imported items. Function types are deduplicated, and sections are
+//! emitted in their canonical order when the module is finished.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_encoder::builder::ModuleBuilder;
+//! use wasm_encoder::{Function, Instruction, ValType};
+//!
+//! let mut builder = ModuleBuilder::new();
+//! let ty = builder.func_type([ValType::I32], [ValType::I32]);
+//! let log = builder.import_func("env", "log", ty);
+//!
+//! let mut body = Function::new([]);
+//! body.instruction(&Instruction::LocalGet(0));
+//! body.instruction(&Instruction::Call(log.index()));
+//! body.instruction(&Instruction::End);
+//! let f = builder.function(ty, body);
+//! builder.export("f", f);
+//!
+//! // `f` comes after the imported `log` function in the index space.
+//! assert_eq!(f.index(), 1);
+//! let wasm = builder.finish();
+//! # assert!(wasmparser::validate(&wasm).is_ok());
+//! ```
+
+use crate::*;
+use std::collections::HashMap;
+
+macro_rules! ids {
+    ($($(#[$attr:meta])* $name:ident => $export:ident,)*) => {$(
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(u32);
+
+        impl $name {
+            /// The raw index of this item in its index space.
+            pub fn index(self) -> u32 {
+                self.0
+            }
+        }
+
+        impl From<$name> for Export {
+            fn from(id: $name) -> Export {
+                Export::$export(id.0)
+            }
+        }
+    )*};
+}
+
+ids! {
+    /// A handle to a function, either imported or defined.
+    FuncId => Function,
+    /// A handle to a table, either imported or defined.
+    TableId => Table,
+    /// A handle to a memory, either imported or defined.
+    MemoryId => Memory,
+    /// A handle to a global, either imported or defined.
+    GlobalId => Global,
+}
+
+/// A handle to a function type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TypeId(u32);
+
+impl TypeId {
+    /// The raw index of this type in the type section.
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// A handle to a data segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DataId(u32);
+
+impl DataId {
+    /// The raw index of this data segment.
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// A handle to an element segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ElementId(u32);
+
+impl ElementId {
+    /// The raw index of this element segment.
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// The number of imported and defined items in one index space.
+#[derive(Clone, Copy, Debug, Default)]
+struct IndexSpace {
+    imported: u32,
+    defined: u32,
+}
+
+impl IndexSpace {
+    fn import(&mut self, kind: &str) -> u32 {
+        assert!(
+            self.defined == 0,
+            "cannot import a {} after a {} has been defined",
+            kind,
+            kind
+        );
+        self.imported += 1;
+        self.imported - 1
+    }
+
+    fn define(&mut self) -> u32 {
+        self.defined += 1;
+        self.imported + self.defined - 1
+    }
+}
+
+/// A builder for a WebAssembly module which tracks index spaces.
+///
+/// Imports of a kind of item must all be added before any item of that kind
+/// is defined, since the indices handed out for defined items would otherwise
+/// shift. Violating this causes a panic.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleBuilder {
+    type_ids: HashMap<(Vec<ValType>, Vec<ValType>), TypeId>,
+    types: TypeSection,
+    imports: ImportSection,
+    functions: FunctionSection,
+    tables: TableSection,
+    memories: MemorySection,
+    globals: GlobalSection,
+    exports: ExportSection,
+    start: Option<FuncId>,
+    elements: ElementSection,
+    bodies: Vec<Option<Function>>,
+    data: DataSection,
+    passive_data: bool,
+    custom: Vec<(String, Vec<u8>)>,
+
+    funcs: IndexSpace,
+    table_space: IndexSpace,
+    memory_space: IndexSpace,
+    global_space: IndexSpace,
+}
+
+impl ModuleBuilder {
+    /// Create a new, empty, module builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the function type with the given parameters and results, adding it
+    /// to the type section if it isn't already there.
+    pub fn func_type<P, R>(&mut self, params: P, results: R) -> TypeId
+    where
+        P: IntoIterator<Item = ValType>,
+        R: IntoIterator<Item = ValType>,
+    {
+        let key = (
+            params.into_iter().collect::<Vec<_>>(),
+            results.into_iter().collect::<Vec<_>>(),
+        );
+        if let Some(id) = self.type_ids.get(&key) {
+            return *id;
+        }
+        let id = TypeId(self.types.len());
+        self.types
+            .function(key.0.iter().copied(), key.1.iter().copied());
+        self.type_ids.insert(key, id);
+        id
+    }
+
+    /// Import a function of the given type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a function has already been declared in this module.
+    pub fn import_func(&mut self, module: &str, name: &str, ty: TypeId) -> FuncId {
+        let id = FuncId(self.funcs.import("function"));
+        self.imports
+            .import(module, Some(name), EntityType::Function(ty.0));
+        id
+    }
+
+    /// Import a table of the given type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a table has already been defined in this module.
+    pub fn import_table(&mut self, module: &str, name: &str, ty: TableType) -> TableId {
+        let id = TableId(self.table_space.import("table"));
+        self.imports.import(module, Some(name), ty);
+        id
+    }
+
+    /// Import a memory of the given type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a memory has already been defined in this module.
+    pub fn import_memory(&mut self, module: &str, name: &str, ty: MemoryType) -> MemoryId {
+        let id = MemoryId(self.memory_space.import("memory"));
+        self.imports.import(module, Some(name), ty);
+        id
+    }
+
+    /// Import a global of the given type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a global has already been defined in this module.
+    pub fn import_global(&mut self, module: &str, name: &str, ty: GlobalType) -> GlobalId {
+        let id = GlobalId(self.global_space.import("global"));
+        self.imports.import(module, Some(name), ty);
+        id
+    }
+
+    /// Declare a function of the given type whose body will be provided later
+    /// with [`ModuleBuilder::define_function`].
+    ///
+    /// This allows functions to refer to each other before their bodies are
+    /// built.
+    pub fn declare_function(&mut self, ty: TypeId) -> FuncId {
+        let id = FuncId(self.funcs.define());
+        self.functions.function(ty.0);
+        self.bodies.push(None);
+        id
+    }
+
+    /// Provide the body of a function previously declared with
+    /// [`ModuleBuilder::declare_function`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `func` is an imported function or already has a body.
+    pub fn define_function(&mut self, func: FuncId, body: Function) {
+        let idx = func
+            .0
+            .checked_sub(self.funcs.imported)
+            .expect("cannot define the body of an imported function");
+        let slot = &mut self.bodies[idx as usize];
+        assert!(slot.is_none(), "function {} is already defined", func.0);
+        *slot = Some(body);
+    }
+
+    /// Define a function of the given type with the given body.
+    pub fn function(&mut self, ty: TypeId, body: Function) -> FuncId {
+        let id = self.declare_function(ty);
+        self.define_function(id, body);
+        id
+    }
+
+    /// Define a table of the given type.
+    pub fn table(&mut self, ty: TableType) -> TableId {
+        let id = TableId(self.table_space.define());
+        self.tables.table(ty);
+        id
+    }
+
+    /// Define a memory of the given type.
+    pub fn memory(&mut self, ty: MemoryType) -> MemoryId {
+        let id = MemoryId(self.memory_space.define());
+        self.memories.memory(ty);
+        id
+    }
+
+    /// Define a global of the given type with the given constant initializer.
+    pub fn global(&mut self, ty: GlobalType, init: &Instruction<'_>) -> GlobalId {
+        let id = GlobalId(self.global_space.define());
+        self.globals.global(ty, init);
+        id
+    }
+
+    /// Export an item under the given name.
+    pub fn export(&mut self, name: &str, item: impl Into<Export>) {
+        self.exports.export(name, item.into());
+    }
+
+    /// Set the start function of this module.
+    pub fn start(&mut self, func: FuncId) {
+        self.start = Some(func);
+    }
+
+    /// Define an active element segment which initializes `table`, starting at
+    /// `offset`, with the given functions.
+    pub fn active_elements(
+        &mut self,
+        table: TableId,
+        offset: &Instruction<'_>,
+        funcs: &[FuncId],
+    ) -> ElementId {
+        let id = ElementId(self.elements.len());
+        let funcs = funcs.iter().map(|f| f.0).collect::<Vec<_>>();
+        self.elements.active(
+            if table.0 == 0 { None } else { Some(table.0) },
+            offset,
+            ValType::FuncRef,
+            Elements::Functions(&funcs),
+        );
+        id
+    }
+
+    /// Define a passive element segment with the given functions.
+    pub fn passive_elements(&mut self, funcs: &[FuncId]) -> ElementId {
+        let id = ElementId(self.elements.len());
+        let funcs = funcs.iter().map(|f| f.0).collect::<Vec<_>>();
+        self.elements
+            .passive(ValType::FuncRef, Elements::Functions(&funcs));
+        id
+    }
+
+    /// Define an active data segment which initializes `memory`, starting at
+    /// `offset`, with the given bytes.
+    pub fn active_data<D>(&mut self, memory: MemoryId, offset: &Instruction<'_>, data: D) -> DataId
+    where
+        D: IntoIterator<Item = u8>,
+        D::IntoIter: ExactSizeIterator,
+    {
+        let id = DataId(self.data.len());
+        self.data.active(memory.0, offset, data);
+        id
+    }
+
+    /// Define a passive data segment with the given bytes.
+    pub fn passive_data<D>(&mut self, data: D) -> DataId
+    where
+        D: IntoIterator<Item = u8>,
+        D::IntoIter: ExactSizeIterator,
+    {
+        let id = DataId(self.data.len());
+        self.data.passive(data);
+        self.passive_data = true;
+        id
+    }
+
+    /// Add a custom section, which is emitted after all other sections.
+    pub fn custom_section(&mut self, name: &str, data: &[u8]) {
+        self.custom.push((name.to_string(), data.to_vec()));
+    }
+
+    /// Emit all sections in canonical order and return the encoded module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a function was declared but its body was never defined.
+    pub fn finish(self) -> Vec<u8> {
+        self.into_module().finish()
+    }
+
+    /// Like [`ModuleBuilder::finish`], but also validates the resulting
+    /// module with `wasmparser`'s default features.
+    ///
+    /// This is only available when the `wasmparser` feature is enabled.
+    #[cfg(feature = "wasmparser")]
+    pub fn finish_validated(self) -> Result<Vec<u8>, wasmparser::BinaryReaderError> {
+        let wasm = self.finish();
+        wasmparser::validate(&wasm)?;
+        Ok(wasm)
+    }
+
+    /// Emit all sections in canonical order into a [`Module`].
+    ///
+    /// Empty sections are omitted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a function was declared but its body was never defined.
+    pub fn into_module(self) -> Module {
+        let mut module = Module::new();
+        if !self.types.is_empty() {
+            module.section(&self.types);
+        }
+        if !self.imports.is_empty() {
+            module.section(&self.imports);
+        }
+        if !self.functions.is_empty() {
+            module.section(&self.functions);
+        }
+        if !self.tables.is_empty() {
+            module.section(&self.tables);
+        }
+        if !self.memories.is_empty() {
+            module.section(&self.memories);
+        }
+        if !self.globals.is_empty() {
+            module.section(&self.globals);
+        }
+        if !self.exports.is_empty() {
+            module.section(&self.exports);
+        }
+        if let Some(start) = self.start {
+            module.section(&StartSection {
+                function_index: start.0,
+            });
+        }
+        if !self.elements.is_empty() {
+            module.section(&self.elements);
+        }
+        // The data count section is required when data segments are referenced
+        // from code, which is what passive segments are for, but `data.drop`
+        // may also refer to an active segment.
+        if self.passive_data || self.bodies.iter().flatten().any(|b| b.uses_data_count()) {
+            module.section(&DataCountSection {
+                count: self.data.len(),
+            });
+        }
+        if !self.bodies.is_empty() {
+            let mut code = CodeSection::new();
+            for (i, body) in self.bodies.iter().enumerate() {
+                match body {
+                    Some(body) => code.function(body),
+                    None => panic!(
+                        "function {} was declared but never defined",
+                        self.funcs.imported as usize + i
+                    ),
+                };
+            }
+            module.section(&code);
+        }
+        if !self.data.is_empty() {
+            module.section(&self.data);
+        }
+        for (name, data) in self.custom.iter() {
+            module.section(&CustomSection { name, data });
+        }
+        module
+    }
+}
@@ -0,0 +1,374 @@
+//! Type-checked construction of function bodies.
+//!
+//! A [`Function`] accepts any sequence of instructions, so a mistake such as
+//! a missing operand is only discovered once the finished module is handed
+//! to a validator, which then reports a byte offset far removed from the code
+//! that emitted the bad instruction. [`CheckedFunction`] instead runs
+//! `wasmparser`'s operator validator as each instruction is appended, and
+//! reports a [`TypeError`] for the exact instruction that introduced the
+//! error, along with the operand stack at that point.
+//!
+//! The module that a function lives in is described to the validator with
+//! [`Resources`], or with any other implementation of
+//! [`wasmparser::WasmModuleResources`].
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_encoder::checked::{CheckedFunction, Resources};
+//! use wasm_encoder::{Instruction, ValType};
+//!
+//! let mut resources = Resources::new();
+//! let ty = resources.func_type([ValType::I32], [ValType::I64]);
+//!
+//! let mut f = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+//! f.instruction(&Instruction::LocalGet(0)).unwrap();
+//!
+//! // `i64.add` expects two `i64` operands but finds an `i32`.
+//! let err = f.instruction(&Instruction::I64Add).unwrap_err();
+//! assert_eq!(err.index, 1);
+//! assert_eq!(err.stack, [Some(ValType::I32)]);
+//! ```
+
+use crate::*;
+use std::fmt;
+use wasmparser::{
+    BinaryReader, BinaryReaderError, FuncType, FuncValidator, Type, WasmFeatures,
+    WasmModuleResources,
+};
+
+/// A [`Function`] builder which type checks every instruction as it is
+/// appended.
+///
+/// Once an instruction has been rejected the validator's state is
+/// unspecified, so after an error the builder should be discarded rather than
+/// used to append further instructions.
+pub struct CheckedFunction<T> {
+    func: Function,
+    validator: FuncValidator<T>,
+    count: usize,
+    scratch: Vec<u8>,
+}
+
+impl<T: WasmModuleResources> CheckedFunction<T> {
+    /// Creates a new function body of type `ty` with the given locals, which
+    /// are defined as in [`Function::new`].
+    ///
+    /// The type index `ty` and every index used by instructions appended
+    /// later are resolved through `resources`.
+    pub fn new<L>(
+        ty: u32,
+        locals: L,
+        resources: T,
+        features: &WasmFeatures,
+    ) -> Result<Self, TypeError>
+    where
+        L: IntoIterator<Item = (u32, ValType)>,
+    {
+        let locals = locals.into_iter().collect::<Vec<_>>();
+        let error = |e: BinaryReaderError| TypeError {
+            index: 0,
+            message: e.message().to_string(),
+            stack: Vec::new(),
+            expected: Vec::new(),
+        };
+        let mut validator = FuncValidator::new(ty, 0, resources, features).map_err(error)?;
+        for (count, ty) in locals.iter() {
+            validator
+                .define_locals(0, *count, parser_type(*ty))
+                .map_err(error)?;
+        }
+        Ok(CheckedFunction {
+            func: Function::new(locals),
+            validator,
+            count: 0,
+            scratch: Vec::new(),
+        })
+    }
+
+    /// Type checks `instruction` against the current operand stack and, if
+    /// it is valid, appends it to this function body.
+    pub fn instruction(&mut self, instruction: &Instruction) -> Result<&mut Self, TypeError> {
+        self.scratch.clear();
+        instruction.encode(&mut self.scratch);
+        let mut reader = BinaryReader::new(&self.scratch);
+        reader.allow_memarg64(true);
+        // The validator pops operands before it reports an error, so the
+        // stack is captured beforehand.
+        let stack = self.stack();
+        let result = reader
+            .read_operator()
+            .and_then(|op| self.validator.op(self.count, &op));
+        if let Err(e) = result {
+            return Err(self.error(e, stack));
+        }
+        self.func.instruction(instruction);
+        self.count += 1;
+        Ok(self)
+    }
+
+    /// Returns the number of instructions appended so far.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Returns whether no instructions have been appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the current operand stack, bottom first.
+    ///
+    /// Operands of unknown type, which are pushed by polymorphic
+    /// instructions in unreachable code, are `None`.
+    pub fn stack(&self) -> Vec<Option<ValType>> {
+        let height = self.validator.operand_stack_height() as usize;
+        (0..height)
+            .rev()
+            .map(|depth| {
+                self.validator
+                    .get_operand_type(depth)
+                    .unwrap()
+                    .and_then(encoder_type)
+            })
+            .collect()
+    }
+
+    /// Checks that the function body is complete, meaning that its final
+    /// `end` has been appended, and returns it.
+    pub fn finish(mut self) -> Result<Function, TypeError> {
+        match self.validator.finish(self.count) {
+            Ok(()) => Ok(self.func),
+            Err(e) => {
+                let stack = self.stack();
+                Err(self.error(e, stack))
+            }
+        }
+    }
+
+    /// Returns the underlying module resources.
+    pub fn resources(&self) -> &T {
+        self.validator.resources()
+    }
+
+    fn error(&self, e: BinaryReaderError, stack: Vec<Option<ValType>>) -> TypeError {
+        // Operands above the validator's remaining stack were popped
+        // successfully, so their types stand in for the expected ones.
+        let expected = match self.validator.mismatched_operand() {
+            Some(ty) => {
+                let height = (self.validator.operand_stack_height() as usize).min(stack.len());
+                std::iter::once(ty.and_then(encoder_type))
+                    .chain(stack[height..].iter().cloned())
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        TypeError {
+            index: self.count,
+            message: e.message().to_string(),
+            stack,
+            expected,
+        }
+    }
+}
+
+impl<T> fmt::Debug for CheckedFunction<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckedFunction")
+            .field("func", &self.func)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
+/// A type error reported by [`CheckedFunction`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeError {
+    /// The index, within the function body, of the instruction that was
+    /// rejected. For errors reported by [`CheckedFunction::finish`] this is
+    /// the number of instructions in the body.
+    pub index: usize,
+    /// The validator's description of the error, which includes the operand
+    /// type that was expected.
+    pub message: String,
+    /// The operand stack just before the rejected instruction, bottom first.
+    /// Operands of unknown type are `None`.
+    pub stack: Vec<Option<ValType>>,
+    /// For an operand that was missing or of the wrong type, the operand
+    /// types that the instruction expected, bottom first, starting with the
+    /// offending operand and ending with the top of the stack. An expected
+    /// type of `None` means that any type was accepted.
+    ///
+    /// This is empty for all other errors.
+    pub expected: Vec<Option<ValType>>,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instruction {}: {} (stack: [", self.index, self.message)?;
+        for (i, ty) in self.stack.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match ty {
+                Some(ty) => write!(f, "{:?}", ty)?,
+                None => write!(f, "unknown")?,
+            }
+        }
+        write!(f, "])")
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// An owned description of a module's index spaces, for use with
+/// [`CheckedFunction`].
+///
+/// Items must be added in index order, so imported items come before the
+/// ones defined in the module. Every function is considered to be declared
+/// as referenced, so `ref.func` is accepted for any function index.
+#[derive(Clone, Debug, Default)]
+pub struct Resources {
+    types: Vec<FuncType>,
+    functions: Vec<u32>,
+    tables: Vec<wasmparser::TableType>,
+    memories: Vec<wasmparser::MemoryType>,
+    globals: Vec<wasmparser::GlobalType>,
+    tags: Vec<u32>,
+    elements: Vec<Type>,
+    data_count: u32,
+}
+
+impl Resources {
+    /// Creates an empty set of resources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a function type and returns its type index.
+    pub fn func_type<P, R>(&mut self, params: P, results: R) -> u32
+    where
+        P: IntoIterator<Item = ValType>,
+        R: IntoIterator<Item = ValType>,
+    {
+        self.types.push(FuncType {
+            params: params.into_iter().map(parser_type).collect(),
+            returns: results.into_iter().map(parser_type).collect(),
+        });
+        self.types.len() as u32 - 1
+    }
+
+    /// Adds a function of type `ty` and returns its function index.
+    pub fn function(&mut self, ty: u32) -> u32 {
+        self.functions.push(ty);
+        self.functions.len() as u32 - 1
+    }
+
+    /// Adds a table and returns its table index.
+    pub fn table(&mut self, ty: TableType) -> u32 {
+        self.tables.push(wasmparser::TableType {
+            element_type: parser_type(ty.element_type),
+            initial: ty.minimum,
+            maximum: ty.maximum,
+        });
+        self.tables.len() as u32 - 1
+    }
+
+    /// Adds a memory and returns its memory index.
+    pub fn memory(&mut self, ty: MemoryType) -> u32 {
+        self.memories.push(wasmparser::MemoryType {
+            memory64: ty.memory64,
+            shared: ty.shared,
+            initial: ty.minimum,
+            maximum: ty.maximum,
+        });
+        self.memories.len() as u32 - 1
+    }
+
+    /// Adds a global and returns its global index.
+    pub fn global(&mut self, ty: GlobalType) -> u32 {
+        self.globals.push(wasmparser::GlobalType {
+            content_type: parser_type(ty.val_type),
+            mutable: ty.mutable,
+        });
+        self.globals.len() as u32 - 1
+    }
+
+    /// Adds a tag and returns its tag index.
+    pub fn tag(&mut self, ty: TagType) -> u32 {
+        self.tags.push(ty.func_type_idx);
+        self.tags.len() as u32 - 1
+    }
+
+    /// Adds an element segment of the given element type and returns its
+    /// index.
+    pub fn element(&mut self, element_type: ValType) -> u32 {
+        self.elements.push(parser_type(element_type));
+        self.elements.len() as u32 - 1
+    }
+
+    /// Sets the number of data segments in the module.
+    pub fn data_count(&mut self, count: u32) {
+        self.data_count = count;
+    }
+}
+
+impl WasmModuleResources for Resources {
+    type FuncType = FuncType;
+
+    fn table_at(&self, at: u32) -> Option<wasmparser::TableType> {
+        self.tables.get(at as usize).copied()
+    }
+    fn memory_at(&self, at: u32) -> Option<wasmparser::MemoryType> {
+        self.memories.get(at as usize).copied()
+    }
+    fn tag_at(&self, at: u32) -> Option<&FuncType> {
+        self.func_type_at(*self.tags.get(at as usize)?)
+    }
+    fn global_at(&self, at: u32) -> Option<wasmparser::GlobalType> {
+        self.globals.get(at as usize).copied()
+    }
+    fn func_type_at(&self, type_idx: u32) -> Option<&FuncType> {
+        self.types.get(type_idx as usize)
+    }
+    fn type_of_function(&self, func_idx: u32) -> Option<&FuncType> {
+        self.func_type_at(*self.functions.get(func_idx as usize)?)
+    }
+    fn element_type_at(&self, at: u32) -> Option<Type> {
+        self.elements.get(at as usize).copied()
+    }
+    fn element_count(&self) -> u32 {
+        self.elements.len() as u32
+    }
+    fn data_count(&self) -> u32 {
+        self.data_count
+    }
+    fn is_function_referenced(&self, idx: u32) -> bool {
+        (idx as usize) < self.functions.len()
+    }
+}
+
+fn parser_type(ty: ValType) -> Type {
+    match ty {
+        ValType::I32 => Type::I32,
+        ValType::I64 => Type::I64,
+        ValType::F32 => Type::F32,
+        ValType::F64 => Type::F64,
+        ValType::V128 => Type::V128,
+        ValType::FuncRef => Type::FuncRef,
+        ValType::ExternRef => Type::ExternRef,
+    }
+}
+
+fn encoder_type(ty: Type) -> Option<ValType> {
+    Some(match ty {
+        Type::I32 => ValType::I32,
+        Type::I64 => ValType::I64,
+        Type::F32 => ValType::F32,
+        Type::F64 => ValType::F64,
+        Type::V128 => ValType::V128,
+        Type::FuncRef => ValType::FuncRef,
+        Type::ExternRef => ValType::ExternRef,
+        _ => return None,
+    })
+}
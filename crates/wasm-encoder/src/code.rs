@@ -1,5 +1,6 @@
 use super::*;
 use std::borrow::Cow;
+use std::ops::Range;
 
 /// An encoder for the code section.
 ///
@@ -36,6 +37,16 @@ use std::borrow::Cow;
 pub struct CodeSection {
     bytes: Vec<u8>,
     num_added: u32,
+    functions: Vec<EncodedFunction>,
+}
+
+/// Where a function body was written within `CodeSection::bytes`.
+#[derive(Clone, Debug)]
+struct EncodedFunction {
+    start: usize,
+    body_start: usize,
+    end: usize,
+    instructions: Vec<usize>,
 }
 
 impl CodeSection {
@@ -56,11 +67,65 @@ impl CodeSection {
 
     /// Write a function body into this code section.
     pub fn function(&mut self, func: &Function) -> &mut Self {
+        let start = self.bytes.len();
         func.encode(&mut self.bytes);
+        let body_start = self.bytes.len() - func.bytes.len();
+        self.functions.push(EncodedFunction {
+            start,
+            body_start,
+            end: self.bytes.len(),
+            instructions: func.offsets.clone().unwrap_or_default(),
+        });
         self.num_added += 1;
         self
     }
 
+    /// Returns the location of each function body in this section, in the
+    /// order they were added.
+    ///
+    /// Offsets are relative to the start of the section's contents, just
+    /// after the section's id and size, which is how DWARF debug info and
+    /// the `R_WASM_FUNCTION_OFFSET_I32` relocation refer to code. They
+    /// account for the section's function count, so they're only final once
+    /// every function has been added.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_encoder::{CodeSection, Function, Instruction};
+    ///
+    /// let mut func = Function::new([]);
+    /// func.record_offsets();
+    /// func.instruction(&Instruction::I32Const(1));
+    /// func.instruction(&Instruction::Drop);
+    /// func.instruction(&Instruction::End);
+    ///
+    /// let mut code = CodeSection::new();
+    /// code.function(&func);
+    ///
+    /// // The count takes 1 byte, followed by the body's size, its locals,
+    /// // and its instructions.
+    /// let offsets = code.function_offsets();
+    /// assert_eq!(offsets[0].range, 1..7);
+    /// assert_eq!(offsets[0].body_start, 2);
+    /// assert_eq!(offsets[0].instructions, [3, 5, 6]);
+    /// ```
+    pub fn function_offsets(&self) -> Vec<FunctionOffsets> {
+        let base = encoders::u32(self.num_added).len();
+        self.functions
+            .iter()
+            .map(|f| FunctionOffsets {
+                range: base + f.start..base + f.end,
+                body_start: base + f.body_start,
+                instructions: f
+                    .instructions
+                    .iter()
+                    .map(|i| base + f.body_start + i)
+                    .collect(),
+            })
+            .collect()
+    }
+
     /// Add a raw byte slice into this code section as a function body.
     ///
     /// The length prefix of the function body will be automatically prepended,
@@ -86,14 +151,39 @@ impl CodeSection {
     /// encoder.raw(&code_section[body_range.start..body_range.end]);
     /// ```
     pub fn raw(&mut self, data: &[u8]) -> &mut Self {
+        let start = self.bytes.len();
         self.bytes
             .extend(encoders::u32(u32::try_from(data.len()).unwrap()));
+        let body_start = self.bytes.len();
         self.bytes.extend(data);
+        self.functions.push(EncodedFunction {
+            start,
+            body_start,
+            end: self.bytes.len(),
+            instructions: Vec::new(),
+        });
         self.num_added += 1;
         self
     }
 }
 
+/// The location of a function body within an encoded [`CodeSection`], as
+/// returned by [`CodeSection::function_offsets`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionOffsets {
+    /// The range of the whole function, including its size prefix.
+    pub range: Range<usize>,
+    /// The offset of the function body just after its size prefix, where
+    /// its locals are declared.
+    pub body_start: usize,
+    /// The offset of each instruction in the body.
+    ///
+    /// This is empty unless [`Function::record_offsets`] was called before
+    /// the instructions were added, and for bodies added with
+    /// [`CodeSection::raw`].
+    pub instructions: Vec<usize>,
+}
+
 impl Section for CodeSection {
     fn id(&self) -> u8 {
         SectionId::Code.into()
@@ -136,9 +226,17 @@ impl Section for CodeSection {
 /// let mut code = CodeSection::new();
 /// code.function(&func);
 /// ```
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct Function {
     bytes: Vec<u8>,
+    offsets: Option<Vec<usize>>,
+    uses_data_count: bool,
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Function) -> bool {
+        self.bytes == other.bytes
+    }
 }
 
 impl Function {
@@ -169,7 +267,11 @@ impl Function {
             bytes.extend(encoders::u32(count));
             bytes.push(ty.into());
         }
-        Function { bytes }
+        Function {
+            bytes,
+            offsets: None,
+            uses_data_count: false,
+        }
     }
 
     /// Create a function from a list of locals' types.
@@ -212,10 +314,79 @@ impl Function {
 
     /// Write an instruction into this function body.
     pub fn instruction(&mut self, instruction: &Instruction) -> &mut Self {
+        self.record_instruction(instruction);
         instruction.encode(&mut self.bytes);
         self
     }
 
+    /// Write an instruction into this function body, padding all of its
+    /// LEB128 immediates, such as indices and constants, to their maximum
+    /// width.
+    ///
+    /// Relocatable object files require padded immediates wherever a
+    /// relocation applies, so that a linker can patch them in place without
+    /// shifting the rest of the code. Opcodes themselves are never padded,
+    /// and neither is the memory index of `memory.size` and `memory.grow`
+    /// when it's zero, since that's a single reserved byte without the
+    /// multi-memory proposal.
+    ///
+    /// ```
+    /// # use wasm_encoder::{Function, Instruction};
+    /// let mut f = Function::new([]);
+    /// f.instruction_padded(&Instruction::Call(1));
+    /// // The call's function index takes up 5 bytes.
+    /// assert_eq!(f.byte_len(), 1 + 1 + 5);
+    /// ```
+    pub fn instruction_padded(&mut self, instruction: &Instruction) -> &mut Self {
+        self.record_instruction(instruction);
+        instruction.encode_with(&mut self.bytes, true);
+        self
+    }
+
+    /// Returns the number of bytes encoded into this function body so far,
+    /// including its locals but not its size prefix.
+    ///
+    /// This is the offset, relative to the start of the body, at which the
+    /// next instruction will be written.
+    pub fn byte_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Starts recording the offset of each instruction added to this function
+    /// from now on.
+    ///
+    /// The offsets are reported by [`Function::instruction_offsets`] and,
+    /// once this function is added to a code section, by
+    /// [`CodeSection::function_offsets`].
+    pub fn record_offsets(&mut self) -> &mut Self {
+        self.offsets.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Returns the offsets, relative to the start of this function's body,
+    /// of the instructions added since [`Function::record_offsets`] was
+    /// called, or `None` if offsets aren't being recorded.
+    pub fn instruction_offsets(&self) -> Option<&[usize]> {
+        self.offsets.as_deref()
+    }
+
+    fn record_instruction(&mut self, instruction: &Instruction) {
+        if let Some(offsets) = &mut self.offsets {
+            offsets.push(self.bytes.len());
+        }
+        if let Instruction::MemoryInit { .. } | Instruction::DataDrop(_) = instruction {
+            self.uses_data_count = true;
+        }
+    }
+
+    /// Returns whether an instruction added to this function refers to a data
+    /// segment by index, which requires a data count section.
+    ///
+    /// Instructions added with [`Function::raw`] aren't taken into account.
+    pub(crate) fn uses_data_count(&self) -> bool {
+        self.uses_data_count
+    }
+
     /// Add raw bytes to this function's body.
     pub fn raw<B>(&mut self, bytes: B) -> &mut Self
     where
@@ -235,6 +406,7 @@ impl Function {
 
 /// The immediate for a memory instruction.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemArg {
     /// A static offset to add to the instruction's dynamic address operand.
     ///
@@ -252,23 +424,64 @@ pub struct MemArg {
 }
 
 impl MemArg {
-    fn encode(&self, bytes: &mut Vec<u8>) {
+    fn encode_with(&self, bytes: &mut Vec<u8>, padded: bool) {
         if self.memory_index == 0 {
             bytes.extend(encoders::u32(self.align));
-            bytes.extend(encoders::u64(self.offset));
         } else {
             bytes.extend(encoders::u32(self.align | (1 << 6)));
-            bytes.extend(encoders::u64(self.offset));
-            bytes.extend(encoders::u32(self.memory_index));
+        }
+        // Offsets into 32-bit memories must fit in a 5-byte LEB, so only
+        // offsets which need more bits are padded to the full 64-bit width.
+        match u32::try_from(self.offset) {
+            Ok(offset) if padded => bytes.extend(encoders::u32_padded(offset)),
+            _ if padded => bytes.extend(encoders::u64_padded(self.offset)),
+            _ => bytes.extend(encoders::u64(self.offset)),
+        }
+        if self.memory_index != 0 {
+            bytes.extend(leb_u32(self.memory_index, padded));
         }
     }
 }
 
+pub(crate) fn leb_u32(n: u32, padded: bool) -> impl ExactSizeIterator<Item = u8> {
+    let mut buf = [0; 5];
+    let len = if padded {
+        buf = encoders::u32_padded(n);
+        buf.len()
+    } else {
+        leb128::write::unsigned(&mut &mut buf[..], n.into()).unwrap()
+    };
+    <_>::into_iter(buf).take(len)
+}
+
+fn leb_s32(x: i32, padded: bool) -> impl ExactSizeIterator<Item = u8> {
+    let mut buf = [0; 5];
+    let len = if padded {
+        buf = encoders::s32_padded(x);
+        buf.len()
+    } else {
+        leb128::write::signed(&mut &mut buf[..], x.into()).unwrap()
+    };
+    <_>::into_iter(buf).take(len)
+}
+
+fn leb_s64(x: i64, padded: bool) -> impl ExactSizeIterator<Item = u8> {
+    let mut buf = [0; 10];
+    let len = if padded {
+        buf = encoders::s64_padded(x);
+        buf.len()
+    } else {
+        leb128::write::signed(&mut &mut buf[..], x).unwrap()
+    };
+    <_>::into_iter(buf).take(len)
+}
+
 /// Describe an unchecked SIMD lane index.
 pub type Lane = u8;
 
 /// The type for a `block`/`if`/`loop`.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlockType {
     /// `[] -> []`
     Empty,
@@ -290,6 +503,7 @@ impl BlockType {
 
 /// WebAssembly instructions.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[allow(missing_docs, non_camel_case_types)]
 pub enum Instruction<'a> {
@@ -768,10 +982,89 @@ pub enum Instruction<'a> {
     F32x4RelaxedMax,
     F64x2RelaxedMin,
     F64x2RelaxedMax,
+
+    // Tail-call instructions.
+    ReturnCall(u32),
+    ReturnCallIndirect { ty: u32, table: u32 },
+
+    // Atomic instructions.
+    MemoryAtomicNotify(MemArg),
+    MemoryAtomicWait32(MemArg),
+    MemoryAtomicWait64(MemArg),
+    AtomicFence,
+    I32AtomicLoad(MemArg),
+    I64AtomicLoad(MemArg),
+    I32AtomicLoad8U(MemArg),
+    I32AtomicLoad16U(MemArg),
+    I64AtomicLoad8U(MemArg),
+    I64AtomicLoad16U(MemArg),
+    I64AtomicLoad32U(MemArg),
+    I32AtomicStore(MemArg),
+    I64AtomicStore(MemArg),
+    I32AtomicStore8(MemArg),
+    I32AtomicStore16(MemArg),
+    I64AtomicStore8(MemArg),
+    I64AtomicStore16(MemArg),
+    I64AtomicStore32(MemArg),
+    I32AtomicRmwAdd(MemArg),
+    I64AtomicRmwAdd(MemArg),
+    I32AtomicRmw8AddU(MemArg),
+    I32AtomicRmw16AddU(MemArg),
+    I64AtomicRmw8AddU(MemArg),
+    I64AtomicRmw16AddU(MemArg),
+    I64AtomicRmw32AddU(MemArg),
+    I32AtomicRmwSub(MemArg),
+    I64AtomicRmwSub(MemArg),
+    I32AtomicRmw8SubU(MemArg),
+    I32AtomicRmw16SubU(MemArg),
+    I64AtomicRmw8SubU(MemArg),
+    I64AtomicRmw16SubU(MemArg),
+    I64AtomicRmw32SubU(MemArg),
+    I32AtomicRmwAnd(MemArg),
+    I64AtomicRmwAnd(MemArg),
+    I32AtomicRmw8AndU(MemArg),
+    I32AtomicRmw16AndU(MemArg),
+    I64AtomicRmw8AndU(MemArg),
+    I64AtomicRmw16AndU(MemArg),
+    I64AtomicRmw32AndU(MemArg),
+    I32AtomicRmwOr(MemArg),
+    I64AtomicRmwOr(MemArg),
+    I32AtomicRmw8OrU(MemArg),
+    I32AtomicRmw16OrU(MemArg),
+    I64AtomicRmw8OrU(MemArg),
+    I64AtomicRmw16OrU(MemArg),
+    I64AtomicRmw32OrU(MemArg),
+    I32AtomicRmwXor(MemArg),
+    I64AtomicRmwXor(MemArg),
+    I32AtomicRmw8XorU(MemArg),
+    I32AtomicRmw16XorU(MemArg),
+    I64AtomicRmw8XorU(MemArg),
+    I64AtomicRmw16XorU(MemArg),
+    I64AtomicRmw32XorU(MemArg),
+    I32AtomicRmwXchg(MemArg),
+    I64AtomicRmwXchg(MemArg),
+    I32AtomicRmw8XchgU(MemArg),
+    I32AtomicRmw16XchgU(MemArg),
+    I64AtomicRmw8XchgU(MemArg),
+    I64AtomicRmw16XchgU(MemArg),
+    I64AtomicRmw32XchgU(MemArg),
+    I32AtomicRmwCmpxchg(MemArg),
+    I64AtomicRmwCmpxchg(MemArg),
+    I32AtomicRmw8CmpxchgU(MemArg),
+    I32AtomicRmw16CmpxchgU(MemArg),
+    I64AtomicRmw8CmpxchgU(MemArg),
+    I64AtomicRmw16CmpxchgU(MemArg),
+    I64AtomicRmw32CmpxchgU(MemArg),
 }
 
 impl Instruction<'_> {
     pub(crate) fn encode(&self, bytes: &mut Vec<u8>) {
+        self.encode_with(bytes, false)
+    }
+
+    /// Encodes this instruction, padding its LEB128 immediates to their
+    /// maximum width if `padded` is set.
+    pub(crate) fn encode_with(&self, bytes: &mut Vec<u8>, padded: bool) {
         match *self {
             // Control instructions.
             Instruction::Unreachable => bytes.push(0x00),
@@ -795,46 +1088,46 @@ impl Instruction<'_> {
             }
             Instruction::Catch(t) => {
                 bytes.push(0x07);
-                bytes.extend(encoders::u32(t));
+                bytes.extend(leb_u32(t, padded));
             }
             Instruction::Throw(t) => {
                 bytes.push(0x08);
-                bytes.extend(encoders::u32(t));
+                bytes.extend(leb_u32(t, padded));
             }
             Instruction::Rethrow(l) => {
                 bytes.push(0x09);
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::End => bytes.push(0x0B),
             Instruction::Br(l) => {
                 bytes.push(0x0C);
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::BrIf(l) => {
                 bytes.push(0x0D);
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::BrTable(ref ls, l) => {
                 bytes.push(0x0E);
-                bytes.extend(encoders::u32(u32::try_from(ls.len()).unwrap()));
+                bytes.extend(leb_u32(u32::try_from(ls.len()).unwrap(), padded));
                 for l in ls.as_ref() {
-                    bytes.extend(encoders::u32(*l));
+                    bytes.extend(leb_u32(*l, padded));
                 }
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::Return => bytes.push(0x0F),
             Instruction::Call(f) => {
                 bytes.push(0x10);
-                bytes.extend(encoders::u32(f));
+                bytes.extend(leb_u32(f, padded));
             }
             Instruction::CallIndirect { ty, table } => {
                 bytes.push(0x11);
-                bytes.extend(encoders::u32(ty));
-                bytes.extend(encoders::u32(table));
+                bytes.extend(leb_u32(ty, padded));
+                bytes.extend(leb_u32(table, padded));
             }
             Instruction::Delegate(l) => {
                 bytes.push(0x18);
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::CatchAll => {
                 bytes.push(0x19);
@@ -852,165 +1145,167 @@ impl Instruction<'_> {
             // Variable instructions.
             Instruction::LocalGet(l) => {
                 bytes.push(0x20);
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::LocalSet(l) => {
                 bytes.push(0x21);
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::LocalTee(l) => {
                 bytes.push(0x22);
-                bytes.extend(encoders::u32(l));
+                bytes.extend(leb_u32(l, padded));
             }
             Instruction::GlobalGet(g) => {
                 bytes.push(0x23);
-                bytes.extend(encoders::u32(g));
+                bytes.extend(leb_u32(g, padded));
             }
             Instruction::GlobalSet(g) => {
                 bytes.push(0x24);
-                bytes.extend(encoders::u32(g));
+                bytes.extend(leb_u32(g, padded));
             }
             Instruction::TableGet { table } => {
                 bytes.push(0x25);
-                bytes.extend(encoders::u32(table));
+                bytes.extend(leb_u32(table, padded));
             }
             Instruction::TableSet { table } => {
                 bytes.push(0x26);
-                bytes.extend(encoders::u32(table));
+                bytes.extend(leb_u32(table, padded));
             }
 
             // Memory instructions.
             Instruction::I32Load(m) => {
                 bytes.push(0x28);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Load(m) => {
                 bytes.push(0x29);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::F32Load(m) => {
                 bytes.push(0x2A);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::F64Load(m) => {
                 bytes.push(0x2B);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I32Load8_S(m) => {
                 bytes.push(0x2C);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I32Load8_U(m) => {
                 bytes.push(0x2D);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I32Load16_S(m) => {
                 bytes.push(0x2E);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I32Load16_U(m) => {
                 bytes.push(0x2F);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Load8_S(m) => {
                 bytes.push(0x30);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Load8_U(m) => {
                 bytes.push(0x31);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Load16_S(m) => {
                 bytes.push(0x32);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Load16_U(m) => {
                 bytes.push(0x33);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Load32_S(m) => {
                 bytes.push(0x34);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Load32_U(m) => {
                 bytes.push(0x35);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I32Store(m) => {
                 bytes.push(0x36);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Store(m) => {
                 bytes.push(0x37);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::F32Store(m) => {
                 bytes.push(0x38);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::F64Store(m) => {
                 bytes.push(0x39);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I32Store8(m) => {
                 bytes.push(0x3A);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I32Store16(m) => {
                 bytes.push(0x3B);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Store8(m) => {
                 bytes.push(0x3C);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Store16(m) => {
                 bytes.push(0x3D);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
             Instruction::I64Store32(m) => {
                 bytes.push(0x3E);
-                m.encode(bytes);
+                m.encode_with(bytes, padded);
             }
+            // Without multi-memory the memory index of these is a reserved
+            // zero byte, so it's only padded when it couldn't be one anyway.
             Instruction::MemorySize(i) => {
                 bytes.push(0x3F);
-                bytes.extend(encoders::u32(i));
+                bytes.extend(leb_u32(i, padded && i != 0));
             }
             Instruction::MemoryGrow(i) => {
                 bytes.push(0x40);
-                bytes.extend(encoders::u32(i));
+                bytes.extend(leb_u32(i, padded && i != 0));
             }
             Instruction::MemoryInit { mem, data } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(8));
-                bytes.extend(encoders::u32(data));
-                bytes.extend(encoders::u32(mem));
+                bytes.extend(leb_u32(data, padded));
+                bytes.extend(leb_u32(mem, padded));
             }
             Instruction::DataDrop(data) => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(9));
-                bytes.extend(encoders::u32(data));
+                bytes.extend(leb_u32(data, padded));
             }
             Instruction::MemoryCopy { src, dst } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(10));
-                bytes.extend(encoders::u32(dst));
-                bytes.extend(encoders::u32(src));
+                bytes.extend(leb_u32(dst, padded));
+                bytes.extend(leb_u32(src, padded));
             }
             Instruction::MemoryFill(mem) => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(11));
-                bytes.extend(encoders::u32(mem));
+                bytes.extend(leb_u32(mem, padded));
             }
 
             // Numeric instructions.
             Instruction::I32Const(x) => {
                 bytes.push(0x41);
-                bytes.extend(encoders::s32(x));
+                bytes.extend(leb_s32(x, padded));
             }
             Instruction::I64Const(x) => {
                 bytes.push(0x42);
-                bytes.extend(encoders::s64(x));
+                bytes.extend(leb_s64(x, padded));
             }
             Instruction::F32Const(x) => {
                 bytes.push(0x43);
@@ -1192,103 +1487,103 @@ impl Instruction<'_> {
             Instruction::RefIsNull => bytes.push(0xd1),
             Instruction::RefFunc(f) => {
                 bytes.push(0xd2);
-                bytes.extend(encoders::u32(f));
+                bytes.extend(leb_u32(f, padded));
             }
 
             // Bulk memory instructions.
             Instruction::TableInit { segment, table } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(0x0c));
-                bytes.extend(encoders::u32(segment));
-                bytes.extend(encoders::u32(table));
+                bytes.extend(leb_u32(segment, padded));
+                bytes.extend(leb_u32(table, padded));
             }
             Instruction::ElemDrop { segment } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(0x0d));
-                bytes.extend(encoders::u32(segment));
+                bytes.extend(leb_u32(segment, padded));
             }
             Instruction::TableCopy { src, dst } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(0x0e));
-                bytes.extend(encoders::u32(dst));
-                bytes.extend(encoders::u32(src));
+                bytes.extend(leb_u32(dst, padded));
+                bytes.extend(leb_u32(src, padded));
             }
             Instruction::TableGrow { table } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(0x0f));
-                bytes.extend(encoders::u32(table));
+                bytes.extend(leb_u32(table, padded));
             }
             Instruction::TableSize { table } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(0x10));
-                bytes.extend(encoders::u32(table));
+                bytes.extend(leb_u32(table, padded));
             }
             Instruction::TableFill { table } => {
                 bytes.push(0xfc);
                 bytes.extend(encoders::u32(0x11));
-                bytes.extend(encoders::u32(table));
+                bytes.extend(leb_u32(table, padded));
             }
 
             // SIMD instructions.
             Instruction::V128Load { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x00));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load8x8S { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x01));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load8x8U { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x02));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load16x4S { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x03));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load16x4U { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x04));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load32x2S { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x05));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load32x2U { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x06));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load8Splat { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x07));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load16Splat { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x08));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load32Splat { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x09));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load64Splat { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x0A));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Store { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x0B));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Const(x) => {
                 bytes.push(0xFD);
@@ -2157,66 +2452,66 @@ impl Instruction<'_> {
             Instruction::V128Load32Zero { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x5C));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load64Zero { memarg } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x5D));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
             }
             Instruction::V128Load8Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x54));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 16);
                 bytes.push(lane);
             }
             Instruction::V128Load16Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x55));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 8);
                 bytes.push(lane);
             }
             Instruction::V128Load32Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x56));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 4);
                 bytes.push(lane);
             }
             Instruction::V128Load64Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x57));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 2);
                 bytes.push(lane);
             }
             Instruction::V128Store8Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x58));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 16);
                 bytes.push(lane);
             }
             Instruction::V128Store16Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x59));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 8);
                 bytes.push(lane);
             }
             Instruction::V128Store32Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x5A));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 4);
                 bytes.push(lane);
             }
             Instruction::V128Store64Lane { memarg, lane } => {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0x5B));
-                memarg.encode(bytes);
+                memarg.encode_with(bytes, padded);
                 assert!(lane < 2);
                 bytes.push(lane);
             }
@@ -2238,7 +2533,7 @@ impl Instruction<'_> {
             }
             Instruction::I64x2LeS => {
                 bytes.push(0xFD);
-                bytes.extend(encoders::u32(0xDD));
+                bytes.extend(encoders::u32(0xDA));
             }
             Instruction::I64x2GeS => {
                 bytes.push(0xFD);
@@ -2312,6 +2607,354 @@ impl Instruction<'_> {
                 bytes.push(0xFD);
                 bytes.extend(encoders::u32(0xEE));
             }
+
+            // Tail-call instructions.
+            Instruction::ReturnCall(f) => {
+                bytes.push(0x12);
+                bytes.extend(leb_u32(f, padded));
+            }
+            Instruction::ReturnCallIndirect { ty, table } => {
+                bytes.push(0x13);
+                bytes.extend(leb_u32(ty, padded));
+                bytes.extend(leb_u32(table, padded));
+            }
+
+            // Atomic instructions.
+            Instruction::MemoryAtomicNotify(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x00));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::MemoryAtomicWait32(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x01));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::MemoryAtomicWait64(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x02));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::AtomicFence => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x03));
+                bytes.push(0x00);
+            }
+            Instruction::I32AtomicLoad(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x10));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicLoad(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x11));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicLoad8U(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x12));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicLoad16U(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x13));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicLoad8U(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x14));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicLoad16U(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x15));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicLoad32U(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x16));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicStore(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x17));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicStore(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x18));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicStore8(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x19));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicStore16(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x1A));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicStore8(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x1B));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicStore16(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x1C));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicStore32(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x1D));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmwAdd(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x1E));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmwAdd(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x1F));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw8AddU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x20));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw16AddU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x21));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw8AddU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x22));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw16AddU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x23));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw32AddU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x24));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmwSub(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x25));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmwSub(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x26));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw8SubU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x27));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw16SubU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x28));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw8SubU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x29));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw16SubU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x2A));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw32SubU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x2B));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmwAnd(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x2C));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmwAnd(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x2D));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw8AndU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x2E));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw16AndU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x2F));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw8AndU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x30));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw16AndU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x31));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw32AndU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x32));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmwOr(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x33));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmwOr(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x34));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw8OrU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x35));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw16OrU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x36));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw8OrU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x37));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw16OrU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x38));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw32OrU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x39));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmwXor(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x3A));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmwXor(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x3B));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw8XorU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x3C));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw16XorU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x3D));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw8XorU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x3E));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw16XorU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x3F));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw32XorU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x40));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmwXchg(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x41));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmwXchg(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x42));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw8XchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x43));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw16XchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x44));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw8XchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x45));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw16XchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x46));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw32XchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x47));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmwCmpxchg(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x48));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmwCmpxchg(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x49));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw8CmpxchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x4A));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I32AtomicRmw16CmpxchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x4B));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw8CmpxchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x4C));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw16CmpxchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x4D));
+                m.encode_with(bytes, padded);
+            }
+            Instruction::I64AtomicRmw32CmpxchgU(m) => {
+                bytes.push(0xFE);
+                bytes.extend(encoders::u32(0x4E));
+                m.encode_with(bytes, padded);
+            }
         }
     }
 }
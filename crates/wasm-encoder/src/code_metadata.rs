@@ -0,0 +1,114 @@
+use super::*;
+
+/// An encoder for a custom section in the `metadata.code.*` family, such as
+/// `metadata.code.branch_hint`, which attaches metadata to instructions.
+///
+/// See the [branch hinting proposal] for the format of these sections. Code
+/// metadata sections must come before the code section, functions must be
+/// added in increasing index order, and the entries of each function in
+/// increasing offset order.
+///
+/// [branch hinting proposal]: https://github.com/WebAssembly/branch-hinting
+///
+/// # Example
+///
+/// ```
+/// use wasm_encoder::{CodeMetadata, CodeMetadataSection, Function, Instruction};
+///
+/// let mut f = Function::new([]);
+/// f.record_offsets();
+/// f.instruction(&Instruction::I32Const(0));
+/// f.instruction(&Instruction::BrIf(0));
+/// f.instruction(&Instruction::End);
+///
+/// // The `br_if` is unlikely to be taken.
+/// let mut hints = CodeMetadata::new();
+/// hints.branch_hint(f.instruction_offsets().unwrap()[1] as u32, false);
+///
+/// let mut section = CodeMetadataSection::branch_hints();
+/// section.function(0, &hints);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CodeMetadataSection {
+    name: String,
+    bytes: Vec<u8>,
+    num_added: u32,
+}
+
+impl CodeMetadataSection {
+    /// Construct a new encoder for the code metadata section called `name`,
+    /// which should start with `metadata.code.`.
+    pub fn new(name: &str) -> Self {
+        CodeMetadataSection {
+            name: name.to_string(),
+            bytes: Vec::new(),
+            num_added: 0,
+        }
+    }
+
+    /// Construct a new encoder for the `metadata.code.branch_hint` section.
+    pub fn branch_hints() -> Self {
+        Self::new("metadata.code.branch_hint")
+    }
+
+    /// Add the metadata of the function at index `func`.
+    pub fn function(&mut self, func: u32, metadata: &CodeMetadata) -> &mut Self {
+        self.bytes.extend(encoders::u32(func));
+        self.bytes.extend(encoders::u32(metadata.num_added));
+        self.bytes.extend_from_slice(&metadata.bytes);
+        self.num_added += 1;
+        self
+    }
+}
+
+impl Section for CodeMetadataSection {
+    fn id(&self) -> u8 {
+        SectionId::Custom.into()
+    }
+
+    fn encode<S>(&self, sink: &mut S)
+    where
+        S: Extend<u8>,
+    {
+        let mut data = encoders::u32(self.num_added).collect::<Vec<_>>();
+        data.extend_from_slice(&self.bytes);
+        CustomSection {
+            name: &self.name,
+            data: &data,
+        }
+        .encode(sink);
+    }
+}
+
+/// The code metadata of a single function in a [`CodeMetadataSection`].
+#[derive(Clone, Debug, Default)]
+pub struct CodeMetadata {
+    bytes: Vec<u8>,
+    num_added: u32,
+}
+
+impl CodeMetadata {
+    /// Construct a new, empty list of code metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add metadata with the payload `data` to the instruction at `offset`.
+    ///
+    /// The `offset` is relative to the start of the function's body, as
+    /// reported by [`Function::instruction_offsets`].
+    pub fn entry(&mut self, offset: u32, data: &[u8]) -> &mut Self {
+        self.bytes.extend(encoders::u32(offset));
+        self.bytes
+            .extend(encoders::u32(u32::try_from(data.len()).unwrap()));
+        self.bytes.extend_from_slice(data);
+        self.num_added += 1;
+        self
+    }
+
+    /// Add a branch hint, for the `metadata.code.branch_hint` section, to the
+    /// branch instruction at `offset`.
+    pub fn branch_hint(&mut self, offset: u32, likely: bool) -> &mut Self {
+        self.entry(offset, &[likely as u8])
+    }
+}
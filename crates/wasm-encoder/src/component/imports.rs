@@ -15,6 +15,7 @@ use crate::encoders;
 ///         minimum: 1,
 ///         maximum: None,
 ///         memory64: false,
+///         shared: false,
 ///     }
 /// );
 ///
@@ -15,6 +15,7 @@ use super::*;
 ///     minimum: 1,
 ///     maximum: None,
 ///     memory64: false,
+///     shared: false,
 /// });
 ///
 /// let mut data = DataSection::new();
@@ -79,6 +80,28 @@ impl DataSection {
 
     /// Define an active data segment.
     pub fn segment<D>(&mut self, segment: DataSegment<D>) -> &mut Self
+    where
+        D: IntoIterator<Item = u8>,
+        D::IntoIter: ExactSizeIterator,
+    {
+        self.segment_with(segment, false)
+    }
+
+    /// Define a data segment whose memory index and offset expression are
+    /// padded to their maximum width.
+    ///
+    /// A memory index of zero is implied by the segment's flags rather than
+    /// encoded, so it is not padded. See [`Function::instruction_padded`] for
+    /// why this is useful.
+    pub fn segment_padded<D>(&mut self, segment: DataSegment<D>) -> &mut Self
+    where
+        D: IntoIterator<Item = u8>,
+        D::IntoIter: ExactSizeIterator,
+    {
+        self.segment_with(segment, true)
+    }
+
+    fn segment_with<D>(&mut self, segment: DataSegment<D>, padded: bool) -> &mut Self
     where
         D: IntoIterator<Item = u8>,
         D::IntoIter: ExactSizeIterator,
@@ -92,7 +115,7 @@ impl DataSection {
                 offset,
             } => {
                 self.bytes.push(0x00);
-                offset.encode(&mut self.bytes);
+                offset.encode_with(&mut self.bytes, padded);
                 Instruction::End.encode(&mut self.bytes);
             }
             DataSegmentMode::Active {
@@ -100,8 +123,8 @@ impl DataSection {
                 offset,
             } => {
                 self.bytes.push(0x02);
-                self.bytes.extend(encoders::u32(memory_index));
-                offset.encode(&mut self.bytes);
+                self.bytes.extend(code::leb_u32(memory_index, padded));
+                offset.encode_with(&mut self.bytes, padded);
                 Instruction::End.encode(&mut self.bytes);
             }
         }
@@ -50,6 +50,7 @@ pub enum Elements<'a> {
 
 /// An element in a segment in the element section.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Element {
     /// A null reference.
     Null,
@@ -109,6 +110,18 @@ impl ElementSection {
 
     /// Define an element segment.
     pub fn segment<'a>(&mut self, segment: ElementSegment<'a>) -> &mut Self {
+        self.segment_with(segment, false)
+    }
+
+    /// Define an element segment whose table index, offset expression, and
+    /// function indices are padded to their maximum width.
+    ///
+    /// See [`Function::instruction_padded`] for why this is useful.
+    pub fn segment_padded<'a>(&mut self, segment: ElementSegment<'a>) -> &mut Self {
+        self.segment_with(segment, true)
+    }
+
+    fn segment_with(&mut self, segment: ElementSegment<'_>, padded: bool) -> &mut Self {
         let expr_bit = match segment.elements {
             Elements::Expressions(_) => 0b100,
             Elements::Functions(_) => 0b000,
@@ -119,7 +132,7 @@ impl ElementSection {
                 offset,
             } => {
                 self.bytes.extend(encoders::u32(0x00 | expr_bit));
-                offset.encode(&mut self.bytes);
+                offset.encode_with(&mut self.bytes, padded);
                 Instruction::End.encode(&mut self.bytes);
             }
             ElementMode::Passive => {
@@ -135,8 +148,8 @@ impl ElementSection {
                 offset,
             } => {
                 self.bytes.extend(encoders::u32(0x02 | expr_bit));
-                self.bytes.extend(encoders::u32(*i));
-                offset.encode(&mut self.bytes);
+                self.bytes.extend(code::leb_u32(*i, padded));
+                offset.encode_with(&mut self.bytes, padded);
                 Instruction::End.encode(&mut self.bytes);
                 if expr_bit == 0 {
                     self.bytes.push(0x00); // elemkind == funcref
@@ -159,14 +172,16 @@ impl ElementSection {
                 self.bytes
                     .extend(encoders::u32(u32::try_from(fs.len()).unwrap()));
                 for f in fs {
-                    self.bytes.extend(encoders::u32(*f));
+                    self.bytes.extend(code::leb_u32(*f, padded));
                 }
             }
             Elements::Expressions(e) => {
                 self.bytes.extend(encoders::u32(e.len() as u32));
                 for expr in e {
                     match expr {
-                        Element::Func(i) => Instruction::RefFunc(*i).encode(&mut self.bytes),
+                        Element::Func(i) => {
+                            Instruction::RefFunc(*i).encode_with(&mut self.bytes, padded)
+                        }
                         Element::Null => {
                             Instruction::RefNull(segment.element_type).encode(&mut self.bytes)
                         }
@@ -64,6 +64,65 @@ pub fn s64(x: i64) -> impl ExactSizeIterator<Item = u8> {
     <_>::into_iter(buf).take(n)
 }
 
+/// Encode a `u32` as a ULEB128 padded to its maximum width of 5 bytes.
+///
+/// Padded encodings are used for values that are patched in place later,
+/// such as the relocated indices of relocatable object files.
+///
+/// ```
+/// assert_eq!(wasm_encoder::encoders::u32_padded(1), [0x81, 0x80, 0x80, 0x80, 0x00]);
+/// ```
+pub fn u32_padded(n: u32) -> [u8; 5] {
+    let mut buf = [0; 5];
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (n >> (7 * i)) as u8 & 0x7f;
+        if i < 4 {
+            *byte |= 0x80;
+        }
+    }
+    buf
+}
+
+/// Encode a `u64` as a ULEB128 padded to its maximum width of 10 bytes.
+pub fn u64_padded(n: u64) -> [u8; 10] {
+    let mut buf = [0; 10];
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (n >> (7 * i)) as u8 & 0x7f;
+        if i < 9 {
+            *byte |= 0x80;
+        }
+    }
+    buf
+}
+
+/// Encode an `i32` as a SLEB128 padded to its maximum width of 5 bytes.
+///
+/// ```
+/// assert_eq!(wasm_encoder::encoders::s32_padded(-1), [0xff, 0xff, 0xff, 0xff, 0x7f]);
+/// ```
+pub fn s32_padded(x: i32) -> [u8; 5] {
+    let mut buf = [0; 5];
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (x >> (7 * i)) as u8 & 0x7f;
+        if i < 4 {
+            *byte |= 0x80;
+        }
+    }
+    buf
+}
+
+/// Encode an `i64` as a SLEB128 padded to its maximum width of 10 bytes.
+pub fn s64_padded(x: i64) -> [u8; 10] {
+    let mut buf = [0; 10];
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (x >> (7 * i)) as u8 & 0x7f;
+        if i < 9 {
+            *byte |= 0x80;
+        }
+    }
+    buf
+}
+
 /// Encode a length-prefixed UTF-8 string.
 pub fn str(s: &'_ str) -> impl Iterator<Item = u8> + '_ {
     u32(u32::try_from(s.len()).unwrap()).chain(s.as_bytes().iter().copied())
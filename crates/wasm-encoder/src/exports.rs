@@ -55,6 +55,16 @@ impl ExportSection {
         self.num_added += 1;
         self
     }
+
+    /// Define an export whose index is padded to its maximum width.
+    ///
+    /// See [`Function::instruction_padded`] for why this is useful.
+    pub fn export_padded(&mut self, name: &str, export: Export) -> &mut Self {
+        self.bytes.extend(encoders::str(name));
+        export.encode_with(&mut self.bytes, true);
+        self.num_added += 1;
+        self
+    }
 }
 
 impl Section for ExportSection {
@@ -78,6 +88,7 @@ impl Section for ExportSection {
 
 /// A WebAssembly export.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Export {
     /// An export of the `n`th function.
     Function(u32),
@@ -107,6 +118,10 @@ pub enum Export {
 
 impl Export {
     pub(crate) fn encode(&self, bytes: &mut Vec<u8>) {
+        self.encode_with(bytes, false)
+    }
+
+    pub(crate) fn encode_with(&self, bytes: &mut Vec<u8>, padded: bool) {
         let idx = match *self {
             Export::Function(x) => {
                 bytes.push(ItemKind::Function as u8);
@@ -137,7 +152,7 @@ impl Export {
                 x
             }
         };
-        bytes.extend(encoders::u32(idx));
+        bytes.extend(code::leb_u32(idx, padded));
     }
 }
 
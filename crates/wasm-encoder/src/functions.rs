@@ -48,6 +48,16 @@ impl FunctionSection {
         self.num_added += 1;
         self
     }
+
+    /// Define a function that uses the given type, with the type index
+    /// padded to its maximum width.
+    ///
+    /// See [`Function::instruction_padded`] for why this is useful.
+    pub fn function_padded(&mut self, type_index: u32) -> &mut Self {
+        self.bytes.extend(encoders::u32_padded(type_index));
+        self.num_added += 1;
+        self
+    }
 }
 
 impl Section for FunctionSection {
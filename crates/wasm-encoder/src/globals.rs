@@ -52,6 +52,22 @@ impl GlobalSection {
         self
     }
 
+    /// Define a global whose initializer has its LEB128 immediates padded to
+    /// their maximum width.
+    ///
+    /// See [`Function::instruction_padded`] for why this is useful.
+    pub fn global_padded(
+        &mut self,
+        global_type: GlobalType,
+        init_expr: &Instruction<'_>,
+    ) -> &mut Self {
+        global_type.encode(&mut self.bytes);
+        init_expr.encode_with(&mut self.bytes, true);
+        Instruction::End.encode(&mut self.bytes);
+        self.num_added += 1;
+        self
+    }
+
     /// Add a raw byte slice into this code section as a global.
     pub fn raw(&mut self, data: &[u8]) -> &mut Self {
         self.bytes.extend(data);
@@ -81,6 +97,7 @@ impl Section for GlobalSection {
 
 /// A global's type.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GlobalType {
     /// This global's value type.
     pub val_type: ValType,
@@ -16,6 +16,7 @@ use std::convert::TryFrom;
 ///         minimum: 1,
 ///         maximum: None,
 ///         memory64: false,
+///         shared: false,
 ///     }
 /// );
 ///
@@ -52,6 +53,29 @@ impl ImportSection {
         module: &str,
         name: Option<&str>,
         ty: impl Into<EntityType>,
+    ) -> &mut Self {
+        self.import_with(module, name, ty.into(), false)
+    }
+
+    /// Define an import whose type index, if it has one, is padded to its
+    /// maximum width.
+    ///
+    /// See [`Function::instruction_padded`] for why this is useful.
+    pub fn import_padded(
+        &mut self,
+        module: &str,
+        name: Option<&str>,
+        ty: impl Into<EntityType>,
+    ) -> &mut Self {
+        self.import_with(module, name, ty.into(), true)
+    }
+
+    fn import_with(
+        &mut self,
+        module: &str,
+        name: Option<&str>,
+        ty: EntityType,
+        padded: bool,
     ) -> &mut Self {
         self.bytes.extend(encoders::str(module));
         match name {
@@ -61,7 +85,7 @@ impl ImportSection {
                 self.bytes.push(0xff);
             }
         }
-        ty.into().encode(&mut self.bytes);
+        ty.encode_with(&mut self.bytes, padded);
         self.num_added += 1;
         self
     }
@@ -88,6 +112,7 @@ impl Section for ImportSection {
 
 /// The type of an entity.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EntityType {
     /// The `n`th type, which is a function.
     Function(u32),
@@ -135,10 +160,14 @@ impl From<TagType> for EntityType {
 
 impl EntityType {
     pub(crate) fn encode(&self, dst: &mut Vec<u8>) {
+        self.encode_with(dst, false)
+    }
+
+    pub(crate) fn encode_with(&self, dst: &mut Vec<u8>, padded: bool) {
         match self {
             EntityType::Function(x) => {
                 dst.push(0x00);
-                dst.extend(encoders::u32(*x));
+                dst.extend(code::leb_u32(*x, padded));
             }
             EntityType::Table(ty) => {
                 dst.push(0x01);
@@ -154,15 +183,15 @@ impl EntityType {
             }
             EntityType::Tag(ty) => {
                 dst.push(0x04);
-                ty.encode(dst);
+                ty.encode_with(dst, padded);
             }
             EntityType::Module(ty) => {
                 dst.push(0x05);
-                dst.extend(encoders::u32(*ty));
+                dst.extend(code::leb_u32(*ty, padded));
             }
             EntityType::Instance(ty) => {
                 dst.push(0x06);
-                dst.extend(encoders::u32(*ty));
+                dst.extend(code::leb_u32(*ty, padded));
             }
         }
     }
@@ -0,0 +1,744 @@
+//! An owned, in-memory representation of a WebAssembly module.
+//!
+//! [`ModuleIr::from_wasm`] parses a whole module with `wasmparser` and
+//! decodes every section, including all function bodies, into plain owned
+//! data that can be inspected and edited freely. [`ModuleIr::encode`] turns
+//! it back into a binary with this crate's section encoders.
+//!
+//! Instructions and types reuse this crate's own [`Instruction`],
+//! [`ValType`], [`GlobalType`], etc., so a [`ModuleIr`] can be built or
+//! rewritten with the same vocabulary as the rest of `wasm-encoder`. With the
+//! `serde` feature enabled every type in this module implements `Serialize`
+//! and `Deserialize`.
+//!
+//! Only the module, function, and local subsections of the `name` section
+//! are kept. Other custom sections are kept verbatim along with the known
+//! section they followed, and are emitted in the same place. Custom sections
+//! which refer to offsets in the code section, namely `linking`, `reloc.*`,
+//! `metadata.code.*`, and `.debug_*`, would be invalidated by re-encoding, so
+//! they are dropped. Module-linking modules are not supported.
+//!
+//! This module is only available when the `wasmparser` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_encoder::builder::ModuleBuilder;
+//! use wasm_encoder::ir::ModuleIr;
+//! use wasm_encoder::{Function, Instruction, ValType};
+//!
+//! # fn main() -> Result<(), wasm_encoder::ir::Error> {
+//! // A module exporting a function which returns `1`.
+//! let mut builder = ModuleBuilder::new();
+//! let ty = builder.func_type([], [ValType::I32]);
+//! let mut body = Function::new([]);
+//! body.instruction(&Instruction::I32Const(1));
+//! body.instruction(&Instruction::End);
+//! let f = builder.function(ty, body);
+//! builder.export("f", f);
+//! let wasm = builder.finish();
+//!
+//! // Make it return `2` instead.
+//! let mut module = ModuleIr::from_wasm(&wasm)?;
+//! module.functions[0].body[0] = Instruction::I32Const(2);
+//!
+//! let wasm = module.encode();
+//! assert!(wasmparser::validate(&wasm).is_ok());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::reencode::{self, Reencode, RoundtripReencoder};
+use crate::{
+    CodeSection, CustomSection, DataCountSection, DataSection, DataSegment, DataSegmentMode,
+    ElementSection, ElementSegment, Elements, EntityType, ExportSection, FunctionSection,
+    GlobalSection, GlobalType, ImportSection, IndirectNameMap, Instruction, MemorySection,
+    MemoryType, Module, NameMap, NameSection, SectionId, StartSection, TableSection, TableType,
+    TagSection, TagType, TypeSection, ValType,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The error returned when a module can't be converted into a [`ModuleIr`].
+pub type Error = reencode::Error;
+
+/// An owned representation of an entire WebAssembly module.
+///
+/// See the [module documentation](self) for more details.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModuleIr {
+    /// The function types in the type section.
+    pub types: Vec<FuncType>,
+    /// The imports, in order.
+    pub imports: Vec<Import>,
+    /// The functions defined by this module, excluding imported functions.
+    pub functions: Vec<Func>,
+    /// The tables defined by this module, excluding imported tables.
+    pub tables: Vec<TableType>,
+    /// The memories defined by this module, excluding imported memories.
+    pub memories: Vec<MemoryType>,
+    /// The tags defined by this module, excluding imported tags.
+    pub tags: Vec<TagType>,
+    /// The globals defined by this module, excluding imported globals.
+    pub globals: Vec<Global>,
+    /// The exports, in order.
+    pub exports: Vec<Export>,
+    /// The start function, if any.
+    pub start: Option<u32>,
+    /// The element segments.
+    pub elements: Vec<Element>,
+    /// The data segments.
+    pub data: Vec<Data>,
+    /// The contents of the `name` custom section.
+    pub names: Names,
+    /// Custom sections other than `name` and those referring to code offsets,
+    /// in order.
+    pub custom_sections: Vec<Custom>,
+}
+
+/// A function type.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FuncType {
+    /// The parameter types.
+    pub params: Vec<ValType>,
+    /// The result types.
+    pub results: Vec<ValType>,
+}
+
+/// An import.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Import {
+    /// The module being imported from.
+    pub module: String,
+    /// The name of the imported item, or `None` for module-linking's
+    /// single-level imports.
+    pub field: Option<String>,
+    /// The type of the imported item.
+    pub ty: EntityType,
+}
+
+/// A function defined by the module.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Func {
+    /// The index of this function's type.
+    pub ty: u32,
+    /// The function's locals, as run-length encoded `(count, type)` pairs.
+    pub locals: Vec<(u32, ValType)>,
+    /// The function's body, including the final `end`.
+    pub body: Vec<Instruction<'static>>,
+}
+
+/// A global defined by the module.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Global {
+    /// The global's type.
+    pub ty: GlobalType,
+    /// The global's initializer, excluding the final `end`.
+    pub init: Instruction<'static>,
+}
+
+/// An export.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Export {
+    /// The name of the export.
+    pub name: String,
+    /// The exported item.
+    pub kind: crate::Export,
+}
+
+/// An element segment.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Element {
+    /// The segment's mode.
+    pub mode: ElementMode,
+    /// The segment's element type.
+    pub ty: ValType,
+    /// The segment's items.
+    pub items: ElementItems,
+}
+
+/// An element segment's mode.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ElementMode {
+    /// A passive element segment.
+    Passive,
+    /// A declared element segment.
+    Declared,
+    /// An active element segment.
+    Active {
+        /// The table index.
+        table: u32,
+        /// The offset within the table, excluding the final `end`.
+        offset: Instruction<'static>,
+    },
+}
+
+/// The items of an element segment.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ElementItems {
+    /// Function indices.
+    Functions(Vec<u32>),
+    /// Reference expressions.
+    Expressions(Vec<crate::Element>),
+}
+
+/// A data segment.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Data {
+    /// The segment's mode.
+    pub mode: DataMode,
+    /// The segment's contents.
+    pub data: Vec<u8>,
+}
+
+/// A data segment's mode.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DataMode {
+    /// A passive data segment.
+    Passive,
+    /// An active data segment.
+    Active {
+        /// The memory index.
+        memory: u32,
+        /// The offset within the memory, excluding the final `end`.
+        offset: Instruction<'static>,
+    },
+}
+
+/// The subsections of the `name` custom section that are understood.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Names {
+    /// The name of the module.
+    pub module: Option<String>,
+    /// Function names, by function index.
+    pub functions: Vec<(u32, String)>,
+    /// Local names, by function index and then by local index.
+    pub locals: Vec<(u32, Vec<(u32, String)>)>,
+}
+
+impl Names {
+    /// Returns whether no names are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.module.is_none() && self.functions.is_empty() && self.locals.is_empty()
+    }
+}
+
+/// A custom section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Custom {
+    /// The name of the custom section.
+    pub name: String,
+    /// The contents of the custom section.
+    pub data: Vec<u8>,
+    /// The known section this custom section is emitted after, or `None` to
+    /// emit it before all other sections.
+    ///
+    /// It's emitted in the same place even when the known section itself is
+    /// empty and omitted.
+    pub after: Option<SectionId>,
+}
+
+impl ModuleIr {
+    /// Parses and decodes the entirety of `wasm`.
+    ///
+    /// The input is not validated; pass it through `wasmparser`'s validator
+    /// first if that's needed.
+    pub fn from_wasm(wasm: &[u8]) -> Result<ModuleIr, Error> {
+        let mut ir = ModuleIr::default();
+        let mut func_types = Vec::new();
+        let r = &mut RoundtripReencoder;
+        let mut parser = wasmparser::Parser::new(0);
+        let mut data = wasm;
+        let mut last_section = None;
+        loop {
+            let payload = match parser.parse(data, true)? {
+                wasmparser::Chunk::Parsed { consumed, payload } => {
+                    data = &data[consumed..];
+                    payload
+                }
+                // This isn't possible since `eof` is always `true`.
+                wasmparser::Chunk::NeedMoreData(_) => unreachable!(),
+            };
+            if let Some(id) = known_section(&payload) {
+                last_section = Some(id);
+            }
+            match payload {
+                wasmparser::Payload::Version { .. } => {}
+                wasmparser::Payload::TypeSection(section) => {
+                    for ty in section {
+                        match ty? {
+                            wasmparser::TypeDef::Func(ty) => ir.types.push(FuncType {
+                                params: val_types(r, &ty.params)?,
+                                results: val_types(r, &ty.returns)?,
+                            }),
+                            _ => return Err(Error::Unsupported("module-linking types")),
+                        }
+                    }
+                }
+                wasmparser::Payload::ImportSection(section) => {
+                    for import in section {
+                        let import = import?;
+                        ir.imports.push(Import {
+                            module: import.module.to_string(),
+                            field: import.field.map(|s| s.to_string()),
+                            ty: r.entity_type(import.ty)?,
+                        });
+                    }
+                }
+                wasmparser::Payload::FunctionSection(section) => {
+                    for ty in section {
+                        func_types.push(ty?);
+                    }
+                }
+                wasmparser::Payload::TableSection(section) => {
+                    for table in section {
+                        ir.tables.push(r.table_type(table?)?);
+                    }
+                }
+                wasmparser::Payload::MemorySection(section) => {
+                    for memory in section {
+                        ir.memories.push(r.memory_type(memory?)?);
+                    }
+                }
+                wasmparser::Payload::TagSection(section) => {
+                    for tag in section {
+                        ir.tags.push(r.tag_type(tag?)?);
+                    }
+                }
+                wasmparser::Payload::GlobalSection(section) => {
+                    for global in section {
+                        let global = global?;
+                        ir.globals.push(Global {
+                            ty: r.global_type(global.ty)?,
+                            init: r.init_expr(&global.init_expr)?,
+                        });
+                    }
+                }
+                wasmparser::Payload::ExportSection(section) => {
+                    for export in section {
+                        let export = export?;
+                        ir.exports.push(Export {
+                            name: export.field.to_string(),
+                            kind: r.export(export.kind, export.index)?,
+                        });
+                    }
+                }
+                wasmparser::Payload::StartSection { func, .. } => ir.start = Some(func),
+                wasmparser::Payload::ElementSection(section) => {
+                    for element in section {
+                        ir.elements.push(element_segment(r, element?)?);
+                    }
+                }
+                wasmparser::Payload::DataCountSection { .. } => {}
+                wasmparser::Payload::DataSection(section) => {
+                    for data in section {
+                        let data = data?;
+                        let mode = match &data.kind {
+                            wasmparser::DataKind::Active {
+                                memory_index,
+                                init_expr,
+                            } => DataMode::Active {
+                                memory: *memory_index,
+                                offset: r.init_expr(init_expr)?,
+                            },
+                            wasmparser::DataKind::Passive => DataMode::Passive,
+                        };
+                        ir.data.push(Data {
+                            mode,
+                            data: data.data.to_vec(),
+                        });
+                    }
+                }
+                wasmparser::Payload::CodeSectionStart { .. } => {}
+                wasmparser::Payload::CodeSectionEntry(body) => {
+                    let ty = match func_types.get(ir.functions.len()) {
+                        Some(ty) => *ty,
+                        None => return Err(Error::Unsupported("function body without a type")),
+                    };
+                    ir.functions.push(function(r, ty, body)?);
+                }
+                wasmparser::Payload::CustomSection {
+                    name: "name",
+                    data,
+                    data_offset,
+                    ..
+                } => ir.names = names(data, data_offset)?,
+                wasmparser::Payload::CustomSection { name, .. } if refers_to_code_offsets(name) => {
+                }
+                wasmparser::Payload::CustomSection { name, data, .. } => {
+                    ir.custom_sections.push(Custom {
+                        name: name.to_string(),
+                        data: data.to_vec(),
+                        after: last_section,
+                    });
+                }
+                wasmparser::Payload::AliasSection(_)
+                | wasmparser::Payload::InstanceSection(_)
+                | wasmparser::Payload::ModuleSectionStart { .. }
+                | wasmparser::Payload::ModuleSectionEntry { .. } => {
+                    return Err(Error::Unsupported("module-linking sections"))
+                }
+                wasmparser::Payload::UnknownSection { .. } => {
+                    return Err(Error::Unsupported("unknown sections"))
+                }
+                wasmparser::Payload::End => break,
+            }
+        }
+        if func_types.len() != ir.functions.len() {
+            return Err(Error::Unsupported(
+                "function and code section counts differ",
+            ));
+        }
+        Ok(ir)
+    }
+
+    /// Encodes this module into a WebAssembly binary.
+    ///
+    /// Empty sections are omitted. A data count section is emitted whenever
+    /// a function body uses `memory.init` or `data.drop`, which require it.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut module = Module::new();
+        self.encode_custom_sections(&mut module, None);
+
+        if !self.types.is_empty() {
+            let mut types = TypeSection::new();
+            for ty in &self.types {
+                types.function(ty.params.iter().copied(), ty.results.iter().copied());
+            }
+            module.section(&types);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Type));
+
+        if !self.imports.is_empty() {
+            let mut imports = ImportSection::new();
+            for import in &self.imports {
+                imports.import(&import.module, import.field.as_deref(), import.ty);
+            }
+            module.section(&imports);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Import));
+
+        if !self.functions.is_empty() {
+            let mut functions = FunctionSection::new();
+            for func in &self.functions {
+                functions.function(func.ty);
+            }
+            module.section(&functions);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Function));
+
+        if !self.tables.is_empty() {
+            let mut tables = TableSection::new();
+            for table in &self.tables {
+                tables.table(*table);
+            }
+            module.section(&tables);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Table));
+
+        if !self.memories.is_empty() {
+            let mut memories = MemorySection::new();
+            for memory in &self.memories {
+                memories.memory(*memory);
+            }
+            module.section(&memories);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Memory));
+
+        if !self.tags.is_empty() {
+            let mut tags = TagSection::new();
+            for tag in &self.tags {
+                tags.tag(*tag);
+            }
+            module.section(&tags);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Tag));
+
+        if !self.globals.is_empty() {
+            let mut globals = GlobalSection::new();
+            for global in &self.globals {
+                globals.global(global.ty, &global.init);
+            }
+            module.section(&globals);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Global));
+
+        if !self.exports.is_empty() {
+            let mut exports = ExportSection::new();
+            for export in &self.exports {
+                exports.export(&export.name, export.kind);
+            }
+            module.section(&exports);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Export));
+
+        if let Some(function_index) = self.start {
+            module.section(&StartSection { function_index });
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Start));
+
+        if !self.elements.is_empty() {
+            let mut elements = ElementSection::new();
+            for element in &self.elements {
+                let mode = match &element.mode {
+                    ElementMode::Passive => crate::ElementMode::Passive,
+                    ElementMode::Declared => crate::ElementMode::Declared,
+                    ElementMode::Active { table, offset } => crate::ElementMode::Active {
+                        // Use the shorter encoding when it's available.
+                        table: if *table == 0 && element.ty == ValType::FuncRef {
+                            None
+                        } else {
+                            Some(*table)
+                        },
+                        offset,
+                    },
+                };
+                elements.segment(ElementSegment {
+                    mode,
+                    element_type: element.ty,
+                    elements: match &element.items {
+                        ElementItems::Functions(f) => Elements::Functions(f),
+                        ElementItems::Expressions(e) => Elements::Expressions(e),
+                    },
+                });
+            }
+            module.section(&elements);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Element));
+
+        if self.needs_data_count() {
+            module.section(&DataCountSection {
+                count: self.data.len() as u32,
+            });
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::DataCount));
+
+        if !self.functions.is_empty() {
+            let mut code = CodeSection::new();
+            for func in &self.functions {
+                let mut f = crate::Function::new(func.locals.iter().copied());
+                for insn in &func.body {
+                    f.instruction(insn);
+                }
+                code.function(&f);
+            }
+            module.section(&code);
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Code));
+
+        if !self.data.is_empty() {
+            let mut data = DataSection::new();
+            for segment in &self.data {
+                let mode = match &segment.mode {
+                    DataMode::Passive => DataSegmentMode::Passive,
+                    DataMode::Active { memory, offset } => DataSegmentMode::Active {
+                        memory_index: *memory,
+                        offset,
+                    },
+                };
+                data.segment(DataSegment {
+                    mode,
+                    data: segment.data.iter().copied(),
+                });
+            }
+            module.section(&data);
+        }
+
+        if !self.names.is_empty() {
+            module.section(&self.names.encode());
+        }
+        self.encode_custom_sections(&mut module, Some(SectionId::Data));
+
+        module.finish()
+    }
+
+    fn encode_custom_sections(&self, module: &mut Module, after: Option<SectionId>) {
+        for custom in self.custom_sections.iter().filter(|c| c.after == after) {
+            module.section(&CustomSection {
+                name: &custom.name,
+                data: &custom.data,
+            });
+        }
+    }
+
+    fn needs_data_count(&self) -> bool {
+        self.functions.iter().flat_map(|f| &f.body).any(|insn| {
+            matches!(
+                insn,
+                Instruction::MemoryInit { .. } | Instruction::DataDrop(_)
+            )
+        })
+    }
+}
+
+impl Names {
+    fn encode(&self) -> NameSection {
+        let mut section = NameSection::new();
+        if let Some(name) = &self.module {
+            section.module(name);
+        }
+        if !self.functions.is_empty() {
+            section.functions(&name_map(&self.functions));
+        }
+        if !self.locals.is_empty() {
+            let mut locals = IndirectNameMap::new();
+            for (func, names) in &self.locals {
+                locals.append(*func, &name_map(names));
+            }
+            section.locals(&locals);
+        }
+        section
+    }
+}
+
+fn name_map(names: &[(u32, String)]) -> NameMap {
+    let mut map = NameMap::new();
+    for (idx, name) in names {
+        map.append(*idx, name);
+    }
+    map
+}
+
+/// Returns the id of the known section starting with `payload`, if any.
+fn known_section(payload: &wasmparser::Payload<'_>) -> Option<SectionId> {
+    use wasmparser::Payload::*;
+    Some(match payload {
+        TypeSection(_) => SectionId::Type,
+        ImportSection(_) => SectionId::Import,
+        FunctionSection(_) => SectionId::Function,
+        TableSection(_) => SectionId::Table,
+        MemorySection(_) => SectionId::Memory,
+        TagSection(_) => SectionId::Tag,
+        GlobalSection(_) => SectionId::Global,
+        ExportSection(_) => SectionId::Export,
+        StartSection { .. } => SectionId::Start,
+        ElementSection(_) => SectionId::Element,
+        DataCountSection { .. } => SectionId::DataCount,
+        CodeSectionStart { .. } => SectionId::Code,
+        DataSection(_) => SectionId::Data,
+        _ => return None,
+    })
+}
+
+/// Returns whether the custom section `name` refers to offsets within the
+/// code section, which don't survive re-encoding.
+fn refers_to_code_offsets(name: &str) -> bool {
+    name == "linking"
+        || name.starts_with("reloc.")
+        || name.starts_with("metadata.code.")
+        || name.starts_with(".debug_")
+}
+
+fn val_types(r: &mut RoundtripReencoder, tys: &[wasmparser::Type]) -> Result<Vec<ValType>, Error> {
+    tys.iter().map(|ty| r.val_type(*ty)).collect()
+}
+
+fn function(
+    r: &mut RoundtripReencoder,
+    ty: u32,
+    body: wasmparser::FunctionBody<'_>,
+) -> Result<Func, Error> {
+    let locals = body
+        .get_locals_reader()?
+        .into_iter()
+        .map(|local| {
+            let (count, ty) = local?;
+            Ok((count, r.val_type(ty)?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut reader = body.get_operators_reader()?;
+    reader.allow_memarg64(true);
+    let mut insns = Vec::new();
+    while !reader.eof() {
+        insns.push(r.instruction(&reader.read()?)?);
+    }
+    Ok(Func {
+        ty,
+        locals,
+        body: insns,
+    })
+}
+
+fn element_segment(
+    r: &mut RoundtripReencoder,
+    element: wasmparser::Element<'_>,
+) -> Result<Element, Error> {
+    let mode = match &element.kind {
+        wasmparser::ElementKind::Active {
+            table_index,
+            init_expr,
+        } => ElementMode::Active {
+            table: *table_index,
+            offset: r.init_expr(init_expr)?,
+        },
+        wasmparser::ElementKind::Passive => ElementMode::Passive,
+        wasmparser::ElementKind::Declared => ElementMode::Declared,
+    };
+    let mut functions = Vec::new();
+    let mut exprs = Vec::new();
+    let mut reader = element.items.get_items_reader()?;
+    for _ in 0..reader.get_count() {
+        match reader.read()? {
+            wasmparser::ElementItem::Func(idx) => functions.push(idx),
+            wasmparser::ElementItem::Expr(expr) => match r.init_expr(&expr)? {
+                Instruction::RefFunc(idx) => exprs.push(crate::Element::Func(idx)),
+                Instruction::RefNull(_) => exprs.push(crate::Element::Null),
+                _ => return Err(Error::InvalidConstExpr),
+            },
+        }
+    }
+    Ok(Element {
+        mode,
+        ty: r.val_type(element.ty)?,
+        items: if reader.uses_exprs() {
+            ElementItems::Expressions(exprs)
+        } else {
+            ElementItems::Functions(functions)
+        },
+    })
+}
+
+fn names(data: &[u8], offset: usize) -> Result<Names, Error> {
+    fn read_map(mut reader: wasmparser::NamingReader<'_>) -> Result<Vec<(u32, String)>, Error> {
+        let mut names = Vec::new();
+        for _ in 0..reader.get_count() {
+            let naming = reader.read()?;
+            names.push((naming.index, naming.name.to_string()));
+        }
+        Ok(names)
+    }
+
+    let mut names = Names::default();
+    let mut reader = wasmparser::NameSectionReader::new(data, offset)?;
+    while !reader.eof() {
+        match reader.read()? {
+            wasmparser::Name::Module(name) => names.module = Some(name.get_name()?.to_string()),
+            wasmparser::Name::Function(map) => names.functions = read_map(map.get_map()?)?,
+            wasmparser::Name::Local(map) => {
+                let mut reader = map.get_indirect_map()?;
+                for _ in 0..reader.get_indirect_count() {
+                    let naming = reader.read()?;
+                    names
+                        .locals
+                        .push((naming.indirect_index, read_map(naming.get_map()?)?));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(names)
+}
@@ -72,6 +72,7 @@
 
 mod aliases;
 mod code;
+mod code_metadata;
 mod custom;
 mod data;
 mod elements;
@@ -84,6 +85,7 @@ mod linking;
 mod memories;
 mod modules;
 mod names;
+mod producers;
 mod start;
 mod tables;
 mod tags;
@@ -91,6 +93,7 @@ mod types;
 
 pub use aliases::*;
 pub use code::*;
+pub use code_metadata::*;
 pub use custom::*;
 pub use data::*;
 pub use elements::*;
@@ -103,14 +106,24 @@ pub use linking::*;
 pub use memories::*;
 pub use modules::*;
 pub use names::*;
+pub use producers::*;
 pub use start::*;
 pub use tables::*;
 pub use tags::*;
 pub use types::*;
 
 pub mod adapter;
+pub mod builder;
+#[cfg(feature = "wasmparser")]
+pub mod checked;
 pub mod component;
 pub mod encoders;
+#[cfg(feature = "wasmparser")]
+pub mod ir;
+#[cfg(feature = "wasmparser")]
+pub mod reencode;
+#[cfg(feature = "wasmparser")]
+pub mod surgery;
 
 use std::convert::TryFrom;
 
@@ -214,6 +227,7 @@ impl Default for Module {
 /// Useful for implementing the `Section` trait, or for setting
 /// `RawSection::id`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 #[allow(missing_docs)]
 pub enum SectionId {
@@ -245,6 +259,7 @@ impl From<SectionId> for u8 {
 
 /// The type of a value.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ValType {
     /// The `i32` type.
@@ -47,11 +47,38 @@ impl LinkingSection {
         Self::default()
     }
 
-    // TODO: `fn segment_info` for the `WASM_SEGMENT_INFO` linking subsection.
+    /// Add a segment info subsection.
+    pub fn segment_info(&mut self, segment_info: &SegmentInfo) -> &mut Self {
+        encode_subsection(
+            WASM_SEGMENT_INFO,
+            segment_info.num_added,
+            &segment_info.bytes,
+            &mut self.bytes,
+        );
+        self
+    }
 
-    // TODO: `fn init_funcs` for the `WASM_INIT_FUNCS` linking subsection.
+    /// Add an init functions subsection.
+    pub fn init_funcs(&mut self, init_funcs: &InitFuncs) -> &mut Self {
+        encode_subsection(
+            WASM_INIT_FUNCS,
+            init_funcs.num_added,
+            &init_funcs.bytes,
+            &mut self.bytes,
+        );
+        self
+    }
 
-    // TODO: `fn comdat_info` for the `WASM_COMDAT_INFO` linking subsection.
+    /// Add a COMDAT info subsection.
+    pub fn comdat_info(&mut self, comdat_info: &ComdatInfo) -> &mut Self {
+        encode_subsection(
+            WASM_COMDAT_INFO,
+            comdat_info.num_added,
+            &comdat_info.bytes,
+            &mut self.bytes,
+        );
+        self
+    }
 
     /// Add a symbol table subsection.
     pub fn symbol_table(&mut self, symbol_table: &SymbolTable) -> &mut Self {
@@ -86,14 +113,23 @@ impl Section for LinkingSection {
     }
 }
 
-#[allow(unused)]
 const WASM_SEGMENT_INFO: u8 = 5;
-#[allow(unused)]
 const WASM_INIT_FUNCS: u8 = 6;
-#[allow(unused)]
 const WASM_COMDAT_INFO: u8 = 7;
 const WASM_SYMBOL_TABLE: u8 = 8;
 
+/// Encodes a linking subsection made up of `count` entries in `entries`.
+fn encode_subsection(id: u8, count: u32, entries: &[u8], bytes: &mut Vec<u8>) {
+    let count = encoders::u32(count);
+    let payload_len = count.len() + entries.len();
+    bytes.extend(
+        std::iter::once(id)
+            .chain(encoders::u32(payload_len.try_into().unwrap()))
+            .chain(count)
+            .chain(entries.iter().copied()),
+    );
+}
+
 /// A subsection of the [linking custom section][crate::LinkingSection] that
 /// provides extra information about the symbols present in this Wasm object
 /// file.
@@ -106,9 +142,7 @@ pub struct SymbolTable {
 const SYMTAB_FUNCTION: u32 = 0;
 const SYMTAB_DATA: u32 = 1;
 const SYMTAB_GLOBAL: u32 = 2;
-#[allow(unused)]
 const SYMTAB_SECTION: u32 = 3;
-#[allow(unused)]
 const SYMTAB_TAG: u32 = 4;
 const SYMTAB_TABLE: u32 = 5;
 
@@ -161,7 +195,25 @@ impl SymbolTable {
         self
     }
 
-    // TODO: tags
+    /// Define a tag symbol in this symbol table.
+    ///
+    /// The `name` must be omitted if `index` references an imported tag and
+    /// the `WASM_SYM_EXPLICIT_NAME` flag is not set.
+    pub fn tag(&mut self, flags: u32, index: u32, name: Option<&str>) -> &mut Self {
+        self.bytes.extend(
+            encoders::u32(SYMTAB_TAG)
+                .chain(encoders::u32(flags))
+                .chain(encoders::u32(index)),
+        );
+        if let Some(name) = name {
+            self.bytes.extend(
+                encoders::u32(name.len().try_into().unwrap())
+                    .chain(name.as_bytes().iter().copied()),
+            );
+        }
+        self.num_added += 1;
+        self
+    }
 
     /// Define a table symbol in this symbol table.
     ///
@@ -207,18 +259,22 @@ impl SymbolTable {
         self
     }
 
-    // TODO: sections
+    /// Define a symbol for the `section`th section of the module.
+    ///
+    /// Section symbols are always local and are used by relocations, such as
+    /// debug info relocations, that refer to offsets within a section.
+    pub fn section(&mut self, flags: u32, section: u32) -> &mut Self {
+        self.bytes.extend(
+            encoders::u32(SYMTAB_SECTION)
+                .chain(encoders::u32(flags))
+                .chain(encoders::u32(section)),
+        );
+        self.num_added += 1;
+        self
+    }
 
     fn encode(&self, bytes: &mut Vec<u8>) {
-        let num_added = encoders::u32(self.num_added);
-        let num_added_len = num_added.len();
-        let payload_len = num_added_len + self.bytes.len();
-        bytes.extend(
-            std::iter::once(WASM_SYMBOL_TABLE)
-                .chain(encoders::u32(payload_len.try_into().unwrap()))
-                .chain(num_added)
-                .chain(self.bytes.iter().copied()),
-        );
+        encode_subsection(WASM_SYMBOL_TABLE, self.num_added, &self.bytes, bytes);
     }
 }
 
@@ -288,3 +344,278 @@ pub struct DataSymbolDefinition {
     /// size.
     pub size: u32,
 }
+
+/// A subsection of the [linking custom section][crate::LinkingSection] that
+/// provides extra information about the data segments in this Wasm object
+/// file.
+///
+/// Segments are described in the same order as the module's data segments.
+#[derive(Clone, Debug, Default)]
+pub struct SegmentInfo {
+    bytes: Vec<u8>,
+    num_added: u32,
+}
+
+impl SegmentInfo {
+    /// Construct a new segment info subsection encoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Describe the next data segment.
+    ///
+    /// The `alignment` is the segment's required alignment, as a power of
+    /// two, and `flags` is a combination of the `WASM_SEG_FLAG_*` constants.
+    pub fn segment(&mut self, name: &str, alignment: u32, flags: u32) -> &mut Self {
+        self.bytes.extend(
+            encoders::str(name)
+                .chain(encoders::u32(alignment))
+                .chain(encoders::u32(flags)),
+        );
+        self.num_added += 1;
+        self
+    }
+}
+
+/// # Segment flags.
+impl SegmentInfo {
+    /// The segment contains only null-terminated strings, which allows the
+    /// linker to merge them.
+    pub const WASM_SEG_FLAG_STRINGS: u32 = 0x1;
+
+    /// The segment contains thread-local data.
+    pub const WASM_SEG_FLAG_TLS: u32 = 0x2;
+}
+
+/// A subsection of the [linking custom section][crate::LinkingSection] that
+/// lists the functions to call when the linked module is instantiated.
+#[derive(Clone, Debug, Default)]
+pub struct InitFuncs {
+    bytes: Vec<u8>,
+    num_added: u32,
+}
+
+impl InitFuncs {
+    /// Construct a new init functions subsection encoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an init function.
+    ///
+    /// The `symbol_index` refers to a function symbol in the symbol table.
+    /// Init functions are called in order of increasing `priority`.
+    pub fn init_func(&mut self, priority: u32, symbol_index: u32) -> &mut Self {
+        self.bytes
+            .extend(encoders::u32(priority).chain(encoders::u32(symbol_index)));
+        self.num_added += 1;
+        self
+    }
+}
+
+/// A subsection of the [linking custom section][crate::LinkingSection] that
+/// groups items into COMDATs, of which the linker keeps only a single copy
+/// per name.
+#[derive(Clone, Debug, Default)]
+pub struct ComdatInfo {
+    bytes: Vec<u8>,
+    num_added: u32,
+}
+
+impl ComdatInfo {
+    /// Construct a new COMDAT info subsection encoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define a COMDAT named `name` containing the given items.
+    pub fn comdat(&mut self, name: &str, symbols: &[ComdatSymbol]) -> &mut Self {
+        // The flags field is currently always zero.
+        self.bytes.extend(
+            encoders::str(name)
+                .chain(encoders::u32(0))
+                .chain(encoders::u32(symbols.len().try_into().unwrap())),
+        );
+        for symbol in symbols {
+            self.bytes.push(symbol.kind as u8);
+            self.bytes.extend(encoders::u32(symbol.index));
+        }
+        self.num_added += 1;
+        self
+    }
+}
+
+/// An item that is part of a COMDAT.
+#[derive(Clone, Copy, Debug)]
+pub struct ComdatSymbol {
+    /// The kind of item.
+    pub kind: ComdatSymbolKind,
+    /// The index of the item in its index space, or the index of the section
+    /// for [`ComdatSymbolKind::Section`].
+    pub index: u32,
+}
+
+/// The kinds of items that may be part of a COMDAT.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComdatSymbolKind {
+    Data = 0,
+    Function = 1,
+    Global = 2,
+    Tag = 3,
+    Table = 4,
+    Section = 5,
+}
+
+/// An encoder for a [relocation custom
+/// section](https://github.com/WebAssembly/tool-conventions/blob/master/Linking.md#relocation-sections).
+///
+/// Each relocation section applies to a single other section of the module,
+/// and is conventionally named `reloc.` followed by that section's name, such
+/// as `reloc.CODE` or `reloc.DATA`. Relocation sections must come after the
+/// [linking section][crate::LinkingSection] and after the section they apply
+/// to.
+///
+/// # Example
+///
+/// ```
+/// use wasm_encoder::{RelocSection, RelocationType};
+///
+/// // Relocations for the code section, which is the module's fourth section.
+/// let mut reloc = RelocSection::new("reloc.CODE", 3);
+///
+/// // The `call` at byte offset 6 of the code section refers to the function
+/// // described by symbol 0.
+/// reloc.relocation(RelocationType::FunctionIndexLeb, 6, 0, 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RelocSection {
+    name: String,
+    section_index: u32,
+    bytes: Vec<u8>,
+    num_added: u32,
+}
+
+impl RelocSection {
+    /// Construct a new encoder for a relocation section named `name`, which
+    /// applies to the `section_index`th section of the module.
+    pub fn new(name: &str, section_index: u32) -> Self {
+        RelocSection {
+            name: name.to_string(),
+            section_index,
+            bytes: Vec::new(),
+            num_added: 0,
+        }
+    }
+
+    /// Add a relocation.
+    ///
+    /// The `offset` is relative to the start of the target section's
+    /// contents, and `index` is a symbol index for most relocation types, or
+    /// a type index for [`RelocationType::TypeIndexLeb`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `addend` is non-zero for a relocation type that has no
+    /// addend; see [`RelocationType::has_addend`].
+    pub fn relocation(
+        &mut self,
+        ty: RelocationType,
+        offset: u32,
+        index: u32,
+        addend: i64,
+    ) -> &mut Self {
+        self.bytes.push(ty as u8);
+        self.bytes
+            .extend(encoders::u32(offset).chain(encoders::u32(index)));
+        if ty.has_addend() {
+            self.bytes.extend(encoders::s64(addend));
+        } else {
+            assert_eq!(addend, 0, "relocation type {:?} has no addend", ty);
+        }
+        self.num_added += 1;
+        self
+    }
+}
+
+impl Section for RelocSection {
+    fn id(&self) -> u8 {
+        SectionId::Custom.into()
+    }
+
+    fn encode<S>(&self, sink: &mut S)
+    where
+        S: Extend<u8>,
+    {
+        let mut data = Vec::new();
+        data.extend(encoders::u32(self.section_index).chain(encoders::u32(self.num_added)));
+        data.extend(&self.bytes);
+        CustomSection {
+            name: &self.name,
+            data: &data,
+        }
+        .encode(sink);
+    }
+}
+
+/// The kinds of relocations.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelocationType {
+    FunctionIndexLeb = 0,
+    TableIndexSleb = 1,
+    TableIndexI32 = 2,
+    MemoryAddrLeb = 3,
+    MemoryAddrSleb = 4,
+    MemoryAddrI32 = 5,
+    TypeIndexLeb = 6,
+    GlobalIndexLeb = 7,
+    FunctionOffsetI32 = 8,
+    SectionOffsetI32 = 9,
+    TagIndexLeb = 10,
+    MemoryAddrRelSleb = 11,
+    TableIndexRelSleb = 12,
+    GlobalIndexI32 = 13,
+    MemoryAddrLeb64 = 14,
+    MemoryAddrSleb64 = 15,
+    MemoryAddrI64 = 16,
+    MemoryAddrRelSleb64 = 17,
+    TableIndexSleb64 = 18,
+    TableIndexI64 = 19,
+    TableNumberLeb = 20,
+    MemoryAddrTlsSleb = 21,
+    FunctionOffsetI64 = 22,
+    MemoryAddrLocrelI32 = 23,
+    TableIndexRelSleb64 = 24,
+    MemoryAddrTlsSleb64 = 25,
+    FunctionIndexI32 = 26,
+}
+
+impl RelocationType {
+    /// Whether relocations of this type carry an addend.
+    ///
+    /// Only memory address, function offset, and section offset relocations
+    /// have an addend.
+    pub fn has_addend(self) -> bool {
+        use RelocationType::*;
+        matches!(
+            self,
+            MemoryAddrLeb
+                | MemoryAddrSleb
+                | MemoryAddrI32
+                | FunctionOffsetI32
+                | SectionOffsetI32
+                | MemoryAddrRelSleb
+                | MemoryAddrLeb64
+                | MemoryAddrSleb64
+                | MemoryAddrI64
+                | MemoryAddrRelSleb64
+                | MemoryAddrTlsSleb
+                | FunctionOffsetI64
+                | MemoryAddrLocrelI32
+                | MemoryAddrTlsSleb64
+        )
+    }
+}
@@ -12,6 +12,7 @@ use super::*;
 ///     minimum: 1,
 ///     maximum: None,
 ///     memory64: false,
+///     shared: false,
 /// });
 ///
 /// let mut module = Module::new();
@@ -70,6 +71,7 @@ impl Section for MemorySection {
 
 /// A memory's type.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryType {
     /// Minimum size, in pages, of this memory
     pub minimum: u64,
@@ -77,6 +79,8 @@ pub struct MemoryType {
     pub maximum: Option<u64>,
     /// Whether or not this is a 64-bit memory.
     pub memory64: bool,
+    /// Whether or not this memory is shared, as part of the threads proposal.
+    pub shared: bool,
 }
 
 impl MemoryType {
@@ -85,6 +89,9 @@ impl MemoryType {
         if self.maximum.is_some() {
             flags |= 0b001;
         }
+        if self.shared {
+            flags |= 0b010;
+        }
         if self.memory64 {
             flags |= 0b100;
         }
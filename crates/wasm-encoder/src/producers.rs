@@ -0,0 +1,275 @@
+use super::*;
+
+/// An encoder for the custom `producers` section.
+///
+/// This section records the languages, tools, and SDKs that produced a
+/// module. See the [tool conventions] for its format.
+///
+/// [tool conventions]: https://github.com/WebAssembly/tool-conventions/blob/main/ProducersSection.md
+///
+/// # Example
+///
+/// ```
+/// use wasm_encoder::{Module, ProducersField, ProducersSection};
+///
+/// let mut languages = ProducersField::new();
+/// languages.value("Rust", "1.58.0");
+///
+/// let mut processed_by = ProducersField::new();
+/// processed_by.value("rustc", "1.58.0 (02072b482 2022-01-11)");
+/// processed_by.value("wasm-opt", "105");
+///
+/// let mut producers = ProducersSection::new();
+/// producers.field("language", &languages);
+/// producers.field("processed-by", &processed_by);
+///
+/// let mut module = Module::new();
+/// module.section(&producers);
+///
+/// let wasm_bytes = module.finish();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ProducersSection {
+    bytes: Vec<u8>,
+    num_fields: u32,
+}
+
+impl ProducersSection {
+    /// Creates a new, empty `producers` section.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a field named `name` with the given `values` to this section.
+    ///
+    /// Each field name should appear at most once in a section, and the
+    /// well-known field names are `language`, `processed-by`, and `sdk`.
+    pub fn field(&mut self, name: &str, values: &ProducersField) -> &mut Self {
+        self.bytes.extend(encoders::str(name));
+        values.encode(&mut self.bytes);
+        self.num_fields += 1;
+        self
+    }
+}
+
+impl Section for ProducersSection {
+    fn id(&self) -> u8 {
+        SectionId::Custom.into()
+    }
+
+    fn encode<S>(&self, sink: &mut S)
+    where
+        S: Extend<u8>,
+    {
+        let mut data = encoders::u32(self.num_fields).collect::<Vec<_>>();
+        data.extend_from_slice(&self.bytes);
+        CustomSection {
+            name: "producers",
+            data: &data,
+        }
+        .encode(sink);
+    }
+}
+
+/// The values of a single field in a [`ProducersSection`].
+#[derive(Clone, Debug, Default)]
+pub struct ProducersField {
+    bytes: Vec<u8>,
+    num_values: u32,
+}
+
+impl ProducersField {
+    /// Creates a new field with no values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a value with the given `name` and `version` to this field.
+    ///
+    /// The `version` may be empty if it is unknown.
+    pub fn value(&mut self, name: &str, version: &str) -> &mut Self {
+        self.bytes.extend(encoders::str(name));
+        self.bytes.extend(encoders::str(version));
+        self.num_values += 1;
+        self
+    }
+
+    fn encode(&self, sink: &mut Vec<u8>) {
+        sink.extend(encoders::u32(self.num_values));
+        sink.extend_from_slice(&self.bytes);
+    }
+}
+
+/// The contents of a `producers` section, which can be merged with entries
+/// from other sources before being encoded.
+///
+/// Unlike [`ProducersSection`], which encodes exactly the fields and values
+/// it is given, `Producers` keeps each value name unique within its field:
+/// adding a value which is already present replaces its version. Fields and
+/// values otherwise keep the order in which they were first added.
+///
+/// # Example
+///
+/// ```
+/// use wasm_encoder::{Module, Producers};
+///
+/// let mut producers = Producers::new();
+/// producers
+///     .add_language("Rust", "")
+///     .add_processed_by("rustc", "1.58.0")
+///     .add_processed_by("rustc", "1.59.0");
+/// assert_eq!(producers.get("processed-by"), [("rustc".to_string(), "1.59.0".to_string())]);
+///
+/// let mut module = Module::new();
+/// module.section(&producers.section());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Producers {
+    fields: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Producers {
+    /// Creates a new, empty set of producers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether no values have been added.
+    pub fn is_empty(&self) -> bool {
+        self.fields.iter().all(|(_, values)| values.is_empty())
+    }
+
+    /// Adds a value with the given `name` and `version` to `field`.
+    ///
+    /// If `field` already has a value called `name` then its version is
+    /// replaced.
+    pub fn add(&mut self, field: &str, name: &str, version: &str) -> &mut Self {
+        let values = match self.fields.iter().position(|(f, _)| f == field) {
+            Some(i) => &mut self.fields[i].1,
+            None => {
+                self.fields.push((field.to_string(), Vec::new()));
+                &mut self.fields.last_mut().unwrap().1
+            }
+        };
+        match values.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = version.to_string(),
+            None => values.push((name.to_string(), version.to_string())),
+        }
+        self
+    }
+
+    /// Adds a value to the `language` field, for a source language of the
+    /// module.
+    pub fn add_language(&mut self, name: &str, version: &str) -> &mut Self {
+        self.add("language", name, version)
+    }
+
+    /// Adds a value to the `processed-by` field, for a tool which produced
+    /// or transformed the module.
+    pub fn add_processed_by(&mut self, name: &str, version: &str) -> &mut Self {
+        self.add("processed-by", name, version)
+    }
+
+    /// Adds a value to the `sdk` field, for an SDK the module was built with.
+    pub fn add_sdk(&mut self, name: &str, version: &str) -> &mut Self {
+        self.add("sdk", name, version)
+    }
+
+    /// Adds every value in `other` to `self`, as if with [`Producers::add`].
+    pub fn merge(&mut self, other: &Producers) -> &mut Self {
+        for (field, values) in other.fields.iter() {
+            for (name, version) in values {
+                self.add(field, name, version);
+            }
+        }
+        self
+    }
+
+    /// Returns the `(name, version)` values of `field`, which is empty if the
+    /// field isn't present.
+    pub fn get(&self, field: &str) -> &[(String, String)] {
+        self.fields
+            .iter()
+            .find(|(f, _)| f == field)
+            .map(|(_, values)| &values[..])
+            .unwrap_or(&[])
+    }
+
+    /// Returns the names of the fields and their `(name, version)` values, in
+    /// order.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &[(String, String)])> + '_ {
+        self.fields
+            .iter()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(field, values)| (&field[..], &values[..]))
+    }
+
+    /// Encodes these producers into a `producers` section.
+    pub fn section(&self) -> ProducersSection {
+        let mut section = ProducersSection::new();
+        for (field, values) in self.fields() {
+            let mut encoded = ProducersField::new();
+            for (name, version) in values {
+                encoded.value(name, version);
+            }
+            section.field(field, &encoded);
+        }
+        section
+    }
+
+    /// Reads the contents of a `producers` section.
+    #[cfg(feature = "wasmparser")]
+    pub fn from_reader(reader: wasmparser::ProducersSectionReader<'_>) -> wasmparser::Result<Self> {
+        let mut producers = Producers::new();
+        for field in reader {
+            let field = field?;
+            for value in field.get_producer_field_values_reader()? {
+                let value = value?;
+                producers.add(field.name, value.name, value.version);
+            }
+        }
+        Ok(producers)
+    }
+
+    /// Reads the `producers` sections of the module `wasm`.
+    ///
+    /// Only the outermost module is inspected, and if it has more than one
+    /// `producers` section then they are merged in order.
+    #[cfg(feature = "wasmparser")]
+    pub fn from_wasm(wasm: &[u8]) -> wasmparser::Result<Self> {
+        let mut producers = Producers::new();
+        let mut depth = 0;
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            match payload? {
+                wasmparser::Payload::Version { .. } => depth += 1,
+                wasmparser::Payload::End => depth -= 1,
+                wasmparser::Payload::CustomSection {
+                    name: "producers",
+                    data,
+                    data_offset,
+                    ..
+                } if depth == 1 => {
+                    let reader = wasmparser::ProducersSectionReader::new(data, data_offset)?;
+                    producers.merge(&Producers::from_reader(reader)?);
+                }
+                _ => {}
+            }
+        }
+        Ok(producers)
+    }
+
+    /// Merges these producers into those already recorded in the module
+    /// `wasm`, returning the edited module.
+    ///
+    /// Any existing `producers` sections are replaced by a single merged one
+    /// at the end of the module. The rest of the module is left as-is.
+    #[cfg(feature = "wasmparser")]
+    pub fn add_to_module(&self, wasm: &[u8]) -> Result<Vec<u8>, surgery::Error> {
+        let mut merged = Producers::from_wasm(wasm)?;
+        merged.merge(self);
+        let mut surgery = surgery::ModuleSurgery::new(wasm)?;
+        surgery.remove_custom_sections("producers");
+        surgery.add_section(&merged.section());
+        surgery.finish()
+    }
+}
@@ -0,0 +1,1989 @@
+//! Conversion from [`wasmparser`] structures to `wasm-encoder` structures.
+//!
+//! The [`Reencode`] trait provides a default translation of every section,
+//! item, and instruction that `wasmparser` can parse into the equivalent
+//! `wasm-encoder` builder calls. Each method can be overridden individually,
+//! which is most useful for the `*_index` hooks: overriding, for example,
+//! [`Reencode::function_index`] renumbers every reference to a function in
+//! the module, including those in instructions, exports, element segments,
+//! and the start section.
+//!
+//! The default implementation of each method is also available as a free
+//! function in [`utils`] so overrides can defer to the default behavior.
+//!
+//! This module is only available when the `wasmparser` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_encoder::reencode::{Error, Reencode};
+//!
+//! /// Shifts every function index up by one, to make room for a new import.
+//! struct ShiftFunctions;
+//!
+//! impl Reencode for ShiftFunctions {
+//!     type Error = std::convert::Infallible;
+//!
+//!     fn function_index(&mut self, func: u32) -> Result<u32, Error> {
+//!         Ok(func + 1)
+//!     }
+//! }
+//!
+//! # fn main() -> Result<(), Error> {
+//! let wasm = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+//! let mut module = wasm_encoder::Module::new();
+//! ShiftFunctions.parse_core_module(&mut module, wasmparser::Parser::new(0), &wasm)?;
+//! assert_eq!(module.finish(), wasm);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::*;
+use std::convert::Infallible;
+use std::fmt;
+
+/// A translation from `wasmparser` structures to `wasm-encoder` structures.
+///
+/// All methods have default implementations which translate items as-is,
+/// deferring to the functions of the same name in [`utils`]. Implementors
+/// override the methods they're interested in, such as the `*_index` methods
+/// to remap indices.
+pub trait Reencode {
+    /// The error type returned by user-defined hooks, wrapped in
+    /// [`Error::UserError`].
+    type Error;
+
+    /// Remaps a type index.
+    fn type_index(&mut self, ty: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(ty)
+    }
+
+    /// Remaps a function index.
+    fn function_index(&mut self, func: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(func)
+    }
+
+    /// Remaps a table index.
+    fn table_index(&mut self, table: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(table)
+    }
+
+    /// Remaps a memory index.
+    fn memory_index(&mut self, memory: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(memory)
+    }
+
+    /// Remaps a global index.
+    fn global_index(&mut self, global: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(global)
+    }
+
+    /// Remaps a tag index.
+    fn tag_index(&mut self, tag: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(tag)
+    }
+
+    /// Remaps a data segment index.
+    fn data_index(&mut self, data: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(data)
+    }
+
+    /// Remaps an element segment index.
+    fn element_index(&mut self, element: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(element)
+    }
+
+    /// Remaps a module index in the module linking proposal.
+    fn module_index(&mut self, module: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(module)
+    }
+
+    /// Remaps an instance index in the module linking proposal.
+    fn instance_index(&mut self, instance: u32) -> Result<u32, Error<Self::Error>> {
+        Ok(instance)
+    }
+
+    /// Remaps the index of a type aliased from an enclosing module.
+    fn outer_type_index(
+        &mut self,
+        relative_depth: u32,
+        ty: u32,
+    ) -> Result<u32, Error<Self::Error>> {
+        let _ = relative_depth;
+        Ok(ty)
+    }
+
+    /// Remaps the index of a module aliased from an enclosing module.
+    fn outer_module_index(
+        &mut self,
+        relative_depth: u32,
+        module: u32,
+    ) -> Result<u32, Error<Self::Error>> {
+        let _ = relative_depth;
+        Ok(module)
+    }
+
+    /// Translates a value type.
+    fn val_type(&mut self, ty: wasmparser::Type) -> Result<ValType, Error<Self::Error>> {
+        utils::val_type(self, ty)
+    }
+
+    /// Translates the type of a table, including its element type.
+    fn table_type(&mut self, ty: wasmparser::TableType) -> Result<TableType, Error<Self::Error>> {
+        utils::table_type(self, ty)
+    }
+
+    /// Translates the type of a memory.
+    fn memory_type(
+        &mut self,
+        ty: wasmparser::MemoryType,
+    ) -> Result<MemoryType, Error<Self::Error>> {
+        utils::memory_type(self, ty)
+    }
+
+    /// Translates the type of a global, including its value type.
+    fn global_type(
+        &mut self,
+        ty: wasmparser::GlobalType,
+    ) -> Result<GlobalType, Error<Self::Error>> {
+        utils::global_type(self, ty)
+    }
+
+    /// Translates the type of a tag, remapping its function type index.
+    fn tag_type(&mut self, ty: wasmparser::TagType) -> Result<TagType, Error<Self::Error>> {
+        utils::tag_type(self, ty)
+    }
+
+    /// Translates the type of an imported item.
+    fn entity_type(
+        &mut self,
+        ty: wasmparser::ImportSectionEntryType,
+    ) -> Result<EntityType, Error<Self::Error>> {
+        utils::entity_type(self, ty)
+    }
+
+    /// Translates the kind of an exported or aliased item.
+    fn item_kind(
+        &mut self,
+        kind: wasmparser::ExternalKind,
+    ) -> Result<ItemKind, Error<Self::Error>> {
+        utils::item_kind(self, kind)
+    }
+
+    /// Translates the kind and index of an exported item, remapping the
+    /// index according to its kind.
+    fn export(
+        &mut self,
+        kind: wasmparser::ExternalKind,
+        index: u32,
+    ) -> Result<Export, Error<Self::Error>> {
+        utils::export(self, kind, index)
+    }
+
+    /// Translates the type of a `block`, `loop`, `if`, or `try`.
+    fn block_type(
+        &mut self,
+        ty: wasmparser::TypeOrFuncType,
+    ) -> Result<BlockType, Error<Self::Error>> {
+        utils::block_type(self, ty)
+    }
+
+    /// Translates the immediate of a memory instruction, remapping its
+    /// memory index.
+    fn memarg(
+        &mut self,
+        memarg: &wasmparser::MemoryImmediate,
+    ) -> Result<MemArg, Error<Self::Error>> {
+        utils::memarg(self, memarg)
+    }
+
+    /// Translates a single instruction, remapping any indices it contains.
+    fn instruction(
+        &mut self,
+        op: &wasmparser::Operator<'_>,
+    ) -> Result<Instruction<'static>, Error<Self::Error>> {
+        utils::instruction(self, op)
+    }
+
+    /// Translates a constant expression, such as a global initializer or
+    /// segment offset, to the single instruction it consists of.
+    fn init_expr(
+        &mut self,
+        expr: &wasmparser::InitExpr<'_>,
+    ) -> Result<Instruction<'static>, Error<Self::Error>> {
+        utils::init_expr(self, expr)
+    }
+
+    /// Translates one entry of the type section into `types`.
+    fn parse_type(
+        &mut self,
+        types: &mut TypeSection,
+        ty: wasmparser::TypeDef<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_type(self, types, ty)
+    }
+
+    /// Translates one import into `imports`.
+    fn parse_import(
+        &mut self,
+        imports: &mut ImportSection,
+        import: wasmparser::Import<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_import(self, imports, import)
+    }
+
+    /// Translates one alias into `aliases`.
+    fn parse_alias(
+        &mut self,
+        aliases: &mut AliasSection,
+        alias: wasmparser::Alias<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_alias(self, aliases, alias)
+    }
+
+    /// Translates one instantiation into `instances`.
+    fn parse_instance(
+        &mut self,
+        instances: &mut InstanceSection,
+        instance: wasmparser::Instance<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_instance(self, instances, instance)
+    }
+
+    /// Translates the type index of one defined function into `functions`.
+    fn parse_function(
+        &mut self,
+        functions: &mut FunctionSection,
+        ty: u32,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_function(self, functions, ty)
+    }
+
+    /// Translates one table definition into `tables`.
+    fn parse_table(
+        &mut self,
+        tables: &mut TableSection,
+        table: wasmparser::TableType,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_table(self, tables, table)
+    }
+
+    /// Translates one memory definition into `memories`.
+    fn parse_memory(
+        &mut self,
+        memories: &mut MemorySection,
+        memory: wasmparser::MemoryType,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_memory(self, memories, memory)
+    }
+
+    /// Translates one tag definition into `tags`.
+    fn parse_tag(
+        &mut self,
+        tags: &mut TagSection,
+        tag: wasmparser::TagType,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_tag(self, tags, tag)
+    }
+
+    /// Translates one global definition, including its initializer, into
+    /// `globals`.
+    fn parse_global(
+        &mut self,
+        globals: &mut GlobalSection,
+        global: wasmparser::Global<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_global(self, globals, global)
+    }
+
+    /// Translates one export into `exports`.
+    fn parse_export(
+        &mut self,
+        exports: &mut ExportSection,
+        export: wasmparser::Export<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_export(self, exports, export)
+    }
+
+    /// Translates one element segment into `elements`.
+    fn parse_element(
+        &mut self,
+        elements: &mut ElementSection,
+        element: wasmparser::Element<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_element(self, elements, element)
+    }
+
+    /// Translates one data segment into `data`.
+    fn parse_data(
+        &mut self,
+        data: &mut DataSection,
+        segment: wasmparser::Data<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_data(self, data, segment)
+    }
+
+    /// Translates the locals and instructions of one function body into
+    /// `code`.
+    fn parse_function_body(
+        &mut self,
+        code: &mut CodeSection,
+        body: wasmparser::FunctionBody<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_function_body(self, code, body)
+    }
+
+    /// Translates a custom section, by default copying it into `module`
+    /// verbatim.
+    ///
+    /// Note that custom sections such as `name` may contain indices which are
+    /// not remapped by default.
+    fn parse_custom_section(
+        &mut self,
+        module: &mut Module,
+        name: &str,
+        data: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_custom_section(self, module, name, data)
+    }
+
+    /// Translates a section that `wasmparser` doesn't know about, by default
+    /// copying it into `module` verbatim.
+    fn parse_unknown_section(
+        &mut self,
+        module: &mut Module,
+        id: u8,
+        contents: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_unknown_section(self, module, id, contents)
+    }
+
+    /// Translates every entry of a type section, by default calling
+    /// [`Reencode::parse_type`] for each of the types.
+    fn parse_type_section(
+        &mut self,
+        types: &mut TypeSection,
+        section: wasmparser::TypeSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_type_section(self, types, section)
+    }
+
+    /// Translates every entry of an import section, by default calling
+    /// [`Reencode::parse_import`] for each of the imports.
+    fn parse_import_section(
+        &mut self,
+        imports: &mut ImportSection,
+        section: wasmparser::ImportSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_import_section(self, imports, section)
+    }
+
+    /// Translates every entry of an alias section, by default calling
+    /// [`Reencode::parse_alias`] for each of the aliases.
+    fn parse_alias_section(
+        &mut self,
+        aliases: &mut AliasSection,
+        section: wasmparser::AliasSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_alias_section(self, aliases, section)
+    }
+
+    /// Translates every entry of an instance section, by default calling
+    /// [`Reencode::parse_instance`] for each of the instantiations.
+    fn parse_instance_section(
+        &mut self,
+        instances: &mut InstanceSection,
+        section: wasmparser::InstanceSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_instance_section(self, instances, section)
+    }
+
+    /// Translates every entry of a function section, by default calling
+    /// [`Reencode::parse_function`] for each of the function declarations.
+    fn parse_function_section(
+        &mut self,
+        functions: &mut FunctionSection,
+        section: wasmparser::FunctionSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_function_section(self, functions, section)
+    }
+
+    /// Translates every entry of a table section, by default calling
+    /// [`Reencode::parse_table`] for each of the tables.
+    fn parse_table_section(
+        &mut self,
+        tables: &mut TableSection,
+        section: wasmparser::TableSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_table_section(self, tables, section)
+    }
+
+    /// Translates every entry of a memory section, by default calling
+    /// [`Reencode::parse_memory`] for each of the memories.
+    fn parse_memory_section(
+        &mut self,
+        memories: &mut MemorySection,
+        section: wasmparser::MemorySectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_memory_section(self, memories, section)
+    }
+
+    /// Translates every entry of a tag section, by default calling
+    /// [`Reencode::parse_tag`] for each of the tags.
+    fn parse_tag_section(
+        &mut self,
+        tags: &mut TagSection,
+        section: wasmparser::TagSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_tag_section(self, tags, section)
+    }
+
+    /// Translates every entry of a global section, by default calling
+    /// [`Reencode::parse_global`] for each of the globals.
+    fn parse_global_section(
+        &mut self,
+        globals: &mut GlobalSection,
+        section: wasmparser::GlobalSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_global_section(self, globals, section)
+    }
+
+    /// Translates every entry of an export section, by default calling
+    /// [`Reencode::parse_export`] for each of the exports.
+    fn parse_export_section(
+        &mut self,
+        exports: &mut ExportSection,
+        section: wasmparser::ExportSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_export_section(self, exports, section)
+    }
+
+    /// Translates every entry of an element section, by default calling
+    /// [`Reencode::parse_element`] for each of the element segments.
+    fn parse_element_section(
+        &mut self,
+        elements: &mut ElementSection,
+        section: wasmparser::ElementSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_element_section(self, elements, section)
+    }
+
+    /// Translates every entry of a data section, by default calling
+    /// [`Reencode::parse_data`] for each of the data segments.
+    fn parse_data_section(
+        &mut self,
+        data: &mut DataSection,
+        section: wasmparser::DataSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_data_section(self, data, section)
+    }
+
+    /// Translates every entry of a code section, by default calling
+    /// [`Reencode::parse_function_body`] for each of the function bodies.
+    fn parse_code_section(
+        &mut self,
+        code: &mut CodeSection,
+        section: wasmparser::CodeSectionReader<'_>,
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_code_section(self, code, section)
+    }
+
+    /// Translates an entire module, including any nested modules, appending
+    /// its sections to `module`.
+    ///
+    /// The `parser` is used to parse `data`, which must contain the whole
+    /// module.
+    fn parse_core_module(
+        &mut self,
+        module: &mut Module,
+        parser: wasmparser::Parser,
+        data: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        utils::parse_core_module(self, module, parser, data)
+    }
+}
+
+/// A [`Reencode`] implementation which translates everything as-is.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RoundtripReencoder;
+
+impl Reencode for RoundtripReencoder {
+    type Error = Infallible;
+}
+
+/// An error that can occur while translating from `wasmparser` to
+/// `wasm-encoder`.
+#[derive(Debug)]
+pub enum Error<E = Infallible> {
+    /// The input could not be parsed.
+    ParseError(wasmparser::BinaryReaderError),
+    /// The input uses a construct which `wasm-encoder` can't represent.
+    Unsupported(&'static str),
+    /// A constant expression wasn't a single instruction followed by `end`.
+    InvalidConstExpr,
+    /// An error returned from a user-defined [`Reencode`] hook.
+    UserError(E),
+}
+
+impl<E> From<wasmparser::BinaryReaderError> for Error<E> {
+    fn from(err: wasmparser::BinaryReaderError) -> Self {
+        Error::ParseError(err)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ParseError(e) => e.fmt(f),
+            Error::Unsupported(what) => write!(f, "unsupported: {}", what),
+            Error::InvalidConstExpr => f.write_str("invalid constant expression"),
+            Error::UserError(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for Error<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::ParseError(e) => Some(e),
+            Error::UserError(e) => Some(e),
+            Error::Unsupported(_) | Error::InvalidConstExpr => None,
+        }
+    }
+}
+
+/// The default implementations of the [`Reencode`] methods.
+///
+/// These are generic over the reencoder so that they call back into any
+/// overridden hooks for nested items.
+pub mod utils {
+    use super::{Error, Reencode};
+    use crate::*;
+
+    /// The default implementation of [`Reencode::val_type`].
+    pub fn val_type<T: ?Sized + Reencode>(
+        _reencoder: &mut T,
+        ty: wasmparser::Type,
+    ) -> Result<ValType, Error<T::Error>> {
+        Ok(match ty {
+            wasmparser::Type::I32 => ValType::I32,
+            wasmparser::Type::I64 => ValType::I64,
+            wasmparser::Type::F32 => ValType::F32,
+            wasmparser::Type::F64 => ValType::F64,
+            wasmparser::Type::V128 => ValType::V128,
+            wasmparser::Type::FuncRef => ValType::FuncRef,
+            wasmparser::Type::ExternRef => ValType::ExternRef,
+            wasmparser::Type::ExnRef => return Err(Error::Unsupported("`exnref` value type")),
+
+            // These are only used in other contexts within `wasmparser`, such
+            // as block types, and aren't value types.
+            wasmparser::Type::Func | wasmparser::Type::EmptyBlockType => {
+                return Err(Error::Unsupported("non-value type used as a value type"))
+            }
+        })
+    }
+
+    /// The default implementation of [`Reencode::table_type`].
+    pub fn table_type<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        ty: wasmparser::TableType,
+    ) -> Result<TableType, Error<T::Error>> {
+        Ok(TableType {
+            element_type: reencoder.val_type(ty.element_type)?,
+            minimum: ty.initial,
+            maximum: ty.maximum,
+        })
+    }
+
+    /// The default implementation of [`Reencode::memory_type`].
+    pub fn memory_type<T: ?Sized + Reencode>(
+        _reencoder: &mut T,
+        ty: wasmparser::MemoryType,
+    ) -> Result<MemoryType, Error<T::Error>> {
+        Ok(MemoryType {
+            minimum: ty.initial,
+            maximum: ty.maximum,
+            memory64: ty.memory64,
+            shared: ty.shared,
+        })
+    }
+
+    /// The default implementation of [`Reencode::global_type`].
+    pub fn global_type<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        ty: wasmparser::GlobalType,
+    ) -> Result<GlobalType, Error<T::Error>> {
+        Ok(GlobalType {
+            val_type: reencoder.val_type(ty.content_type)?,
+            mutable: ty.mutable,
+        })
+    }
+
+    /// The default implementation of [`Reencode::tag_type`].
+    pub fn tag_type<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        ty: wasmparser::TagType,
+    ) -> Result<TagType, Error<T::Error>> {
+        Ok(TagType {
+            kind: TagKind::Exception,
+            func_type_idx: reencoder.type_index(ty.type_index)?,
+        })
+    }
+
+    /// The default implementation of [`Reencode::entity_type`].
+    pub fn entity_type<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        ty: wasmparser::ImportSectionEntryType,
+    ) -> Result<EntityType, Error<T::Error>> {
+        use wasmparser::ImportSectionEntryType as E;
+        Ok(match ty {
+            E::Function(ty) => EntityType::Function(reencoder.type_index(ty)?),
+            E::Table(ty) => EntityType::Table(reencoder.table_type(ty)?),
+            E::Memory(ty) => EntityType::Memory(reencoder.memory_type(ty)?),
+            E::Tag(ty) => EntityType::Tag(reencoder.tag_type(ty)?),
+            E::Global(ty) => EntityType::Global(reencoder.global_type(ty)?),
+            E::Module(ty) => EntityType::Module(reencoder.type_index(ty)?),
+            E::Instance(ty) => EntityType::Instance(reencoder.type_index(ty)?),
+        })
+    }
+
+    /// The default implementation of [`Reencode::item_kind`].
+    pub fn item_kind<T: ?Sized + Reencode>(
+        _reencoder: &mut T,
+        kind: wasmparser::ExternalKind,
+    ) -> Result<ItemKind, Error<T::Error>> {
+        use wasmparser::ExternalKind as K;
+        Ok(match kind {
+            K::Function => ItemKind::Function,
+            K::Table => ItemKind::Table,
+            K::Memory => ItemKind::Memory,
+            K::Tag => ItemKind::Tag,
+            K::Global => ItemKind::Global,
+            K::Module => ItemKind::Module,
+            K::Instance => ItemKind::Instance,
+            K::Type => return Err(Error::Unsupported("type exports")),
+        })
+    }
+
+    /// The default implementation of [`Reencode::export`].
+    pub fn export<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        kind: wasmparser::ExternalKind,
+        index: u32,
+    ) -> Result<Export, Error<T::Error>> {
+        use wasmparser::ExternalKind as K;
+        Ok(match kind {
+            K::Function => Export::Function(reencoder.function_index(index)?),
+            K::Table => Export::Table(reencoder.table_index(index)?),
+            K::Memory => Export::Memory(reencoder.memory_index(index)?),
+            K::Tag => Export::Tag(reencoder.tag_index(index)?),
+            K::Global => Export::Global(reencoder.global_index(index)?),
+            K::Module => Export::Module(reencoder.module_index(index)?),
+            K::Instance => Export::Instance(reencoder.instance_index(index)?),
+            K::Type => return Err(Error::Unsupported("type exports")),
+        })
+    }
+
+    /// The default implementation of [`Reencode::block_type`].
+    pub fn block_type<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        ty: wasmparser::TypeOrFuncType,
+    ) -> Result<BlockType, Error<T::Error>> {
+        Ok(match ty {
+            wasmparser::TypeOrFuncType::Type(wasmparser::Type::EmptyBlockType) => BlockType::Empty,
+            wasmparser::TypeOrFuncType::Type(ty) => BlockType::Result(reencoder.val_type(ty)?),
+            wasmparser::TypeOrFuncType::FuncType(ty) => {
+                BlockType::FunctionType(reencoder.type_index(ty)?)
+            }
+        })
+    }
+
+    /// The default implementation of [`Reencode::memarg`].
+    pub fn memarg<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        memarg: &wasmparser::MemoryImmediate,
+    ) -> Result<MemArg, Error<T::Error>> {
+        Ok(MemArg {
+            offset: memarg.offset,
+            align: memarg.align.into(),
+            memory_index: reencoder.memory_index(memarg.memory)?,
+        })
+    }
+
+    /// Translates a single `wasmparser` operator to a `wasm-encoder`
+    /// instruction.
+    ///
+    /// This is quite large because there are quite a few wasm instructions,
+    /// but each individual case is self-contained.
+    pub fn instruction<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        op: &wasmparser::Operator<'_>,
+    ) -> Result<Instruction<'static>, Error<T::Error>> {
+        use crate::Instruction as I;
+        use wasmparser::Operator as O;
+        Ok(match op {
+            O::Unreachable => I::Unreachable,
+            O::Nop => I::Nop,
+
+            O::Block { ty } => I::Block(reencoder.block_type(*ty)?),
+            O::Loop { ty } => I::Loop(reencoder.block_type(*ty)?),
+            O::If { ty } => I::If(reencoder.block_type(*ty)?),
+            O::Else => I::Else,
+
+            O::Try { ty } => I::Try(reencoder.block_type(*ty)?),
+            O::Catch { index } => I::Catch(reencoder.tag_index(*index)?),
+            O::Throw { index } => I::Throw(reencoder.tag_index(*index)?),
+            O::Rethrow { relative_depth } => I::Rethrow(*relative_depth),
+            O::End => I::End,
+            O::Br { relative_depth } => I::Br(*relative_depth),
+            O::BrIf { relative_depth } => I::BrIf(*relative_depth),
+            O::BrTable { table } => I::BrTable(
+                table
+                    .targets()
+                    .collect::<Result<Vec<_>, wasmparser::BinaryReaderError>>()?
+                    .into(),
+                table.default(),
+            ),
+
+            O::Return => I::Return,
+            O::Call { function_index } => I::Call(reencoder.function_index(*function_index)?),
+            O::CallIndirect { index, table_index } => I::CallIndirect {
+                ty: reencoder.type_index(*index)?,
+                table: reencoder.table_index(*table_index)?,
+            },
+            O::Delegate { relative_depth } => I::Delegate(*relative_depth),
+            O::CatchAll => I::CatchAll,
+            O::Drop => I::Drop,
+            O::Select => I::Select,
+            O::TypedSelect { ty } => I::TypedSelect(reencoder.val_type(*ty)?),
+
+            O::LocalGet { local_index } => I::LocalGet(*local_index),
+            O::LocalSet { local_index } => I::LocalSet(*local_index),
+            O::LocalTee { local_index } => I::LocalTee(*local_index),
+
+            O::GlobalGet { global_index } => I::GlobalGet(reencoder.global_index(*global_index)?),
+            O::GlobalSet { global_index } => I::GlobalSet(reencoder.global_index(*global_index)?),
+
+            O::I32Load { memarg } => I::I32Load(reencoder.memarg(memarg)?),
+            O::I64Load { memarg } => I::I64Load(reencoder.memarg(memarg)?),
+            O::F32Load { memarg } => I::F32Load(reencoder.memarg(memarg)?),
+            O::F64Load { memarg } => I::F64Load(reencoder.memarg(memarg)?),
+            O::I32Load8S { memarg } => I::I32Load8_S(reencoder.memarg(memarg)?),
+            O::I32Load8U { memarg } => I::I32Load8_U(reencoder.memarg(memarg)?),
+            O::I32Load16S { memarg } => I::I32Load16_S(reencoder.memarg(memarg)?),
+            O::I32Load16U { memarg } => I::I32Load16_U(reencoder.memarg(memarg)?),
+            O::I64Load8S { memarg } => I::I64Load8_S(reencoder.memarg(memarg)?),
+            O::I64Load8U { memarg } => I::I64Load8_U(reencoder.memarg(memarg)?),
+            O::I64Load16S { memarg } => I::I64Load16_S(reencoder.memarg(memarg)?),
+            O::I64Load16U { memarg } => I::I64Load16_U(reencoder.memarg(memarg)?),
+            O::I64Load32S { memarg } => I::I64Load32_S(reencoder.memarg(memarg)?),
+            O::I64Load32U { memarg } => I::I64Load32_U(reencoder.memarg(memarg)?),
+            O::I32Store { memarg } => I::I32Store(reencoder.memarg(memarg)?),
+            O::I64Store { memarg } => I::I64Store(reencoder.memarg(memarg)?),
+            O::F32Store { memarg } => I::F32Store(reencoder.memarg(memarg)?),
+            O::F64Store { memarg } => I::F64Store(reencoder.memarg(memarg)?),
+            O::I32Store8 { memarg } => I::I32Store8(reencoder.memarg(memarg)?),
+            O::I32Store16 { memarg } => I::I32Store16(reencoder.memarg(memarg)?),
+            O::I64Store8 { memarg } => I::I64Store8(reencoder.memarg(memarg)?),
+            O::I64Store16 { memarg } => I::I64Store16(reencoder.memarg(memarg)?),
+            O::I64Store32 { memarg } => I::I64Store32(reencoder.memarg(memarg)?),
+
+            O::MemorySize { mem, .. } => I::MemorySize(reencoder.memory_index(*mem)?),
+            O::MemoryGrow { mem, .. } => I::MemoryGrow(reencoder.memory_index(*mem)?),
+
+            O::I32Const { value } => I::I32Const(*value),
+            O::I64Const { value } => I::I64Const(*value),
+            O::F32Const { value } => I::F32Const(f32::from_bits(value.bits())),
+            O::F64Const { value } => I::F64Const(f64::from_bits(value.bits())),
+
+            O::RefNull { ty } => I::RefNull(reencoder.val_type(*ty)?),
+            O::RefIsNull => I::RefIsNull,
+            O::RefFunc { function_index } => I::RefFunc(reencoder.function_index(*function_index)?),
+
+            O::I32Eqz => I::I32Eqz,
+            O::I32Eq => I::I32Eq,
+            O::I32Ne => I::I32Ne,
+            O::I32LtS => I::I32LtS,
+            O::I32LtU => I::I32LtU,
+            O::I32GtS => I::I32GtS,
+            O::I32GtU => I::I32GtU,
+            O::I32LeS => I::I32LeS,
+            O::I32LeU => I::I32LeU,
+            O::I32GeS => I::I32GeS,
+            O::I32GeU => I::I32GeU,
+            O::I64Eqz => I::I64Eqz,
+            O::I64Eq => I::I64Eq,
+            O::I64Ne => I::I64Ne,
+            O::I64LtS => I::I64LtS,
+            O::I64LtU => I::I64LtU,
+            O::I64GtS => I::I64GtS,
+            O::I64GtU => I::I64GtU,
+            O::I64LeS => I::I64LeS,
+            O::I64LeU => I::I64LeU,
+            O::I64GeS => I::I64GeS,
+            O::I64GeU => I::I64GeU,
+            O::F32Eq => I::F32Eq,
+            O::F32Ne => I::F32Ne,
+            O::F32Lt => I::F32Lt,
+            O::F32Gt => I::F32Gt,
+            O::F32Le => I::F32Le,
+            O::F32Ge => I::F32Ge,
+            O::F64Eq => I::F64Eq,
+            O::F64Ne => I::F64Ne,
+            O::F64Lt => I::F64Lt,
+            O::F64Gt => I::F64Gt,
+            O::F64Le => I::F64Le,
+            O::F64Ge => I::F64Ge,
+            O::I32Clz => I::I32Clz,
+            O::I32Ctz => I::I32Ctz,
+            O::I32Popcnt => I::I32Popcnt,
+            O::I32Add => I::I32Add,
+            O::I32Sub => I::I32Sub,
+            O::I32Mul => I::I32Mul,
+            O::I32DivS => I::I32DivS,
+            O::I32DivU => I::I32DivU,
+            O::I32RemS => I::I32RemS,
+            O::I32RemU => I::I32RemU,
+            O::I32And => I::I32And,
+            O::I32Or => I::I32Or,
+            O::I32Xor => I::I32Xor,
+            O::I32Shl => I::I32Shl,
+            O::I32ShrS => I::I32ShrS,
+            O::I32ShrU => I::I32ShrU,
+            O::I32Rotl => I::I32Rotl,
+            O::I32Rotr => I::I32Rotr,
+            O::I64Clz => I::I64Clz,
+            O::I64Ctz => I::I64Ctz,
+            O::I64Popcnt => I::I64Popcnt,
+            O::I64Add => I::I64Add,
+            O::I64Sub => I::I64Sub,
+            O::I64Mul => I::I64Mul,
+            O::I64DivS => I::I64DivS,
+            O::I64DivU => I::I64DivU,
+            O::I64RemS => I::I64RemS,
+            O::I64RemU => I::I64RemU,
+            O::I64And => I::I64And,
+            O::I64Or => I::I64Or,
+            O::I64Xor => I::I64Xor,
+            O::I64Shl => I::I64Shl,
+            O::I64ShrS => I::I64ShrS,
+            O::I64ShrU => I::I64ShrU,
+            O::I64Rotl => I::I64Rotl,
+            O::I64Rotr => I::I64Rotr,
+            O::F32Abs => I::F32Abs,
+            O::F32Neg => I::F32Neg,
+            O::F32Ceil => I::F32Ceil,
+            O::F32Floor => I::F32Floor,
+            O::F32Trunc => I::F32Trunc,
+            O::F32Nearest => I::F32Nearest,
+            O::F32Sqrt => I::F32Sqrt,
+            O::F32Add => I::F32Add,
+            O::F32Sub => I::F32Sub,
+            O::F32Mul => I::F32Mul,
+            O::F32Div => I::F32Div,
+            O::F32Min => I::F32Min,
+            O::F32Max => I::F32Max,
+            O::F32Copysign => I::F32Copysign,
+            O::F64Abs => I::F64Abs,
+            O::F64Neg => I::F64Neg,
+            O::F64Ceil => I::F64Ceil,
+            O::F64Floor => I::F64Floor,
+            O::F64Trunc => I::F64Trunc,
+            O::F64Nearest => I::F64Nearest,
+            O::F64Sqrt => I::F64Sqrt,
+            O::F64Add => I::F64Add,
+            O::F64Sub => I::F64Sub,
+            O::F64Mul => I::F64Mul,
+            O::F64Div => I::F64Div,
+            O::F64Min => I::F64Min,
+            O::F64Max => I::F64Max,
+            O::F64Copysign => I::F64Copysign,
+            O::I32WrapI64 => I::I32WrapI64,
+            O::I32TruncF32S => I::I32TruncF32S,
+            O::I32TruncF32U => I::I32TruncF32U,
+            O::I32TruncF64S => I::I32TruncF64S,
+            O::I32TruncF64U => I::I32TruncF64U,
+            O::I64ExtendI32S => I::I64ExtendI32S,
+            O::I64ExtendI32U => I::I64ExtendI32U,
+            O::I64TruncF32S => I::I64TruncF32S,
+            O::I64TruncF32U => I::I64TruncF32U,
+            O::I64TruncF64S => I::I64TruncF64S,
+            O::I64TruncF64U => I::I64TruncF64U,
+            O::F32ConvertI32S => I::F32ConvertI32S,
+            O::F32ConvertI32U => I::F32ConvertI32U,
+            O::F32ConvertI64S => I::F32ConvertI64S,
+            O::F32ConvertI64U => I::F32ConvertI64U,
+            O::F32DemoteF64 => I::F32DemoteF64,
+            O::F64ConvertI32S => I::F64ConvertI32S,
+            O::F64ConvertI32U => I::F64ConvertI32U,
+            O::F64ConvertI64S => I::F64ConvertI64S,
+            O::F64ConvertI64U => I::F64ConvertI64U,
+            O::F64PromoteF32 => I::F64PromoteF32,
+            O::I32ReinterpretF32 => I::I32ReinterpretF32,
+            O::I64ReinterpretF64 => I::I64ReinterpretF64,
+            O::F32ReinterpretI32 => I::F32ReinterpretI32,
+            O::F64ReinterpretI64 => I::F64ReinterpretI64,
+            O::I32Extend8S => I::I32Extend8S,
+            O::I32Extend16S => I::I32Extend16S,
+            O::I64Extend8S => I::I64Extend8S,
+            O::I64Extend16S => I::I64Extend16S,
+            O::I64Extend32S => I::I64Extend32S,
+
+            O::I32TruncSatF32S => I::I32TruncSatF32S,
+            O::I32TruncSatF32U => I::I32TruncSatF32U,
+            O::I32TruncSatF64S => I::I32TruncSatF64S,
+            O::I32TruncSatF64U => I::I32TruncSatF64U,
+            O::I64TruncSatF32S => I::I64TruncSatF32S,
+            O::I64TruncSatF32U => I::I64TruncSatF32U,
+            O::I64TruncSatF64S => I::I64TruncSatF64S,
+            O::I64TruncSatF64U => I::I64TruncSatF64U,
+
+            O::MemoryInit { segment, mem } => I::MemoryInit {
+                data: reencoder.data_index(*segment)?,
+                mem: reencoder.memory_index(*mem)?,
+            },
+            O::DataDrop { segment } => I::DataDrop(reencoder.data_index(*segment)?),
+            O::MemoryCopy { src, dst } => I::MemoryCopy {
+                src: reencoder.memory_index(*src)?,
+                dst: reencoder.memory_index(*dst)?,
+            },
+            O::MemoryFill { mem, .. } => I::MemoryFill(reencoder.memory_index(*mem)?),
+
+            O::TableInit { segment, table } => I::TableInit {
+                segment: reencoder.element_index(*segment)?,
+                table: reencoder.table_index(*table)?,
+            },
+            O::ElemDrop { segment } => I::ElemDrop {
+                segment: reencoder.element_index(*segment)?,
+            },
+            O::TableCopy {
+                dst_table,
+                src_table,
+            } => I::TableCopy {
+                dst: reencoder.table_index(*dst_table)?,
+                src: reencoder.table_index(*src_table)?,
+            },
+            O::TableFill { table } => I::TableFill {
+                table: reencoder.table_index(*table)?,
+            },
+            O::TableGet { table } => I::TableGet {
+                table: reencoder.table_index(*table)?,
+            },
+            O::TableSet { table } => I::TableSet {
+                table: reencoder.table_index(*table)?,
+            },
+            O::TableGrow { table } => I::TableGrow {
+                table: reencoder.table_index(*table)?,
+            },
+            O::TableSize { table } => I::TableSize {
+                table: reencoder.table_index(*table)?,
+            },
+
+            O::V128Load { memarg } => I::V128Load {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load8x8S { memarg } => I::V128Load8x8S {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load8x8U { memarg } => I::V128Load8x8U {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load16x4S { memarg } => I::V128Load16x4S {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load16x4U { memarg } => I::V128Load16x4U {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load32x2S { memarg } => I::V128Load32x2S {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load32x2U { memarg } => I::V128Load32x2U {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load8Splat { memarg } => I::V128Load8Splat {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load16Splat { memarg } => I::V128Load16Splat {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load32Splat { memarg } => I::V128Load32Splat {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load64Splat { memarg } => I::V128Load64Splat {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load32Zero { memarg } => I::V128Load32Zero {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load64Zero { memarg } => I::V128Load64Zero {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Store { memarg } => I::V128Store {
+                memarg: reencoder.memarg(memarg)?,
+            },
+            O::V128Load8Lane { memarg, lane } => I::V128Load8Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+            O::V128Load16Lane { memarg, lane } => I::V128Load16Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+            O::V128Load32Lane { memarg, lane } => I::V128Load32Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+            O::V128Load64Lane { memarg, lane } => I::V128Load64Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+            O::V128Store8Lane { memarg, lane } => I::V128Store8Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+            O::V128Store16Lane { memarg, lane } => I::V128Store16Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+            O::V128Store32Lane { memarg, lane } => I::V128Store32Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+            O::V128Store64Lane { memarg, lane } => I::V128Store64Lane {
+                memarg: reencoder.memarg(memarg)?,
+                lane: *lane,
+            },
+
+            O::V128Const { value } => I::V128Const(value.i128()),
+            O::I8x16Shuffle { lanes } => I::I8x16Shuffle { lanes: *lanes },
+            O::I8x16ExtractLaneS { lane } => I::I8x16ExtractLaneS { lane: *lane },
+            O::I8x16ExtractLaneU { lane } => I::I8x16ExtractLaneU { lane: *lane },
+            O::I8x16ReplaceLane { lane } => I::I8x16ReplaceLane { lane: *lane },
+            O::I16x8ExtractLaneS { lane } => I::I16x8ExtractLaneS { lane: *lane },
+            O::I16x8ExtractLaneU { lane } => I::I16x8ExtractLaneU { lane: *lane },
+            O::I16x8ReplaceLane { lane } => I::I16x8ReplaceLane { lane: *lane },
+            O::I32x4ExtractLane { lane } => I::I32x4ExtractLane { lane: *lane },
+            O::I32x4ReplaceLane { lane } => I::I32x4ReplaceLane { lane: *lane },
+            O::I64x2ExtractLane { lane } => I::I64x2ExtractLane { lane: *lane },
+            O::I64x2ReplaceLane { lane } => I::I64x2ReplaceLane { lane: *lane },
+            O::F32x4ExtractLane { lane } => I::F32x4ExtractLane { lane: *lane },
+            O::F32x4ReplaceLane { lane } => I::F32x4ReplaceLane { lane: *lane },
+            O::F64x2ExtractLane { lane } => I::F64x2ExtractLane { lane: *lane },
+            O::F64x2ReplaceLane { lane } => I::F64x2ReplaceLane { lane: *lane },
+
+            O::I8x16Swizzle => I::I8x16Swizzle,
+            O::I8x16Splat => I::I8x16Splat,
+            O::I16x8Splat => I::I16x8Splat,
+            O::I32x4Splat => I::I32x4Splat,
+            O::I64x2Splat => I::I64x2Splat,
+            O::F32x4Splat => I::F32x4Splat,
+            O::F64x2Splat => I::F64x2Splat,
+            O::I8x16Eq => I::I8x16Eq,
+            O::I8x16Ne => I::I8x16Ne,
+            O::I8x16LtS => I::I8x16LtS,
+            O::I8x16LtU => I::I8x16LtU,
+            O::I8x16GtS => I::I8x16GtS,
+            O::I8x16GtU => I::I8x16GtU,
+            O::I8x16LeS => I::I8x16LeS,
+            O::I8x16LeU => I::I8x16LeU,
+            O::I8x16GeS => I::I8x16GeS,
+            O::I8x16GeU => I::I8x16GeU,
+            O::I16x8Eq => I::I16x8Eq,
+            O::I16x8Ne => I::I16x8Ne,
+            O::I16x8LtS => I::I16x8LtS,
+            O::I16x8LtU => I::I16x8LtU,
+            O::I16x8GtS => I::I16x8GtS,
+            O::I16x8GtU => I::I16x8GtU,
+            O::I16x8LeS => I::I16x8LeS,
+            O::I16x8LeU => I::I16x8LeU,
+            O::I16x8GeS => I::I16x8GeS,
+            O::I16x8GeU => I::I16x8GeU,
+            O::I32x4Eq => I::I32x4Eq,
+            O::I32x4Ne => I::I32x4Ne,
+            O::I32x4LtS => I::I32x4LtS,
+            O::I32x4LtU => I::I32x4LtU,
+            O::I32x4GtS => I::I32x4GtS,
+            O::I32x4GtU => I::I32x4GtU,
+            O::I32x4LeS => I::I32x4LeS,
+            O::I32x4LeU => I::I32x4LeU,
+            O::I32x4GeS => I::I32x4GeS,
+            O::I32x4GeU => I::I32x4GeU,
+            O::I64x2Eq => I::I64x2Eq,
+            O::I64x2Ne => I::I64x2Ne,
+            O::I64x2LtS => I::I64x2LtS,
+            O::I64x2GtS => I::I64x2GtS,
+            O::I64x2LeS => I::I64x2LeS,
+            O::I64x2GeS => I::I64x2GeS,
+            O::F32x4Eq => I::F32x4Eq,
+            O::F32x4Ne => I::F32x4Ne,
+            O::F32x4Lt => I::F32x4Lt,
+            O::F32x4Gt => I::F32x4Gt,
+            O::F32x4Le => I::F32x4Le,
+            O::F32x4Ge => I::F32x4Ge,
+            O::F64x2Eq => I::F64x2Eq,
+            O::F64x2Ne => I::F64x2Ne,
+            O::F64x2Lt => I::F64x2Lt,
+            O::F64x2Gt => I::F64x2Gt,
+            O::F64x2Le => I::F64x2Le,
+            O::F64x2Ge => I::F64x2Ge,
+            O::V128Not => I::V128Not,
+            O::V128And => I::V128And,
+            O::V128AndNot => I::V128AndNot,
+            O::V128Or => I::V128Or,
+            O::V128Xor => I::V128Xor,
+            O::V128Bitselect => I::V128Bitselect,
+            O::V128AnyTrue => I::V128AnyTrue,
+            O::I8x16Abs => I::I8x16Abs,
+            O::I8x16Neg => I::I8x16Neg,
+            O::I8x16Popcnt => I::I8x16Popcnt,
+            O::I8x16AllTrue => I::I8x16AllTrue,
+            O::I8x16Bitmask => I::I8x16Bitmask,
+            O::I8x16NarrowI16x8S => I::I8x16NarrowI16x8S,
+            O::I8x16NarrowI16x8U => I::I8x16NarrowI16x8U,
+            O::I8x16Shl => I::I8x16Shl,
+            O::I8x16ShrS => I::I8x16ShrS,
+            O::I8x16ShrU => I::I8x16ShrU,
+            O::I8x16Add => I::I8x16Add,
+            O::I8x16AddSatS => I::I8x16AddSatS,
+            O::I8x16AddSatU => I::I8x16AddSatU,
+            O::I8x16Sub => I::I8x16Sub,
+            O::I8x16SubSatS => I::I8x16SubSatS,
+            O::I8x16SubSatU => I::I8x16SubSatU,
+            O::I8x16MinS => I::I8x16MinS,
+            O::I8x16MinU => I::I8x16MinU,
+            O::I8x16MaxS => I::I8x16MaxS,
+            O::I8x16MaxU => I::I8x16MaxU,
+            O::I8x16RoundingAverageU => I::I8x16RoundingAverageU,
+            O::I16x8ExtAddPairwiseI8x16S => I::I16x8ExtAddPairwiseI8x16S,
+            O::I16x8ExtAddPairwiseI8x16U => I::I16x8ExtAddPairwiseI8x16U,
+            O::I16x8Abs => I::I16x8Abs,
+            O::I16x8Neg => I::I16x8Neg,
+            O::I16x8Q15MulrSatS => I::I16x8Q15MulrSatS,
+            O::I16x8AllTrue => I::I16x8AllTrue,
+            O::I16x8Bitmask => I::I16x8Bitmask,
+            O::I16x8NarrowI32x4S => I::I16x8NarrowI32x4S,
+            O::I16x8NarrowI32x4U => I::I16x8NarrowI32x4U,
+            O::I16x8ExtendLowI8x16S => I::I16x8ExtendLowI8x16S,
+            O::I16x8ExtendHighI8x16S => I::I16x8ExtendHighI8x16S,
+            O::I16x8ExtendLowI8x16U => I::I16x8ExtendLowI8x16U,
+            O::I16x8ExtendHighI8x16U => I::I16x8ExtendHighI8x16U,
+            O::I16x8Shl => I::I16x8Shl,
+            O::I16x8ShrS => I::I16x8ShrS,
+            O::I16x8ShrU => I::I16x8ShrU,
+            O::I16x8Add => I::I16x8Add,
+            O::I16x8AddSatS => I::I16x8AddSatS,
+            O::I16x8AddSatU => I::I16x8AddSatU,
+            O::I16x8Sub => I::I16x8Sub,
+            O::I16x8SubSatS => I::I16x8SubSatS,
+            O::I16x8SubSatU => I::I16x8SubSatU,
+            O::I16x8Mul => I::I16x8Mul,
+            O::I16x8MinS => I::I16x8MinS,
+            O::I16x8MinU => I::I16x8MinU,
+            O::I16x8MaxS => I::I16x8MaxS,
+            O::I16x8MaxU => I::I16x8MaxU,
+            O::I16x8RoundingAverageU => I::I16x8RoundingAverageU,
+            O::I16x8ExtMulLowI8x16S => I::I16x8ExtMulLowI8x16S,
+            O::I16x8ExtMulHighI8x16S => I::I16x8ExtMulHighI8x16S,
+            O::I16x8ExtMulLowI8x16U => I::I16x8ExtMulLowI8x16U,
+            O::I16x8ExtMulHighI8x16U => I::I16x8ExtMulHighI8x16U,
+            O::I32x4ExtAddPairwiseI16x8S => I::I32x4ExtAddPairwiseI16x8S,
+            O::I32x4ExtAddPairwiseI16x8U => I::I32x4ExtAddPairwiseI16x8U,
+            O::I32x4Abs => I::I32x4Abs,
+            O::I32x4Neg => I::I32x4Neg,
+            O::I32x4AllTrue => I::I32x4AllTrue,
+            O::I32x4Bitmask => I::I32x4Bitmask,
+            O::I32x4ExtendLowI16x8S => I::I32x4ExtendLowI16x8S,
+            O::I32x4ExtendHighI16x8S => I::I32x4ExtendHighI16x8S,
+            O::I32x4ExtendLowI16x8U => I::I32x4ExtendLowI16x8U,
+            O::I32x4ExtendHighI16x8U => I::I32x4ExtendHighI16x8U,
+            O::I32x4Shl => I::I32x4Shl,
+            O::I32x4ShrS => I::I32x4ShrS,
+            O::I32x4ShrU => I::I32x4ShrU,
+            O::I32x4Add => I::I32x4Add,
+            O::I32x4Sub => I::I32x4Sub,
+            O::I32x4Mul => I::I32x4Mul,
+            O::I32x4MinS => I::I32x4MinS,
+            O::I32x4MinU => I::I32x4MinU,
+            O::I32x4MaxS => I::I32x4MaxS,
+            O::I32x4MaxU => I::I32x4MaxU,
+            O::I32x4DotI16x8S => I::I32x4DotI16x8S,
+            O::I32x4ExtMulLowI16x8S => I::I32x4ExtMulLowI16x8S,
+            O::I32x4ExtMulHighI16x8S => I::I32x4ExtMulHighI16x8S,
+            O::I32x4ExtMulLowI16x8U => I::I32x4ExtMulLowI16x8U,
+            O::I32x4ExtMulHighI16x8U => I::I32x4ExtMulHighI16x8U,
+            O::I64x2Abs => I::I64x2Abs,
+            O::I64x2Neg => I::I64x2Neg,
+            O::I64x2AllTrue => I::I64x2AllTrue,
+            O::I64x2Bitmask => I::I64x2Bitmask,
+            O::I64x2ExtendLowI32x4S => I::I64x2ExtendLowI32x4S,
+            O::I64x2ExtendHighI32x4S => I::I64x2ExtendHighI32x4S,
+            O::I64x2ExtendLowI32x4U => I::I64x2ExtendLowI32x4U,
+            O::I64x2ExtendHighI32x4U => I::I64x2ExtendHighI32x4U,
+            O::I64x2Shl => I::I64x2Shl,
+            O::I64x2ShrS => I::I64x2ShrS,
+            O::I64x2ShrU => I::I64x2ShrU,
+            O::I64x2Add => I::I64x2Add,
+            O::I64x2Sub => I::I64x2Sub,
+            O::I64x2Mul => I::I64x2Mul,
+            O::I64x2ExtMulLowI32x4S => I::I64x2ExtMulLowI32x4S,
+            O::I64x2ExtMulHighI32x4S => I::I64x2ExtMulHighI32x4S,
+            O::I64x2ExtMulLowI32x4U => I::I64x2ExtMulLowI32x4U,
+            O::I64x2ExtMulHighI32x4U => I::I64x2ExtMulHighI32x4U,
+            O::F32x4Ceil => I::F32x4Ceil,
+            O::F32x4Floor => I::F32x4Floor,
+            O::F32x4Trunc => I::F32x4Trunc,
+            O::F32x4Nearest => I::F32x4Nearest,
+            O::F32x4Abs => I::F32x4Abs,
+            O::F32x4Neg => I::F32x4Neg,
+            O::F32x4Sqrt => I::F32x4Sqrt,
+            O::F32x4Add => I::F32x4Add,
+            O::F32x4Sub => I::F32x4Sub,
+            O::F32x4Mul => I::F32x4Mul,
+            O::F32x4Div => I::F32x4Div,
+            O::F32x4Min => I::F32x4Min,
+            O::F32x4Max => I::F32x4Max,
+            O::F32x4PMin => I::F32x4PMin,
+            O::F32x4PMax => I::F32x4PMax,
+            O::F64x2Ceil => I::F64x2Ceil,
+            O::F64x2Floor => I::F64x2Floor,
+            O::F64x2Trunc => I::F64x2Trunc,
+            O::F64x2Nearest => I::F64x2Nearest,
+            O::F64x2Abs => I::F64x2Abs,
+            O::F64x2Neg => I::F64x2Neg,
+            O::F64x2Sqrt => I::F64x2Sqrt,
+            O::F64x2Add => I::F64x2Add,
+            O::F64x2Sub => I::F64x2Sub,
+            O::F64x2Mul => I::F64x2Mul,
+            O::F64x2Div => I::F64x2Div,
+            O::F64x2Min => I::F64x2Min,
+            O::F64x2Max => I::F64x2Max,
+            O::F64x2PMin => I::F64x2PMin,
+            O::F64x2PMax => I::F64x2PMax,
+            O::I32x4TruncSatF32x4S => I::I32x4TruncSatF32x4S,
+            O::I32x4TruncSatF32x4U => I::I32x4TruncSatF32x4U,
+            O::F32x4ConvertI32x4S => I::F32x4ConvertI32x4S,
+            O::F32x4ConvertI32x4U => I::F32x4ConvertI32x4U,
+            O::I32x4TruncSatF64x2SZero => I::I32x4TruncSatF64x2SZero,
+            O::I32x4TruncSatF64x2UZero => I::I32x4TruncSatF64x2UZero,
+            O::F64x2ConvertLowI32x4S => I::F64x2ConvertLowI32x4S,
+            O::F64x2ConvertLowI32x4U => I::F64x2ConvertLowI32x4U,
+            O::F32x4DemoteF64x2Zero => I::F32x4DemoteF64x2Zero,
+            O::F64x2PromoteLowF32x4 => I::F64x2PromoteLowF32x4,
+            O::I8x16RelaxedSwizzle => I::I8x16RelaxedSwizzle,
+            O::I32x4RelaxedTruncSatF32x4S => I::I32x4RelaxedTruncSatF32x4S,
+            O::I32x4RelaxedTruncSatF32x4U => I::I32x4RelaxedTruncSatF32x4U,
+            O::I32x4RelaxedTruncSatF64x2SZero => I::I32x4RelaxedTruncSatF64x2SZero,
+            O::I32x4RelaxedTruncSatF64x2UZero => I::I32x4RelaxedTruncSatF64x2UZero,
+            O::F32x4Fma => I::F32x4Fma,
+            O::F32x4Fms => I::F32x4Fms,
+            O::F64x2Fma => I::F64x2Fma,
+            O::F64x2Fms => I::F64x2Fms,
+            O::I8x16LaneSelect => I::I8x16LaneSelect,
+            O::I16x8LaneSelect => I::I16x8LaneSelect,
+            O::I32x4LaneSelect => I::I32x4LaneSelect,
+            O::I64x2LaneSelect => I::I64x2LaneSelect,
+            O::F32x4RelaxedMin => I::F32x4RelaxedMin,
+            O::F32x4RelaxedMax => I::F32x4RelaxedMax,
+            O::F64x2RelaxedMin => I::F64x2RelaxedMin,
+            O::F64x2RelaxedMax => I::F64x2RelaxedMax,
+
+            O::ReturnCall { function_index } => {
+                I::ReturnCall(reencoder.function_index(*function_index)?)
+            }
+            O::ReturnCallIndirect { index, table_index } => I::ReturnCallIndirect {
+                ty: reencoder.type_index(*index)?,
+                table: reencoder.table_index(*table_index)?,
+            },
+
+            O::MemoryAtomicNotify { memarg } => I::MemoryAtomicNotify(reencoder.memarg(memarg)?),
+            O::MemoryAtomicWait32 { memarg } => I::MemoryAtomicWait32(reencoder.memarg(memarg)?),
+            O::MemoryAtomicWait64 { memarg } => I::MemoryAtomicWait64(reencoder.memarg(memarg)?),
+            O::AtomicFence { .. } => I::AtomicFence,
+            O::I32AtomicLoad { memarg } => I::I32AtomicLoad(reencoder.memarg(memarg)?),
+            O::I64AtomicLoad { memarg } => I::I64AtomicLoad(reencoder.memarg(memarg)?),
+            O::I32AtomicLoad8U { memarg } => I::I32AtomicLoad8U(reencoder.memarg(memarg)?),
+            O::I32AtomicLoad16U { memarg } => I::I32AtomicLoad16U(reencoder.memarg(memarg)?),
+            O::I64AtomicLoad8U { memarg } => I::I64AtomicLoad8U(reencoder.memarg(memarg)?),
+            O::I64AtomicLoad16U { memarg } => I::I64AtomicLoad16U(reencoder.memarg(memarg)?),
+            O::I64AtomicLoad32U { memarg } => I::I64AtomicLoad32U(reencoder.memarg(memarg)?),
+            O::I32AtomicStore { memarg } => I::I32AtomicStore(reencoder.memarg(memarg)?),
+            O::I64AtomicStore { memarg } => I::I64AtomicStore(reencoder.memarg(memarg)?),
+            O::I32AtomicStore8 { memarg } => I::I32AtomicStore8(reencoder.memarg(memarg)?),
+            O::I32AtomicStore16 { memarg } => I::I32AtomicStore16(reencoder.memarg(memarg)?),
+            O::I64AtomicStore8 { memarg } => I::I64AtomicStore8(reencoder.memarg(memarg)?),
+            O::I64AtomicStore16 { memarg } => I::I64AtomicStore16(reencoder.memarg(memarg)?),
+            O::I64AtomicStore32 { memarg } => I::I64AtomicStore32(reencoder.memarg(memarg)?),
+            O::I32AtomicRmwAdd { memarg } => I::I32AtomicRmwAdd(reencoder.memarg(memarg)?),
+            O::I64AtomicRmwAdd { memarg } => I::I64AtomicRmwAdd(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw8AddU { memarg } => I::I32AtomicRmw8AddU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw16AddU { memarg } => I::I32AtomicRmw16AddU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw8AddU { memarg } => I::I64AtomicRmw8AddU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw16AddU { memarg } => I::I64AtomicRmw16AddU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw32AddU { memarg } => I::I64AtomicRmw32AddU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmwSub { memarg } => I::I32AtomicRmwSub(reencoder.memarg(memarg)?),
+            O::I64AtomicRmwSub { memarg } => I::I64AtomicRmwSub(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw8SubU { memarg } => I::I32AtomicRmw8SubU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw16SubU { memarg } => I::I32AtomicRmw16SubU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw8SubU { memarg } => I::I64AtomicRmw8SubU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw16SubU { memarg } => I::I64AtomicRmw16SubU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw32SubU { memarg } => I::I64AtomicRmw32SubU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmwAnd { memarg } => I::I32AtomicRmwAnd(reencoder.memarg(memarg)?),
+            O::I64AtomicRmwAnd { memarg } => I::I64AtomicRmwAnd(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw8AndU { memarg } => I::I32AtomicRmw8AndU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw16AndU { memarg } => I::I32AtomicRmw16AndU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw8AndU { memarg } => I::I64AtomicRmw8AndU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw16AndU { memarg } => I::I64AtomicRmw16AndU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw32AndU { memarg } => I::I64AtomicRmw32AndU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmwOr { memarg } => I::I32AtomicRmwOr(reencoder.memarg(memarg)?),
+            O::I64AtomicRmwOr { memarg } => I::I64AtomicRmwOr(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw8OrU { memarg } => I::I32AtomicRmw8OrU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw16OrU { memarg } => I::I32AtomicRmw16OrU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw8OrU { memarg } => I::I64AtomicRmw8OrU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw16OrU { memarg } => I::I64AtomicRmw16OrU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw32OrU { memarg } => I::I64AtomicRmw32OrU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmwXor { memarg } => I::I32AtomicRmwXor(reencoder.memarg(memarg)?),
+            O::I64AtomicRmwXor { memarg } => I::I64AtomicRmwXor(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw8XorU { memarg } => I::I32AtomicRmw8XorU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw16XorU { memarg } => I::I32AtomicRmw16XorU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw8XorU { memarg } => I::I64AtomicRmw8XorU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw16XorU { memarg } => I::I64AtomicRmw16XorU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw32XorU { memarg } => I::I64AtomicRmw32XorU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmwXchg { memarg } => I::I32AtomicRmwXchg(reencoder.memarg(memarg)?),
+            O::I64AtomicRmwXchg { memarg } => I::I64AtomicRmwXchg(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw8XchgU { memarg } => I::I32AtomicRmw8XchgU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw16XchgU { memarg } => I::I32AtomicRmw16XchgU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw8XchgU { memarg } => I::I64AtomicRmw8XchgU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw16XchgU { memarg } => I::I64AtomicRmw16XchgU(reencoder.memarg(memarg)?),
+            O::I64AtomicRmw32XchgU { memarg } => I::I64AtomicRmw32XchgU(reencoder.memarg(memarg)?),
+            O::I32AtomicRmwCmpxchg { memarg } => I::I32AtomicRmwCmpxchg(reencoder.memarg(memarg)?),
+            O::I64AtomicRmwCmpxchg { memarg } => I::I64AtomicRmwCmpxchg(reencoder.memarg(memarg)?),
+            O::I32AtomicRmw8CmpxchgU { memarg } => {
+                I::I32AtomicRmw8CmpxchgU(reencoder.memarg(memarg)?)
+            }
+            O::I32AtomicRmw16CmpxchgU { memarg } => {
+                I::I32AtomicRmw16CmpxchgU(reencoder.memarg(memarg)?)
+            }
+            O::I64AtomicRmw8CmpxchgU { memarg } => {
+                I::I64AtomicRmw8CmpxchgU(reencoder.memarg(memarg)?)
+            }
+            O::I64AtomicRmw16CmpxchgU { memarg } => {
+                I::I64AtomicRmw16CmpxchgU(reencoder.memarg(memarg)?)
+            }
+            O::I64AtomicRmw32CmpxchgU { memarg } => {
+                I::I64AtomicRmw32CmpxchgU(reencoder.memarg(memarg)?)
+            }
+        })
+    }
+
+    /// The default implementation of [`Reencode::init_expr`].
+    pub fn init_expr<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        expr: &wasmparser::InitExpr<'_>,
+    ) -> Result<Instruction<'static>, Error<T::Error>> {
+        let mut reader = expr.get_operators_reader();
+        let insn = reencoder.instruction(&reader.read()?)?;
+        match reader.read()? {
+            wasmparser::Operator::End if reader.eof() => Ok(insn),
+            _ => Err(Error::InvalidConstExpr),
+        }
+    }
+
+    /// The default implementation of [`Reencode::parse_type`].
+    pub fn parse_type<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        types: &mut TypeSection,
+        ty: wasmparser::TypeDef<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        match ty {
+            wasmparser::TypeDef::Func(ty) => {
+                let params = ty
+                    .params
+                    .iter()
+                    .map(|ty| reencoder.val_type(*ty))
+                    .collect::<Result<Vec<_>, Error<T::Error>>>()?;
+                let results = ty
+                    .returns
+                    .iter()
+                    .map(|ty| reencoder.val_type(*ty))
+                    .collect::<Result<Vec<_>, Error<T::Error>>>()?;
+                types.function(params, results);
+            }
+            wasmparser::TypeDef::Module(ty) => {
+                let imports = ty
+                    .imports
+                    .iter()
+                    .map(|i| Ok((i.module, i.field, reencoder.entity_type(i.ty)?)))
+                    .collect::<Result<Vec<_>, Error<T::Error>>>()?;
+                let exports = ty
+                    .exports
+                    .iter()
+                    .map(|e| Ok((e.name, reencoder.entity_type(e.ty)?)))
+                    .collect::<Result<Vec<_>, Error<T::Error>>>()?;
+                types.module(imports, exports);
+            }
+            wasmparser::TypeDef::Instance(ty) => {
+                let exports = ty
+                    .exports
+                    .iter()
+                    .map(|e| Ok((e.name, reencoder.entity_type(e.ty)?)))
+                    .collect::<Result<Vec<_>, Error<T::Error>>>()?;
+                types.instance(exports);
+            }
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_import`].
+    pub fn parse_import<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        imports: &mut ImportSection,
+        import: wasmparser::Import<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        imports.import(
+            import.module,
+            import.field,
+            reencoder.entity_type(import.ty)?,
+        );
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_alias`].
+    pub fn parse_alias<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        aliases: &mut AliasSection,
+        alias: wasmparser::Alias<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        match alias {
+            wasmparser::Alias::InstanceExport {
+                instance,
+                kind,
+                export,
+            } => {
+                let instance = reencoder.instance_index(instance)?;
+                let kind = reencoder.item_kind(kind)?;
+                aliases.instance_export(instance, kind, export);
+            }
+            wasmparser::Alias::OuterType {
+                relative_depth,
+                index,
+            } => {
+                let index = reencoder.outer_type_index(relative_depth, index)?;
+                aliases.outer_type(relative_depth, index);
+            }
+            wasmparser::Alias::OuterModule {
+                relative_depth,
+                index,
+            } => {
+                let index = reencoder.outer_module_index(relative_depth, index)?;
+                aliases.outer_module(relative_depth, index);
+            }
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_instance`].
+    pub fn parse_instance<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        instances: &mut InstanceSection,
+        instance: wasmparser::Instance<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        let module = reencoder.module_index(instance.module())?;
+        let args = instance
+            .args()?
+            .into_iter()
+            .map(|arg| {
+                let arg = arg?;
+                Ok((arg.name, reencoder.export(arg.kind, arg.index)?))
+            })
+            .collect::<Result<Vec<_>, Error<T::Error>>>()?;
+        instances.instantiate(module, args);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_function`].
+    pub fn parse_function<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        functions: &mut FunctionSection,
+        ty: u32,
+    ) -> Result<(), Error<T::Error>> {
+        functions.function(reencoder.type_index(ty)?);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_table`].
+    pub fn parse_table<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        tables: &mut TableSection,
+        table: wasmparser::TableType,
+    ) -> Result<(), Error<T::Error>> {
+        tables.table(reencoder.table_type(table)?);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_memory`].
+    pub fn parse_memory<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        memories: &mut MemorySection,
+        memory: wasmparser::MemoryType,
+    ) -> Result<(), Error<T::Error>> {
+        memories.memory(reencoder.memory_type(memory)?);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_tag`].
+    pub fn parse_tag<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        tags: &mut TagSection,
+        tag: wasmparser::TagType,
+    ) -> Result<(), Error<T::Error>> {
+        tags.tag(reencoder.tag_type(tag)?);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_global`].
+    pub fn parse_global<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        globals: &mut GlobalSection,
+        global: wasmparser::Global<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        let ty = reencoder.global_type(global.ty)?;
+        let init = reencoder.init_expr(&global.init_expr)?;
+        globals.global(ty, &init);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_export`].
+    pub fn parse_export<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        exports: &mut ExportSection,
+        export: wasmparser::Export<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        exports.export(export.field, reencoder.export(export.kind, export.index)?);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_element`].
+    pub fn parse_element<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        elements: &mut ElementSection,
+        element: wasmparser::Element<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        let element_type = reencoder.val_type(element.ty)?;
+        let offset;
+        let mode = match &element.kind {
+            wasmparser::ElementKind::Active {
+                table_index,
+                init_expr,
+            } => {
+                offset = reencoder.init_expr(init_expr)?;
+                let table = reencoder.table_index(*table_index)?;
+                ElementMode::Active {
+                    // Use the shorter encoding when it's available.
+                    table: if table == 0 && element_type == ValType::FuncRef {
+                        None
+                    } else {
+                        Some(table)
+                    },
+                    offset: &offset,
+                }
+            }
+            wasmparser::ElementKind::Passive => ElementMode::Passive,
+            wasmparser::ElementKind::Declared => ElementMode::Declared,
+        };
+        let mut functions = Vec::new();
+        let mut exprs = Vec::new();
+        let mut reader = element.items.get_items_reader()?;
+        for _ in 0..reader.get_count() {
+            match reader.read()? {
+                wasmparser::ElementItem::Func(idx) => {
+                    functions.push(reencoder.function_index(idx)?);
+                }
+                wasmparser::ElementItem::Expr(expr) => match reencoder.init_expr(&expr)? {
+                    Instruction::RefFunc(idx) => exprs.push(Element::Func(idx)),
+                    Instruction::RefNull(_) => exprs.push(Element::Null),
+                    _ => return Err(Error::InvalidConstExpr),
+                },
+            }
+        }
+        elements.segment(ElementSegment {
+            mode,
+            element_type,
+            elements: if reader.uses_exprs() {
+                Elements::Expressions(&exprs)
+            } else {
+                Elements::Functions(&functions)
+            },
+        });
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_data`].
+    pub fn parse_data<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        data: &mut DataSection,
+        segment: wasmparser::Data<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        let offset;
+        let mode = match &segment.kind {
+            wasmparser::DataKind::Active {
+                memory_index,
+                init_expr,
+            } => {
+                offset = reencoder.init_expr(init_expr)?;
+                DataSegmentMode::Active {
+                    memory_index: reencoder.memory_index(*memory_index)?,
+                    offset: &offset,
+                }
+            }
+            wasmparser::DataKind::Passive => DataSegmentMode::Passive,
+        };
+        data.segment(DataSegment {
+            mode,
+            data: segment.data.iter().copied(),
+        });
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_function_body`].
+    pub fn parse_function_body<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        code: &mut CodeSection,
+        body: wasmparser::FunctionBody<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        let locals = body
+            .get_locals_reader()?
+            .into_iter()
+            .map(|local| {
+                let (count, ty) = local?;
+                Ok((count, reencoder.val_type(ty)?))
+            })
+            .collect::<Result<Vec<_>, Error<T::Error>>>()?;
+        let mut func = Function::new(locals);
+        let mut reader = body.get_operators_reader()?;
+        reader.allow_memarg64(true);
+        while !reader.eof() {
+            func.instruction(&reencoder.instruction(&reader.read()?)?);
+        }
+        code.function(&func);
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_custom_section`].
+    pub fn parse_custom_section<T: ?Sized + Reencode>(
+        _reencoder: &mut T,
+        module: &mut Module,
+        name: &str,
+        data: &[u8],
+    ) -> Result<(), Error<T::Error>> {
+        module.section(&CustomSection { name, data });
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_unknown_section`].
+    pub fn parse_unknown_section<T: ?Sized + Reencode>(
+        _reencoder: &mut T,
+        module: &mut Module,
+        id: u8,
+        contents: &[u8],
+    ) -> Result<(), Error<T::Error>> {
+        module.section(&RawSection { id, data: contents });
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_type_section`].
+    pub fn parse_type_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        types: &mut TypeSection,
+        section: wasmparser::TypeSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for ty in section {
+            reencoder.parse_type(types, ty?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_import_section`].
+    pub fn parse_import_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        imports: &mut ImportSection,
+        section: wasmparser::ImportSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for import in section {
+            reencoder.parse_import(imports, import?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_alias_section`].
+    pub fn parse_alias_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        aliases: &mut AliasSection,
+        section: wasmparser::AliasSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for alias in section {
+            reencoder.parse_alias(aliases, alias?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_instance_section`].
+    pub fn parse_instance_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        instances: &mut InstanceSection,
+        section: wasmparser::InstanceSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for instance in section {
+            reencoder.parse_instance(instances, instance?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_function_section`].
+    pub fn parse_function_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        functions: &mut FunctionSection,
+        section: wasmparser::FunctionSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for ty in section {
+            reencoder.parse_function(functions, ty?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_table_section`].
+    pub fn parse_table_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        tables: &mut TableSection,
+        section: wasmparser::TableSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for table in section {
+            reencoder.parse_table(tables, table?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_memory_section`].
+    pub fn parse_memory_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        memories: &mut MemorySection,
+        section: wasmparser::MemorySectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for memory in section {
+            reencoder.parse_memory(memories, memory?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_tag_section`].
+    pub fn parse_tag_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        tags: &mut TagSection,
+        section: wasmparser::TagSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for tag in section {
+            reencoder.parse_tag(tags, tag?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_global_section`].
+    pub fn parse_global_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        globals: &mut GlobalSection,
+        section: wasmparser::GlobalSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for global in section {
+            reencoder.parse_global(globals, global?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_export_section`].
+    pub fn parse_export_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        exports: &mut ExportSection,
+        section: wasmparser::ExportSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for export in section {
+            reencoder.parse_export(exports, export?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_element_section`].
+    pub fn parse_element_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        elements: &mut ElementSection,
+        section: wasmparser::ElementSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for element in section {
+            reencoder.parse_element(elements, element?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_data_section`].
+    pub fn parse_data_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        data: &mut DataSection,
+        section: wasmparser::DataSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for segment in section {
+            reencoder.parse_data(data, segment?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_code_section`].
+    pub fn parse_code_section<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        code: &mut CodeSection,
+        section: wasmparser::CodeSectionReader<'_>,
+    ) -> Result<(), Error<T::Error>> {
+        for body in section {
+            reencoder.parse_function_body(code, body?)?;
+        }
+        Ok(())
+    }
+
+    /// The default implementation of [`Reencode::parse_core_module`].
+    pub fn parse_core_module<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        module: &mut Module,
+        parser: wasmparser::Parser,
+        mut data: &[u8],
+    ) -> Result<(), Error<T::Error>> {
+        parse_module_payloads(reencoder, module, parser, &mut data)
+    }
+
+    /// Translates the payloads of a single module, recursing into nested
+    /// modules, and advances `data` past the bytes consumed.
+    fn parse_module_payloads<T: ?Sized + Reencode>(
+        reencoder: &mut T,
+        module: &mut Module,
+        mut parser: wasmparser::Parser,
+        data: &mut &[u8],
+    ) -> Result<(), Error<T::Error>> {
+        // The code and module sections are streamed entry-by-entry, so these
+        // track the section being built along with how many entries are left.
+        let mut code = (CodeSection::new(), 0);
+        let mut modules = (ModuleSection::new(), 0);
+        loop {
+            let input = *data;
+            let payload = match parser.parse(input, true)? {
+                wasmparser::Chunk::Parsed { consumed, payload } => {
+                    *data = &input[consumed..];
+                    payload
+                }
+                // This isn't possible since `eof` is always `true`.
+                wasmparser::Chunk::NeedMoreData(_) => unreachable!(),
+            };
+            match payload {
+                wasmparser::Payload::Version { .. } => {}
+                wasmparser::Payload::TypeSection(section) => {
+                    let mut types = TypeSection::new();
+                    reencoder.parse_type_section(&mut types, section)?;
+                    module.section(&types);
+                }
+                wasmparser::Payload::ImportSection(section) => {
+                    let mut imports = ImportSection::new();
+                    reencoder.parse_import_section(&mut imports, section)?;
+                    module.section(&imports);
+                }
+                wasmparser::Payload::AliasSection(section) => {
+                    let mut aliases = AliasSection::new();
+                    reencoder.parse_alias_section(&mut aliases, section)?;
+                    module.section(&aliases);
+                }
+                wasmparser::Payload::InstanceSection(section) => {
+                    let mut instances = InstanceSection::new();
+                    reencoder.parse_instance_section(&mut instances, section)?;
+                    module.section(&instances);
+                }
+                wasmparser::Payload::FunctionSection(section) => {
+                    let mut functions = FunctionSection::new();
+                    reencoder.parse_function_section(&mut functions, section)?;
+                    module.section(&functions);
+                }
+                wasmparser::Payload::TableSection(section) => {
+                    let mut tables = TableSection::new();
+                    reencoder.parse_table_section(&mut tables, section)?;
+                    module.section(&tables);
+                }
+                wasmparser::Payload::MemorySection(section) => {
+                    let mut memories = MemorySection::new();
+                    reencoder.parse_memory_section(&mut memories, section)?;
+                    module.section(&memories);
+                }
+                wasmparser::Payload::TagSection(section) => {
+                    let mut tags = TagSection::new();
+                    reencoder.parse_tag_section(&mut tags, section)?;
+                    module.section(&tags);
+                }
+                wasmparser::Payload::GlobalSection(section) => {
+                    let mut globals = GlobalSection::new();
+                    reencoder.parse_global_section(&mut globals, section)?;
+                    module.section(&globals);
+                }
+                wasmparser::Payload::ExportSection(section) => {
+                    let mut exports = ExportSection::new();
+                    reencoder.parse_export_section(&mut exports, section)?;
+                    module.section(&exports);
+                }
+                wasmparser::Payload::StartSection { func, .. } => {
+                    module.section(&StartSection {
+                        function_index: reencoder.function_index(func)?,
+                    });
+                }
+                wasmparser::Payload::ElementSection(section) => {
+                    let mut elements = ElementSection::new();
+                    reencoder.parse_element_section(&mut elements, section)?;
+                    module.section(&elements);
+                }
+                wasmparser::Payload::DataCountSection { count, .. } => {
+                    module.section(&DataCountSection { count });
+                }
+                wasmparser::Payload::DataSection(section) => {
+                    let mut data = DataSection::new();
+                    reencoder.parse_data_section(&mut data, section)?;
+                    module.section(&data);
+                }
+                wasmparser::Payload::CustomSection { name, data, .. } => {
+                    reencoder.parse_custom_section(module, name, data)?;
+                }
+                wasmparser::Payload::UnknownSection { id, contents, .. } => {
+                    reencoder.parse_unknown_section(module, id, contents)?;
+                }
+                wasmparser::Payload::CodeSectionStart { count, .. } => {
+                    code = (CodeSection::new(), count);
+                    if count == 0 {
+                        module.section(&code.0);
+                    }
+                }
+                wasmparser::Payload::CodeSectionEntry(body) => {
+                    reencoder.parse_function_body(&mut code.0, body)?;
+                    code.1 -= 1;
+                    if code.1 == 0 {
+                        module.section(&code.0);
+                    }
+                }
+                wasmparser::Payload::ModuleSectionStart { count, .. } => {
+                    modules = (ModuleSection::new(), count);
+                    if count == 0 {
+                        module.section(&modules.0);
+                    }
+                }
+                wasmparser::Payload::ModuleSectionEntry { parser, .. } => {
+                    let mut nested = Module::new();
+                    parse_module_payloads(reencoder, &mut nested, parser, data)?;
+                    modules.0.module(&nested);
+                    modules.1 -= 1;
+                    if modules.1 == 0 {
+                        module.section(&modules.0);
+                    }
+                }
+                wasmparser::Payload::End => return Ok(()),
+            }
+        }
+    }
+}
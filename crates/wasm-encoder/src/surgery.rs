@@ -0,0 +1,817 @@
+//! In-place editing of existing WebAssembly modules.
+//!
+//! [`ModuleSurgery`] parses a module once and then allows individual
+//! sections, imports, exports, and functions to be added, removed, or
+//! replaced before the module is emitted again. Anything that wasn't edited
+//! is copied into the output verbatim, so small rewrites of large modules are
+//! cheap and don't disturb unrelated bytes.
+//!
+//! Adding or removing an import shifts the indices of every item of the same
+//! kind defined after it, and removing a function shifts the functions
+//! defined after it. When that happens, the sections that refer to those
+//! items (code, exports, globals, element and data segments, and the start
+//! section) are rewritten with the [`reencode`](crate::reencode) machinery so
+//! the module stays consistent. The function and code sections are always
+//! edited together, so they can't get out of sync. Custom sections are always copied verbatim,
+//! so index-bearing custom sections such as `name` may become stale.
+//!
+//! Indices passed to and returned by [`ModuleSurgery`] methods always refer
+//! to the edited module. Module-linking modules are not supported.
+//!
+//! This module is only available when the `wasmparser` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use wasm_encoder::builder::ModuleBuilder;
+//! use wasm_encoder::surgery::ModuleSurgery;
+//! use wasm_encoder::{EntityType, Export, Function, Instruction};
+//!
+//! # fn main() -> Result<(), wasm_encoder::surgery::Error> {
+//! // A module whose only function calls itself.
+//! let mut builder = ModuleBuilder::new();
+//! let ty = builder.func_type([], []);
+//! let f = builder.declare_function(ty);
+//! let mut body = Function::new([]);
+//! body.instruction(&Instruction::Call(f.index()));
+//! body.instruction(&Instruction::End);
+//! builder.define_function(f, body);
+//! builder.export("f", f);
+//! let wasm = builder.finish();
+//!
+//! let mut surgery = ModuleSurgery::new(&wasm)?;
+//! let log = surgery.add_import("env", "log", EntityType::Function(ty.index()));
+//! surgery.add_export("log", Export::Function(log));
+//! let edited = surgery.finish()?;
+//!
+//! // The defined function moved from index 0 to 1, and the `call` in its
+//! // body and its export were renumbered to match.
+//! assert!(wasmparser::validate(&edited).is_ok());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::reencode::{self, Reencode};
+use crate::*;
+use std::fmt;
+use wasmparser::SectionReader;
+
+/// The errors produced by [`ModuleSurgery`].
+pub type Error = reencode::Error<RemovedItem>;
+
+/// The error reported when an imported item or function which was removed is
+/// still referenced by the module.
+#[derive(Clone, Debug)]
+pub struct RemovedItem {
+    /// The kind of the removed item.
+    pub kind: ItemKind,
+    /// The index of the removed item in the original module.
+    pub index: u32,
+}
+
+impl fmt::Display for RemovedItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} is still referenced after it was removed",
+            self.kind, self.index
+        )
+    }
+}
+
+impl std::error::Error for RemovedItem {}
+
+/// An editor for an existing WebAssembly module.
+///
+/// See the [module documentation](self) for more details.
+#[derive(Clone, Debug)]
+pub struct ModuleSurgery<'a> {
+    wasm: &'a [u8],
+    sections: Vec<SectionEntry>,
+    imports: Vec<ImportEntry>,
+    imports_edited: bool,
+    original_imports: [u32; KINDS],
+    exports: Vec<ExportEntry>,
+    exports_edited: bool,
+    code: Option<(usize, usize)>,
+    functions: Vec<FunctionEntry>,
+    functions_edited: bool,
+    original_functions: u32,
+}
+
+#[derive(Clone, Debug)]
+struct SectionEntry {
+    id: u8,
+    contents: Contents,
+}
+
+#[derive(Clone, Debug)]
+enum Contents {
+    Original { start: usize, end: usize },
+    Replaced(Vec<u8>),
+}
+
+#[derive(Clone, Debug)]
+struct ImportEntry {
+    module: String,
+    field: String,
+    ty: EntityType,
+    /// The index of this import in the original module, or `None` if it was
+    /// added.
+    original: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+struct FunctionEntry {
+    /// The index of this function's type.
+    ty: u32,
+    /// The index of this function among the defined functions of the
+    /// original module, or `None` if it was added.
+    original: Option<u32>,
+    /// The body replacing the original one, which is always present for
+    /// added functions.
+    body: Option<Function>,
+}
+
+#[derive(Clone, Debug)]
+struct ExportEntry {
+    name: String,
+    export: Export,
+    /// Whether the index in `export` refers to the original module.
+    original: bool,
+}
+
+const KINDS: usize = 5;
+
+impl<'a> ModuleSurgery<'a> {
+    /// Parses `wasm` in preparation for editing it.
+    pub fn new(wasm: &'a [u8]) -> Result<Self, Error> {
+        let mut surgery = ModuleSurgery {
+            wasm,
+            sections: Vec::new(),
+            imports: Vec::new(),
+            imports_edited: false,
+            original_imports: [0; KINDS],
+            exports: Vec::new(),
+            exports_edited: false,
+            code: None,
+            functions: Vec::new(),
+            functions_edited: false,
+            original_functions: 0,
+        };
+        let mut reencoder = reencode::RoundtripReencoder;
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            let (id, range) = match payload? {
+                wasmparser::Payload::Version { .. } => continue,
+                wasmparser::Payload::End => break,
+                wasmparser::Payload::TypeSection(s) => (SectionId::Type, s.range()),
+                wasmparser::Payload::ImportSection(s) => {
+                    for import in s.clone() {
+                        let import = import?;
+                        let field = import
+                            .field
+                            .ok_or(Error::Unsupported("single-level imports"))?;
+                        let ty = reencoder.entity_type(import.ty).map_err(never)?;
+                        let kind = kind_index(&ty)?;
+                        surgery.imports.push(ImportEntry {
+                            module: import.module.to_string(),
+                            field: field.to_string(),
+                            ty,
+                            original: Some(surgery.original_imports[kind]),
+                        });
+                        surgery.original_imports[kind] += 1;
+                    }
+                    (SectionId::Import, s.range())
+                }
+                wasmparser::Payload::FunctionSection(s) => {
+                    for ty in s.clone() {
+                        surgery.functions.push(FunctionEntry {
+                            ty: ty?,
+                            original: Some(surgery.original_functions),
+                            body: None,
+                        });
+                        surgery.original_functions += 1;
+                    }
+                    (SectionId::Function, s.range())
+                }
+                wasmparser::Payload::TableSection(s) => (SectionId::Table, s.range()),
+                wasmparser::Payload::MemorySection(s) => (SectionId::Memory, s.range()),
+                wasmparser::Payload::TagSection(s) => (SectionId::Tag, s.range()),
+                wasmparser::Payload::GlobalSection(s) => (SectionId::Global, s.range()),
+                wasmparser::Payload::ExportSection(s) => {
+                    for export in s.clone() {
+                        let export = export?;
+                        surgery.exports.push(ExportEntry {
+                            name: export.field.to_string(),
+                            export: reencoder.export(export.kind, export.index).map_err(never)?,
+                            original: true,
+                        });
+                    }
+                    (SectionId::Export, s.range())
+                }
+                wasmparser::Payload::StartSection { range, .. } => (SectionId::Start, range),
+                wasmparser::Payload::ElementSection(s) => (SectionId::Element, s.range()),
+                wasmparser::Payload::DataCountSection { range, .. } => {
+                    (SectionId::DataCount, range)
+                }
+                wasmparser::Payload::DataSection(s) => (SectionId::Data, s.range()),
+                wasmparser::Payload::CodeSectionStart { count, range, .. } => {
+                    if count != surgery.original_functions {
+                        return Err(Error::Unsupported(
+                            "function and code section counts differ",
+                        ));
+                    }
+                    surgery.code = Some((range.start, range.end));
+                    (SectionId::Code, range)
+                }
+                wasmparser::Payload::CodeSectionEntry(_) => continue,
+                wasmparser::Payload::CustomSection { range, .. } => (SectionId::Custom, range),
+                wasmparser::Payload::UnknownSection { id, range, .. } => {
+                    surgery.sections.push(SectionEntry {
+                        id,
+                        contents: Contents::Original {
+                            start: range.start,
+                            end: range.end,
+                        },
+                    });
+                    continue;
+                }
+                wasmparser::Payload::AliasSection(_)
+                | wasmparser::Payload::InstanceSection(_)
+                | wasmparser::Payload::ModuleSectionStart { .. }
+                | wasmparser::Payload::ModuleSectionEntry { .. } => {
+                    return Err(Error::Unsupported("module linking"))
+                }
+            };
+            surgery.sections.push(SectionEntry {
+                id: id.into(),
+                contents: Contents::Original {
+                    start: range.start,
+                    end: range.end,
+                },
+            });
+        }
+        Ok(surgery)
+    }
+
+    /// Returns the sections of the module, in order.
+    ///
+    /// The position of a section in this iterator is the `index` accepted by
+    /// the other section methods. The import, export, function, and code
+    /// sections are returned as they were originally, without any structured
+    /// edits.
+    pub fn sections(&self) -> impl ExactSizeIterator<Item = RawSection<'_>> + '_ {
+        self.sections.iter().map(move |s| RawSection {
+            id: s.id,
+            data: self.contents(s),
+        })
+    }
+
+    /// Returns the index of the first section with the given `id`.
+    pub fn position(&self, id: SectionId) -> Option<usize> {
+        let id = u8::from(id);
+        self.sections.iter().position(|s| s.id == id)
+    }
+
+    /// Replaces the `index`th section with `section`.
+    ///
+    /// The new section is emitted as-is, so any indices within it must
+    /// already refer to the edited module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if either section is an import,
+    /// export, function, or code section, which are edited with the
+    /// dedicated methods.
+    pub fn replace_section(&mut self, index: usize, section: &impl Section) -> &mut Self {
+        assert_editable(self.sections[index].id);
+        assert_editable(section.id());
+        self.sections[index] = SectionEntry {
+            id: section.id(),
+            contents: Contents::Replaced(section_contents(section)),
+        };
+        self
+    }
+
+    /// Removes the `index`th section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if the section is an import,
+    /// export, function, or code section.
+    pub fn remove_section(&mut self, index: usize) -> &mut Self {
+        assert_editable(self.sections[index].id);
+        self.sections.remove(index);
+        self
+    }
+
+    /// Inserts `section` so that it becomes the `index`th section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of sections, or if
+    /// `section` is an import, export, function, or code section.
+    pub fn insert_section(&mut self, index: usize, section: &impl Section) -> &mut Self {
+        assert_editable(section.id());
+        self.sections.insert(
+            index,
+            SectionEntry {
+                id: section.id(),
+                contents: Contents::Replaced(section_contents(section)),
+            },
+        );
+        self
+    }
+
+    /// Adds `section` at its canonical position: custom sections are
+    /// appended to the end of the module, and known sections are inserted
+    /// after the sections that must precede them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `section` is an import, export, function, or code section.
+    pub fn add_section(&mut self, section: &impl Section) -> &mut Self {
+        let index = self.canonical_position(section.id());
+        self.insert_section(index, section)
+    }
+
+    /// Removes every custom section with the given `name`, returning how
+    /// many were removed.
+    pub fn remove_custom_sections(&mut self, name: &str) -> usize {
+        let before = self.sections.len();
+        let wasm = self.wasm;
+        self.sections.retain(|s| {
+            if s.id != u8::from(SectionId::Custom) {
+                return true;
+            }
+            let data = match &s.contents {
+                Contents::Original { start, end } => &wasm[*start..*end],
+                Contents::Replaced(data) => data,
+            };
+            let mut reader = wasmparser::BinaryReader::new(data);
+            reader.read_string().map(|n| n != name).unwrap_or(true)
+        });
+        before - self.sections.len()
+    }
+
+    /// Returns the module's imports as `(module, field, type)` triples.
+    pub fn imports(&self) -> impl ExactSizeIterator<Item = (&str, &str, &EntityType)> + '_ {
+        self.imports
+            .iter()
+            .map(|i| (i.module.as_str(), i.field.as_str(), &i.ty))
+    }
+
+    /// Adds an import and returns its index in the index space of its kind.
+    ///
+    /// The new import is placed after the existing imports of its kind, so
+    /// every defined item of that kind moves up by one index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ty` is a module-linking instance or module type.
+    pub fn add_import(&mut self, module: &str, field: &str, ty: EntityType) -> u32 {
+        let kind = kind_index(&ty).expect("module linking imports are not supported");
+        let index = self.imported(kind);
+        self.imports.push(ImportEntry {
+            module: module.to_string(),
+            field: field.to_string(),
+            ty,
+            original: None,
+        });
+        self.ensure_section(SectionId::Import);
+        self.imports_edited = true;
+        index
+    }
+
+    /// Removes the first import of `module` and `field`, returning its type,
+    /// or `None` if there is no such import.
+    ///
+    /// Items of the same kind after the removed import move down by one
+    /// index. The removed item must no longer be referenced by the module
+    /// when it is finished.
+    pub fn remove_import(&mut self, module: &str, field: &str) -> Option<EntityType> {
+        let i = self
+            .imports
+            .iter()
+            .position(|i| i.module == module && i.field == field)?;
+        self.imports_edited = true;
+        Some(self.imports.remove(i).ty)
+    }
+
+    /// Returns the module's exports.
+    pub fn exports(&self) -> impl ExactSizeIterator<Item = (&str, Export)> + '_ {
+        self.exports.iter().map(|e| (e.name.as_str(), e.export))
+    }
+
+    /// Adds an export of the item at the given index of the edited module.
+    pub fn add_export(&mut self, name: &str, export: Export) -> &mut Self {
+        self.exports.push(ExportEntry {
+            name: name.to_string(),
+            export,
+            original: false,
+        });
+        self.ensure_section(SectionId::Export);
+        self.exports_edited = true;
+        self
+    }
+
+    /// Removes the export named `name`, returning what it exported, or
+    /// `None` if there is no such export.
+    ///
+    /// The returned index refers to the original module for exports that
+    /// were not added with [`ModuleSurgery::add_export`].
+    pub fn remove_export(&mut self, name: &str) -> Option<Export> {
+        let i = self.exports.iter().position(|e| e.name == name)?;
+        self.exports_edited = true;
+        Some(self.exports.remove(i).export)
+    }
+
+    /// Replaces the body of the function at `index` in the edited module's
+    /// function index space.
+    ///
+    /// The new body is emitted as-is, so any indices within it must already
+    /// refer to the edited module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` refers to an imported function or is out of bounds.
+    pub fn replace_function_body(&mut self, index: u32, body: &Function) -> &mut Self {
+        let defined = self.defined_function(index);
+        self.functions[defined].body = Some(body.clone());
+        self
+    }
+
+    /// Adds a function of the type at `type_index` with the given `body`,
+    /// returning its index in the edited module.
+    ///
+    /// The new function is placed after every other function. Its body is
+    /// emitted as-is, so any indices within it must already refer to the
+    /// edited module.
+    pub fn add_function(&mut self, type_index: u32, body: &Function) -> u32 {
+        self.functions.push(FunctionEntry {
+            ty: type_index,
+            original: None,
+            body: Some(body.clone()),
+        });
+        self.ensure_section(SectionId::Function);
+        self.ensure_section(SectionId::Code);
+        self.functions_edited = true;
+        self.imported(0) + self.functions.len() as u32 - 1
+    }
+
+    /// Removes the function at `index` in the edited module's function index
+    /// space.
+    ///
+    /// Functions defined after the removed one move down by one index. The
+    /// removed function must no longer be referenced by the module when it is
+    /// finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` refers to an imported function or is out of bounds.
+    pub fn remove_function(&mut self, index: u32) -> &mut Self {
+        let defined = self.defined_function(index);
+        self.functions.remove(defined);
+        self.functions_edited = true;
+        self
+    }
+
+    /// Emits the edited module.
+    ///
+    /// Returns an error if the module references an imported item or
+    /// function which was removed, or if a section that needs renumbering
+    /// fails to parse.
+    pub fn finish(&self) -> Result<Vec<u8>, Error> {
+        let mut renumber = Renumber {
+            maps: Default::default(),
+        };
+        for kind in 0..KINDS {
+            let mut map = vec![None; self.original_imports[kind] as usize];
+            let mut new_imports = 0;
+            for import in self
+                .imports
+                .iter()
+                .filter(|i| kind_index(&i.ty).ok() == Some(kind))
+            {
+                if let Some(original) = import.original {
+                    map[original as usize] = Some(new_imports);
+                }
+                new_imports += 1;
+            }
+            renumber.maps[kind] = IndexMap {
+                imports: map,
+                defined: Vec::new(),
+                shift: i64::from(new_imports) - i64::from(self.original_imports[kind]),
+            };
+        }
+        let new_imports = self.imported(0);
+        let functions = &mut renumber.maps[0].defined;
+        *functions = vec![None; self.original_functions as usize];
+        for (i, func) in self.functions.iter().enumerate() {
+            if let Some(original) = func.original {
+                functions[original as usize] = Some(new_imports + i as u32);
+            }
+        }
+        let renumbering = renumber.maps.iter().any(|m| !m.is_identity());
+
+        let mut module = Module::new();
+        for section in self.sections.iter() {
+            let data = self.contents(section);
+            let original = match section.contents {
+                Contents::Original { start, .. } => Some(start),
+                Contents::Replaced(_) => None,
+            };
+            let rewrite = |id| renumbering && original.is_some() && section.id == u8::from(id);
+            if section.id == u8::from(SectionId::Import) && self.imports_edited {
+                let mut imports = ImportSection::new();
+                for i in self.imports.iter() {
+                    imports.import(&i.module, Some(&i.field), i.ty);
+                }
+                if !imports.is_empty() {
+                    module.section(&imports);
+                }
+            } else if section.id == u8::from(SectionId::Export)
+                && (self.exports_edited || renumbering)
+            {
+                let mut exports = ExportSection::new();
+                for e in self.exports.iter() {
+                    let export = if e.original {
+                        renumber.remap_export(e.export)?
+                    } else {
+                        e.export
+                    };
+                    exports.export(&e.name, export);
+                }
+                if !exports.is_empty() {
+                    module.section(&exports);
+                }
+            } else if section.id == u8::from(SectionId::Function) && self.functions_edited {
+                let mut functions = FunctionSection::new();
+                for func in self.functions.iter() {
+                    functions.function(func.ty);
+                }
+                if !functions.is_empty() {
+                    module.section(&functions);
+                }
+            } else if section.id == u8::from(SectionId::Code)
+                && (renumbering
+                    || self.functions_edited
+                    || self.functions.iter().any(|f| f.body.is_some()))
+            {
+                let bodies = match self.code {
+                    Some((start, end)) => {
+                        wasmparser::CodeSectionReader::new(&self.wasm[start..end], start)?
+                            .into_iter()
+                            .collect::<Result<Vec<_>, _>>()?
+                    }
+                    None => Vec::new(),
+                };
+                let mut code = CodeSection::new();
+                for func in self.functions.iter() {
+                    if let Some(f) = &func.body {
+                        code.function(f);
+                        continue;
+                    }
+                    // Functions without a replacement body are original
+                    // ones, whose bodies were checked to exist in `new`.
+                    let body = bodies[func.original.unwrap() as usize];
+                    if renumbering {
+                        renumber.parse_function_body(&mut code, body)?;
+                    } else {
+                        let range = body.range();
+                        code.raw(&self.wasm[range.start..range.end]);
+                    }
+                }
+                if !code.is_empty() {
+                    module.section(&code);
+                }
+            } else if rewrite(SectionId::Global) {
+                let mut globals = GlobalSection::new();
+                let reader = wasmparser::GlobalSectionReader::new(data, original.unwrap())?;
+                renumber.parse_global_section(&mut globals, reader)?;
+                module.section(&globals);
+            } else if rewrite(SectionId::Element) {
+                let mut elements = ElementSection::new();
+                let reader = wasmparser::ElementSectionReader::new(data, original.unwrap())?;
+                renumber.parse_element_section(&mut elements, reader)?;
+                module.section(&elements);
+            } else if rewrite(SectionId::Data) {
+                let mut segments = DataSection::new();
+                let reader = wasmparser::DataSectionReader::new(data, original.unwrap())?;
+                renumber.parse_data_section(&mut segments, reader)?;
+                module.section(&segments);
+            } else if rewrite(SectionId::Start) {
+                let func = wasmparser::BinaryReader::new_with_offset(data, original.unwrap())
+                    .read_var_u32()?;
+                module.section(&StartSection {
+                    function_index: renumber.function_index(func)?,
+                });
+            } else {
+                module.section(&RawSection {
+                    id: section.id,
+                    data,
+                });
+            }
+        }
+        Ok(module.finish())
+    }
+
+    fn contents<'b>(&'b self, section: &'b SectionEntry) -> &'b [u8] {
+        match &section.contents {
+            Contents::Original { start, end } => &self.wasm[*start..*end],
+            Contents::Replaced(data) => data,
+        }
+    }
+
+    /// Returns the index among the defined functions of the function at
+    /// `index` in the edited module.
+    fn defined_function(&self, index: u32) -> usize {
+        let defined = index
+            .checked_sub(self.imported(0))
+            .expect("function is imported rather than defined") as usize;
+        assert!(
+            defined < self.functions.len(),
+            "function index {} is out of bounds",
+            index
+        );
+        defined
+    }
+
+    /// Returns the number of imports of the given kind in the edited module.
+    fn imported(&self, kind: usize) -> u32 {
+        self.imports
+            .iter()
+            .filter(|i| kind_index(&i.ty).ok() == Some(kind))
+            .count() as u32
+    }
+
+    /// Makes sure the module has a section with the given `id`, whose
+    /// contents are produced from structured edits when it is finished.
+    fn ensure_section(&mut self, id: SectionId) {
+        if self.position(id).is_none() {
+            let index = self.canonical_position(id.into());
+            self.sections.insert(
+                index,
+                SectionEntry {
+                    id: id.into(),
+                    contents: Contents::Replaced(Vec::new()),
+                },
+            );
+        }
+    }
+
+    fn canonical_position(&self, id: u8) -> usize {
+        let rank = match canonical_rank(id) {
+            Some(rank) => rank,
+            None => return self.sections.len(),
+        };
+        self.sections
+            .iter()
+            .position(|s| matches!(canonical_rank(s.id), Some(r) if r > rank))
+            .unwrap_or(self.sections.len())
+    }
+}
+
+/// The position of a known, non-custom section in a valid module.
+fn canonical_rank(id: u8) -> Option<usize> {
+    const ORDER: [SectionId; 13] = [
+        SectionId::Type,
+        SectionId::Import,
+        SectionId::Function,
+        SectionId::Table,
+        SectionId::Memory,
+        SectionId::Tag,
+        SectionId::Global,
+        SectionId::Export,
+        SectionId::Start,
+        SectionId::Element,
+        SectionId::DataCount,
+        SectionId::Code,
+        SectionId::Data,
+    ];
+    ORDER.iter().position(|s| u8::from(*s) == id)
+}
+
+fn assert_editable(id: u8) {
+    assert!(
+        id != u8::from(SectionId::Import)
+            && id != u8::from(SectionId::Export)
+            && id != u8::from(SectionId::Function)
+            && id != u8::from(SectionId::Code),
+        "import, export, function, and code sections must be edited through their dedicated \
+         methods"
+    );
+}
+
+/// Encodes `section` and strips its leading size.
+fn section_contents(section: &impl Section) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    section.encode(&mut bytes);
+    let mut reader = wasmparser::BinaryReader::new(&bytes);
+    reader.read_var_u32().unwrap();
+    let start = reader.current_position();
+    bytes.split_off(start)
+}
+
+fn kind_index(ty: &EntityType) -> Result<usize, Error> {
+    Ok(match ty {
+        EntityType::Function(_) => 0,
+        EntityType::Table(_) => 1,
+        EntityType::Memory(_) => 2,
+        EntityType::Global(_) => 3,
+        EntityType::Tag(_) => 4,
+        EntityType::Instance(_) | EntityType::Module(_) => {
+            return Err(Error::Unsupported("module linking"))
+        }
+    })
+}
+
+fn never(e: reencode::Error) -> Error {
+    match e {
+        reencode::Error::ParseError(e) => Error::ParseError(e),
+        reencode::Error::Unsupported(s) => Error::Unsupported(s),
+        reencode::Error::InvalidConstExpr => Error::InvalidConstExpr,
+        reencode::Error::UserError(e) => match e {},
+    }
+}
+
+/// How the indices of one kind of item change between the original and the
+/// edited module.
+#[derive(Default)]
+struct IndexMap {
+    /// The new index of each original import, or `None` if it was removed.
+    imports: Vec<Option<u32>>,
+    /// The new index of each original defined item, or `None` if it was
+    /// removed. This is only tracked for functions.
+    defined: Vec<Option<u32>>,
+    /// How far defined items which aren't in `defined` move.
+    shift: i64,
+}
+
+impl IndexMap {
+    fn is_identity(&self) -> bool {
+        self.shift == 0
+            && self
+                .imports
+                .iter()
+                .chain(&self.defined)
+                .enumerate()
+                .all(|(i, new)| *new == Some(i as u32))
+    }
+
+    fn remap(&self, kind: ItemKind, index: u32) -> Result<u32, Error> {
+        let new = match self.imports.get(index as usize) {
+            Some(new) => *new,
+            None => match self.defined.get(index as usize - self.imports.len()) {
+                Some(new) => *new,
+                None => return Ok((i64::from(index) + self.shift) as u32),
+            },
+        };
+        new.ok_or(Error::UserError(RemovedItem { kind, index }))
+    }
+}
+
+/// Rewrites references to items in the original module into references to
+/// the edited module.
+struct Renumber {
+    maps: [IndexMap; KINDS],
+}
+
+impl Renumber {
+    fn remap_export(&self, export: Export) -> Result<Export, Error> {
+        Ok(match export {
+            Export::Function(i) => Export::Function(self.maps[0].remap(ItemKind::Function, i)?),
+            Export::Table(i) => Export::Table(self.maps[1].remap(ItemKind::Table, i)?),
+            Export::Memory(i) => Export::Memory(self.maps[2].remap(ItemKind::Memory, i)?),
+            Export::Global(i) => Export::Global(self.maps[3].remap(ItemKind::Global, i)?),
+            Export::Tag(i) => Export::Tag(self.maps[4].remap(ItemKind::Tag, i)?),
+            Export::Instance(_) | Export::Module(_) => {
+                return Err(Error::Unsupported("module linking"))
+            }
+        })
+    }
+}
+
+impl Reencode for Renumber {
+    type Error = RemovedItem;
+
+    fn function_index(&mut self, func: u32) -> Result<u32, Error> {
+        self.maps[0].remap(ItemKind::Function, func)
+    }
+
+    fn table_index(&mut self, table: u32) -> Result<u32, Error> {
+        self.maps[1].remap(ItemKind::Table, table)
+    }
+
+    fn memory_index(&mut self, memory: u32) -> Result<u32, Error> {
+        self.maps[2].remap(ItemKind::Memory, memory)
+    }
+
+    fn global_index(&mut self, global: u32) -> Result<u32, Error> {
+        self.maps[3].remap(ItemKind::Global, global)
+    }
+
+    fn tag_index(&mut self, tag: u32) -> Result<u32, Error> {
+        self.maps[4].remap(ItemKind::Tag, tag)
+    }
+}
@@ -70,6 +70,7 @@ impl Section for TableSection {
 
 /// A table's type.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableType {
     /// The table's element type.
     pub element_type: ValType,
@@ -71,12 +71,14 @@ impl Section for TagSection {
 #[allow(missing_docs)]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TagKind {
     Exception = 0x0,
 }
 
 /// A tag's type.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TagType {
     /// The kind of tag
     pub kind: TagKind,
@@ -86,7 +88,11 @@ pub struct TagType {
 
 impl TagType {
     pub(crate) fn encode(&self, bytes: &mut Vec<u8>) {
+        self.encode_with(bytes, false)
+    }
+
+    pub(crate) fn encode_with(&self, bytes: &mut Vec<u8>, padded: bool) {
         bytes.push(self.kind as u8);
-        bytes.extend(encoders::u32(self.func_type_idx));
+        bytes.extend(code::leb_u32(self.func_type_idx, padded));
     }
 }
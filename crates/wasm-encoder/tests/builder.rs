@@ -0,0 +1,153 @@
+use wasm_encoder::builder::ModuleBuilder;
+use wasm_encoder::*;
+
+fn body(instrs: &[Instruction]) -> Function {
+    let mut f = Function::new([]);
+    for i in instrs {
+        f.instruction(i);
+    }
+    f.instruction(&Instruction::End);
+    f
+}
+
+#[test]
+fn dedup_types() {
+    let mut builder = ModuleBuilder::new();
+    let a = builder.func_type([ValType::I32], []);
+    let b = builder.func_type([], [ValType::I32]);
+    let c = builder.func_type(vec![ValType::I32], vec![]);
+    assert_eq!(a, c);
+    assert_ne!(a, b);
+    assert_eq!(b.index(), 1);
+}
+
+#[test]
+fn indices_account_for_imports() {
+    let mut builder = ModuleBuilder::new();
+    let ty = builder.func_type([], []);
+    let imported = builder.import_func("env", "f", ty);
+    let global = builder.import_global(
+        "env",
+        "g",
+        GlobalType {
+            val_type: ValType::I32,
+            mutable: false,
+        },
+    );
+    let memory = builder.import_memory(
+        "env",
+        "m",
+        MemoryType {
+            minimum: 1,
+            maximum: None,
+            memory64: false,
+            shared: false,
+        },
+    );
+
+    // Declare `a` before `b` is built so that `b` can call it.
+    let a = builder.declare_function(ty);
+    let b = builder.function(ty, body(&[Instruction::Call(a.index())]));
+    builder.define_function(a, body(&[Instruction::Call(imported.index())]));
+    let defined = builder.global(
+        GlobalType {
+            val_type: ValType::I32,
+            mutable: true,
+        },
+        &Instruction::GlobalGet(global.index()),
+    );
+    let data = builder.passive_data([1, 2, 3]);
+    let init = builder.function(
+        ty,
+        body(&[
+            Instruction::I32Const(0),
+            Instruction::I32Const(0),
+            Instruction::I32Const(3),
+            Instruction::MemoryInit {
+                mem: memory.index(),
+                data: data.index(),
+            },
+        ]),
+    );
+    builder.export("a", a);
+    builder.export("b", b);
+    builder.export("global", defined);
+    builder.start(init);
+    builder.custom_section("hello", b"world");
+
+    assert_eq!(imported.index(), 0);
+    assert_eq!(a.index(), 1);
+    assert_eq!(b.index(), 2);
+    assert_eq!(init.index(), 3);
+    assert_eq!(global.index(), 0);
+    assert_eq!(defined.index(), 1);
+
+    let wasm = builder.finish();
+    wasmparser::validate(&wasm).unwrap();
+    let mut ids = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&wasm) {
+        use wasmparser::Payload::*;
+        ids.push(match payload.unwrap() {
+            TypeSection(_) => "type",
+            ImportSection(_) => "import",
+            FunctionSection(_) => "function",
+            GlobalSection(_) => "global",
+            ExportSection(_) => "export",
+            StartSection { .. } => "start",
+            DataCountSection { .. } => "datacount",
+            CodeSectionStart { .. } => "code",
+            DataSection(_) => "data",
+            CustomSection { .. } => "custom",
+            _ => continue,
+        });
+    }
+    assert_eq!(
+        ids,
+        [
+            "type",
+            "import",
+            "function",
+            "global",
+            "export",
+            "start",
+            "datacount",
+            "code",
+            "data",
+            "custom"
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot import a function after a function has been defined")]
+fn import_after_definition() {
+    let mut builder = ModuleBuilder::new();
+    let ty = builder.func_type([], []);
+    builder.function(ty, body(&[]));
+    builder.import_func("env", "f", ty);
+}
+
+#[test]
+#[should_panic(expected = "function 0 was declared but never defined")]
+fn missing_body() {
+    let mut builder = ModuleBuilder::new();
+    let ty = builder.func_type([], []);
+    builder.declare_function(ty);
+    builder.finish();
+}
+
+#[test]
+#[cfg(feature = "wasmparser")]
+fn data_drop_of_active_segment() {
+    let mut builder = ModuleBuilder::new();
+    let memory = builder.memory(MemoryType {
+        minimum: 1,
+        maximum: None,
+        memory64: false,
+        shared: false,
+    });
+    let data = builder.active_data(memory, &Instruction::I32Const(0), [1, 2, 3]);
+    let ty = builder.func_type([], []);
+    builder.function(ty, body(&[Instruction::DataDrop(data.index())]));
+    builder.finish_validated().unwrap();
+}
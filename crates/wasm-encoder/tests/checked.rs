@@ -0,0 +1,129 @@
+#![cfg(feature = "wasmparser")]
+
+use wasm_encoder::checked::{CheckedFunction, Resources};
+use wasm_encoder::*;
+
+#[test]
+fn valid_body() {
+    let mut resources = Resources::new();
+    let ty = resources.func_type([ValType::I32, ValType::I32], [ValType::I32]);
+    let f = resources.function(ty);
+    resources.memory(MemoryType {
+        minimum: 1,
+        maximum: None,
+        memory64: false,
+        shared: false,
+    });
+
+    let mut body =
+        CheckedFunction::new(ty, [(1, ValType::I64)], &resources, &Default::default()).unwrap();
+    body.instruction(&Instruction::LocalGet(0))
+        .unwrap()
+        .instruction(&Instruction::I32Load(MemArg {
+            offset: 0,
+            align: 2,
+            memory_index: 0,
+        }))
+        .unwrap()
+        .instruction(&Instruction::LocalGet(1))
+        .unwrap();
+    assert_eq!(body.stack(), [Some(ValType::I32), Some(ValType::I32)]);
+    body.instruction(&Instruction::Call(f))
+        .unwrap()
+        .instruction(&Instruction::End)
+        .unwrap();
+    assert_eq!(body.len(), 5);
+    let checked = body.finish().unwrap();
+
+    let mut unchecked = Function::new([(1, ValType::I64)]);
+    unchecked
+        .instruction(&Instruction::LocalGet(0))
+        .instruction(&Instruction::I32Load(MemArg {
+            offset: 0,
+            align: 2,
+            memory_index: 0,
+        }))
+        .instruction(&Instruction::LocalGet(1))
+        .instruction(&Instruction::Call(f))
+        .instruction(&Instruction::End);
+    assert_eq!(checked, unchecked);
+}
+
+#[test]
+fn type_mismatch() {
+    let mut resources = Resources::new();
+    let ty = resources.func_type([], []);
+    let mut body = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+    body.instruction(&Instruction::I32Const(1)).unwrap();
+    body.instruction(&Instruction::F32Const(2.0)).unwrap();
+    let err = body.instruction(&Instruction::I32Add).unwrap_err();
+    assert_eq!(err.index, 2);
+    assert_eq!(err.stack, [Some(ValType::I32), Some(ValType::F32)]);
+    assert_eq!(err.expected, [Some(ValType::I32)]);
+    assert!(err.message.contains("type mismatch"), "{}", err);
+    assert!(err.to_string().ends_with("(stack: [I32, F32])"), "{}", err);
+}
+
+#[test]
+fn expected_operands() {
+    let mut resources = Resources::new();
+    let ty = resources.func_type([], []);
+
+    // The second operand has the wrong type.
+    let mut body = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+    body.instruction(&Instruction::F32Const(1.0)).unwrap();
+    body.instruction(&Instruction::I64Const(2)).unwrap();
+    let err = body.instruction(&Instruction::I64Eq).unwrap_err();
+    assert_eq!(err.stack, [Some(ValType::F32), Some(ValType::I64)]);
+    assert_eq!(err.expected, [Some(ValType::I64), Some(ValType::I64)]);
+
+    // The second operand is missing.
+    let mut body = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+    body.instruction(&Instruction::I32Const(1)).unwrap();
+    let err = body.instruction(&Instruction::I32Sub).unwrap_err();
+    assert_eq!(err.stack, [Some(ValType::I32)]);
+    assert_eq!(err.expected, [Some(ValType::I32), Some(ValType::I32)]);
+
+    // Errors other than operand mismatches have no expected operands.
+    let mut body = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+    let err = body.instruction(&Instruction::LocalGet(0)).unwrap_err();
+    assert!(err.expected.is_empty());
+}
+
+#[test]
+fn unknown_operands() {
+    let mut resources = Resources::new();
+    let ty = resources.func_type([], [ValType::I32]);
+    let mut body = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+    body.instruction(&Instruction::Unreachable)
+        .unwrap()
+        .instruction(&Instruction::Drop)
+        .unwrap()
+        .instruction(&Instruction::I64Const(0))
+        .unwrap();
+    let err = body.instruction(&Instruction::End).unwrap_err();
+    assert_eq!(err.index, 3);
+    assert_eq!(err.stack, [Some(ValType::I64)]);
+}
+
+#[test]
+fn unknown_index() {
+    let mut resources = Resources::new();
+    let ty = resources.func_type([], []);
+    let mut body = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+    let err = body.instruction(&Instruction::GlobalGet(0)).unwrap_err();
+    assert_eq!(err.index, 0);
+    assert!(err.stack.is_empty());
+
+    assert!(CheckedFunction::new(1, [], &resources, &Default::default()).is_err());
+}
+
+#[test]
+fn missing_end() {
+    let mut resources = Resources::new();
+    let ty = resources.func_type([], []);
+    let mut body = CheckedFunction::new(ty, [], &resources, &Default::default()).unwrap();
+    body.instruction(&Instruction::Nop).unwrap();
+    let err = body.finish().unwrap_err();
+    assert_eq!(err.index, 1);
+}
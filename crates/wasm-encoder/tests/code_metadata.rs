@@ -0,0 +1,67 @@
+use anyhow::Result;
+use wasm_encoder::*;
+use wasmparser::{CodeMetadataSectionReader, Parser, Payload};
+
+#[test]
+fn branch_hints_roundtrip() -> Result<()> {
+    let mut types = TypeSection::new();
+    types.function([ValType::I32], []);
+    let mut functions = FunctionSection::new();
+    functions.function(0);
+    functions.function(0);
+
+    let mut code = CodeSection::new();
+    let mut hints = CodeMetadataSection::branch_hints();
+    for likely in [false, true] {
+        let mut f = Function::new([]);
+        f.record_offsets();
+        f.instruction(&Instruction::Block(BlockType::Empty))
+            .instruction(&Instruction::LocalGet(0))
+            .instruction(&Instruction::BrIf(0))
+            .instruction(&Instruction::LocalGet(0))
+            .instruction(&Instruction::If(BlockType::Empty))
+            .instruction(&Instruction::End)
+            .instruction(&Instruction::End)
+            .instruction(&Instruction::End);
+        let offsets = f.instruction_offsets().unwrap();
+        let mut metadata = CodeMetadata::new();
+        metadata
+            .branch_hint(offsets[2] as u32, likely)
+            .branch_hint(offsets[4] as u32, !likely);
+        hints.function(code.len(), &metadata);
+        code.function(&f);
+    }
+
+    let mut module = Module::new();
+    module
+        .section(&types)
+        .section(&functions)
+        .section(&hints)
+        .section(&code);
+    let wasm = module.finish();
+    wasmparser::validate(&wasm)?;
+
+    let mut actual = Vec::new();
+    for payload in Parser::new(0).parse_all(&wasm) {
+        if let Payload::CustomSection {
+            name: "metadata.code.branch_hint",
+            data,
+            data_offset,
+            ..
+        } = payload?
+        {
+            for func in CodeMetadataSectionReader::new(data, data_offset)? {
+                let func = func?;
+                for hint in func.get_code_metadata_reader() {
+                    let hint = hint?;
+                    actual.push((func.func, hint.offset, hint.branch_hint()?));
+                }
+            }
+        }
+    }
+    assert_eq!(
+        actual,
+        [(0, 5, false), (0, 9, true), (1, 5, true), (1, 9, false)]
+    );
+    Ok(())
+}
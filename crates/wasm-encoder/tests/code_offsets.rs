@@ -0,0 +1,94 @@
+use anyhow::Result;
+use wasm_encoder::*;
+use wasmparser::{Parser, Payload};
+
+#[test]
+fn offsets_match_wasmparser() -> Result<()> {
+    let mut types = TypeSection::new();
+    types.function([], []);
+    let mut functions = FunctionSection::new();
+    let mut code = CodeSection::new();
+
+    // Enough functions for the section's count to need a multi-byte LEB, and
+    // bodies of varying sizes.
+    for i in 0..200 {
+        functions.function(0);
+        let mut f = Function::new([(1, ValType::I64)]);
+        f.record_offsets();
+        for j in 0..i {
+            f.instruction(&Instruction::I64Const(j * 1000));
+            f.instruction(&Instruction::LocalSet(0));
+        }
+        f.instruction(&Instruction::End);
+        if i % 7 == 0 {
+            // Raw bodies have a range but no recorded instructions.
+            let mut raw = Function::new([]);
+            raw.instruction(&Instruction::End);
+            let mut bytes = Vec::new();
+            CodeSection::new().function(&raw).encode(&mut bytes);
+            code.raw(&bytes[3..]);
+        } else {
+            code.function(&f);
+        }
+    }
+    let offsets = code.function_offsets();
+
+    let mut module = Module::new();
+    module.section(&types).section(&functions).section(&code);
+    let wasm = module.finish();
+    wasmparser::validate(&wasm)?;
+
+    let mut section_start = 0;
+    let mut actual = Vec::new();
+    for payload in Parser::new(0).parse_all(&wasm) {
+        match payload? {
+            Payload::CodeSectionStart { range, .. } => section_start = range.start,
+            Payload::CodeSectionEntry(body) => {
+                let mut reader = body.get_operators_reader()?;
+                let mut instructions = Vec::new();
+                while !reader.eof() {
+                    instructions.push(reader.read_with_offset()?.1 - section_start);
+                }
+                actual.push((
+                    body.range().start - section_start,
+                    body.range().end - section_start,
+                    instructions,
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    assert_eq!(actual.len(), offsets.len());
+    for (i, (expected, (start, end, instructions))) in offsets.iter().zip(actual).enumerate() {
+        assert_eq!(expected.body_start, start);
+        assert_eq!(expected.range.end, end);
+        assert!(expected.range.start < start);
+        if i % 7 == 0 {
+            assert!(expected.instructions.is_empty());
+        } else {
+            assert_eq!(expected.instructions, instructions);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn recording_starts_on_request() {
+    let mut f = Function::new([]);
+    f.instruction(&Instruction::Nop);
+    assert_eq!(f.instruction_offsets(), None);
+    f.record_offsets();
+    f.instruction(&Instruction::Nop);
+    f.instruction_padded(&Instruction::Call(0));
+    f.instruction(&Instruction::End);
+    assert_eq!(f.instruction_offsets(), Some(&[2, 3, 9][..]));
+
+    // Recording offsets doesn't affect the encoded function.
+    let mut g = Function::new([]);
+    g.instruction(&Instruction::Nop);
+    g.instruction(&Instruction::Nop);
+    g.instruction_padded(&Instruction::Call(0));
+    g.instruction(&Instruction::End);
+    assert_eq!(f, g);
+}
@@ -0,0 +1,101 @@
+//! Helpers shared by the integration tests.
+//!
+//! The tests of `wasm-analysis` include this module as well, so it only
+//! depends on `wasmparser` and `wast`.
+
+// Each test crate includes this module but only uses some of it.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use wasmparser::{Validator, WasmFeatures};
+
+/// Every feature supported by the tools under test, except for module
+/// linking which most of them reject.
+pub fn features() -> WasmFeatures {
+    WasmFeatures {
+        threads: true,
+        reference_types: true,
+        simd: true,
+        relaxed_simd: true,
+        exceptions: true,
+        bulk_memory: true,
+        tail_call: true,
+        module_linking: false,
+        deterministic_only: false,
+        multi_value: true,
+        multi_memory: true,
+        memory64: true,
+        extended_const: false,
+    }
+}
+
+/// Validates `wasm` with [`features`].
+pub fn validate(wasm: &[u8]) -> bool {
+    let mut validator = Validator::new();
+    validator.wasm_features(features());
+    validator.validate_all(wasm).is_ok()
+}
+
+/// Returns every module in the `tests/local` directory of the workspace which
+/// is valid with `features`, along with the test file it came from.
+pub fn local_modules(features: WasmFeatures) -> Vec<(PathBuf, Vec<u8>)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/local");
+    let mut tests = Vec::new();
+    find_tests(&dir, &mut tests);
+    tests.sort();
+
+    let mut ret = Vec::new();
+    for test in tests {
+        for wasm in modules_in(&test) {
+            let mut validator = Validator::new();
+            validator.wasm_features(features);
+            if validator.validate_all(&wasm).is_ok() {
+                ret.push((test.clone(), wasm));
+            }
+        }
+    }
+    assert!(!ret.is_empty(), "no modules found in {}", dir.display());
+    ret
+}
+
+/// Collects the `.wat` and `.wast` files under `dir` into `tests`, skipping
+/// the directories of tests which are expected to fail.
+fn find_tests(dir: &Path, tests: &mut Vec<PathBuf>) {
+    for entry in dir.read_dir().unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            if path.ends_with("invalid") || path.ends_with("missing-features") {
+                continue;
+            }
+            find_tests(&path, tests);
+        } else if matches!(
+            path.extension().and_then(|s| s.to_str()),
+            Some("wat" | "wast")
+        ) {
+            tests.push(path);
+        }
+    }
+}
+
+/// Returns the binary encoding of every module in the test file at `path`
+/// which can be parsed and encoded.
+fn modules_in(path: &Path) -> Vec<Vec<u8>> {
+    let contents = std::fs::read_to_string(path).unwrap();
+    let buf = match wast::parser::ParseBuffer::new(&contents) {
+        Ok(buf) => buf,
+        Err(_) => return Vec::new(),
+    };
+    let mut ret = Vec::new();
+    if path.extension().and_then(|s| s.to_str()) == Some("wat") {
+        if let Ok(mut wat) = wast::parser::parse::<wast::Wat>(&buf) {
+            ret.extend(wat.module.encode().ok());
+        }
+    } else if let Ok(wast) = wast::parser::parse::<wast::Wast>(&buf) {
+        for directive in wast.directives {
+            if let wast::WastDirective::Module(mut module) = directive {
+                ret.extend(module.encode().ok());
+            }
+        }
+    }
+    ret
+}
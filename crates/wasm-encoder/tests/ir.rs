@@ -0,0 +1,144 @@
+#![cfg(feature = "wasmparser")]
+
+use anyhow::Result;
+use common::validate;
+use wasm_encoder::ir::{DataMode, ElementItems, ModuleIr};
+use wasm_encoder::reencode::Error;
+use wasm_encoder::{Export, Instruction};
+
+mod common;
+
+fn wat(text: &str) -> Vec<u8> {
+    let buf = wast::parser::ParseBuffer::new(text).unwrap();
+    let mut wat = wast::parser::parse::<wast::Wat>(&buf).unwrap();
+    wat.module.encode().unwrap()
+}
+
+const MODULE: &str = r#"
+    (module $m
+        (import "env" "f" (func $imported (param i32)))
+        (memory 1)
+        (table 2 funcref)
+        (global $g (mut i32) (i32.const 7))
+        (func $run (export "run") (param $x i32) (local $y i64)
+            local.get $x
+            call $imported
+            i32.const 0
+            i32.const 0
+            i32.const 4
+            memory.init 1
+            data.drop 1)
+        (elem (i32.const 0) func $run $run)
+        (data (i32.const 8) "hello")
+        (data "world")
+        (@custom "extra" "\01\02")
+    )
+"#;
+
+#[test]
+fn decode_module() -> Result<()> {
+    let ir = ModuleIr::from_wasm(&wat(MODULE))?;
+    assert_eq!(ir.types.len(), 1);
+    assert_eq!(ir.imports.len(), 1);
+    assert_eq!(ir.imports[0].field.as_deref(), Some("f"));
+    assert_eq!(ir.functions.len(), 1);
+    assert_eq!(ir.functions[0].ty, 0);
+    assert!(matches!(ir.functions[0].body[1], Instruction::Call(0)));
+    assert!(matches!(
+        ir.functions[0].body.last(),
+        Some(Instruction::End)
+    ));
+    assert!(matches!(ir.globals[0].init, Instruction::I32Const(7)));
+    assert_eq!(ir.exports[0].name, "run");
+    assert!(matches!(ir.exports[0].kind, Export::Function(1)));
+    assert!(matches!(&ir.elements[0].items, ElementItems::Functions(f) if f == &[1, 1]));
+    assert!(matches!(
+        ir.data[0].mode,
+        DataMode::Active {
+            memory: 0,
+            offset: Instruction::I32Const(8)
+        }
+    ));
+    assert_eq!(ir.data[1].data, b"world");
+    assert_eq!(ir.names.module.as_deref(), Some("m"));
+    assert_eq!(
+        ir.names.functions,
+        [(0, "imported".to_string()), (1, "run".to_string())]
+    );
+    assert_eq!(
+        ir.names.locals,
+        [(1, vec![(0, "x".to_string()), (1, "y".to_string())])]
+    );
+    assert_eq!(ir.custom_sections.len(), 1);
+    assert_eq!(ir.custom_sections[0].name, "extra");
+
+    let wasm = ir.encode();
+    assert!(validate(&wasm));
+    let again = ModuleIr::from_wasm(&wasm)?;
+    assert_eq!(again.names, ir.names);
+    assert_eq!(again.custom_sections, ir.custom_sections);
+    Ok(())
+}
+
+#[test]
+fn custom_section_placement() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (@custom "first" (before first) "1")
+            (type (func))
+            (@custom "after-type" (after type) "2")
+            (@custom "before-code" (before code) "3")
+            (func)
+            (@custom "last" (after last) "4")
+        )
+        "#);
+    let ir = ModuleIr::from_wasm(&wasm)?;
+    let names: Vec<_> = ir.custom_sections.iter().map(|c| &c.name[..]).collect();
+    assert_eq!(names, ["first", "after-type", "before-code", "last"]);
+    assert_eq!(ir.encode(), wasm);
+    Ok(())
+}
+
+#[test]
+fn code_offset_sections_are_dropped() -> Result<()> {
+    let wasm = wat(&std::fs::read_to_string(
+        "../../tests/local/branch-hints.wat",
+    )?);
+    let has_branch_hints = |wasm: &[u8]| {
+        wasmparser::Parser::new(0).parse_all(wasm).any(|p| {
+            matches!(
+                p,
+                Ok(wasmparser::Payload::CustomSection { name, .. })
+                    if name == "metadata.code.branch_hint"
+            )
+        })
+    };
+    assert!(has_branch_hints(&wasm));
+
+    let ir = ModuleIr::from_wasm(&wasm)?;
+    assert!(ir.custom_sections.is_empty());
+    let encoded = ir.encode();
+    assert!(validate(&encoded));
+    assert!(!has_branch_hints(&encoded));
+    assert_eq!(ModuleIr::from_wasm(&encoded)?.encode(), encoded);
+    Ok(())
+}
+
+#[test]
+fn module_linking_is_unsupported() {
+    let wasm = wat(r#"(module (module))"#);
+    assert!(matches!(
+        ModuleIr::from_wasm(&wasm),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_roundtrip() -> Result<()> {
+    let ir = ModuleIr::from_wasm(&wat(MODULE))?;
+    let json = serde_json::to_string(&ir)?;
+    let decoded: ModuleIr = serde_json::from_str(&json)?;
+    assert_eq!(decoded.encode(), ir.encode());
+    Ok(())
+}
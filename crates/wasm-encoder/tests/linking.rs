@@ -196,3 +196,149 @@ fn sym_tab_data_undefined() -> Result<()> {
 ",
     )
 }
+
+/// Returns the contents of the custom section named `name` in `wasm`.
+fn custom_section<'a>(wasm: &'a [u8], name: &str) -> Result<(&'a [u8], usize)> {
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        if let wasmparser::Payload::CustomSection {
+            name: n,
+            data,
+            data_offset,
+            ..
+        } = payload?
+        {
+            if n == name {
+                return Ok((data, data_offset));
+            }
+        }
+    }
+    anyhow::bail!("no custom section named `{}`", name)
+}
+
+fn linking_bytes(linking: &LinkingSection) -> Result<Vec<u8>> {
+    let mut module = Module::new();
+    module.section(linking);
+    let wasm = module.finish();
+    Ok(custom_section(&wasm, "linking")?.0.to_vec())
+}
+
+#[test]
+fn segment_info() -> Result<()> {
+    let mut segments = SegmentInfo::new();
+    segments.segment(".rodata", 2, SegmentInfo::WASM_SEG_FLAG_STRINGS);
+    segments.segment(".tdata", 3, SegmentInfo::WASM_SEG_FLAG_TLS);
+
+    let mut linking = LinkingSection::new();
+    linking.segment_info(&segments);
+
+    #[rustfmt::skip]
+    assert_eq!(linking_bytes(&linking)?, [
+        // Version.
+        2,
+        // Subsection id, size, and count.
+        5, 20, 2,
+        7, b'.', b'r', b'o', b'd', b'a', b't', b'a', 2, 1,
+        6, b'.', b't', b'd', b'a', b't', b'a', 3, 2,
+    ]);
+    Ok(())
+}
+
+#[test]
+fn init_funcs() -> Result<()> {
+    let mut init_funcs = InitFuncs::new();
+    init_funcs.init_func(65535, 3);
+    init_funcs.init_func(1, 0);
+
+    let mut linking = LinkingSection::new();
+    linking.init_funcs(&init_funcs);
+
+    #[rustfmt::skip]
+    assert_eq!(linking_bytes(&linking)?, [
+        2,
+        6, 7, 2,
+        0xff, 0xff, 0x03, 3,
+        1, 0,
+    ]);
+    Ok(())
+}
+
+#[test]
+fn comdat_info() -> Result<()> {
+    let mut comdats = ComdatInfo::new();
+    comdats.comdat(
+        "c",
+        &[
+            ComdatSymbol {
+                kind: ComdatSymbolKind::Function,
+                index: 4,
+            },
+            ComdatSymbol {
+                kind: ComdatSymbolKind::Data,
+                index: 1,
+            },
+        ],
+    );
+
+    let mut linking = LinkingSection::new();
+    linking.comdat_info(&comdats);
+
+    #[rustfmt::skip]
+    assert_eq!(linking_bytes(&linking)?, [
+        2,
+        7, 9, 1,
+        // Name, flags, and symbol count.
+        1, b'c', 0, 2,
+        1, 4,
+        0, 1,
+    ]);
+    Ok(())
+}
+
+#[test]
+fn reloc_roundtrip() -> Result<()> {
+    // Only the relocation types understood by `wasmparser` are used here.
+    let relocs = [
+        (RelocationType::FunctionIndexLeb, 1, 2, 0),
+        (RelocationType::TableIndexSleb, 10, 0, 0),
+        (RelocationType::TableIndexI32, 20, 1, 0),
+        (RelocationType::MemoryAddrLeb, 30, 3, 16),
+        (RelocationType::MemoryAddrSleb, 300, 4, -8),
+        (RelocationType::MemoryAddrI32, 3000, 5, 1 << 40),
+        (RelocationType::MemoryAddrLeb, 30000, 5, i64::MIN),
+        (RelocationType::TypeIndexLeb, 40, 6, 0),
+        (RelocationType::GlobalIndexLeb, 50, 7, 0),
+    ];
+    // The section index is deliberately one which does not correspond to a
+    // known section ID, to catch readers which confuse the two.
+    let mut reloc = RelocSection::new("reloc.CODE", 42);
+    for (ty, offset, index, addend) in relocs {
+        reloc.relocation(ty, offset, index, addend);
+    }
+
+    let mut module = Module::new();
+    module.section(&LinkingSection::new());
+    module.section(&reloc);
+    let wasm = module.finish();
+
+    let (data, offset) = custom_section(&wasm, "reloc.CODE")?;
+    let reader = wasmparser::RelocSectionReader::new(data, offset)?;
+    assert_eq!(reader.get_section_index(), 42);
+    assert_eq!(reader.get_count(), relocs.len() as u32);
+    for (actual, (ty, offset, index, addend)) in reader.into_iter().zip(relocs) {
+        let actual = actual?;
+        assert_eq!(actual.ty as u8, ty as u8);
+        assert_eq!(actual.offset, offset);
+        assert_eq!(actual.index, index);
+        assert_eq!(
+            actual.addend,
+            if ty.has_addend() { Some(addend) } else { None }
+        );
+    }
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "has no addend")]
+fn reloc_unexpected_addend() {
+    RelocSection::new("reloc.CODE", 0).relocation(RelocationType::FunctionIndexLeb, 0, 0, 4);
+}
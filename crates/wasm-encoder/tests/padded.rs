@@ -0,0 +1,315 @@
+use anyhow::Result;
+use wasm_encoder::*;
+use wasmparser::{Parser, Payload};
+
+fn module(padded: bool) -> Vec<u8> {
+    let mut types = TypeSection::new();
+    types.function([ValType::I32], [ValType::I32]);
+    let mut imports = ImportSection::new();
+    let mut functions = FunctionSection::new();
+    if padded {
+        imports.import_padded("env", Some("f"), EntityType::Function(0));
+        functions.function_padded(0);
+    } else {
+        imports.import("env", Some("f"), EntityType::Function(0));
+        functions.function(0);
+    }
+    let mut tables = TableSection::new();
+    tables.table(TableType {
+        element_type: ValType::FuncRef,
+        minimum: 1,
+        maximum: None,
+    });
+    let mut memories = MemorySection::new();
+    memories.memory(MemoryType {
+        minimum: 1,
+        maximum: None,
+        memory64: false,
+        shared: false,
+    });
+    let ty = GlobalType {
+        val_type: ValType::I32,
+        mutable: true,
+    };
+    let mut globals = GlobalSection::new();
+    if padded {
+        globals.global_padded(ty, &Instruction::I32Const(1024));
+    } else {
+        globals.global(ty, &Instruction::I32Const(1024));
+    }
+
+    let mut exports = ExportSection::new();
+    let mut elements = ElementSection::new();
+    let mut data = DataSection::new();
+    let element_segments = [
+        ElementSegment {
+            mode: ElementMode::Active {
+                table: Some(0),
+                offset: &Instruction::I32Const(0),
+            },
+            element_type: ValType::FuncRef,
+            elements: Elements::Functions(&[1, 0]),
+        },
+        ElementSegment {
+            mode: ElementMode::Declared,
+            element_type: ValType::FuncRef,
+            elements: Elements::Expressions(&[Element::Func(1), Element::Null]),
+        },
+    ];
+    let data_segment = || DataSegment {
+        mode: DataSegmentMode::Active {
+            memory_index: 0,
+            offset: &Instruction::I32Const(200),
+        },
+        data: b"hello".iter().copied(),
+    };
+    if padded {
+        exports.export_padded("f", Export::Function(1));
+        exports.export_padded("g", Export::Global(0));
+        for segment in element_segments {
+            elements.segment_padded(segment);
+        }
+        data.segment_padded(data_segment());
+    } else {
+        exports.export("f", Export::Function(1));
+        exports.export("g", Export::Global(0));
+        for segment in element_segments {
+            elements.segment(segment);
+        }
+        data.segment(data_segment());
+    }
+
+    let memarg = MemArg {
+        offset: 16,
+        align: 2,
+        memory_index: 0,
+    };
+    let instructions = [
+        Instruction::LocalGet(0),
+        Instruction::I32Load(memarg),
+        Instruction::GlobalSet(0),
+        Instruction::I64Const(-1),
+        Instruction::Drop,
+        Instruction::MemorySize(0),
+        Instruction::MemoryGrow(0),
+        Instruction::Drop,
+        Instruction::I32Const(-100_000),
+        Instruction::LocalGet(0),
+        Instruction::CallIndirect { ty: 0, table: 0 },
+        Instruction::Call(0),
+        Instruction::End,
+    ];
+    let mut f = Function::new([]);
+    for i in instructions.iter() {
+        if padded {
+            f.instruction_padded(i);
+        } else {
+            f.instruction(i);
+        }
+    }
+    let mut code = CodeSection::new();
+    code.function(&f);
+
+    let mut module = Module::new();
+    module
+        .section(&types)
+        .section(&imports)
+        .section(&functions)
+        .section(&tables)
+        .section(&memories)
+        .section(&globals)
+        .section(&exports)
+        .section(&elements)
+        .section(&code)
+        .section(&data);
+    module.finish()
+}
+
+/// Decodes the contents of `wasm` into a list of strings which are the same
+/// regardless of whether LEB128s are padded.
+fn decode(wasm: &[u8]) -> Result<Vec<String>> {
+    fn init_expr(expr: &wasmparser::InitExpr<'_>) -> Result<String> {
+        let mut ops = Vec::new();
+        let mut reader = expr.get_operators_reader();
+        while !reader.eof() {
+            ops.push(format!("{:?}", reader.read()?));
+        }
+        Ok(ops.join(" "))
+    }
+
+    let mut ops = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    ops.push(format!("{:?}", import?));
+                }
+            }
+            Payload::FunctionSection(reader) => {
+                for ty in reader {
+                    ops.push(format!("function {}", ty?));
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    ops.push(format!("{:?}", export?));
+                }
+            }
+            Payload::ElementSection(reader) => {
+                for element in reader {
+                    let element = element?;
+                    match &element.kind {
+                        wasmparser::ElementKind::Active {
+                            table_index,
+                            init_expr: expr,
+                        } => ops.push(format!("elem {} {}", table_index, init_expr(expr)?)),
+                        _ => ops.push("elem".to_string()),
+                    }
+                    for item in element.items.get_items_reader()? {
+                        ops.push(match item? {
+                            wasmparser::ElementItem::Func(f) => format!("item {}", f),
+                            wasmparser::ElementItem::Expr(expr) => init_expr(&expr)?,
+                        });
+                    }
+                }
+            }
+            Payload::DataSection(reader) => {
+                for data in reader {
+                    let data = data?;
+                    if let wasmparser::DataKind::Active {
+                        memory_index,
+                        init_expr: expr,
+                    } = &data.kind
+                    {
+                        ops.push(format!("data {} {}", memory_index, init_expr(expr)?));
+                    }
+                    ops.push(format!("{:?}", data.data));
+                }
+            }
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    let mut reader = global?.init_expr.get_operators_reader();
+                    while !reader.eof() {
+                        ops.push(format!("{:?}", reader.read()?));
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                for op in body.get_operators_reader()? {
+                    ops.push(format!("{:?}", op?));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(ops)
+}
+
+#[test]
+fn padded_instructions_decode_identically() -> Result<()> {
+    let minimal = module(false);
+    let padded = module(true);
+    wasmparser::validate(&minimal)?;
+    wasmparser::validate(&padded)?;
+    assert!(padded.len() > minimal.len());
+    assert_eq!(decode(&minimal)?, decode(&padded)?);
+    Ok(())
+}
+
+#[test]
+fn padded_immediates_are_fixed_width() {
+    let mut f = Function::new([]);
+    let start = f.byte_len();
+    f.instruction_padded(&Instruction::Call(0));
+    assert_eq!(f.byte_len() - start, 6);
+    f.instruction_padded(&Instruction::I32Const(0));
+    assert_eq!(f.byte_len() - start, 12);
+    f.instruction_padded(&Instruction::I64Const(0));
+    assert_eq!(f.byte_len() - start, 23);
+
+    // Prefixed opcodes are not padded, only their immediates.
+    let start = f.byte_len();
+    f.instruction_padded(&Instruction::TableSize { table: 0 });
+    assert_eq!(f.byte_len() - start, 2 + 5);
+
+    // The memory index of `memory.size` is a reserved byte when it's zero.
+    let start = f.byte_len();
+    f.instruction_padded(&Instruction::MemorySize(0));
+    assert_eq!(f.byte_len() - start, 2);
+    f.instruction_padded(&Instruction::MemorySize(1));
+    assert_eq!(f.byte_len() - start, 2 + 6);
+}
+
+#[test]
+fn padded_entries_are_fixed_width() {
+    fn len(section: &impl Section) -> usize {
+        let mut bytes = Vec::new();
+        section.encode(&mut bytes);
+        bytes.len()
+    }
+
+    let mut functions = FunctionSection::new();
+    functions.function_padded(0);
+    assert_eq!(len(&functions), 1 + 1 + 5);
+
+    let mut exports = ExportSection::new();
+    exports.export_padded("", Export::Function(0));
+    assert_eq!(len(&exports), 1 + 1 + 1 + 1 + 5);
+
+    let mut imports = ImportSection::new();
+    imports.import_padded("", Some(""), EntityType::Function(0));
+    assert_eq!(len(&imports), 1 + 1 + 1 + 1 + 1 + 5);
+
+    // Flags, table index, `i32.const 0`, `end`, element kind, count, and one
+    // function.
+    let mut elements = ElementSection::new();
+    elements.segment_padded(ElementSegment {
+        mode: ElementMode::Active {
+            table: Some(0),
+            offset: &Instruction::I32Const(0),
+        },
+        element_type: ValType::FuncRef,
+        elements: Elements::Functions(&[0]),
+    });
+    assert_eq!(len(&elements), 1 + 1 + 1 + 5 + 1 + 5 + 1 + 1 + 1 + 5);
+
+    // Flags, memory index, `i32.const 0`, `end`, and an empty payload.
+    let mut data = DataSection::new();
+    data.segment_padded(DataSegment {
+        mode: DataSegmentMode::Active {
+            memory_index: 1,
+            offset: &Instruction::I32Const(0),
+        },
+        data: [],
+    });
+    assert_eq!(len(&data), 1 + 1 + 1 + 5 + 1 + 5 + 1 + 1);
+}
+
+#[test]
+fn padded_encoders() {
+    use wasm_encoder::encoders;
+    for n in [0, 1, 127, 128, u32::MAX] {
+        let bytes = encoders::u32_padded(n);
+        let mut reader = wasmparser::BinaryReader::new(&bytes);
+        assert_eq!(reader.read_var_u32().unwrap(), n);
+        assert!(reader.eof());
+    }
+    for x in [0, -1, 63, -64, 64, i32::MIN, i32::MAX] {
+        let bytes = encoders::s32_padded(x);
+        let mut reader = wasmparser::BinaryReader::new(&bytes);
+        assert_eq!(reader.read_var_i32().unwrap(), x);
+        assert!(reader.eof());
+    }
+    for n in [0, 1, u64::from(u32::MAX) + 1, u64::MAX] {
+        let bytes = encoders::u64_padded(n);
+        let mut reader = wasmparser::BinaryReader::new(&bytes);
+        assert_eq!(reader.read_var_u64().unwrap(), n);
+        assert!(reader.eof());
+    }
+    for x in [0, -1, i64::MIN, i64::MAX] {
+        let bytes = encoders::s64_padded(x);
+        let mut reader = wasmparser::BinaryReader::new(&bytes);
+        assert_eq!(reader.read_var_i64().unwrap(), x);
+        assert!(reader.eof());
+    }
+}
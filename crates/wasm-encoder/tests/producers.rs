@@ -0,0 +1,115 @@
+use wasm_encoder::*;
+
+#[test]
+fn encode_matches_wasmparser() {
+    let mut languages = ProducersField::new();
+    languages.value("Rust", "1.58.0").value("C", "");
+    let mut sdks = ProducersField::new();
+    sdks.value("wasi-sdk", "14");
+    let mut section = ProducersSection::new();
+    section.field("language", &languages).field("sdk", &sdks);
+
+    let mut module = Module::new();
+    module.section(&section);
+    let wasm = module.finish();
+    wasmparser::validate(&wasm).unwrap();
+
+    let mut fields = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&wasm) {
+        if let wasmparser::Payload::CustomSection {
+            name: "producers",
+            data,
+            data_offset,
+            ..
+        } = payload.unwrap()
+        {
+            let reader = wasmparser::ProducersSectionReader::new(data, data_offset).unwrap();
+            for field in reader {
+                let field = field.unwrap();
+                let values = field
+                    .get_producer_field_values_reader()
+                    .unwrap()
+                    .into_iter()
+                    .map(|v| {
+                        let v = v.unwrap();
+                        (v.name.to_string(), v.version.to_string())
+                    })
+                    .collect::<Vec<_>>();
+                fields.push((field.name.to_string(), values));
+            }
+        }
+    }
+    let s = |a: &str, b: &str| (a.to_string(), b.to_string());
+    assert_eq!(
+        fields,
+        [
+            (
+                "language".to_string(),
+                vec![s("Rust", "1.58.0"), s("C", "")]
+            ),
+            ("sdk".to_string(), vec![s("wasi-sdk", "14")]),
+        ]
+    );
+}
+
+#[test]
+fn merge_replaces_versions() {
+    let mut a = Producers::new();
+    a.add_language("Rust", "")
+        .add_processed_by("rustc", "1.58.0");
+    let mut b = Producers::new();
+    b.add_processed_by("wasm-opt", "105")
+        .add_processed_by("rustc", "1.59.0")
+        .add_sdk("wasi-sdk", "14");
+    a.merge(&b);
+
+    let s = |a: &str, b: &str| (a.to_string(), b.to_string());
+    assert_eq!(a.get("language"), [s("Rust", "")]);
+    assert_eq!(
+        a.get("processed-by"),
+        [s("rustc", "1.59.0"), s("wasm-opt", "105")]
+    );
+    assert_eq!(a.get("sdk"), [s("wasi-sdk", "14")]);
+    assert!(a.get("other").is_empty());
+    assert_eq!(
+        a.fields().map(|(f, _)| f).collect::<Vec<_>>(),
+        ["language", "processed-by", "sdk"]
+    );
+}
+
+#[cfg(feature = "wasmparser")]
+#[test]
+fn add_to_module() -> anyhow::Result<()> {
+    let buf = wast::parser::ParseBuffer::new(
+        r#"(module
+            (func (export "f"))
+            (@custom "producers" "\01\08language\01\04Rust\00")
+            (@custom "other" "x"))"#,
+    )?;
+    let wasm = wast::parser::parse::<wast::Wat>(&buf)?.module.encode()?;
+    assert_eq!(Producers::from_wasm(&wasm)?.get("language").len(), 1);
+
+    let mut stamp = Producers::new();
+    stamp.add_processed_by("wasm-tools", "1.0");
+    let stamped = stamp.add_to_module(&wasm)?;
+    wasmparser::validate(&stamped)?;
+
+    let mut expected = Producers::new();
+    expected
+        .add_language("Rust", "")
+        .add_processed_by("wasm-tools", "1.0");
+    assert_eq!(Producers::from_wasm(&stamped)?, expected);
+
+    // The merged section replaces the original one at the end of the module.
+    let mut names = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&stamped) {
+        if let wasmparser::Payload::CustomSection { name, .. } = payload? {
+            names.push(name.to_string());
+        }
+    }
+    assert_eq!(names, ["other", "producers"]);
+
+    // Stamping again is idempotent.
+    assert_eq!(stamp.add_to_module(&stamped)?, stamped);
+    Ok(())
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "wasmparser")]
+
+use anyhow::{Context, Result};
+use wasm_encoder::ir::ModuleIr;
+use wasm_encoder::reencode::{Error, Reencode, RoundtripReencoder};
+use wasm_encoder::Module;
+use wasmparser::{Parser, WasmFeatures};
+
+mod common;
+
+fn reencode<R: Reencode>(reencoder: &mut R, wasm: &[u8]) -> Result<Vec<u8>, Error<R::Error>> {
+    let mut module = Module::new();
+    reencoder.parse_core_module(&mut module, Parser::new(0), wasm)?;
+    Ok(module.finish())
+}
+
+/// Round trips every valid module of the local test suite through both the
+/// `Reencode` trait and `ModuleIr`.
+#[test]
+fn roundtrip_local_tests() -> Result<()> {
+    let features = WasmFeatures {
+        module_linking: true,
+        ..common::features()
+    };
+    let mut decoded = 0;
+    for (test, wasm) in common::local_modules(features) {
+        let reencoded = reencode(&mut RoundtripReencoder, &wasm)
+            .with_context(|| format!("failed to reencode {}", test.display()))?;
+        assert!(wasm == reencoded, "mismatch in {}", test.display());
+
+        let ir = match ModuleIr::from_wasm(&wasm) {
+            Ok(ir) => ir,
+            Err(Error::Unsupported(_)) => continue,
+            Err(e) => return Err(e).context(format!("failed to decode {}", test.display())),
+        };
+        let encoded = ir.encode();
+        assert!(
+            common::validate(&encoded),
+            "invalid output for {}",
+            test.display()
+        );
+
+        // Section order and the data count section may differ from the
+        // input, but a second round trip through the IR must be exact.
+        let again = ModuleIr::from_wasm(&encoded)?.encode();
+        assert!(encoded == again, "mismatch in {}", test.display());
+        decoded += 1;
+    }
+    assert!(decoded > 0);
+    Ok(())
+}
+
+fn wat(text: &str) -> Vec<u8> {
+    let buf = wast::parser::ParseBuffer::new(text).unwrap();
+    let mut wat = wast::parser::parse::<wast::Wat>(&buf).unwrap();
+    wat.module.encode().unwrap()
+}
+
+/// Renumbers defined functions as if a new function had been imported
+/// after the first import.
+struct ShiftFunctions;
+
+impl Reencode for ShiftFunctions {
+    type Error = std::convert::Infallible;
+
+    fn function_index(&mut self, func: u32) -> Result<u32, Error> {
+        Ok(if func == 0 { 0 } else { func + 1 })
+    }
+}
+
+#[test]
+fn remap_function_indices() {
+    let before = wat(r#"
+        (module
+            (import "" "f" (func))
+            (func (call 0) (call 2))
+            (func (drop (ref.func 1)))
+            (table 2 funcref)
+            (elem (i32.const 0) func 2 1)
+            (export "g" (func 1))
+            (start 2)
+        )
+    "#);
+    let after = wat(r#"
+        (module
+            (import "" "f" (func))
+            (func (call 0) (call 3))
+            (func (drop (ref.func 2)))
+            (table 2 funcref)
+            (elem (i32.const 0) func 3 2)
+            (export "g" (func 2))
+            (start 3)
+        )
+    "#);
+    assert_eq!(reencode(&mut ShiftFunctions, &before).unwrap(), after);
+}
+
+/// Rejects any use of the global at index 0.
+struct RejectGlobal;
+
+impl Reencode for RejectGlobal {
+    type Error = &'static str;
+
+    fn global_index(&mut self, global: u32) -> Result<u32, Error<&'static str>> {
+        match global {
+            0 => Err(Error::UserError("global 0 is used")),
+            n => Ok(n),
+        }
+    }
+}
+
+#[test]
+fn user_errors() {
+    let wasm = wat(r#"
+        (module
+            (global i32 (i32.const 0))
+            (global i32 (i32.const 0))
+            (func (result i32) global.get 1)
+        )
+    "#);
+    assert!(reencode(&mut RejectGlobal, &wasm).is_ok());
+
+    let wasm = wat(r#"
+        (module
+            (global i32 (i32.const 0))
+            (func (result i32) global.get 0)
+        )
+    "#);
+    assert!(matches!(
+        reencode(&mut RejectGlobal, &wasm),
+        Err(Error::UserError("global 0 is used"))
+    ));
+}
@@ -0,0 +1,306 @@
+#![cfg(feature = "wasmparser")]
+
+use anyhow::Result;
+use wasm_encoder::reencode::Error;
+use wasm_encoder::surgery::ModuleSurgery;
+use wasm_encoder::*;
+use wasmparser::{Operator, Parser, Payload};
+
+fn wat(text: &str) -> Result<Vec<u8>> {
+    let buf = wast::parser::ParseBuffer::new(text)?;
+    let mut wat = wast::parser::parse::<wast::Wat>(&buf)?;
+    Ok(wat.module.encode()?)
+}
+
+/// Returns the targets of every `call` in the module, in order.
+fn calls(wasm: &[u8]) -> Result<Vec<u32>> {
+    let mut calls = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CodeSectionEntry(body) = payload? {
+            for op in body.get_operators_reader()? {
+                if let Operator::Call { function_index } = op? {
+                    calls.push(function_index);
+                }
+            }
+        }
+    }
+    Ok(calls)
+}
+
+fn exports(wasm: &[u8]) -> Result<Vec<(String, u32)>> {
+    let mut exports = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::ExportSection(reader) = payload? {
+            for export in reader {
+                let export = export?;
+                exports.push((export.field.to_string(), export.index));
+            }
+        }
+    }
+    Ok(exports)
+}
+
+#[test]
+fn unedited_is_verbatim() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (import "env" "a" (func $a))
+            (global $g (mut i32) (i32.const 0))
+            (func $f (export "f") call $a global.get $g drop)
+            (@custom "x" "payload"))
+    "#)?;
+    assert_eq!(ModuleSurgery::new(&wasm)?.finish()?, wasm);
+    Ok(())
+}
+
+#[test]
+fn add_import_renumbers() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (import "env" "a" (func $a))
+            (table 1 funcref)
+            (func $f (export "f") call $a call $f)
+            (elem (i32.const 0) $f)
+            (start $f))
+    "#)?;
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    let b = surgery.add_import("env", "b", EntityType::Function(0));
+    assert_eq!(b, 1);
+    surgery.add_export("b", Export::Function(b));
+    let edited = surgery.finish()?;
+
+    wasmparser::validate(&edited)?;
+    assert_eq!(calls(&edited)?, [0, 2]);
+    assert_eq!(
+        exports(&edited)?,
+        [("f".to_string(), 2), ("b".to_string(), 1)]
+    );
+    Ok(())
+}
+
+#[test]
+fn remove_import_renumbers() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (import "env" "a" (func $a))
+            (import "env" "b" (func $b))
+            (func $f (export "f") call $b call $f))
+    "#)?;
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    assert!(surgery.remove_import("env", "a").is_some());
+    assert!(surgery.remove_import("env", "a").is_none());
+    let edited = surgery.finish()?;
+    wasmparser::validate(&edited)?;
+    assert_eq!(calls(&edited)?, [0, 1]);
+    assert_eq!(exports(&edited)?, [("f".to_string(), 1)]);
+
+    // Removing an import which is still called is an error.
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    surgery.remove_import("env", "b");
+    match surgery.finish() {
+        Err(Error::UserError(removed)) => assert_eq!(removed.index, 1),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn replace_body_and_sections() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (func (export "f") (result i32) i32.const 1)
+            (func (export "g") (result i32) i32.const 2)
+            (@custom "a" "1")
+            (@custom "b" "2"))
+    "#)?;
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    let mut body = Function::new([]);
+    body.instruction(&Instruction::I32Const(3));
+    body.instruction(&Instruction::End);
+    surgery.replace_function_body(1, &body);
+    assert_eq!(surgery.remove_custom_sections("a"), 1);
+    surgery.remove_export("f");
+    let mut memories = MemorySection::new();
+    memories.memory(MemoryType {
+        minimum: 1,
+        maximum: None,
+        memory64: false,
+        shared: false,
+    });
+    surgery.add_section(&memories);
+    let edited = surgery.finish()?;
+    wasmparser::validate(&edited)?;
+
+    let mut ids = Vec::new();
+    let mut consts = Vec::new();
+    for payload in Parser::new(0).parse_all(&edited) {
+        match payload? {
+            Payload::CodeSectionEntry(body) => {
+                for op in body.get_operators_reader()? {
+                    if let Operator::I32Const { value } = op? {
+                        consts.push(value);
+                    }
+                }
+            }
+            Payload::CustomSection { name, .. } => ids.push(name.to_string()),
+            Payload::MemorySection(_) => ids.push("memory".to_string()),
+            Payload::ExportSection(_) => ids.push("export".to_string()),
+            _ => {}
+        }
+    }
+    assert_eq!(consts, [1, 3]);
+    assert_eq!(ids, ["memory", "export", "b"]);
+    assert_eq!(exports(&edited)?, [("g".to_string(), 1)]);
+    Ok(())
+}
+
+#[test]
+fn new_import_section() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (type (func))
+            (global $g i32 (i32.const 0))
+            (func (result i32) global.get $g))
+    "#)?;
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    let g = surgery.add_import(
+        "env",
+        "g",
+        EntityType::Global(GlobalType {
+            val_type: ValType::I32,
+            mutable: false,
+        }),
+    );
+    assert_eq!(g, 0);
+    let edited = surgery.finish()?;
+    wasmparser::validate(&edited)?;
+    for payload in Parser::new(0).parse_all(&edited) {
+        if let Payload::CodeSectionEntry(body) = payload? {
+            let ops = body
+                .get_operators_reader()?
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            assert!(matches!(ops[0], Operator::GlobalGet { global_index: 1 }));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn add_function() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (type (func (result i32)))
+            (import "env" "a" (func $a))
+            (func $f (export "f") (result i32) i32.const 1))
+    "#)?;
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    let mut body = Function::new([]);
+    body.instruction(&Instruction::Call(1));
+    body.instruction(&Instruction::End);
+    let g = surgery.add_function(0, &body);
+    assert_eq!(g, 2);
+    surgery.add_export("g", Export::Function(g));
+    let edited = surgery.finish()?;
+    wasmparser::validate(&edited)?;
+    assert_eq!(calls(&edited)?, [1]);
+    assert_eq!(
+        exports(&edited)?,
+        [("f".to_string(), 1), ("g".to_string(), 2)]
+    );
+
+    // Removing the added function again gives back the original module.
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    let g = surgery.add_function(0, &body);
+    surgery.remove_function(g);
+    assert_eq!(surgery.finish()?, wasm);
+
+    // Functions can be added to a module without any.
+    let wasm = wat("(module (type (func)))")?;
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    let mut body = Function::new([]);
+    body.instruction(&Instruction::End);
+    assert_eq!(surgery.add_function(0, &body), 0);
+    let edited = surgery.finish()?;
+    wasmparser::validate(&edited)?;
+    assert_eq!(ModuleSurgery::new(&edited)?.finish()?, edited);
+    Ok(())
+}
+
+#[test]
+fn remove_function_renumbers() -> Result<()> {
+    let wasm = wat(r#"
+        (module
+            (import "env" "a" (func $a))
+            (table 2 funcref)
+            (global funcref (ref.func $g))
+            (func $dead)
+            (func $f (export "f") call $g call $a)
+            (func $g (export "g") (drop (ref.func $f)) call $f)
+            (elem (i32.const 0) $f $g)
+            (start $g))
+    "#)?;
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    surgery.remove_function(1);
+    let edited = surgery.finish()?;
+    wasmparser::validate(&edited)?;
+    assert_eq!(calls(&edited)?, [2, 0, 1]);
+    assert_eq!(
+        exports(&edited)?,
+        [("f".to_string(), 1), ("g".to_string(), 2)]
+    );
+
+    let mut ref_funcs = Vec::new();
+    let mut elements = Vec::new();
+    let mut start = None;
+    for payload in Parser::new(0).parse_all(&edited) {
+        match payload? {
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    for op in global?.init_expr.get_operators_reader() {
+                        if let Operator::RefFunc { function_index } = op? {
+                            ref_funcs.push(function_index);
+                        }
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                for op in body.get_operators_reader()? {
+                    if let Operator::RefFunc { function_index } = op? {
+                        ref_funcs.push(function_index);
+                    }
+                }
+            }
+            Payload::ElementSection(reader) => {
+                for element in reader {
+                    for item in element?.items.get_items_reader()? {
+                        if let wasmparser::ElementItem::Func(f) = item? {
+                            elements.push(f);
+                        }
+                    }
+                }
+            }
+            Payload::StartSection { func, .. } => start = Some(func),
+            _ => {}
+        }
+    }
+    assert_eq!(ref_funcs, [2, 1]);
+    assert_eq!(elements, [1, 2]);
+    assert_eq!(start, Some(2));
+
+    // Removing a function which is still referenced is an error.
+    let mut surgery = ModuleSurgery::new(&wasm)?;
+    surgery.remove_function(2);
+    match surgery.finish() {
+        Err(Error::UserError(removed)) => assert_eq!(removed.index, 2),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "function is imported rather than defined")]
+fn remove_imported_function() {
+    let wasm = wat(r#"(module (import "env" "a" (func)))"#).unwrap();
+    ModuleSurgery::new(&wasm).unwrap().remove_function(0);
+}
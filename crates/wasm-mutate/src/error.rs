@@ -56,6 +56,19 @@ impl From<wasmparser::BinaryReaderError> for Error {
     }
 }
 
+impl From<wasm_encoder::reencode::Error<Error>> for Error {
+    fn from(e: wasm_encoder::reencode::Error<Error>) -> Self {
+        use wasm_encoder::reencode::Error as E;
+        match e {
+            E::ParseError(e) => e.into(),
+            E::UserError(e) => e,
+            // Anything `wasm-encoder` can't represent means that this mutation
+            // can't create the new module anyway.
+            E::Unsupported(_) | E::InvalidConstExpr => Error::no_mutations_applicable(),
+        }
+    }
+}
+
 /// The kind of error.
 #[derive(thiserror::Error, Debug)]
 pub enum ErrorKind {
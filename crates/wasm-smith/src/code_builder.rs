@@ -867,7 +867,10 @@ impl CodeBuilder<'_> {
                     | Instruction::F32Min
                     | Instruction::F32Mul
                     | Instruction::F32Sub
-                    | Instruction::F32Add => self.canonicalize_nan(Float::F32, &mut instructions),
+                    | Instruction::F32Add
+                    | Instruction::F32DemoteF64 => {
+                        self.canonicalize_nan(Float::F32, &mut instructions)
+                    }
                     Instruction::F64Ceil
                     | Instruction::F64Floor
                     | Instruction::F64Nearest
@@ -878,7 +881,10 @@ impl CodeBuilder<'_> {
                     | Instruction::F64Min
                     | Instruction::F64Mul
                     | Instruction::F64Sub
-                    | Instruction::F64Add => self.canonicalize_nan(Float::F64, &mut instructions),
+                    | Instruction::F64Add
+                    | Instruction::F64PromoteF32 => {
+                        self.canonicalize_nan(Float::F64, &mut instructions)
+                    }
                     Instruction::F32x4Ceil
                     | Instruction::F32x4Floor
                     | Instruction::F32x4Nearest
@@ -889,7 +895,8 @@ impl CodeBuilder<'_> {
                     | Instruction::F32x4Min
                     | Instruction::F32x4Mul
                     | Instruction::F32x4Sub
-                    | Instruction::F32x4Add => {
+                    | Instruction::F32x4Add
+                    | Instruction::F32x4DemoteF64x2Zero => {
                         self.canonicalize_nan(Float::F32x4, &mut instructions)
                     }
                     Instruction::F64x2Ceil
@@ -902,7 +909,8 @@ impl CodeBuilder<'_> {
                     | Instruction::F64x2Min
                     | Instruction::F64x2Mul
                     | Instruction::F64x2Sub
-                    | Instruction::F64x2Add => {
+                    | Instruction::F64x2Add
+                    | Instruction::F64x2PromoteLowF32x4 => {
                         self.canonicalize_nan(Float::F64x2, &mut instructions)
                     }
                     _ => {}
@@ -1277,6 +1277,7 @@ impl Module {
             minimum,
             maximum,
             memory64,
+            shared: false,
         })
     }
 
@@ -13,36 +13,32 @@
  * limitations under the License.
  */
 
-use std::convert::TryFrom;
-use std::convert::TryInto;
-use std::fmt;
-use std::str;
+use crate::prelude::*;
+use core::convert::TryFrom;
+use core::convert::TryInto;
+use core::fmt;
+use core::str;
 
 use crate::limits::*;
 
+use crate::operator_visitor::OperatorFactory;
 use crate::primitives::{
-    BinaryReaderError, BrTable, CustomSectionKind, ExternalKind, FuncType, GlobalType, Ieee32,
-    Ieee64, LinkingType, MemoryImmediate, MemoryType, NameType, Operator, RelocType, Result,
-    SIMDLaneIndex, SectionCode, TableType, Type, TypeOrFuncType, V128,
+    BinaryReaderError, BrTable, ExternalKind, FuncType, GlobalType, Ieee32, Ieee64, LinkingType,
+    MemoryImmediate, MemoryType, NameType, Operator, RelocType, Result, SIMDLaneIndex, TableType,
+    Type, TypeOrFuncType, V128,
 };
 use crate::{
     ExportType, Import, ImportSectionEntryType, InitExpr, InstanceType, ModuleType, TagType,
+    VisitOperator,
 };
 
-fn is_name(name: &str, expected: &'static str) -> bool {
-    name == expected
-}
-
-fn is_name_prefix(name: &str, prefix: &'static str) -> bool {
-    name.starts_with(prefix)
-}
-
 const WASM_MAGIC_NUMBER: &[u8; 4] = b"\0asm";
 const WASM_EXPERIMENTAL_VERSION: u32 = 0xd;
 const WASM_SUPPORTED_VERSION: u32 = 0x1;
 
 /// Bytecode range in the WebAssembly module.
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Range {
     /// The start bound of the range.
     pub start: usize,
@@ -217,7 +213,7 @@ impl<'a> BinaryReader<'a> {
     pub(crate) fn read_func_type(&mut self) -> Result<FuncType> {
         let params_len = self.read_var_u32()? as usize;
         if params_len > MAX_WASM_FUNCTION_PARAMS {
-            return Err(BinaryReaderError::new(
+            return Err(BinaryReaderError::limit_exceeded(
                 "function params size is out of bound",
                 self.original_position() - 1,
             ));
@@ -228,7 +224,7 @@ impl<'a> BinaryReader<'a> {
         }
         let returns_len = self.read_var_u32()? as usize;
         if returns_len > MAX_WASM_FUNCTION_RETURNS {
-            return Err(BinaryReaderError::new(
+            return Err(BinaryReaderError::limit_exceeded(
                 "function returns size is out of bound",
                 self.original_position() - 1,
             ));
@@ -247,7 +243,10 @@ impl<'a> BinaryReader<'a> {
         let pos = self.original_position();
         let imports_len = self.read_var_u32()? as usize;
         if imports_len > MAX_WASM_IMPORTS {
-            return Err(BinaryReaderError::new("imports size is out of bounds", pos));
+            return Err(BinaryReaderError::limit_exceeded(
+                "imports size is out of bounds",
+                pos,
+            ));
         }
         Ok(ModuleType {
             imports: (0..imports_len)
@@ -267,7 +266,10 @@ impl<'a> BinaryReader<'a> {
         let pos = self.original_position();
         let exports_len = self.read_var_u32()? as usize;
         if exports_len > MAX_WASM_EXPORTS {
-            return Err(BinaryReaderError::new("exports size is out of bound", pos));
+            return Err(BinaryReaderError::limit_exceeded(
+                "exports size is out of bound",
+                pos,
+            ));
         }
         (0..exports_len).map(|_| self.read_export_type()).collect()
     }
@@ -426,54 +428,8 @@ impl<'a> BinaryReader<'a> {
         })
     }
 
-    pub(crate) fn read_section_code(&mut self, id: u32, offset: usize) -> Result<SectionCode<'a>> {
-        match id {
-            0 => {
-                let name = self.read_string()?;
-                let kind = if is_name(name, "name") {
-                    CustomSectionKind::Name
-                } else if is_name(name, "producers") {
-                    CustomSectionKind::Producers
-                } else if is_name(name, "sourceMappingURL") {
-                    CustomSectionKind::SourceMappingURL
-                } else if is_name_prefix(name, "reloc.") {
-                    CustomSectionKind::Reloc
-                } else if is_name(name, "linking") {
-                    CustomSectionKind::Linking
-                } else {
-                    CustomSectionKind::Unknown
-                };
-                Ok(SectionCode::Custom { name, kind })
-            }
-            1 => Ok(SectionCode::Type),
-            2 => Ok(SectionCode::Import),
-            3 => Ok(SectionCode::Function),
-            4 => Ok(SectionCode::Table),
-            5 => Ok(SectionCode::Memory),
-            6 => Ok(SectionCode::Global),
-            7 => Ok(SectionCode::Export),
-            8 => Ok(SectionCode::Start),
-            9 => Ok(SectionCode::Element),
-            10 => Ok(SectionCode::Code),
-            11 => Ok(SectionCode::Data),
-            12 => Ok(SectionCode::DataCount),
-            13 => Ok(SectionCode::Tag),
-            14 => Ok(SectionCode::Module),
-            15 => Ok(SectionCode::Instance),
-            16 => Ok(SectionCode::Alias),
-            17 => Ok(SectionCode::ModuleCode),
-            _ => Err(BinaryReaderError::new("Invalid section code", offset)),
-        }
-    }
-
     fn read_br_table(&mut self) -> Result<BrTable<'a>> {
         let cnt = self.read_var_u32()?;
-        if cnt > MAX_WASM_BR_TABLE_SIZE {
-            return Err(BinaryReaderError::new(
-                "br_table size is out of bound",
-                self.original_position() - 1,
-            ));
-        }
         let start = self.position;
         for _ in 0..cnt {
             self.skip_var_32()?;
@@ -688,7 +644,7 @@ impl<'a> BinaryReader<'a> {
     pub fn skip_string(&mut self) -> Result<()> {
         let len = self.read_var_u32()? as usize;
         if len > MAX_WASM_STRING_SIZE {
-            return Err(BinaryReaderError::new(
+            return Err(BinaryReaderError::limit_exceeded(
                 "string size out of bounds",
                 self.original_position() - 1,
             ));
@@ -837,7 +793,7 @@ impl<'a> BinaryReader<'a> {
     pub fn read_string(&mut self) -> Result<&'a str> {
         let len = self.read_var_u32()? as usize;
         if len > MAX_WASM_STRING_SIZE {
-            return Err(BinaryReaderError::new(
+            return Err(BinaryReaderError::limit_exceeded(
                 "string size out of bounds",
                 self.original_position() - 1,
             ));
@@ -860,210 +816,79 @@ impl<'a> BinaryReader<'a> {
         Ok(imm)
     }
 
-    fn read_0xfe_operator(&mut self) -> Result<Operator<'a>> {
+    fn visit_0xfe_operator<T>(&mut self, pos: usize, visitor: &mut T) -> Result<T::Output>
+    where
+        T: VisitOperator<'a>,
+    {
         let code = self.read_var_u32()?;
         Ok(match code {
-            0x00 => Operator::MemoryAtomicNotify {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x01 => Operator::MemoryAtomicWait32 {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x02 => Operator::MemoryAtomicWait64 {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x03 => Operator::AtomicFence {
-                flags: self.read_u8()? as u8,
-            },
-            0x10 => Operator::I32AtomicLoad {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x11 => Operator::I64AtomicLoad {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x12 => Operator::I32AtomicLoad8U {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x13 => Operator::I32AtomicLoad16U {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x14 => Operator::I64AtomicLoad8U {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x15 => Operator::I64AtomicLoad16U {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x16 => Operator::I64AtomicLoad32U {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x17 => Operator::I32AtomicStore {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x18 => Operator::I64AtomicStore {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x19 => Operator::I32AtomicStore8 {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x1a => Operator::I32AtomicStore16 {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x1b => Operator::I64AtomicStore8 {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x1c => Operator::I64AtomicStore16 {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x1d => Operator::I64AtomicStore32 {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x1e => Operator::I32AtomicRmwAdd {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x1f => Operator::I64AtomicRmwAdd {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x20 => Operator::I32AtomicRmw8AddU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x21 => Operator::I32AtomicRmw16AddU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x22 => Operator::I64AtomicRmw8AddU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x23 => Operator::I64AtomicRmw16AddU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x24 => Operator::I64AtomicRmw32AddU {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x25 => Operator::I32AtomicRmwSub {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x26 => Operator::I64AtomicRmwSub {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x27 => Operator::I32AtomicRmw8SubU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x28 => Operator::I32AtomicRmw16SubU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x29 => Operator::I64AtomicRmw8SubU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x2a => Operator::I64AtomicRmw16SubU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x2b => Operator::I64AtomicRmw32SubU {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x2c => Operator::I32AtomicRmwAnd {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x2d => Operator::I64AtomicRmwAnd {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x2e => Operator::I32AtomicRmw8AndU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x2f => Operator::I32AtomicRmw16AndU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x30 => Operator::I64AtomicRmw8AndU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x31 => Operator::I64AtomicRmw16AndU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x32 => Operator::I64AtomicRmw32AndU {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x33 => Operator::I32AtomicRmwOr {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x34 => Operator::I64AtomicRmwOr {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x35 => Operator::I32AtomicRmw8OrU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x36 => Operator::I32AtomicRmw16OrU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x37 => Operator::I64AtomicRmw8OrU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x38 => Operator::I64AtomicRmw16OrU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x39 => Operator::I64AtomicRmw32OrU {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x3a => Operator::I32AtomicRmwXor {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x3b => Operator::I64AtomicRmwXor {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x3c => Operator::I32AtomicRmw8XorU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x3d => Operator::I32AtomicRmw16XorU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x3e => Operator::I64AtomicRmw8XorU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x3f => Operator::I64AtomicRmw16XorU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x40 => Operator::I64AtomicRmw32XorU {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x41 => Operator::I32AtomicRmwXchg {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x42 => Operator::I64AtomicRmwXchg {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x43 => Operator::I32AtomicRmw8XchgU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x44 => Operator::I32AtomicRmw16XchgU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x45 => Operator::I64AtomicRmw8XchgU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x46 => Operator::I64AtomicRmw16XchgU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x47 => Operator::I64AtomicRmw32XchgU {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x48 => Operator::I32AtomicRmwCmpxchg {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x49 => Operator::I64AtomicRmwCmpxchg {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x4a => Operator::I32AtomicRmw8CmpxchgU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x4b => Operator::I32AtomicRmw16CmpxchgU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x4c => Operator::I64AtomicRmw8CmpxchgU {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x4d => Operator::I64AtomicRmw16CmpxchgU {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x4e => Operator::I64AtomicRmw32CmpxchgU {
-                memarg: self.read_memarg_of_align(2)?,
-            },
+            0x00 => visitor.visit_memory_atomic_notify(pos, self.read_memarg_of_align(2)?),
+            0x01 => visitor.visit_memory_atomic_wait32(pos, self.read_memarg_of_align(2)?),
+            0x02 => visitor.visit_memory_atomic_wait64(pos, self.read_memarg_of_align(3)?),
+            0x03 => visitor.visit_atomic_fence(pos, self.read_u8()? as u8),
+            0x10 => visitor.visit_i32_atomic_load(pos, self.read_memarg_of_align(2)?),
+            0x11 => visitor.visit_i64_atomic_load(pos, self.read_memarg_of_align(3)?),
+            0x12 => visitor.visit_i32_atomic_load8_u(pos, self.read_memarg_of_align(0)?),
+            0x13 => visitor.visit_i32_atomic_load16_u(pos, self.read_memarg_of_align(1)?),
+            0x14 => visitor.visit_i64_atomic_load8_u(pos, self.read_memarg_of_align(0)?),
+            0x15 => visitor.visit_i64_atomic_load16_u(pos, self.read_memarg_of_align(1)?),
+            0x16 => visitor.visit_i64_atomic_load32_u(pos, self.read_memarg_of_align(2)?),
+            0x17 => visitor.visit_i32_atomic_store(pos, self.read_memarg_of_align(2)?),
+            0x18 => visitor.visit_i64_atomic_store(pos, self.read_memarg_of_align(3)?),
+            0x19 => visitor.visit_i32_atomic_store8(pos, self.read_memarg_of_align(0)?),
+            0x1a => visitor.visit_i32_atomic_store16(pos, self.read_memarg_of_align(1)?),
+            0x1b => visitor.visit_i64_atomic_store8(pos, self.read_memarg_of_align(0)?),
+            0x1c => visitor.visit_i64_atomic_store16(pos, self.read_memarg_of_align(1)?),
+            0x1d => visitor.visit_i64_atomic_store32(pos, self.read_memarg_of_align(2)?),
+            0x1e => visitor.visit_i32_atomic_rmw_add(pos, self.read_memarg_of_align(2)?),
+            0x1f => visitor.visit_i64_atomic_rmw_add(pos, self.read_memarg_of_align(3)?),
+            0x20 => visitor.visit_i32_atomic_rmw8_add_u(pos, self.read_memarg_of_align(0)?),
+            0x21 => visitor.visit_i32_atomic_rmw16_add_u(pos, self.read_memarg_of_align(1)?),
+            0x22 => visitor.visit_i64_atomic_rmw8_add_u(pos, self.read_memarg_of_align(0)?),
+            0x23 => visitor.visit_i64_atomic_rmw16_add_u(pos, self.read_memarg_of_align(1)?),
+            0x24 => visitor.visit_i64_atomic_rmw32_add_u(pos, self.read_memarg_of_align(2)?),
+            0x25 => visitor.visit_i32_atomic_rmw_sub(pos, self.read_memarg_of_align(2)?),
+            0x26 => visitor.visit_i64_atomic_rmw_sub(pos, self.read_memarg_of_align(3)?),
+            0x27 => visitor.visit_i32_atomic_rmw8_sub_u(pos, self.read_memarg_of_align(0)?),
+            0x28 => visitor.visit_i32_atomic_rmw16_sub_u(pos, self.read_memarg_of_align(1)?),
+            0x29 => visitor.visit_i64_atomic_rmw8_sub_u(pos, self.read_memarg_of_align(0)?),
+            0x2a => visitor.visit_i64_atomic_rmw16_sub_u(pos, self.read_memarg_of_align(1)?),
+            0x2b => visitor.visit_i64_atomic_rmw32_sub_u(pos, self.read_memarg_of_align(2)?),
+            0x2c => visitor.visit_i32_atomic_rmw_and(pos, self.read_memarg_of_align(2)?),
+            0x2d => visitor.visit_i64_atomic_rmw_and(pos, self.read_memarg_of_align(3)?),
+            0x2e => visitor.visit_i32_atomic_rmw8_and_u(pos, self.read_memarg_of_align(0)?),
+            0x2f => visitor.visit_i32_atomic_rmw16_and_u(pos, self.read_memarg_of_align(1)?),
+            0x30 => visitor.visit_i64_atomic_rmw8_and_u(pos, self.read_memarg_of_align(0)?),
+            0x31 => visitor.visit_i64_atomic_rmw16_and_u(pos, self.read_memarg_of_align(1)?),
+            0x32 => visitor.visit_i64_atomic_rmw32_and_u(pos, self.read_memarg_of_align(2)?),
+            0x33 => visitor.visit_i32_atomic_rmw_or(pos, self.read_memarg_of_align(2)?),
+            0x34 => visitor.visit_i64_atomic_rmw_or(pos, self.read_memarg_of_align(3)?),
+            0x35 => visitor.visit_i32_atomic_rmw8_or_u(pos, self.read_memarg_of_align(0)?),
+            0x36 => visitor.visit_i32_atomic_rmw16_or_u(pos, self.read_memarg_of_align(1)?),
+            0x37 => visitor.visit_i64_atomic_rmw8_or_u(pos, self.read_memarg_of_align(0)?),
+            0x38 => visitor.visit_i64_atomic_rmw16_or_u(pos, self.read_memarg_of_align(1)?),
+            0x39 => visitor.visit_i64_atomic_rmw32_or_u(pos, self.read_memarg_of_align(2)?),
+            0x3a => visitor.visit_i32_atomic_rmw_xor(pos, self.read_memarg_of_align(2)?),
+            0x3b => visitor.visit_i64_atomic_rmw_xor(pos, self.read_memarg_of_align(3)?),
+            0x3c => visitor.visit_i32_atomic_rmw8_xor_u(pos, self.read_memarg_of_align(0)?),
+            0x3d => visitor.visit_i32_atomic_rmw16_xor_u(pos, self.read_memarg_of_align(1)?),
+            0x3e => visitor.visit_i64_atomic_rmw8_xor_u(pos, self.read_memarg_of_align(0)?),
+            0x3f => visitor.visit_i64_atomic_rmw16_xor_u(pos, self.read_memarg_of_align(1)?),
+            0x40 => visitor.visit_i64_atomic_rmw32_xor_u(pos, self.read_memarg_of_align(2)?),
+            0x41 => visitor.visit_i32_atomic_rmw_xchg(pos, self.read_memarg_of_align(2)?),
+            0x42 => visitor.visit_i64_atomic_rmw_xchg(pos, self.read_memarg_of_align(3)?),
+            0x43 => visitor.visit_i32_atomic_rmw8_xchg_u(pos, self.read_memarg_of_align(0)?),
+            0x44 => visitor.visit_i32_atomic_rmw16_xchg_u(pos, self.read_memarg_of_align(1)?),
+            0x45 => visitor.visit_i64_atomic_rmw8_xchg_u(pos, self.read_memarg_of_align(0)?),
+            0x46 => visitor.visit_i64_atomic_rmw16_xchg_u(pos, self.read_memarg_of_align(1)?),
+            0x47 => visitor.visit_i64_atomic_rmw32_xchg_u(pos, self.read_memarg_of_align(2)?),
+            0x48 => visitor.visit_i32_atomic_rmw_cmpxchg(pos, self.read_memarg_of_align(2)?),
+            0x49 => visitor.visit_i64_atomic_rmw_cmpxchg(pos, self.read_memarg_of_align(3)?),
+            0x4a => visitor.visit_i32_atomic_rmw8_cmpxchg_u(pos, self.read_memarg_of_align(0)?),
+            0x4b => visitor.visit_i32_atomic_rmw16_cmpxchg_u(pos, self.read_memarg_of_align(1)?),
+            0x4c => visitor.visit_i64_atomic_rmw8_cmpxchg_u(pos, self.read_memarg_of_align(0)?),
+            0x4d => visitor.visit_i64_atomic_rmw16_cmpxchg_u(pos, self.read_memarg_of_align(1)?),
+            0x4e => visitor.visit_i64_atomic_rmw32_cmpxchg_u(pos, self.read_memarg_of_align(2)?),
 
             _ => {
                 return Err(BinaryReaderError::new(
@@ -1081,7 +906,7 @@ impl<'a> BinaryReader<'a> {
         } else {
             self.position = position;
             let idx = self.read_var_s33()?;
-            if idx < 0 || idx > (std::u32::MAX as i64) {
+            if idx < 0 || idx > (u32::MAX as i64) {
                 return Err(BinaryReaderError::new("invalid function type", position));
             }
             Ok(TypeOrFuncType::FuncType(idx as u32))
@@ -1093,63 +918,49 @@ impl<'a> BinaryReader<'a> {
     /// If `BinaryReader` has less bytes remaining than required to parse
     /// the `Operator`.
     pub fn read_operator(&mut self) -> Result<Operator<'a>> {
+        self.visit_operator(&mut OperatorFactory)
+    }
+
+    /// Reads the next available operator and passes it to the corresponding
+    /// method of `visitor`, returning its output.
+    ///
+    /// Unlike [`BinaryReader::read_operator`] no [`Operator`] is built unless
+    /// `visitor` asks for one.
+    /// # Errors
+    /// If `BinaryReader` has less bytes remaining than required to parse
+    /// the operator.
+    pub fn visit_operator<T>(&mut self, visitor: &mut T) -> Result<T::Output>
+    where
+        T: VisitOperator<'a>,
+    {
+        let pos = self.original_position();
         let code = self.read_u8()? as u8;
         Ok(match code {
-            0x00 => Operator::Unreachable,
-            0x01 => Operator::Nop,
-            0x02 => Operator::Block {
-                ty: self.read_blocktype()?,
-            },
-            0x03 => Operator::Loop {
-                ty: self.read_blocktype()?,
-            },
-            0x04 => Operator::If {
-                ty: self.read_blocktype()?,
-            },
-            0x05 => Operator::Else,
-            0x06 => Operator::Try {
-                ty: self.read_blocktype()?,
-            },
-            0x07 => Operator::Catch {
-                index: self.read_var_u32()?,
-            },
-            0x08 => Operator::Throw {
-                index: self.read_var_u32()?,
-            },
-            0x09 => Operator::Rethrow {
-                relative_depth: self.read_var_u32()?,
-            },
-            0x0b => Operator::End,
-            0x0c => Operator::Br {
-                relative_depth: self.read_var_u32()?,
-            },
-            0x0d => Operator::BrIf {
-                relative_depth: self.read_var_u32()?,
-            },
-            0x0e => Operator::BrTable {
-                table: self.read_br_table()?,
-            },
-            0x0f => Operator::Return,
-            0x10 => Operator::Call {
-                function_index: self.read_var_u32()?,
-            },
-            0x11 => Operator::CallIndirect {
-                index: self.read_var_u32()?,
-                table_index: self.read_var_u32()?,
-            },
-            0x12 => Operator::ReturnCall {
-                function_index: self.read_var_u32()?,
-            },
-            0x13 => Operator::ReturnCallIndirect {
-                index: self.read_var_u32()?,
-                table_index: self.read_var_u32()?,
-            },
-            0x18 => Operator::Delegate {
-                relative_depth: self.read_var_u32()?,
-            },
-            0x19 => Operator::CatchAll,
-            0x1a => Operator::Drop,
-            0x1b => Operator::Select,
+            0x00 => visitor.visit_unreachable(pos),
+            0x01 => visitor.visit_nop(pos),
+            0x02 => visitor.visit_block(pos, self.read_blocktype()?),
+            0x03 => visitor.visit_loop(pos, self.read_blocktype()?),
+            0x04 => visitor.visit_if(pos, self.read_blocktype()?),
+            0x05 => visitor.visit_else(pos),
+            0x06 => visitor.visit_try(pos, self.read_blocktype()?),
+            0x07 => visitor.visit_catch(pos, self.read_var_u32()?),
+            0x08 => visitor.visit_throw(pos, self.read_var_u32()?),
+            0x09 => visitor.visit_rethrow(pos, self.read_var_u32()?),
+            0x0b => visitor.visit_end(pos),
+            0x0c => visitor.visit_br(pos, self.read_var_u32()?),
+            0x0d => visitor.visit_br_if(pos, self.read_var_u32()?),
+            0x0e => visitor.visit_br_table(pos, self.read_br_table()?),
+            0x0f => visitor.visit_return(pos),
+            0x10 => visitor.visit_call(pos, self.read_var_u32()?),
+            0x11 => visitor.visit_call_indirect(pos, self.read_var_u32()?, self.read_var_u32()?),
+            0x12 => visitor.visit_return_call(pos, self.read_var_u32()?),
+            0x13 => {
+                visitor.visit_return_call_indirect(pos, self.read_var_u32()?, self.read_var_u32()?)
+            }
+            0x18 => visitor.visit_delegate(pos, self.read_var_u32()?),
+            0x19 => visitor.visit_catch_all(pos),
+            0x1a => visitor.visit_drop(pos),
+            0x1b => visitor.visit_select(pos),
             0x1c => {
                 let results = self.read_var_u32()?;
                 if results != 1 {
@@ -1158,261 +969,187 @@ impl<'a> BinaryReader<'a> {
                         self.position,
                     ));
                 }
-                Operator::TypedSelect {
-                    ty: self.read_type()?,
-                }
+                visitor.visit_typed_select(pos, self.read_type()?)
             }
-            0x20 => Operator::LocalGet {
-                local_index: self.read_var_u32()?,
-            },
-            0x21 => Operator::LocalSet {
-                local_index: self.read_var_u32()?,
-            },
-            0x22 => Operator::LocalTee {
-                local_index: self.read_var_u32()?,
-            },
-            0x23 => Operator::GlobalGet {
-                global_index: self.read_var_u32()?,
-            },
-            0x24 => Operator::GlobalSet {
-                global_index: self.read_var_u32()?,
-            },
-            0x25 => Operator::TableGet {
-                table: self.read_var_u32()?,
-            },
-            0x26 => Operator::TableSet {
-                table: self.read_var_u32()?,
-            },
-            0x28 => Operator::I32Load {
-                memarg: self.read_memarg()?,
-            },
-            0x29 => Operator::I64Load {
-                memarg: self.read_memarg()?,
-            },
-            0x2a => Operator::F32Load {
-                memarg: self.read_memarg()?,
-            },
-            0x2b => Operator::F64Load {
-                memarg: self.read_memarg()?,
-            },
-            0x2c => Operator::I32Load8S {
-                memarg: self.read_memarg()?,
-            },
-            0x2d => Operator::I32Load8U {
-                memarg: self.read_memarg()?,
-            },
-            0x2e => Operator::I32Load16S {
-                memarg: self.read_memarg()?,
-            },
-            0x2f => Operator::I32Load16U {
-                memarg: self.read_memarg()?,
-            },
-            0x30 => Operator::I64Load8S {
-                memarg: self.read_memarg()?,
-            },
-            0x31 => Operator::I64Load8U {
-                memarg: self.read_memarg()?,
-            },
-            0x32 => Operator::I64Load16S {
-                memarg: self.read_memarg()?,
-            },
-            0x33 => Operator::I64Load16U {
-                memarg: self.read_memarg()?,
-            },
-            0x34 => Operator::I64Load32S {
-                memarg: self.read_memarg()?,
-            },
-            0x35 => Operator::I64Load32U {
-                memarg: self.read_memarg()?,
-            },
-            0x36 => Operator::I32Store {
-                memarg: self.read_memarg()?,
-            },
-            0x37 => Operator::I64Store {
-                memarg: self.read_memarg()?,
-            },
-            0x38 => Operator::F32Store {
-                memarg: self.read_memarg()?,
-            },
-            0x39 => Operator::F64Store {
-                memarg: self.read_memarg()?,
-            },
-            0x3a => Operator::I32Store8 {
-                memarg: self.read_memarg()?,
-            },
-            0x3b => Operator::I32Store16 {
-                memarg: self.read_memarg()?,
-            },
-            0x3c => Operator::I64Store8 {
-                memarg: self.read_memarg()?,
-            },
-            0x3d => Operator::I64Store16 {
-                memarg: self.read_memarg()?,
-            },
-            0x3e => Operator::I64Store32 {
-                memarg: self.read_memarg()?,
-            },
+            0x20 => visitor.visit_local_get(pos, self.read_var_u32()?),
+            0x21 => visitor.visit_local_set(pos, self.read_var_u32()?),
+            0x22 => visitor.visit_local_tee(pos, self.read_var_u32()?),
+            0x23 => visitor.visit_global_get(pos, self.read_var_u32()?),
+            0x24 => visitor.visit_global_set(pos, self.read_var_u32()?),
+            0x25 => visitor.visit_table_get(pos, self.read_var_u32()?),
+            0x26 => visitor.visit_table_set(pos, self.read_var_u32()?),
+            0x28 => visitor.visit_i32_load(pos, self.read_memarg()?),
+            0x29 => visitor.visit_i64_load(pos, self.read_memarg()?),
+            0x2a => visitor.visit_f32_load(pos, self.read_memarg()?),
+            0x2b => visitor.visit_f64_load(pos, self.read_memarg()?),
+            0x2c => visitor.visit_i32_load8_s(pos, self.read_memarg()?),
+            0x2d => visitor.visit_i32_load8_u(pos, self.read_memarg()?),
+            0x2e => visitor.visit_i32_load16_s(pos, self.read_memarg()?),
+            0x2f => visitor.visit_i32_load16_u(pos, self.read_memarg()?),
+            0x30 => visitor.visit_i64_load8_s(pos, self.read_memarg()?),
+            0x31 => visitor.visit_i64_load8_u(pos, self.read_memarg()?),
+            0x32 => visitor.visit_i64_load16_s(pos, self.read_memarg()?),
+            0x33 => visitor.visit_i64_load16_u(pos, self.read_memarg()?),
+            0x34 => visitor.visit_i64_load32_s(pos, self.read_memarg()?),
+            0x35 => visitor.visit_i64_load32_u(pos, self.read_memarg()?),
+            0x36 => visitor.visit_i32_store(pos, self.read_memarg()?),
+            0x37 => visitor.visit_i64_store(pos, self.read_memarg()?),
+            0x38 => visitor.visit_f32_store(pos, self.read_memarg()?),
+            0x39 => visitor.visit_f64_store(pos, self.read_memarg()?),
+            0x3a => visitor.visit_i32_store8(pos, self.read_memarg()?),
+            0x3b => visitor.visit_i32_store16(pos, self.read_memarg()?),
+            0x3c => visitor.visit_i64_store8(pos, self.read_memarg()?),
+            0x3d => visitor.visit_i64_store16(pos, self.read_memarg()?),
+            0x3e => visitor.visit_i64_store32(pos, self.read_memarg()?),
             0x3f => {
                 let (mem_byte, mem) = self.read_first_byte_and_var_u32()?;
-                Operator::MemorySize { mem_byte, mem }
+                visitor.visit_memory_size(pos, mem, mem_byte)
             }
             0x40 => {
                 let (mem_byte, mem) = self.read_first_byte_and_var_u32()?;
-                Operator::MemoryGrow { mem_byte, mem }
+                visitor.visit_memory_grow(pos, mem, mem_byte)
             }
-            0x41 => Operator::I32Const {
-                value: self.read_var_i32()?,
-            },
-            0x42 => Operator::I64Const {
-                value: self.read_var_i64()?,
-            },
-            0x43 => Operator::F32Const {
-                value: self.read_f32()?,
-            },
-            0x44 => Operator::F64Const {
-                value: self.read_f64()?,
-            },
-            0x45 => Operator::I32Eqz,
-            0x46 => Operator::I32Eq,
-            0x47 => Operator::I32Ne,
-            0x48 => Operator::I32LtS,
-            0x49 => Operator::I32LtU,
-            0x4a => Operator::I32GtS,
-            0x4b => Operator::I32GtU,
-            0x4c => Operator::I32LeS,
-            0x4d => Operator::I32LeU,
-            0x4e => Operator::I32GeS,
-            0x4f => Operator::I32GeU,
-            0x50 => Operator::I64Eqz,
-            0x51 => Operator::I64Eq,
-            0x52 => Operator::I64Ne,
-            0x53 => Operator::I64LtS,
-            0x54 => Operator::I64LtU,
-            0x55 => Operator::I64GtS,
-            0x56 => Operator::I64GtU,
-            0x57 => Operator::I64LeS,
-            0x58 => Operator::I64LeU,
-            0x59 => Operator::I64GeS,
-            0x5a => Operator::I64GeU,
-            0x5b => Operator::F32Eq,
-            0x5c => Operator::F32Ne,
-            0x5d => Operator::F32Lt,
-            0x5e => Operator::F32Gt,
-            0x5f => Operator::F32Le,
-            0x60 => Operator::F32Ge,
-            0x61 => Operator::F64Eq,
-            0x62 => Operator::F64Ne,
-            0x63 => Operator::F64Lt,
-            0x64 => Operator::F64Gt,
-            0x65 => Operator::F64Le,
-            0x66 => Operator::F64Ge,
-            0x67 => Operator::I32Clz,
-            0x68 => Operator::I32Ctz,
-            0x69 => Operator::I32Popcnt,
-            0x6a => Operator::I32Add,
-            0x6b => Operator::I32Sub,
-            0x6c => Operator::I32Mul,
-            0x6d => Operator::I32DivS,
-            0x6e => Operator::I32DivU,
-            0x6f => Operator::I32RemS,
-            0x70 => Operator::I32RemU,
-            0x71 => Operator::I32And,
-            0x72 => Operator::I32Or,
-            0x73 => Operator::I32Xor,
-            0x74 => Operator::I32Shl,
-            0x75 => Operator::I32ShrS,
-            0x76 => Operator::I32ShrU,
-            0x77 => Operator::I32Rotl,
-            0x78 => Operator::I32Rotr,
-            0x79 => Operator::I64Clz,
-            0x7a => Operator::I64Ctz,
-            0x7b => Operator::I64Popcnt,
-            0x7c => Operator::I64Add,
-            0x7d => Operator::I64Sub,
-            0x7e => Operator::I64Mul,
-            0x7f => Operator::I64DivS,
-            0x80 => Operator::I64DivU,
-            0x81 => Operator::I64RemS,
-            0x82 => Operator::I64RemU,
-            0x83 => Operator::I64And,
-            0x84 => Operator::I64Or,
-            0x85 => Operator::I64Xor,
-            0x86 => Operator::I64Shl,
-            0x87 => Operator::I64ShrS,
-            0x88 => Operator::I64ShrU,
-            0x89 => Operator::I64Rotl,
-            0x8a => Operator::I64Rotr,
-            0x8b => Operator::F32Abs,
-            0x8c => Operator::F32Neg,
-            0x8d => Operator::F32Ceil,
-            0x8e => Operator::F32Floor,
-            0x8f => Operator::F32Trunc,
-            0x90 => Operator::F32Nearest,
-            0x91 => Operator::F32Sqrt,
-            0x92 => Operator::F32Add,
-            0x93 => Operator::F32Sub,
-            0x94 => Operator::F32Mul,
-            0x95 => Operator::F32Div,
-            0x96 => Operator::F32Min,
-            0x97 => Operator::F32Max,
-            0x98 => Operator::F32Copysign,
-            0x99 => Operator::F64Abs,
-            0x9a => Operator::F64Neg,
-            0x9b => Operator::F64Ceil,
-            0x9c => Operator::F64Floor,
-            0x9d => Operator::F64Trunc,
-            0x9e => Operator::F64Nearest,
-            0x9f => Operator::F64Sqrt,
-            0xa0 => Operator::F64Add,
-            0xa1 => Operator::F64Sub,
-            0xa2 => Operator::F64Mul,
-            0xa3 => Operator::F64Div,
-            0xa4 => Operator::F64Min,
-            0xa5 => Operator::F64Max,
-            0xa6 => Operator::F64Copysign,
-            0xa7 => Operator::I32WrapI64,
-            0xa8 => Operator::I32TruncF32S,
-            0xa9 => Operator::I32TruncF32U,
-            0xaa => Operator::I32TruncF64S,
-            0xab => Operator::I32TruncF64U,
-            0xac => Operator::I64ExtendI32S,
-            0xad => Operator::I64ExtendI32U,
-            0xae => Operator::I64TruncF32S,
-            0xaf => Operator::I64TruncF32U,
-            0xb0 => Operator::I64TruncF64S,
-            0xb1 => Operator::I64TruncF64U,
-            0xb2 => Operator::F32ConvertI32S,
-            0xb3 => Operator::F32ConvertI32U,
-            0xb4 => Operator::F32ConvertI64S,
-            0xb5 => Operator::F32ConvertI64U,
-            0xb6 => Operator::F32DemoteF64,
-            0xb7 => Operator::F64ConvertI32S,
-            0xb8 => Operator::F64ConvertI32U,
-            0xb9 => Operator::F64ConvertI64S,
-            0xba => Operator::F64ConvertI64U,
-            0xbb => Operator::F64PromoteF32,
-            0xbc => Operator::I32ReinterpretF32,
-            0xbd => Operator::I64ReinterpretF64,
-            0xbe => Operator::F32ReinterpretI32,
-            0xbf => Operator::F64ReinterpretI64,
-
-            0xc0 => Operator::I32Extend8S,
-            0xc1 => Operator::I32Extend16S,
-            0xc2 => Operator::I64Extend8S,
-            0xc3 => Operator::I64Extend16S,
-            0xc4 => Operator::I64Extend32S,
-
-            0xd0 => Operator::RefNull {
-                ty: self.read_type()?,
-            },
-            0xd1 => Operator::RefIsNull,
-            0xd2 => Operator::RefFunc {
-                function_index: self.read_var_u32()?,
-            },
-
-            0xfc => self.read_0xfc_operator()?,
-            0xfd => self.read_0xfd_operator()?,
-            0xfe => self.read_0xfe_operator()?,
+            0x41 => visitor.visit_i32_const(pos, self.read_var_i32()?),
+            0x42 => visitor.visit_i64_const(pos, self.read_var_i64()?),
+            0x43 => visitor.visit_f32_const(pos, self.read_f32()?),
+            0x44 => visitor.visit_f64_const(pos, self.read_f64()?),
+            0x45 => visitor.visit_i32_eqz(pos),
+            0x46 => visitor.visit_i32_eq(pos),
+            0x47 => visitor.visit_i32_ne(pos),
+            0x48 => visitor.visit_i32_lt_s(pos),
+            0x49 => visitor.visit_i32_lt_u(pos),
+            0x4a => visitor.visit_i32_gt_s(pos),
+            0x4b => visitor.visit_i32_gt_u(pos),
+            0x4c => visitor.visit_i32_le_s(pos),
+            0x4d => visitor.visit_i32_le_u(pos),
+            0x4e => visitor.visit_i32_ge_s(pos),
+            0x4f => visitor.visit_i32_ge_u(pos),
+            0x50 => visitor.visit_i64_eqz(pos),
+            0x51 => visitor.visit_i64_eq(pos),
+            0x52 => visitor.visit_i64_ne(pos),
+            0x53 => visitor.visit_i64_lt_s(pos),
+            0x54 => visitor.visit_i64_lt_u(pos),
+            0x55 => visitor.visit_i64_gt_s(pos),
+            0x56 => visitor.visit_i64_gt_u(pos),
+            0x57 => visitor.visit_i64_le_s(pos),
+            0x58 => visitor.visit_i64_le_u(pos),
+            0x59 => visitor.visit_i64_ge_s(pos),
+            0x5a => visitor.visit_i64_ge_u(pos),
+            0x5b => visitor.visit_f32_eq(pos),
+            0x5c => visitor.visit_f32_ne(pos),
+            0x5d => visitor.visit_f32_lt(pos),
+            0x5e => visitor.visit_f32_gt(pos),
+            0x5f => visitor.visit_f32_le(pos),
+            0x60 => visitor.visit_f32_ge(pos),
+            0x61 => visitor.visit_f64_eq(pos),
+            0x62 => visitor.visit_f64_ne(pos),
+            0x63 => visitor.visit_f64_lt(pos),
+            0x64 => visitor.visit_f64_gt(pos),
+            0x65 => visitor.visit_f64_le(pos),
+            0x66 => visitor.visit_f64_ge(pos),
+            0x67 => visitor.visit_i32_clz(pos),
+            0x68 => visitor.visit_i32_ctz(pos),
+            0x69 => visitor.visit_i32_popcnt(pos),
+            0x6a => visitor.visit_i32_add(pos),
+            0x6b => visitor.visit_i32_sub(pos),
+            0x6c => visitor.visit_i32_mul(pos),
+            0x6d => visitor.visit_i32_div_s(pos),
+            0x6e => visitor.visit_i32_div_u(pos),
+            0x6f => visitor.visit_i32_rem_s(pos),
+            0x70 => visitor.visit_i32_rem_u(pos),
+            0x71 => visitor.visit_i32_and(pos),
+            0x72 => visitor.visit_i32_or(pos),
+            0x73 => visitor.visit_i32_xor(pos),
+            0x74 => visitor.visit_i32_shl(pos),
+            0x75 => visitor.visit_i32_shr_s(pos),
+            0x76 => visitor.visit_i32_shr_u(pos),
+            0x77 => visitor.visit_i32_rotl(pos),
+            0x78 => visitor.visit_i32_rotr(pos),
+            0x79 => visitor.visit_i64_clz(pos),
+            0x7a => visitor.visit_i64_ctz(pos),
+            0x7b => visitor.visit_i64_popcnt(pos),
+            0x7c => visitor.visit_i64_add(pos),
+            0x7d => visitor.visit_i64_sub(pos),
+            0x7e => visitor.visit_i64_mul(pos),
+            0x7f => visitor.visit_i64_div_s(pos),
+            0x80 => visitor.visit_i64_div_u(pos),
+            0x81 => visitor.visit_i64_rem_s(pos),
+            0x82 => visitor.visit_i64_rem_u(pos),
+            0x83 => visitor.visit_i64_and(pos),
+            0x84 => visitor.visit_i64_or(pos),
+            0x85 => visitor.visit_i64_xor(pos),
+            0x86 => visitor.visit_i64_shl(pos),
+            0x87 => visitor.visit_i64_shr_s(pos),
+            0x88 => visitor.visit_i64_shr_u(pos),
+            0x89 => visitor.visit_i64_rotl(pos),
+            0x8a => visitor.visit_i64_rotr(pos),
+            0x8b => visitor.visit_f32_abs(pos),
+            0x8c => visitor.visit_f32_neg(pos),
+            0x8d => visitor.visit_f32_ceil(pos),
+            0x8e => visitor.visit_f32_floor(pos),
+            0x8f => visitor.visit_f32_trunc(pos),
+            0x90 => visitor.visit_f32_nearest(pos),
+            0x91 => visitor.visit_f32_sqrt(pos),
+            0x92 => visitor.visit_f32_add(pos),
+            0x93 => visitor.visit_f32_sub(pos),
+            0x94 => visitor.visit_f32_mul(pos),
+            0x95 => visitor.visit_f32_div(pos),
+            0x96 => visitor.visit_f32_min(pos),
+            0x97 => visitor.visit_f32_max(pos),
+            0x98 => visitor.visit_f32_copysign(pos),
+            0x99 => visitor.visit_f64_abs(pos),
+            0x9a => visitor.visit_f64_neg(pos),
+            0x9b => visitor.visit_f64_ceil(pos),
+            0x9c => visitor.visit_f64_floor(pos),
+            0x9d => visitor.visit_f64_trunc(pos),
+            0x9e => visitor.visit_f64_nearest(pos),
+            0x9f => visitor.visit_f64_sqrt(pos),
+            0xa0 => visitor.visit_f64_add(pos),
+            0xa1 => visitor.visit_f64_sub(pos),
+            0xa2 => visitor.visit_f64_mul(pos),
+            0xa3 => visitor.visit_f64_div(pos),
+            0xa4 => visitor.visit_f64_min(pos),
+            0xa5 => visitor.visit_f64_max(pos),
+            0xa6 => visitor.visit_f64_copysign(pos),
+            0xa7 => visitor.visit_i32_wrap_i64(pos),
+            0xa8 => visitor.visit_i32_trunc_f32_s(pos),
+            0xa9 => visitor.visit_i32_trunc_f32_u(pos),
+            0xaa => visitor.visit_i32_trunc_f64_s(pos),
+            0xab => visitor.visit_i32_trunc_f64_u(pos),
+            0xac => visitor.visit_i64_extend_i32_s(pos),
+            0xad => visitor.visit_i64_extend_i32_u(pos),
+            0xae => visitor.visit_i64_trunc_f32_s(pos),
+            0xaf => visitor.visit_i64_trunc_f32_u(pos),
+            0xb0 => visitor.visit_i64_trunc_f64_s(pos),
+            0xb1 => visitor.visit_i64_trunc_f64_u(pos),
+            0xb2 => visitor.visit_f32_convert_i32_s(pos),
+            0xb3 => visitor.visit_f32_convert_i32_u(pos),
+            0xb4 => visitor.visit_f32_convert_i64_s(pos),
+            0xb5 => visitor.visit_f32_convert_i64_u(pos),
+            0xb6 => visitor.visit_f32_demote_f64(pos),
+            0xb7 => visitor.visit_f64_convert_i32_s(pos),
+            0xb8 => visitor.visit_f64_convert_i32_u(pos),
+            0xb9 => visitor.visit_f64_convert_i64_s(pos),
+            0xba => visitor.visit_f64_convert_i64_u(pos),
+            0xbb => visitor.visit_f64_promote_f32(pos),
+            0xbc => visitor.visit_i32_reinterpret_f32(pos),
+            0xbd => visitor.visit_i64_reinterpret_f64(pos),
+            0xbe => visitor.visit_f32_reinterpret_i32(pos),
+            0xbf => visitor.visit_f64_reinterpret_i64(pos),
+
+            0xc0 => visitor.visit_i32_extend8_s(pos),
+            0xc1 => visitor.visit_i32_extend16_s(pos),
+            0xc2 => visitor.visit_i64_extend8_s(pos),
+            0xc3 => visitor.visit_i64_extend16_s(pos),
+            0xc4 => visitor.visit_i64_extend32_s(pos),
+
+            0xd0 => visitor.visit_ref_null(pos, self.read_type()?),
+            0xd1 => visitor.visit_ref_is_null(pos),
+            0xd2 => visitor.visit_ref_func(pos, self.read_var_u32()?),
+
+            0xfc => self.visit_0xfc_operator(pos, visitor)?,
+            0xfd => self.visit_0xfd_operator(pos, visitor)?,
+            0xfe => self.visit_0xfe_operator(pos, visitor)?,
 
             _ => {
                 return Err(BinaryReaderError::new(
@@ -1423,66 +1160,66 @@ impl<'a> BinaryReader<'a> {
         })
     }
 
-    fn read_0xfc_operator(&mut self) -> Result<Operator<'a>> {
+    fn visit_0xfc_operator<T>(&mut self, pos: usize, visitor: &mut T) -> Result<T::Output>
+    where
+        T: VisitOperator<'a>,
+    {
         let code = self.read_var_u32()?;
         Ok(match code {
-            0x00 => Operator::I32TruncSatF32S,
-            0x01 => Operator::I32TruncSatF32U,
-            0x02 => Operator::I32TruncSatF64S,
-            0x03 => Operator::I32TruncSatF64U,
-            0x04 => Operator::I64TruncSatF32S,
-            0x05 => Operator::I64TruncSatF32U,
-            0x06 => Operator::I64TruncSatF64S,
-            0x07 => Operator::I64TruncSatF64U,
+            0x00 => visitor.visit_i32_trunc_sat_f32_s(pos),
+            0x01 => visitor.visit_i32_trunc_sat_f32_u(pos),
+            0x02 => visitor.visit_i32_trunc_sat_f64_s(pos),
+            0x03 => visitor.visit_i32_trunc_sat_f64_u(pos),
+            0x04 => visitor.visit_i64_trunc_sat_f32_s(pos),
+            0x05 => visitor.visit_i64_trunc_sat_f32_u(pos),
+            0x06 => visitor.visit_i64_trunc_sat_f64_s(pos),
+            0x07 => visitor.visit_i64_trunc_sat_f64_u(pos),
 
             0x08 => {
                 let segment = self.read_var_u32()?;
                 let mem = self.read_var_u32()?;
-                Operator::MemoryInit { segment, mem }
+                visitor.visit_memory_init(pos, segment, mem)
             }
             0x09 => {
                 let segment = self.read_var_u32()?;
-                Operator::DataDrop { segment }
+                visitor.visit_data_drop(pos, segment)
             }
             0x0a => {
                 let dst = self.read_var_u32()?;
                 let src = self.read_var_u32()?;
-                Operator::MemoryCopy { src, dst }
+                visitor.visit_memory_copy(pos, src, dst)
             }
             0x0b => {
                 let mem = self.read_var_u32()?;
-                Operator::MemoryFill { mem }
+                visitor.visit_memory_fill(pos, mem)
             }
             0x0c => {
                 let segment = self.read_var_u32()?;
                 let table = self.read_var_u32()?;
-                Operator::TableInit { segment, table }
+                visitor.visit_table_init(pos, segment, table)
             }
             0x0d => {
                 let segment = self.read_var_u32()?;
-                Operator::ElemDrop { segment }
+                visitor.visit_elem_drop(pos, segment)
             }
             0x0e => {
                 let dst_table = self.read_var_u32()?;
                 let src_table = self.read_var_u32()?;
-                Operator::TableCopy {
-                    src_table,
-                    dst_table,
-                }
+                visitor.visit_table_copy(pos, dst_table, src_table)
             }
 
             0x0f => {
                 let table = self.read_var_u32()?;
-                Operator::TableGrow { table }
+                visitor.visit_table_grow(pos, table)
             }
             0x10 => {
                 let table = self.read_var_u32()?;
-                Operator::TableSize { table }
+                visitor.visit_table_size(pos, table)
             }
 
             0x11 => {
                 let table = self.read_var_u32()?;
-                Operator::TableFill { table }
+                visitor.visit_table_fill(pos, table)
             }
 
             _ => {
@@ -1511,350 +1248,287 @@ impl<'a> BinaryReader<'a> {
         Ok(V128(bytes))
     }
 
-    fn read_0xfd_operator(&mut self) -> Result<Operator<'a>> {
+    fn visit_0xfd_operator<T>(&mut self, pos: usize, visitor: &mut T) -> Result<T::Output>
+    where
+        T: VisitOperator<'a>,
+    {
         let code = self.read_var_u32()?;
         Ok(match code {
-            0x00 => Operator::V128Load {
-                memarg: self.read_memarg()?,
-            },
-            0x01 => Operator::V128Load8x8S {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x02 => Operator::V128Load8x8U {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x03 => Operator::V128Load16x4S {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x04 => Operator::V128Load16x4U {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x05 => Operator::V128Load32x2S {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x06 => Operator::V128Load32x2U {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x07 => Operator::V128Load8Splat {
-                memarg: self.read_memarg_of_align(0)?,
-            },
-            0x08 => Operator::V128Load16Splat {
-                memarg: self.read_memarg_of_align(1)?,
-            },
-            0x09 => Operator::V128Load32Splat {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x0a => Operator::V128Load64Splat {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x0b => Operator::V128Store {
-                memarg: self.read_memarg()?,
-            },
-            0x0c => Operator::V128Const {
-                value: self.read_v128()?,
-            },
+            0x00 => visitor.visit_v128_load(pos, self.read_memarg()?),
+            0x01 => visitor.visit_v128_load8x8_s(pos, self.read_memarg_of_align(3)?),
+            0x02 => visitor.visit_v128_load8x8_u(pos, self.read_memarg_of_align(3)?),
+            0x03 => visitor.visit_v128_load16x4_s(pos, self.read_memarg_of_align(3)?),
+            0x04 => visitor.visit_v128_load16x4_u(pos, self.read_memarg_of_align(3)?),
+            0x05 => visitor.visit_v128_load32x2_s(pos, self.read_memarg_of_align(3)?),
+            0x06 => visitor.visit_v128_load32x2_u(pos, self.read_memarg_of_align(3)?),
+            0x07 => visitor.visit_v128_load8_splat(pos, self.read_memarg_of_align(0)?),
+            0x08 => visitor.visit_v128_load16_splat(pos, self.read_memarg_of_align(1)?),
+            0x09 => visitor.visit_v128_load32_splat(pos, self.read_memarg_of_align(2)?),
+            0x0a => visitor.visit_v128_load64_splat(pos, self.read_memarg_of_align(3)?),
+            0x0b => visitor.visit_v128_store(pos, self.read_memarg()?),
+            0x0c => visitor.visit_v128_const(pos, self.read_v128()?),
             0x0d => {
                 let mut lanes: [SIMDLaneIndex; 16] = [0; 16];
                 for lane in &mut lanes {
                     *lane = self.read_lane_index(32)?
                 }
-                Operator::I8x16Shuffle { lanes }
+                visitor.visit_i8x16_shuffle(pos, lanes)
             }
-            0x0e => Operator::I8x16Swizzle,
-            0x0f => Operator::I8x16Splat,
-            0x10 => Operator::I16x8Splat,
-            0x11 => Operator::I32x4Splat,
-            0x12 => Operator::I64x2Splat,
-            0x13 => Operator::F32x4Splat,
-            0x14 => Operator::F64x2Splat,
-            0x15 => Operator::I8x16ExtractLaneS {
-                lane: self.read_lane_index(16)?,
-            },
-            0x16 => Operator::I8x16ExtractLaneU {
-                lane: self.read_lane_index(16)?,
-            },
-            0x17 => Operator::I8x16ReplaceLane {
-                lane: self.read_lane_index(16)?,
-            },
-            0x18 => Operator::I16x8ExtractLaneS {
-                lane: self.read_lane_index(8)?,
-            },
-            0x19 => Operator::I16x8ExtractLaneU {
-                lane: self.read_lane_index(8)?,
-            },
-            0x1a => Operator::I16x8ReplaceLane {
-                lane: self.read_lane_index(8)?,
-            },
-            0x1b => Operator::I32x4ExtractLane {
-                lane: self.read_lane_index(4)?,
-            },
-            0x1c => Operator::I32x4ReplaceLane {
-                lane: self.read_lane_index(4)?,
-            },
-            0x1d => Operator::I64x2ExtractLane {
-                lane: self.read_lane_index(2)?,
-            },
-            0x1e => Operator::I64x2ReplaceLane {
-                lane: self.read_lane_index(2)?,
-            },
-            0x1f => Operator::F32x4ExtractLane {
-                lane: self.read_lane_index(4)?,
-            },
-            0x20 => Operator::F32x4ReplaceLane {
-                lane: self.read_lane_index(4)?,
-            },
-            0x21 => Operator::F64x2ExtractLane {
-                lane: self.read_lane_index(2)?,
-            },
-            0x22 => Operator::F64x2ReplaceLane {
-                lane: self.read_lane_index(2)?,
-            },
-            0x23 => Operator::I8x16Eq,
-            0x24 => Operator::I8x16Ne,
-            0x25 => Operator::I8x16LtS,
-            0x26 => Operator::I8x16LtU,
-            0x27 => Operator::I8x16GtS,
-            0x28 => Operator::I8x16GtU,
-            0x29 => Operator::I8x16LeS,
-            0x2a => Operator::I8x16LeU,
-            0x2b => Operator::I8x16GeS,
-            0x2c => Operator::I8x16GeU,
-            0x2d => Operator::I16x8Eq,
-            0x2e => Operator::I16x8Ne,
-            0x2f => Operator::I16x8LtS,
-            0x30 => Operator::I16x8LtU,
-            0x31 => Operator::I16x8GtS,
-            0x32 => Operator::I16x8GtU,
-            0x33 => Operator::I16x8LeS,
-            0x34 => Operator::I16x8LeU,
-            0x35 => Operator::I16x8GeS,
-            0x36 => Operator::I16x8GeU,
-            0x37 => Operator::I32x4Eq,
-            0x38 => Operator::I32x4Ne,
-            0x39 => Operator::I32x4LtS,
-            0x3a => Operator::I32x4LtU,
-            0x3b => Operator::I32x4GtS,
-            0x3c => Operator::I32x4GtU,
-            0x3d => Operator::I32x4LeS,
-            0x3e => Operator::I32x4LeU,
-            0x3f => Operator::I32x4GeS,
-            0x40 => Operator::I32x4GeU,
-            0x41 => Operator::F32x4Eq,
-            0x42 => Operator::F32x4Ne,
-            0x43 => Operator::F32x4Lt,
-            0x44 => Operator::F32x4Gt,
-            0x45 => Operator::F32x4Le,
-            0x46 => Operator::F32x4Ge,
-            0x47 => Operator::F64x2Eq,
-            0x48 => Operator::F64x2Ne,
-            0x49 => Operator::F64x2Lt,
-            0x4a => Operator::F64x2Gt,
-            0x4b => Operator::F64x2Le,
-            0x4c => Operator::F64x2Ge,
-            0x4d => Operator::V128Not,
-            0x4e => Operator::V128And,
-            0x4f => Operator::V128AndNot,
-            0x50 => Operator::V128Or,
-            0x51 => Operator::V128Xor,
-            0x52 => Operator::V128Bitselect,
-            0x53 => Operator::V128AnyTrue,
-            0x54 => Operator::V128Load8Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(16)?,
-            },
-            0x55 => Operator::V128Load16Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(8)?,
-            },
-            0x56 => Operator::V128Load32Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(4)?,
-            },
-            0x57 => Operator::V128Load64Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(2)?,
-            },
-            0x58 => Operator::V128Store8Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(16)?,
-            },
-            0x59 => Operator::V128Store16Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(8)?,
-            },
-            0x5a => Operator::V128Store32Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(4)?,
-            },
-            0x5b => Operator::V128Store64Lane {
-                memarg: self.read_memarg()?,
-                lane: self.read_lane_index(2)?,
-            },
-            0x5c => Operator::V128Load32Zero {
-                memarg: self.read_memarg_of_align(2)?,
-            },
-            0x5d => Operator::V128Load64Zero {
-                memarg: self.read_memarg_of_align(3)?,
-            },
-            0x5e => Operator::F32x4DemoteF64x2Zero,
-            0x5f => Operator::F64x2PromoteLowF32x4,
-            0x60 => Operator::I8x16Abs,
-            0x61 => Operator::I8x16Neg,
-            0x62 => Operator::I8x16Popcnt,
-            0x63 => Operator::I8x16AllTrue,
-            0x64 => Operator::I8x16Bitmask,
-            0x65 => Operator::I8x16NarrowI16x8S,
-            0x66 => Operator::I8x16NarrowI16x8U,
-            0x67 => Operator::F32x4Ceil,
-            0x68 => Operator::F32x4Floor,
-            0x69 => Operator::F32x4Trunc,
-            0x6a => Operator::F32x4Nearest,
-            0x6b => Operator::I8x16Shl,
-            0x6c => Operator::I8x16ShrS,
-            0x6d => Operator::I8x16ShrU,
-            0x6e => Operator::I8x16Add,
-            0x6f => Operator::I8x16AddSatS,
-            0x70 => Operator::I8x16AddSatU,
-            0x71 => Operator::I8x16Sub,
-            0x72 => Operator::I8x16SubSatS,
-            0x73 => Operator::I8x16SubSatU,
-            0x74 => Operator::F64x2Ceil,
-            0x75 => Operator::F64x2Floor,
-            0x76 => Operator::I8x16MinS,
-            0x77 => Operator::I8x16MinU,
-            0x78 => Operator::I8x16MaxS,
-            0x79 => Operator::I8x16MaxU,
-            0x7a => Operator::F64x2Trunc,
-            0x7b => Operator::I8x16RoundingAverageU,
-            0x7c => Operator::I16x8ExtAddPairwiseI8x16S,
-            0x7d => Operator::I16x8ExtAddPairwiseI8x16U,
-            0x7e => Operator::I32x4ExtAddPairwiseI16x8S,
-            0x7f => Operator::I32x4ExtAddPairwiseI16x8U,
-            0x80 => Operator::I16x8Abs,
-            0x81 => Operator::I16x8Neg,
-            0x82 => Operator::I16x8Q15MulrSatS,
-            0x83 => Operator::I16x8AllTrue,
-            0x84 => Operator::I16x8Bitmask,
-            0x85 => Operator::I16x8NarrowI32x4S,
-            0x86 => Operator::I16x8NarrowI32x4U,
-            0x87 => Operator::I16x8ExtendLowI8x16S,
-            0x88 => Operator::I16x8ExtendHighI8x16S,
-            0x89 => Operator::I16x8ExtendLowI8x16U,
-            0x8a => Operator::I16x8ExtendHighI8x16U,
-            0x8b => Operator::I16x8Shl,
-            0x8c => Operator::I16x8ShrS,
-            0x8d => Operator::I16x8ShrU,
-            0x8e => Operator::I16x8Add,
-            0x8f => Operator::I16x8AddSatS,
-            0x90 => Operator::I16x8AddSatU,
-            0x91 => Operator::I16x8Sub,
-            0x92 => Operator::I16x8SubSatS,
-            0x93 => Operator::I16x8SubSatU,
-            0x94 => Operator::F64x2Nearest,
-            0x95 => Operator::I16x8Mul,
-            0x96 => Operator::I16x8MinS,
-            0x97 => Operator::I16x8MinU,
-            0x98 => Operator::I16x8MaxS,
-            0x99 => Operator::I16x8MaxU,
-            0x9b => Operator::I16x8RoundingAverageU,
-            0x9c => Operator::I16x8ExtMulLowI8x16S,
-            0x9d => Operator::I16x8ExtMulHighI8x16S,
-            0x9e => Operator::I16x8ExtMulLowI8x16U,
-            0x9f => Operator::I16x8ExtMulHighI8x16U,
-            0xa0 => Operator::I32x4Abs,
-            0xa2 => Operator::I8x16RelaxedSwizzle,
-            0xa1 => Operator::I32x4Neg,
-            0xa3 => Operator::I32x4AllTrue,
-            0xa4 => Operator::I32x4Bitmask,
-            0xa5 => Operator::I32x4RelaxedTruncSatF32x4S,
-            0xa6 => Operator::I32x4RelaxedTruncSatF32x4U,
-            0xa7 => Operator::I32x4ExtendLowI16x8S,
-            0xa8 => Operator::I32x4ExtendHighI16x8S,
-            0xa9 => Operator::I32x4ExtendLowI16x8U,
-            0xaa => Operator::I32x4ExtendHighI16x8U,
-            0xab => Operator::I32x4Shl,
-            0xac => Operator::I32x4ShrS,
-            0xad => Operator::I32x4ShrU,
-            0xae => Operator::I32x4Add,
-            0xaf => Operator::F32x4Fma,
-            0xb0 => Operator::F32x4Fms,
-            0xb1 => Operator::I32x4Sub,
-            0xb2 => Operator::I8x16LaneSelect,
-            0xb3 => Operator::I16x8LaneSelect,
-            0xb4 => Operator::F32x4RelaxedMin,
-            0xb5 => Operator::I32x4Mul,
-            0xb6 => Operator::I32x4MinS,
-            0xb7 => Operator::I32x4MinU,
-            0xb8 => Operator::I32x4MaxS,
-            0xb9 => Operator::I32x4MaxU,
-            0xba => Operator::I32x4DotI16x8S,
-            0xbc => Operator::I32x4ExtMulLowI16x8S,
-            0xbd => Operator::I32x4ExtMulHighI16x8S,
-            0xbe => Operator::I32x4ExtMulLowI16x8U,
-            0xbf => Operator::I32x4ExtMulHighI16x8U,
-            0xc0 => Operator::I64x2Abs,
-            0xc1 => Operator::I64x2Neg,
-            0xc3 => Operator::I64x2AllTrue,
-            0xc4 => Operator::I64x2Bitmask,
-            0xc5 => Operator::I32x4RelaxedTruncSatF64x2SZero,
-            0xc6 => Operator::I32x4RelaxedTruncSatF64x2UZero,
-            0xc7 => Operator::I64x2ExtendLowI32x4S,
-            0xc8 => Operator::I64x2ExtendHighI32x4S,
-            0xc9 => Operator::I64x2ExtendLowI32x4U,
-            0xca => Operator::I64x2ExtendHighI32x4U,
-            0xcb => Operator::I64x2Shl,
-            0xcc => Operator::I64x2ShrS,
-            0xcd => Operator::I64x2ShrU,
-            0xce => Operator::I64x2Add,
-            0xcf => Operator::F64x2Fma,
-            0xd0 => Operator::F64x2Fms,
-            0xd1 => Operator::I64x2Sub,
-            0xd2 => Operator::I32x4LaneSelect,
-            0xd3 => Operator::I64x2LaneSelect,
-            0xd4 => Operator::F64x2RelaxedMin,
-            0xd5 => Operator::I64x2Mul,
-            0xd6 => Operator::I64x2Eq,
-            0xd7 => Operator::I64x2Ne,
-            0xd8 => Operator::I64x2LtS,
-            0xd9 => Operator::I64x2GtS,
-            0xda => Operator::I64x2LeS,
-            0xdb => Operator::I64x2GeS,
-            0xdc => Operator::I64x2ExtMulLowI32x4S,
-            0xdd => Operator::I64x2ExtMulHighI32x4S,
-            0xde => Operator::I64x2ExtMulLowI32x4U,
-            0xdf => Operator::I64x2ExtMulHighI32x4U,
-            0xe0 => Operator::F32x4Abs,
-            0xe1 => Operator::F32x4Neg,
-            0xe2 => Operator::F32x4RelaxedMax,
-            0xe3 => Operator::F32x4Sqrt,
-            0xe4 => Operator::F32x4Add,
-            0xe5 => Operator::F32x4Sub,
-            0xe6 => Operator::F32x4Mul,
-            0xe7 => Operator::F32x4Div,
-            0xe8 => Operator::F32x4Min,
-            0xe9 => Operator::F32x4Max,
-            0xea => Operator::F32x4PMin,
-            0xeb => Operator::F32x4PMax,
-            0xec => Operator::F64x2Abs,
-            0xed => Operator::F64x2Neg,
-            0xee => Operator::F64x2RelaxedMax,
-            0xef => Operator::F64x2Sqrt,
-            0xf0 => Operator::F64x2Add,
-            0xf1 => Operator::F64x2Sub,
-            0xf2 => Operator::F64x2Mul,
-            0xf3 => Operator::F64x2Div,
-            0xf4 => Operator::F64x2Min,
-            0xf5 => Operator::F64x2Max,
-            0xf6 => Operator::F64x2PMin,
-            0xf7 => Operator::F64x2PMax,
-            0xf8 => Operator::I32x4TruncSatF32x4S,
-            0xf9 => Operator::I32x4TruncSatF32x4U,
-            0xfa => Operator::F32x4ConvertI32x4S,
-            0xfb => Operator::F32x4ConvertI32x4U,
-            0xfc => Operator::I32x4TruncSatF64x2SZero,
-            0xfd => Operator::I32x4TruncSatF64x2UZero,
-            0xfe => Operator::F64x2ConvertLowI32x4S,
-            0xff => Operator::F64x2ConvertLowI32x4U,
+            0x0e => visitor.visit_i8x16_swizzle(pos),
+            0x0f => visitor.visit_i8x16_splat(pos),
+            0x10 => visitor.visit_i16x8_splat(pos),
+            0x11 => visitor.visit_i32x4_splat(pos),
+            0x12 => visitor.visit_i64x2_splat(pos),
+            0x13 => visitor.visit_f32x4_splat(pos),
+            0x14 => visitor.visit_f64x2_splat(pos),
+            0x15 => visitor.visit_i8x16_extract_lane_s(pos, self.read_lane_index(16)?),
+            0x16 => visitor.visit_i8x16_extract_lane_u(pos, self.read_lane_index(16)?),
+            0x17 => visitor.visit_i8x16_replace_lane(pos, self.read_lane_index(16)?),
+            0x18 => visitor.visit_i16x8_extract_lane_s(pos, self.read_lane_index(8)?),
+            0x19 => visitor.visit_i16x8_extract_lane_u(pos, self.read_lane_index(8)?),
+            0x1a => visitor.visit_i16x8_replace_lane(pos, self.read_lane_index(8)?),
+            0x1b => visitor.visit_i32x4_extract_lane(pos, self.read_lane_index(4)?),
+            0x1c => visitor.visit_i32x4_replace_lane(pos, self.read_lane_index(4)?),
+            0x1d => visitor.visit_i64x2_extract_lane(pos, self.read_lane_index(2)?),
+            0x1e => visitor.visit_i64x2_replace_lane(pos, self.read_lane_index(2)?),
+            0x1f => visitor.visit_f32x4_extract_lane(pos, self.read_lane_index(4)?),
+            0x20 => visitor.visit_f32x4_replace_lane(pos, self.read_lane_index(4)?),
+            0x21 => visitor.visit_f64x2_extract_lane(pos, self.read_lane_index(2)?),
+            0x22 => visitor.visit_f64x2_replace_lane(pos, self.read_lane_index(2)?),
+            0x23 => visitor.visit_i8x16_eq(pos),
+            0x24 => visitor.visit_i8x16_ne(pos),
+            0x25 => visitor.visit_i8x16_lt_s(pos),
+            0x26 => visitor.visit_i8x16_lt_u(pos),
+            0x27 => visitor.visit_i8x16_gt_s(pos),
+            0x28 => visitor.visit_i8x16_gt_u(pos),
+            0x29 => visitor.visit_i8x16_le_s(pos),
+            0x2a => visitor.visit_i8x16_le_u(pos),
+            0x2b => visitor.visit_i8x16_ge_s(pos),
+            0x2c => visitor.visit_i8x16_ge_u(pos),
+            0x2d => visitor.visit_i16x8_eq(pos),
+            0x2e => visitor.visit_i16x8_ne(pos),
+            0x2f => visitor.visit_i16x8_lt_s(pos),
+            0x30 => visitor.visit_i16x8_lt_u(pos),
+            0x31 => visitor.visit_i16x8_gt_s(pos),
+            0x32 => visitor.visit_i16x8_gt_u(pos),
+            0x33 => visitor.visit_i16x8_le_s(pos),
+            0x34 => visitor.visit_i16x8_le_u(pos),
+            0x35 => visitor.visit_i16x8_ge_s(pos),
+            0x36 => visitor.visit_i16x8_ge_u(pos),
+            0x37 => visitor.visit_i32x4_eq(pos),
+            0x38 => visitor.visit_i32x4_ne(pos),
+            0x39 => visitor.visit_i32x4_lt_s(pos),
+            0x3a => visitor.visit_i32x4_lt_u(pos),
+            0x3b => visitor.visit_i32x4_gt_s(pos),
+            0x3c => visitor.visit_i32x4_gt_u(pos),
+            0x3d => visitor.visit_i32x4_le_s(pos),
+            0x3e => visitor.visit_i32x4_le_u(pos),
+            0x3f => visitor.visit_i32x4_ge_s(pos),
+            0x40 => visitor.visit_i32x4_ge_u(pos),
+            0x41 => visitor.visit_f32x4_eq(pos),
+            0x42 => visitor.visit_f32x4_ne(pos),
+            0x43 => visitor.visit_f32x4_lt(pos),
+            0x44 => visitor.visit_f32x4_gt(pos),
+            0x45 => visitor.visit_f32x4_le(pos),
+            0x46 => visitor.visit_f32x4_ge(pos),
+            0x47 => visitor.visit_f64x2_eq(pos),
+            0x48 => visitor.visit_f64x2_ne(pos),
+            0x49 => visitor.visit_f64x2_lt(pos),
+            0x4a => visitor.visit_f64x2_gt(pos),
+            0x4b => visitor.visit_f64x2_le(pos),
+            0x4c => visitor.visit_f64x2_ge(pos),
+            0x4d => visitor.visit_v128_not(pos),
+            0x4e => visitor.visit_v128_and(pos),
+            0x4f => visitor.visit_v128_and_not(pos),
+            0x50 => visitor.visit_v128_or(pos),
+            0x51 => visitor.visit_v128_xor(pos),
+            0x52 => visitor.visit_v128_bitselect(pos),
+            0x53 => visitor.visit_v128_any_true(pos),
+            0x54 => {
+                visitor.visit_v128_load8_lane(pos, self.read_memarg()?, self.read_lane_index(16)?)
+            }
+            0x55 => {
+                visitor.visit_v128_load16_lane(pos, self.read_memarg()?, self.read_lane_index(8)?)
+            }
+            0x56 => {
+                visitor.visit_v128_load32_lane(pos, self.read_memarg()?, self.read_lane_index(4)?)
+            }
+            0x57 => {
+                visitor.visit_v128_load64_lane(pos, self.read_memarg()?, self.read_lane_index(2)?)
+            }
+            0x58 => {
+                visitor.visit_v128_store8_lane(pos, self.read_memarg()?, self.read_lane_index(16)?)
+            }
+            0x59 => {
+                visitor.visit_v128_store16_lane(pos, self.read_memarg()?, self.read_lane_index(8)?)
+            }
+            0x5a => {
+                visitor.visit_v128_store32_lane(pos, self.read_memarg()?, self.read_lane_index(4)?)
+            }
+            0x5b => {
+                visitor.visit_v128_store64_lane(pos, self.read_memarg()?, self.read_lane_index(2)?)
+            }
+            0x5c => visitor.visit_v128_load32_zero(pos, self.read_memarg_of_align(2)?),
+            0x5d => visitor.visit_v128_load64_zero(pos, self.read_memarg_of_align(3)?),
+            0x5e => visitor.visit_f32x4_demote_f64x2_zero(pos),
+            0x5f => visitor.visit_f64x2_promote_low_f32x4(pos),
+            0x60 => visitor.visit_i8x16_abs(pos),
+            0x61 => visitor.visit_i8x16_neg(pos),
+            0x62 => visitor.visit_i8x16_popcnt(pos),
+            0x63 => visitor.visit_i8x16_all_true(pos),
+            0x64 => visitor.visit_i8x16_bitmask(pos),
+            0x65 => visitor.visit_i8x16_narrow_i16x8_s(pos),
+            0x66 => visitor.visit_i8x16_narrow_i16x8_u(pos),
+            0x67 => visitor.visit_f32x4_ceil(pos),
+            0x68 => visitor.visit_f32x4_floor(pos),
+            0x69 => visitor.visit_f32x4_trunc(pos),
+            0x6a => visitor.visit_f32x4_nearest(pos),
+            0x6b => visitor.visit_i8x16_shl(pos),
+            0x6c => visitor.visit_i8x16_shr_s(pos),
+            0x6d => visitor.visit_i8x16_shr_u(pos),
+            0x6e => visitor.visit_i8x16_add(pos),
+            0x6f => visitor.visit_i8x16_add_sat_s(pos),
+            0x70 => visitor.visit_i8x16_add_sat_u(pos),
+            0x71 => visitor.visit_i8x16_sub(pos),
+            0x72 => visitor.visit_i8x16_sub_sat_s(pos),
+            0x73 => visitor.visit_i8x16_sub_sat_u(pos),
+            0x74 => visitor.visit_f64x2_ceil(pos),
+            0x75 => visitor.visit_f64x2_floor(pos),
+            0x76 => visitor.visit_i8x16_min_s(pos),
+            0x77 => visitor.visit_i8x16_min_u(pos),
+            0x78 => visitor.visit_i8x16_max_s(pos),
+            0x79 => visitor.visit_i8x16_max_u(pos),
+            0x7a => visitor.visit_f64x2_trunc(pos),
+            0x7b => visitor.visit_i8x16_rounding_average_u(pos),
+            0x7c => visitor.visit_i16x8_ext_add_pairwise_i8x16_s(pos),
+            0x7d => visitor.visit_i16x8_ext_add_pairwise_i8x16_u(pos),
+            0x7e => visitor.visit_i32x4_ext_add_pairwise_i16x8_s(pos),
+            0x7f => visitor.visit_i32x4_ext_add_pairwise_i16x8_u(pos),
+            0x80 => visitor.visit_i16x8_abs(pos),
+            0x81 => visitor.visit_i16x8_neg(pos),
+            0x82 => visitor.visit_i16x8_q15_mulr_sat_s(pos),
+            0x83 => visitor.visit_i16x8_all_true(pos),
+            0x84 => visitor.visit_i16x8_bitmask(pos),
+            0x85 => visitor.visit_i16x8_narrow_i32x4_s(pos),
+            0x86 => visitor.visit_i16x8_narrow_i32x4_u(pos),
+            0x87 => visitor.visit_i16x8_extend_low_i8x16_s(pos),
+            0x88 => visitor.visit_i16x8_extend_high_i8x16_s(pos),
+            0x89 => visitor.visit_i16x8_extend_low_i8x16_u(pos),
+            0x8a => visitor.visit_i16x8_extend_high_i8x16_u(pos),
+            0x8b => visitor.visit_i16x8_shl(pos),
+            0x8c => visitor.visit_i16x8_shr_s(pos),
+            0x8d => visitor.visit_i16x8_shr_u(pos),
+            0x8e => visitor.visit_i16x8_add(pos),
+            0x8f => visitor.visit_i16x8_add_sat_s(pos),
+            0x90 => visitor.visit_i16x8_add_sat_u(pos),
+            0x91 => visitor.visit_i16x8_sub(pos),
+            0x92 => visitor.visit_i16x8_sub_sat_s(pos),
+            0x93 => visitor.visit_i16x8_sub_sat_u(pos),
+            0x94 => visitor.visit_f64x2_nearest(pos),
+            0x95 => visitor.visit_i16x8_mul(pos),
+            0x96 => visitor.visit_i16x8_min_s(pos),
+            0x97 => visitor.visit_i16x8_min_u(pos),
+            0x98 => visitor.visit_i16x8_max_s(pos),
+            0x99 => visitor.visit_i16x8_max_u(pos),
+            0x9b => visitor.visit_i16x8_rounding_average_u(pos),
+            0x9c => visitor.visit_i16x8_ext_mul_low_i8x16_s(pos),
+            0x9d => visitor.visit_i16x8_ext_mul_high_i8x16_s(pos),
+            0x9e => visitor.visit_i16x8_ext_mul_low_i8x16_u(pos),
+            0x9f => visitor.visit_i16x8_ext_mul_high_i8x16_u(pos),
+            0xa0 => visitor.visit_i32x4_abs(pos),
+            0xa2 => visitor.visit_i8x16_relaxed_swizzle(pos),
+            0xa1 => visitor.visit_i32x4_neg(pos),
+            0xa3 => visitor.visit_i32x4_all_true(pos),
+            0xa4 => visitor.visit_i32x4_bitmask(pos),
+            0xa5 => visitor.visit_i32x4_relaxed_trunc_sat_f32x4_s(pos),
+            0xa6 => visitor.visit_i32x4_relaxed_trunc_sat_f32x4_u(pos),
+            0xa7 => visitor.visit_i32x4_extend_low_i16x8_s(pos),
+            0xa8 => visitor.visit_i32x4_extend_high_i16x8_s(pos),
+            0xa9 => visitor.visit_i32x4_extend_low_i16x8_u(pos),
+            0xaa => visitor.visit_i32x4_extend_high_i16x8_u(pos),
+            0xab => visitor.visit_i32x4_shl(pos),
+            0xac => visitor.visit_i32x4_shr_s(pos),
+            0xad => visitor.visit_i32x4_shr_u(pos),
+            0xae => visitor.visit_i32x4_add(pos),
+            0xaf => visitor.visit_f32x4_fma(pos),
+            0xb0 => visitor.visit_f32x4_fms(pos),
+            0xb1 => visitor.visit_i32x4_sub(pos),
+            0xb2 => visitor.visit_i8x16_lane_select(pos),
+            0xb3 => visitor.visit_i16x8_lane_select(pos),
+            0xb4 => visitor.visit_f32x4_relaxed_min(pos),
+            0xb5 => visitor.visit_i32x4_mul(pos),
+            0xb6 => visitor.visit_i32x4_min_s(pos),
+            0xb7 => visitor.visit_i32x4_min_u(pos),
+            0xb8 => visitor.visit_i32x4_max_s(pos),
+            0xb9 => visitor.visit_i32x4_max_u(pos),
+            0xba => visitor.visit_i32x4_dot_i16x8_s(pos),
+            0xbc => visitor.visit_i32x4_ext_mul_low_i16x8_s(pos),
+            0xbd => visitor.visit_i32x4_ext_mul_high_i16x8_s(pos),
+            0xbe => visitor.visit_i32x4_ext_mul_low_i16x8_u(pos),
+            0xbf => visitor.visit_i32x4_ext_mul_high_i16x8_u(pos),
+            0xc0 => visitor.visit_i64x2_abs(pos),
+            0xc1 => visitor.visit_i64x2_neg(pos),
+            0xc3 => visitor.visit_i64x2_all_true(pos),
+            0xc4 => visitor.visit_i64x2_bitmask(pos),
+            0xc5 => visitor.visit_i32x4_relaxed_trunc_sat_f64x2_szero(pos),
+            0xc6 => visitor.visit_i32x4_relaxed_trunc_sat_f64x2_uzero(pos),
+            0xc7 => visitor.visit_i64x2_extend_low_i32x4_s(pos),
+            0xc8 => visitor.visit_i64x2_extend_high_i32x4_s(pos),
+            0xc9 => visitor.visit_i64x2_extend_low_i32x4_u(pos),
+            0xca => visitor.visit_i64x2_extend_high_i32x4_u(pos),
+            0xcb => visitor.visit_i64x2_shl(pos),
+            0xcc => visitor.visit_i64x2_shr_s(pos),
+            0xcd => visitor.visit_i64x2_shr_u(pos),
+            0xce => visitor.visit_i64x2_add(pos),
+            0xcf => visitor.visit_f64x2_fma(pos),
+            0xd0 => visitor.visit_f64x2_fms(pos),
+            0xd1 => visitor.visit_i64x2_sub(pos),
+            0xd2 => visitor.visit_i32x4_lane_select(pos),
+            0xd3 => visitor.visit_i64x2_lane_select(pos),
+            0xd4 => visitor.visit_f64x2_relaxed_min(pos),
+            0xd5 => visitor.visit_i64x2_mul(pos),
+            0xd6 => visitor.visit_i64x2_eq(pos),
+            0xd7 => visitor.visit_i64x2_ne(pos),
+            0xd8 => visitor.visit_i64x2_lt_s(pos),
+            0xd9 => visitor.visit_i64x2_gt_s(pos),
+            0xda => visitor.visit_i64x2_le_s(pos),
+            0xdb => visitor.visit_i64x2_ge_s(pos),
+            0xdc => visitor.visit_i64x2_ext_mul_low_i32x4_s(pos),
+            0xdd => visitor.visit_i64x2_ext_mul_high_i32x4_s(pos),
+            0xde => visitor.visit_i64x2_ext_mul_low_i32x4_u(pos),
+            0xdf => visitor.visit_i64x2_ext_mul_high_i32x4_u(pos),
+            0xe0 => visitor.visit_f32x4_abs(pos),
+            0xe1 => visitor.visit_f32x4_neg(pos),
+            0xe2 => visitor.visit_f32x4_relaxed_max(pos),
+            0xe3 => visitor.visit_f32x4_sqrt(pos),
+            0xe4 => visitor.visit_f32x4_add(pos),
+            0xe5 => visitor.visit_f32x4_sub(pos),
+            0xe6 => visitor.visit_f32x4_mul(pos),
+            0xe7 => visitor.visit_f32x4_div(pos),
+            0xe8 => visitor.visit_f32x4_min(pos),
+            0xe9 => visitor.visit_f32x4_max(pos),
+            0xea => visitor.visit_f32x4_pmin(pos),
+            0xeb => visitor.visit_f32x4_pmax(pos),
+            0xec => visitor.visit_f64x2_abs(pos),
+            0xed => visitor.visit_f64x2_neg(pos),
+            0xee => visitor.visit_f64x2_relaxed_max(pos),
+            0xef => visitor.visit_f64x2_sqrt(pos),
+            0xf0 => visitor.visit_f64x2_add(pos),
+            0xf1 => visitor.visit_f64x2_sub(pos),
+            0xf2 => visitor.visit_f64x2_mul(pos),
+            0xf3 => visitor.visit_f64x2_div(pos),
+            0xf4 => visitor.visit_f64x2_min(pos),
+            0xf5 => visitor.visit_f64x2_max(pos),
+            0xf6 => visitor.visit_f64x2_pmin(pos),
+            0xf7 => visitor.visit_f64x2_pmax(pos),
+            0xf8 => visitor.visit_i32x4_trunc_sat_f32x4_s(pos),
+            0xf9 => visitor.visit_i32x4_trunc_sat_f32x4_u(pos),
+            0xfa => visitor.visit_f32x4_convert_i32x4_s(pos),
+            0xfb => visitor.visit_f32x4_convert_i32x4_u(pos),
+            0xfc => visitor.visit_i32x4_trunc_sat_f64x2_szero(pos),
+            0xfd => visitor.visit_i32x4_trunc_sat_f64x2_uzero(pos),
+            0xfe => visitor.visit_f64x2_convert_low_i32x4_s(pos),
+            0xff => visitor.visit_f64x2_convert_low_i32x4_u(pos),
 
             _ => {
                 return Err(BinaryReaderError::new(
@@ -2044,3 +1718,33 @@ impl fmt::Debug for BrTable<'_> {
         f.finish()
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BrTable<'_> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{Error, SerializeSeq, SerializeStruct};
+
+        struct Targets<'a, 'b>(&'b BrTable<'a>);
+
+        impl serde::Serialize for Targets<'_, '_> {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut seq = serializer.serialize_seq(Some(self.0.len() as usize))?;
+                for target in self.0.targets() {
+                    seq.serialize_element(&target.map_err(S::Error::custom)?)?;
+                }
+                seq.end()
+            }
+        }
+
+        let mut s = serializer.serialize_struct("BrTable", 2)?;
+        s.serialize_field("targets", &Targets(self))?;
+        s.serialize_field("default", &self.default)?;
+        s.end()
+    }
+}
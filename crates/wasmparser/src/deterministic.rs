@@ -0,0 +1,561 @@
+/* Copyright 2022 Mozilla Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::prelude::*;
+use crate::{FunctionBody, Operator, Parser, Payload, Result};
+use core::fmt;
+
+/// The bit pattern of the canonical `f32` NaN.
+const CANONICAL_F32_NAN: u32 = 0x7fc0_0000;
+/// The bit pattern of the canonical `f64` NaN.
+const CANONICAL_F64_NAN: u64 = 0x7ff8_0000_0000_0000;
+
+/// A profile of WebAssembly whose execution is the same on every engine and
+/// every hardware.
+///
+/// Unlike [`WasmFeatures::deterministic_only`](crate::WasmFeatures), which
+/// gates float operators entirely, this profile allows floats and only
+/// rejects the operators whose results aren't fully specified: the relaxed
+/// SIMD operators and the atomic operators of the threads proposal.
+///
+/// The bits of a NaN produced by float arithmetic or by a conversion between
+/// `f32` and `f64` aren't specified either. With
+/// [`canonicalize_nans`](DeterministicProfile::canonicalize_nans) enabled,
+/// every such operator, as listed by [`FloatShape::of_nan_result`], must be
+/// immediately followed by a sequence which replaces any NaN result with the
+/// canonical NaN, in the same form as generated by `wasm-smith`:
+///
+/// ```text
+/// local.tee $scratch
+/// f32.const nan          ;; or f64.const, or v128.const of canonical NaNs
+/// local.get $scratch
+/// local.get $scratch
+/// f32.eq                 ;; or f64.eq, f32x4.eq, f64x2.eq
+/// select                 ;; or v128.bitselect
+/// ```
+///
+/// The profile can be checked on its own with
+/// [`check_module`](DeterministicProfile::check_module), in which case the
+/// module should also be passed to a [`Validator`](crate::Validator), or as
+/// part of validation with
+/// [`Validator::deterministic_profile`](crate::Validator::deterministic_profile).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DeterministicProfile {
+    /// Whether float operators which may produce NaNs must canonicalize them.
+    pub canonicalize_nans: bool,
+}
+
+/// An operator which isn't allowed by a [`DeterministicProfile`].
+#[derive(Debug, Clone)]
+pub struct DeterminismViolation<'a> {
+    /// The offset of the operator in the module.
+    pub offset: usize,
+    /// The operator itself.
+    pub operator: Operator<'a>,
+    /// Why the operator isn't allowed.
+    pub reason: Nondeterminism,
+}
+
+/// Why an operator isn't allowed by a [`DeterministicProfile`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Nondeterminism {
+    /// A relaxed SIMD operator, whose results depend on the hardware.
+    RelaxedSimd,
+    /// An atomic operator, which is only useful with shared memories whose
+    /// contents depend on the scheduling of threads.
+    Threads,
+    /// A float operator whose NaN results of the given shape aren't
+    /// canonicalized.
+    NonCanonicalNan(FloatShape),
+}
+
+/// How a float operator interprets its operands.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FloatShape {
+    /// A single `f32`.
+    F32,
+    /// A single `f64`.
+    F64,
+    /// A `v128` of four `f32` lanes.
+    F32x4,
+    /// A `v128` of two `f64` lanes.
+    F64x2,
+}
+
+impl DeterministicProfile {
+    /// Creates a profile which allows NaNs to be left uncanonicalized.
+    pub fn new() -> DeterministicProfile {
+        DeterministicProfile::default()
+    }
+
+    /// Checks every function body in the module `wasm`, returning all of the
+    /// operators which aren't allowed by this profile in order.
+    ///
+    /// Returns an error if `wasm` can't be parsed.
+    pub fn check_module<'a>(&self, wasm: &'a [u8]) -> Result<Vec<DeterminismViolation<'a>>> {
+        let mut violations = Vec::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            if let Payload::CodeSectionEntry(body) = payload? {
+                violations.extend(self.check_function_body(&body)?);
+            }
+        }
+        Ok(violations)
+    }
+
+    /// Checks the operators of `body`, returning all of those which aren't
+    /// allowed by this profile in order.
+    ///
+    /// Returns an error if `body` can't be parsed.
+    pub fn check_function_body<'a>(
+        &self,
+        body: &FunctionBody<'a>,
+    ) -> Result<Vec<DeterminismViolation<'a>>> {
+        let mut violations = Vec::new();
+        // The NaN-producing operator whose canonicalization is being matched,
+        // along with how many operators of it have been seen and the scratch
+        // local it uses.
+        let mut pending: Option<(usize, Operator<'a>, FloatShape, usize, u32)> = None;
+        let mut reader = body.get_operators_reader()?;
+        reader.allow_memarg64(true);
+        while !reader.eof() {
+            let (op, offset) = reader.read_with_offset()?;
+
+            if let Some((nan_offset, nan_op, shape, step, local)) = pending.take() {
+                match canonicalization_step(shape, step, local, &op) {
+                    Some(local) if step + 1 < CANONICALIZATION_LEN => {
+                        pending = Some((nan_offset, nan_op, shape, step + 1, local));
+                    }
+                    Some(_) => {}
+                    None => violations.push(DeterminismViolation {
+                        offset: nan_offset,
+                        operator: nan_op,
+                        reason: Nondeterminism::NonCanonicalNan(shape),
+                    }),
+                }
+            }
+
+            if let Some(reason) = nondeterminism(&op) {
+                violations.push(DeterminismViolation {
+                    offset,
+                    operator: op,
+                    reason,
+                });
+            } else if self.canonicalize_nans {
+                if let Some(shape) = FloatShape::of_nan_result(&op) {
+                    pending = Some((offset, op, shape, 0, 0));
+                }
+            }
+        }
+        if let Some((offset, operator, shape, _, _)) = pending {
+            violations.push(DeterminismViolation {
+                offset,
+                operator,
+                reason: Nondeterminism::NonCanonicalNan(shape),
+            });
+        }
+        Ok(violations)
+    }
+}
+
+/// The number of operators in a NaN canonicalization sequence.
+const CANONICALIZATION_LEN: usize = 6;
+
+/// Returns whether `op` is the `step`th operator of the canonicalization of
+/// a NaN of `shape` through `local`, along with the scratch local it uses.
+fn canonicalization_step(shape: FloatShape, step: usize, local: u32, op: &Operator) -> Option<u32> {
+    let matches = match (step, op) {
+        (0, Operator::LocalTee { local_index }) => return Some(*local_index),
+        (1, Operator::F32Const { value }) => {
+            shape == FloatShape::F32 && value.bits() == CANONICAL_F32_NAN
+        }
+        (1, Operator::F64Const { value }) => {
+            shape == FloatShape::F64 && value.bits() == CANONICAL_F64_NAN
+        }
+        (1, Operator::V128Const { value }) => match shape {
+            FloatShape::F32x4 | FloatShape::F64x2 => value.i128() == shape.canonical_nan(),
+            FloatShape::F32 | FloatShape::F64 => false,
+        },
+        (2, Operator::LocalGet { local_index }) | (3, Operator::LocalGet { local_index }) => {
+            *local_index == local
+        }
+        (4, Operator::F32Eq) => shape == FloatShape::F32,
+        (4, Operator::F64Eq) => shape == FloatShape::F64,
+        (4, Operator::F32x4Eq) => shape == FloatShape::F32x4,
+        (4, Operator::F64x2Eq) => shape == FloatShape::F64x2,
+        (5, Operator::Select) | (5, Operator::TypedSelect { .. }) => {
+            shape == FloatShape::F32 || shape == FloatShape::F64
+        }
+        (5, Operator::V128Bitselect) => shape == FloatShape::F32x4 || shape == FloatShape::F64x2,
+        _ => false,
+    };
+    if matches {
+        Some(local)
+    } else {
+        None
+    }
+}
+
+/// Returns why `op` is never allowed by a [`DeterministicProfile`], if it
+/// isn't.
+fn nondeterminism(op: &Operator) -> Option<Nondeterminism> {
+    use Operator::*;
+    match op {
+        I8x16RelaxedSwizzle
+        | I32x4RelaxedTruncSatF32x4S
+        | I32x4RelaxedTruncSatF32x4U
+        | I32x4RelaxedTruncSatF64x2SZero
+        | I32x4RelaxedTruncSatF64x2UZero
+        | F32x4Fma
+        | F32x4Fms
+        | F64x2Fma
+        | F64x2Fms
+        | I8x16LaneSelect
+        | I16x8LaneSelect
+        | I32x4LaneSelect
+        | I64x2LaneSelect
+        | F32x4RelaxedMin
+        | F32x4RelaxedMax
+        | F64x2RelaxedMin
+        | F64x2RelaxedMax => Some(Nondeterminism::RelaxedSimd),
+
+        MemoryAtomicNotify { .. }
+        | MemoryAtomicWait32 { .. }
+        | MemoryAtomicWait64 { .. }
+        | AtomicFence { .. }
+        | I32AtomicLoad { .. }
+        | I64AtomicLoad { .. }
+        | I32AtomicLoad8U { .. }
+        | I32AtomicLoad16U { .. }
+        | I64AtomicLoad8U { .. }
+        | I64AtomicLoad16U { .. }
+        | I64AtomicLoad32U { .. }
+        | I32AtomicStore { .. }
+        | I64AtomicStore { .. }
+        | I32AtomicStore8 { .. }
+        | I32AtomicStore16 { .. }
+        | I64AtomicStore8 { .. }
+        | I64AtomicStore16 { .. }
+        | I64AtomicStore32 { .. }
+        | I32AtomicRmwAdd { .. }
+        | I64AtomicRmwAdd { .. }
+        | I32AtomicRmw8AddU { .. }
+        | I32AtomicRmw16AddU { .. }
+        | I64AtomicRmw8AddU { .. }
+        | I64AtomicRmw16AddU { .. }
+        | I64AtomicRmw32AddU { .. }
+        | I32AtomicRmwSub { .. }
+        | I64AtomicRmwSub { .. }
+        | I32AtomicRmw8SubU { .. }
+        | I32AtomicRmw16SubU { .. }
+        | I64AtomicRmw8SubU { .. }
+        | I64AtomicRmw16SubU { .. }
+        | I64AtomicRmw32SubU { .. }
+        | I32AtomicRmwAnd { .. }
+        | I64AtomicRmwAnd { .. }
+        | I32AtomicRmw8AndU { .. }
+        | I32AtomicRmw16AndU { .. }
+        | I64AtomicRmw8AndU { .. }
+        | I64AtomicRmw16AndU { .. }
+        | I64AtomicRmw32AndU { .. }
+        | I32AtomicRmwOr { .. }
+        | I64AtomicRmwOr { .. }
+        | I32AtomicRmw8OrU { .. }
+        | I32AtomicRmw16OrU { .. }
+        | I64AtomicRmw8OrU { .. }
+        | I64AtomicRmw16OrU { .. }
+        | I64AtomicRmw32OrU { .. }
+        | I32AtomicRmwXor { .. }
+        | I64AtomicRmwXor { .. }
+        | I32AtomicRmw8XorU { .. }
+        | I32AtomicRmw16XorU { .. }
+        | I64AtomicRmw8XorU { .. }
+        | I64AtomicRmw16XorU { .. }
+        | I64AtomicRmw32XorU { .. }
+        | I32AtomicRmwXchg { .. }
+        | I64AtomicRmwXchg { .. }
+        | I32AtomicRmw8XchgU { .. }
+        | I32AtomicRmw16XchgU { .. }
+        | I64AtomicRmw8XchgU { .. }
+        | I64AtomicRmw16XchgU { .. }
+        | I64AtomicRmw32XchgU { .. }
+        | I32AtomicRmwCmpxchg { .. }
+        | I64AtomicRmwCmpxchg { .. }
+        | I32AtomicRmw8CmpxchgU { .. }
+        | I32AtomicRmw16CmpxchgU { .. }
+        | I64AtomicRmw8CmpxchgU { .. }
+        | I64AtomicRmw16CmpxchgU { .. }
+        | I64AtomicRmw32CmpxchgU { .. } => Some(Nondeterminism::Threads),
+
+        _ => None,
+    }
+}
+
+impl FloatShape {
+    /// Returns the shape of the result of `op` if it's an operator which may
+    /// produce a NaN with unspecified bits.
+    ///
+    /// These are the arithmetic operators which `wasm-smith` canonicalizes,
+    /// along with the conversions between `f32` and `f64`, which don't
+    /// preserve NaN payloads either.
+    pub fn of_nan_result(op: &Operator) -> Option<FloatShape> {
+        use Operator::*;
+        match op {
+            F32Ceil | F32Floor | F32Nearest | F32Sqrt | F32Trunc | F32Div | F32Max | F32Min
+            | F32Mul | F32Sub | F32Add | F32DemoteF64 => Some(FloatShape::F32),
+            F64Ceil | F64Floor | F64Nearest | F64Sqrt | F64Trunc | F64Div | F64Max | F64Min
+            | F64Mul | F64Sub | F64Add | F64PromoteF32 => Some(FloatShape::F64),
+            F32x4Ceil | F32x4Floor | F32x4Nearest | F32x4Sqrt | F32x4Trunc | F32x4Div
+            | F32x4Max | F32x4Min | F32x4Mul | F32x4Sub | F32x4Add | F32x4DemoteF64x2Zero => {
+                Some(FloatShape::F32x4)
+            }
+            F64x2Ceil | F64x2Floor | F64x2Nearest | F64x2Sqrt | F64x2Trunc | F64x2Div
+            | F64x2Max | F64x2Min | F64x2Mul | F64x2Sub | F64x2Add | F64x2PromoteLowF32x4 => {
+                Some(FloatShape::F64x2)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the bits of the canonical NaN of this shape, with the bits of
+    /// every lane for vector shapes.
+    pub fn canonical_nan(self) -> i128 {
+        match self {
+            FloatShape::F32 => i128::from(CANONICAL_F32_NAN),
+            FloatShape::F64 => i128::from(CANONICAL_F64_NAN),
+            FloatShape::F32x4 => {
+                let nan = i128::from(CANONICAL_F32_NAN);
+                nan | (nan << 32) | (nan << 64) | (nan << 96)
+            }
+            FloatShape::F64x2 => {
+                let nan = i128::from(CANONICAL_F64_NAN);
+                nan | (nan << 64)
+            }
+        }
+    }
+}
+
+impl fmt::Display for FloatShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FloatShape::F32 => "f32",
+            FloatShape::F64 => "f64",
+            FloatShape::F32x4 => "f32x4",
+            FloatShape::F64x2 => "f64x2",
+        })
+    }
+}
+
+impl fmt::Display for Nondeterminism {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Nondeterminism::RelaxedSimd => {
+                f.write_str("relaxed SIMD operators are nondeterministic")
+            }
+            Nondeterminism::Threads => f.write_str("atomic operators are nondeterministic"),
+            Nondeterminism::NonCanonicalNan(shape) => {
+                write!(f, "{} NaN result is not canonicalized", shape)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DeterminismViolation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:?} (at offset {})",
+            self.reason, self.operator, self.offset
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(profile: DeterministicProfile, wat: &str) -> Vec<(usize, String)> {
+        let wasm = wat::parse_str(wat).unwrap();
+        crate::Validator::new()
+            .wasm_features(crate::WasmFeatures {
+                relaxed_simd: true,
+                threads: true,
+                deterministic_only: true,
+                ..Default::default()
+            })
+            .validate_all(&wasm)
+            .unwrap();
+        profile
+            .check_module(&wasm)
+            .unwrap()
+            .iter()
+            .map(|v| (v.offset, v.reason.to_string()))
+            .collect()
+    }
+
+    fn canonicalizing() -> DeterministicProfile {
+        DeterministicProfile {
+            canonicalize_nans: true,
+        }
+    }
+
+    #[test]
+    fn floats_are_allowed() {
+        let wat = r#"
+            (module
+                (func (param f32 f64) (result f64)
+                    local.get 0
+                    f32.sqrt
+                    f64.promote_f32
+                    local.get 1
+                    f64.add))
+        "#;
+        assert_eq!(check(DeterministicProfile::new(), wat), []);
+    }
+
+    #[test]
+    fn relaxed_simd_and_atomics_are_rejected() {
+        let wat = r#"
+            (module
+                (memory 1 1 shared)
+                (func (param v128) (result v128)
+                    i32.const 0
+                    i32.atomic.load
+                    drop
+                    local.get 0
+                    local.get 0
+                    f32x4.relaxed_min))
+        "#;
+        let violations = check(DeterministicProfile::new(), wat);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].1, "atomic operators are nondeterministic");
+        assert_eq!(
+            violations[1].1,
+            "relaxed SIMD operators are nondeterministic"
+        );
+        assert!(violations[0].0 < violations[1].0);
+
+        let wasm = wat::parse_str(wat).unwrap();
+        let violations = DeterministicProfile::new().check_module(&wasm).unwrap();
+        assert!(matches!(violations[1].operator, Operator::F32x4RelaxedMin));
+        assert_eq!(
+            violations[1].to_string(),
+            format!(
+                "relaxed SIMD operators are nondeterministic: F32x4RelaxedMin (at offset {})",
+                violations[1].offset
+            )
+        );
+    }
+
+    #[test]
+    fn canonicalized_nans() {
+        let wat = r#"
+            (module
+                (func (param f32 v128) (result f32 v128) (local f32 v128)
+                    local.get 0
+                    local.get 0
+                    f32.div
+                    local.tee 2
+                    f32.const nan
+                    local.get 2
+                    local.get 2
+                    f32.eq
+                    select
+
+                    local.get 1
+                    f64x2.sqrt
+                    local.tee 3
+                    v128.const i64x2 0x7ff8000000000000 0x7ff8000000000000
+                    local.get 3
+                    local.get 3
+                    f64x2.eq
+                    v128.bitselect))
+        "#;
+        assert_eq!(check(canonicalizing(), wat), []);
+    }
+
+    #[test]
+    fn uncanonicalized_nans() {
+        let wat = r#"
+            (module
+                (func (param f32 f64) (result f32 f64) (local f32)
+                    ;; not canonicalized at all
+                    local.get 0
+                    f32.ceil
+                    ;; canonicalized through the wrong local
+                    f32.floor
+                    local.tee 2
+                    f32.const nan
+                    local.get 0
+                    local.get 2
+                    f32.eq
+                    select
+                    ;; canonicalized with a non-canonical NaN
+                    local.get 1
+                    f64.sqrt
+                    local.tee 1
+                    f64.const nan:0x1
+                    local.get 1
+                    local.get 1
+                    f64.eq
+                    select
+                    ;; conversions don't preserve NaN payloads
+                    local.get 0
+                    f64.promote_f32
+                    f32.demote_f64
+                    drop
+                    ;; left on the stack at the end of the function
+                    local.get 1
+                    f64.add))
+        "#;
+        let violations = check(canonicalizing(), wat);
+        let reasons = violations.iter().map(|v| &v.1[..]).collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            [
+                "f32 NaN result is not canonicalized",
+                "f32 NaN result is not canonicalized",
+                "f64 NaN result is not canonicalized",
+                "f64 NaN result is not canonicalized",
+                "f32 NaN result is not canonicalized",
+                "f64 NaN result is not canonicalized",
+            ]
+        );
+        assert!(violations.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(check(DeterministicProfile::new(), wat), []);
+    }
+
+    #[test]
+    fn validator_checks_the_profile() {
+        let wat = r#"
+            (module
+                (func (param f32) (result f32)
+                    local.get 0
+                    f32.sqrt))
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let validate = |profile| {
+            crate::Validator::new()
+                .deterministic_profile(profile)
+                .validate_all(&wasm)
+        };
+        validate(None).unwrap();
+        validate(Some(DeterministicProfile::new())).unwrap();
+
+        let err = validate(Some(canonicalizing())).unwrap_err();
+        assert_eq!(err.message(), "f32 NaN result is not canonicalized");
+        let violations = canonicalizing().check_module(&wasm).unwrap();
+        assert_eq!(err.offset(), violations[0].offset);
+    }
+}
@@ -22,21 +22,34 @@
 //! If you need random access to the entire WebAssembly data-structure,
 //! this is not the right library for you. You could however, build such
 //! a data-structure using this library.
+//!
+//! This crate supports `no_std` environments which provide `alloc` when its
+//! default `std` feature is disabled.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub use crate::binary_reader::BinaryReader;
 pub use crate::binary_reader::Range;
+pub use crate::deterministic::*;
 
 pub use crate::module_resources::*;
+pub use crate::operator_visitor::VisitOperator;
+pub use crate::operators_validator::{Frame, FrameKind};
 pub use crate::parser::*;
 pub use crate::primitives::*;
 pub use crate::readers::*;
 pub use crate::validator::*;
 
 mod binary_reader;
+mod deterministic;
 mod limits;
 mod module_resources;
+mod operator_visitor;
 mod operators_validator;
 mod parser;
+mod prelude;
 mod primitives;
 mod readers;
 mod validator;
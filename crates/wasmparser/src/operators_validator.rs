@@ -22,9 +22,12 @@
 // confusing it's recomended to read over that section to see how it maps to
 // the various methods here.
 
-use crate::limits::MAX_WASM_FUNCTION_LOCALS;
+use crate::prelude::*;
 use crate::primitives::{MemoryImmediate, Operator, SIMDLaneIndex, Type, TypeOrFuncType};
-use crate::{BinaryReaderError, Result, WasmFeatures, WasmFuncType, WasmModuleResources};
+use crate::{
+    BinaryReaderError, Result, ValidatorLimits, VisitOperator, WasmFeatures, WasmFuncType,
+    WasmModuleResources,
+};
 
 /// A wrapper around a `BinaryReaderError` where the inner error's offset is a
 /// temporary placeholder value. This can be converted into a proper
@@ -49,7 +52,7 @@ macro_rules! bail_op_err {
 impl OperatorValidatorError {
     /// Create a new `OperatorValidatorError` with a placeholder offset.
     pub(crate) fn new(message: impl Into<String>) -> Self {
-        let offset = std::usize::MAX;
+        let offset = usize::MAX;
         let e = BinaryReaderError::new(message, offset);
         OperatorValidatorError(e)
     }
@@ -57,13 +60,13 @@ impl OperatorValidatorError {
     /// Convert this `OperatorValidatorError` into a `BinaryReaderError` by
     /// supplying an actual offset to replace the internal placeholder offset.
     pub(crate) fn set_offset(mut self, offset: usize) -> BinaryReaderError {
-        debug_assert_eq!(self.0.inner.offset, std::usize::MAX);
+        debug_assert_eq!(self.0.inner.offset, usize::MAX);
         self.0.inner.offset = offset;
         self.0
     }
 }
 
-type OperatorValidatorResult<T> = std::result::Result<T, OperatorValidatorError>;
+type OperatorValidatorResult<T> = core::result::Result<T, OperatorValidatorError>;
 
 pub(crate) struct OperatorValidator {
     // The total number of locals that this function contains
@@ -83,38 +86,60 @@ pub(crate) struct OperatorValidator {
     // The `operands` is the current type stack, and the `control` list is the
     // list of blocks that we're currently in.
     pub(crate) operands: Vec<Option<Type>>,
-    control: Vec<Frame>,
+    pub(crate) control: Vec<Frame>,
 
     // This is a list of flags for wasm features which are used to gate various
     // instructions.
     pub(crate) features: WasmFeatures,
 
+    // The implementation limits on the function, such as how many locals it
+    // may have.
+    pub(crate) limits: ValidatorLimits,
+
     // Temporary storage used during the validation of `br_table`.
     br_table_tmp: Vec<Option<Type>>,
+
+    // The type that `pop_operand` expected when it last failed, reset by
+    // `FuncValidator::op` for each operator.
+    pub(crate) mismatch: Option<Option<Type>>,
 }
 
-// This structure corresponds to `ctrl_frame` as specified at in the validation
-// appendix of the wasm spec
-struct Frame {
-    // Indicator for what kind of instruction pushed this frame.
-    kind: FrameKind,
-    // The type signature of this frame, represented as a singular return type
-    // or a type index pointing into the module's types.
-    block_type: TypeOrFuncType,
-    // The index, below which, this frame cannot modify the operand stack.
-    height: usize,
-    // Whether this frame is unreachable so far.
-    unreachable: bool,
+/// A control frame of a function being validated, as returned by
+/// [`FuncValidator::get_control_frame`](crate::FuncValidator::get_control_frame).
+///
+/// This corresponds to `ctrl_frame` as specified in the validation appendix
+/// of the wasm spec.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Indicator for what kind of instruction pushed this frame.
+    pub kind: FrameKind,
+    /// The type signature of this frame, represented as a singular return
+    /// type or a type index pointing into the module's types.
+    pub block_type: TypeOrFuncType,
+    /// The height of the operand stack when this frame was pushed, below
+    /// which this frame cannot modify the operand stack.
+    pub height: usize,
+    /// Whether the rest of this frame is unreachable so far, such as after a
+    /// `br` or `unreachable`.
+    pub unreachable: bool,
 }
 
-#[derive(PartialEq, Copy, Clone)]
-enum FrameKind {
+/// The kind of instruction which pushed a [`Frame`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum FrameKind {
+    /// A `block`, or the body of the function itself.
     Block,
+    /// An `if` whose `else` hasn't been reached.
     If,
+    /// The `else` of an `if`.
     Else,
+    /// A `loop`.
     Loop,
+    /// A `try` whose first `catch` hasn't been reached.
     Try,
+    /// A `catch` of a `try`.
     Catch,
+    /// The `catch_all` of a `try`.
     CatchAll,
 }
 
@@ -128,6 +153,7 @@ impl OperatorValidator {
         ty: u32,
         offset: usize,
         features: &WasmFeatures,
+        limits: &ValidatorLimits,
         resources: &impl WasmModuleResources,
     ) -> Result<OperatorValidator> {
         let locals = func_type_at(resources, ty)
@@ -147,7 +173,9 @@ impl OperatorValidator {
                 unreachable: false,
             }],
             features: *features,
+            limits: *limits,
             br_table_tmp: Vec::new(),
+            mismatch: None,
         })
     }
 
@@ -166,7 +194,9 @@ impl OperatorValidator {
                 unreachable: false,
             }],
             features: *features,
+            limits: ValidatorLimits::default(),
             br_table_tmp: Vec::new(),
+            mismatch: None,
         }
     }
 
@@ -181,16 +211,24 @@ impl OperatorValidator {
             Some(n) => self.num_locals = n,
             None => return Err(BinaryReaderError::new("locals overflow", offset)),
         }
-        if self.num_locals > (MAX_WASM_FUNCTION_LOCALS as u32) {
-            return Err(BinaryReaderError::new("locals exceed maximum", offset));
+        if self.num_locals as usize > self.limits.max_function_locals {
+            return Err(BinaryReaderError::limit_exceeded(
+                "locals exceed maximum",
+                offset,
+            ));
         }
         self.locals.push((self.num_locals - 1, ty));
         Ok(())
     }
 
+    /// Returns the total number of locals, including parameters.
+    pub(crate) fn num_locals(&self) -> u32 {
+        self.num_locals
+    }
+
     /// Fetches the type for the local at `idx`, returning an error if it's out
     /// of bounds.
-    fn local(&self, idx: u32) -> OperatorValidatorResult<Type> {
+    pub(crate) fn local(&self, idx: u32) -> OperatorValidatorResult<Type> {
         match self.locals.binary_search_by_key(&idx, |(idx, _)| *idx) {
             // If this index would be inserted at the end of the list, then the
             // index is out of bounds and we return an error.
@@ -211,7 +249,7 @@ impl OperatorValidator {
     /// This is used by instructions to represent a value that is pushed to the
     /// operand stack. This can fail, but only if `Type` is feature gated.
     /// Otherwise the push operation always succeeds.
-    fn push_operand(&mut self, ty: Type) -> OperatorValidatorResult<()> {
+    pub(crate) fn push_operand(&mut self, ty: Type) -> OperatorValidatorResult<()> {
         self.features
             .check_value_type(ty)
             .map_err(OperatorValidatorError::new)?;
@@ -247,6 +285,7 @@ impl OperatorValidator {
                     Some(ty) => ty_to_str(ty),
                     None => "a type",
                 };
+                self.mismatch = Some(expected);
                 bail_op_err!("type mismatch: expected {} but nothing on stack", desc)
             }
         } else {
@@ -254,6 +293,10 @@ impl OperatorValidator {
         };
         if let (Some(actual_ty), Some(expected_ty)) = (actual, expected) {
             if actual_ty != expected_ty {
+                // Leave the mismatched operand on the stack so that the stack
+                // reflects the state just before the failing pop.
+                self.operands.push(actual);
+                self.mismatch = Some(expected);
                 bail_op_err!(
                     "type mismatch: expected {}, found {}",
                     ty_to_str(expected_ty),
@@ -508,7 +551,7 @@ impl OperatorValidator {
 
     /// Validates a `call` instruction, ensuring that the function index is
     /// in-bounds and the right types are on the stack to call the function.
-    fn check_call(
+    pub(crate) fn check_call(
         &mut self,
         function_index: u32,
         resources: &impl WasmModuleResources,
@@ -576,14 +619,93 @@ impl OperatorValidator {
         Ok(())
     }
 
+    /// Checks that the function's final `end` hasn't been seen yet, which
+    /// must happen before validating any operator.
+    pub(crate) fn check_in_body(&self) -> OperatorValidatorResult<()> {
+        if self.control.is_empty() {
+            bail_op_err!("operators remaining after end of function");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_end(
+        &mut self,
+        resources: &impl WasmModuleResources,
+    ) -> OperatorValidatorResult<()> {
+        let mut frame = self.pop_ctrl(resources)?;
+
+        // Note that this `if` isn't included in the appendix right now, but
+        // it's used to allow for `if` statements that are missing an `else`
+        // block which have the same parameter/return types on the block (since
+        // that's valid).
+        if frame.kind == FrameKind::If {
+            self.push_ctrl(FrameKind::Else, frame.block_type, resources)?;
+            frame = self.pop_ctrl(resources)?;
+        }
+        for ty in results(frame.block_type, resources)? {
+            self.push_operand(ty)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_br_if(
+        &mut self,
+        relative_depth: u32,
+        resources: &impl WasmModuleResources,
+    ) -> OperatorValidatorResult<()> {
+        self.pop_operand(Some(Type::I32))?;
+        let (ty, kind) = self.jump(relative_depth)?;
+        for ty in label_types(ty, resources, kind)?.rev() {
+            self.pop_operand(Some(ty))?;
+        }
+        for ty in label_types(ty, resources, kind)? {
+            self.push_operand(ty)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_local_get(&mut self, local_index: u32) -> OperatorValidatorResult<()> {
+        let ty = self.local(local_index)?;
+        self.push_operand(ty)
+    }
+
+    pub(crate) fn check_local_set(&mut self, local_index: u32) -> OperatorValidatorResult<()> {
+        let ty = self.local(local_index)?;
+        self.pop_operand(Some(ty))?;
+        Ok(())
+    }
+
+    pub(crate) fn check_local_tee(&mut self, local_index: u32) -> OperatorValidatorResult<()> {
+        let ty = self.local(local_index)?;
+        self.pop_operand(Some(ty))?;
+        self.push_operand(ty)
+    }
+
+    pub(crate) fn check_global_get(
+        &mut self,
+        global_index: u32,
+        resources: &impl WasmModuleResources,
+    ) -> OperatorValidatorResult<()> {
+        match resources.global_at(global_index) {
+            Some(ty) => self.push_operand(ty.content_type),
+            None => bail_op_err!("unknown global: global index out of bounds"),
+        }
+    }
+
+    /// Checks a binary operator whose operands and result are all of type
+    /// `ty`, such as `i32.add`.
+    pub(crate) fn check_binary_op(&mut self, ty: Type) -> OperatorValidatorResult<()> {
+        self.pop_operand(Some(ty))?;
+        self.pop_operand(Some(ty))?;
+        self.push_operand(ty)
+    }
+
     pub fn process_operator(
         &mut self,
         operator: &Operator,
         resources: &impl WasmModuleResources,
     ) -> OperatorValidatorResult<()> {
-        if self.control.is_empty() {
-            bail_op_err!("operators remaining after end of function");
-        }
+        self.check_in_body()?;
         match *operator {
             Operator::Nop => {}
 
@@ -697,21 +819,7 @@ impl OperatorValidator {
                     unreachable: false,
                 });
             }
-            Operator::End => {
-                let mut frame = self.pop_ctrl(resources)?;
-
-                // Note that this `if` isn't included in the appendix right
-                // now, but it's used to allow for `if` statements that are
-                // missing an `else` block which have the same parameter/return
-                // types on the block (since that's valid).
-                if frame.kind == FrameKind::If {
-                    self.push_ctrl(FrameKind::Else, frame.block_type, resources)?;
-                    frame = self.pop_ctrl(resources)?;
-                }
-                for ty in results(frame.block_type, resources)? {
-                    self.push_operand(ty)?;
-                }
-            }
+            Operator::End => self.check_end(resources)?,
             Operator::Br { relative_depth } => {
                 let (ty, kind) = self.jump(relative_depth)?;
                 for ty in label_types(ty, resources, kind)?.rev() {
@@ -719,17 +827,14 @@ impl OperatorValidator {
                 }
                 self.unreachable();
             }
-            Operator::BrIf { relative_depth } => {
-                self.pop_operand(Some(Type::I32))?;
-                let (ty, kind) = self.jump(relative_depth)?;
-                for ty in label_types(ty, resources, kind)?.rev() {
-                    self.pop_operand(Some(ty))?;
-                }
-                for ty in label_types(ty, resources, kind)? {
-                    self.push_operand(ty)?;
-                }
-            }
+            Operator::BrIf { relative_depth } => self.check_br_if(relative_depth, resources)?,
             Operator::BrTable { ref table } => {
+                if table.len() as usize > self.limits.max_br_table_size {
+                    return Err(OperatorValidatorError(BinaryReaderError::limit_exceeded(
+                        "br_table size is out of bound",
+                        usize::MAX,
+                    )));
+                }
                 self.pop_operand(Some(Type::I32))?;
                 let default = self.jump(table.default())?;
                 let default_types = label_types(default.0, resources, default.1)?;
@@ -812,27 +917,11 @@ impl OperatorValidator {
                 self.pop_operand(Some(ty))?;
                 self.push_operand(ty)?;
             }
-            Operator::LocalGet { local_index } => {
-                let ty = self.local(local_index)?;
-                self.push_operand(ty)?;
-            }
-            Operator::LocalSet { local_index } => {
-                let ty = self.local(local_index)?;
-                self.pop_operand(Some(ty))?;
-            }
-            Operator::LocalTee { local_index } => {
-                let ty = self.local(local_index)?;
-                self.pop_operand(Some(ty))?;
-                self.push_operand(ty)?;
-            }
+            Operator::LocalGet { local_index } => self.check_local_get(local_index)?,
+            Operator::LocalSet { local_index } => self.check_local_set(local_index)?,
+            Operator::LocalTee { local_index } => self.check_local_tee(local_index)?,
             Operator::GlobalGet { global_index } => {
-                if let Some(ty) = resources.global_at(global_index) {
-                    self.push_operand(ty.content_type)?;
-                } else {
-                    return Err(OperatorValidatorError::new(
-                        "unknown global: global index out of bounds",
-                    ));
-                };
+                self.check_global_get(global_index, resources)?
             }
             Operator::GlobalSet { global_index } => {
                 if let Some(ty) = resources.global_at(global_index) {
@@ -1043,11 +1132,7 @@ impl OperatorValidator {
             | Operator::I32ShrS
             | Operator::I32ShrU
             | Operator::I32Rotl
-            | Operator::I32Rotr => {
-                self.pop_operand(Some(Type::I32))?;
-                self.pop_operand(Some(Type::I32))?;
-                self.push_operand(Type::I32)?;
-            }
+            | Operator::I32Rotr => self.check_binary_op(Type::I32)?,
             Operator::I64Clz | Operator::I64Ctz | Operator::I64Popcnt => {
                 self.pop_operand(Some(Type::I64))?;
                 self.push_operand(Type::I64)?;
@@ -1066,11 +1151,7 @@ impl OperatorValidator {
             | Operator::I64ShrS
             | Operator::I64ShrU
             | Operator::I64Rotl
-            | Operator::I64Rotr => {
-                self.pop_operand(Some(Type::I64))?;
-                self.pop_operand(Some(Type::I64))?;
-                self.push_operand(Type::I64)?;
-            }
+            | Operator::I64Rotr => self.check_binary_op(Type::I64)?,
             Operator::F32Abs
             | Operator::F32Neg
             | Operator::F32Ceil
@@ -2042,6 +2123,89 @@ impl OperatorValidator {
     }
 }
 
+/// A [`VisitOperator`] which validates each operator as it's decoded.
+///
+/// The most common operators are validated directly from their immediates,
+/// and all others are built into an [`Operator`] for `process_operator`.
+pub(crate) struct ValidatingVisitor<'v, R> {
+    pub(crate) validator: &'v mut OperatorValidator,
+    pub(crate) resources: &'v R,
+}
+
+impl<'a, R: WasmModuleResources> VisitOperator<'a> for ValidatingVisitor<'_, R> {
+    type Output = OperatorValidatorResult<()>;
+
+    fn visit_operator(&mut self, _offset: usize, op: Operator<'a>) -> Self::Output {
+        self.validator.process_operator(&op, self.resources)
+    }
+
+    fn visit_end(&mut self, _offset: usize) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_end(self.resources)
+    }
+
+    fn visit_br_if(&mut self, _offset: usize, relative_depth: u32) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_br_if(relative_depth, self.resources)
+    }
+
+    fn visit_call(&mut self, _offset: usize, function_index: u32) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_call(function_index, self.resources)
+    }
+
+    fn visit_local_get(&mut self, _offset: usize, local_index: u32) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_local_get(local_index)
+    }
+
+    fn visit_local_set(&mut self, _offset: usize, local_index: u32) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_local_set(local_index)
+    }
+
+    fn visit_local_tee(&mut self, _offset: usize, local_index: u32) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_local_tee(local_index)
+    }
+
+    fn visit_global_get(&mut self, _offset: usize, global_index: u32) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator
+            .check_global_get(global_index, self.resources)
+    }
+
+    fn visit_i32_const(&mut self, _offset: usize, _value: i32) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.push_operand(Type::I32)
+    }
+
+    fn visit_i64_const(&mut self, _offset: usize, _value: i64) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.push_operand(Type::I64)
+    }
+
+    fn visit_i32_add(&mut self, _offset: usize) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_binary_op(Type::I32)
+    }
+
+    fn visit_i32_sub(&mut self, _offset: usize) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_binary_op(Type::I32)
+    }
+
+    fn visit_i32_and(&mut self, _offset: usize) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_binary_op(Type::I32)
+    }
+
+    fn visit_i64_add(&mut self, _offset: usize) -> Self::Output {
+        self.validator.check_in_body()?;
+        self.validator.check_binary_op(Type::I64)
+    }
+}
+
 fn func_type_at<T: WasmModuleResources>(
     resources: &T,
     at: u32,
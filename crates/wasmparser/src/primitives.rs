@@ -13,9 +13,9 @@
  * limitations under the License.
  */
 
-use std::error::Error;
-use std::fmt;
-use std::result;
+use crate::prelude::*;
+use core::fmt;
+use core::result;
 
 #[derive(Debug, Clone)]
 pub struct BinaryReaderError {
@@ -30,11 +30,13 @@ pub(crate) struct BinaryReaderErrorInner {
     pub(crate) message: String,
     pub(crate) offset: usize,
     pub(crate) needed_hint: Option<usize>,
+    pub(crate) limit_exceeded: bool,
 }
 
 pub type Result<T, E = BinaryReaderError> = result::Result<T, E>;
 
-impl Error for BinaryReaderError {}
+#[cfg(feature = "std")]
+impl std::error::Error for BinaryReaderError {}
 
 impl fmt::Display for BinaryReaderError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -54,16 +56,24 @@ impl BinaryReaderError {
                 message,
                 offset,
                 needed_hint: None,
+                limit_exceeded: false,
             }),
         }
     }
 
+    pub(crate) fn limit_exceeded(message: impl Into<String>, offset: usize) -> Self {
+        let mut err = BinaryReaderError::new(message, offset);
+        err.inner.limit_exceeded = true;
+        err
+    }
+
     pub(crate) fn eof(offset: usize, needed_hint: usize) -> Self {
         BinaryReaderError {
             inner: Box::new(BinaryReaderErrorInner {
                 message: "Unexpected EOF".to_string(),
                 offset,
                 needed_hint: Some(needed_hint),
+                limit_exceeded: false,
             }),
         }
     }
@@ -77,6 +87,13 @@ impl BinaryReaderError {
     pub fn offset(&self) -> usize {
         self.inner.offset
     }
+
+    /// Returns whether this error is due to an implementation limit, such as
+    /// those configured with [`ValidatorLimits`](crate::ValidatorLimits),
+    /// rather than the module being invalid.
+    pub fn is_limit_exceeded(&self) -> bool {
+        self.inner.limit_exceeded
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -121,6 +138,7 @@ pub enum SectionCode<'a> {
 ///
 /// [here]: https://webassembly.github.io/spec/core/syntax/types.html#types
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Type {
     I32,
     I64,
@@ -136,6 +154,7 @@ pub enum Type {
 
 /// Either a value type or a function type.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeOrFuncType {
     /// A value type.
     ///
@@ -263,6 +282,7 @@ pub enum ImportSectionEntryType {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MemoryImmediate {
     /// Alignment, stored as `n` where the actual alignment is `2^n`
     pub align: u8,
@@ -334,6 +354,7 @@ pub struct BrTable<'a> {
 ///
 /// All bit patterns are allowed.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Ieee32(pub(crate) u32);
 
 impl Ieee32 {
@@ -347,6 +368,7 @@ impl Ieee32 {
 ///
 /// All bit patterns are allowed.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Ieee64(pub(crate) u64);
 
 impl Ieee64 {
@@ -356,6 +378,7 @@ impl Ieee64 {
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct V128(pub(crate) [u8; 16]);
 
 impl V128 {
@@ -374,6 +397,7 @@ pub type SIMDLaneIndex = u8;
 ///
 /// [here]: https://webassembly.github.io/spec/core/binary/instructions.html
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Operator<'a> {
     Unreachable,
     Nop,
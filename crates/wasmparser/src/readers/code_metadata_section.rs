@@ -0,0 +1,239 @@
+/* Copyright 2022 Mozilla Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{
+    BinaryReader, BinaryReaderError, Range, Result, SectionIteratorLimited, SectionReader,
+    SectionWithLimitedItems,
+};
+
+/// A single item of code metadata, attached to an instruction.
+#[derive(Debug, Copy, Clone)]
+pub struct CodeMetadata<'a> {
+    /// The byte offset of the instruction this metadata applies to, relative
+    /// to the start of the function body (the locals declarations, after the
+    /// body's size).
+    pub offset: u32,
+    /// The payload of this metadata, whose meaning depends on the section.
+    pub data: &'a [u8],
+    data_offset: usize,
+}
+
+impl<'a> CodeMetadata<'a> {
+    /// Interprets this metadata as an entry of the `metadata.code.branch_hint`
+    /// section, returning whether the branch is likely to be taken.
+    pub fn branch_hint(&self) -> Result<bool> {
+        match self.data {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(BinaryReaderError::new(
+                "invalid branch hint",
+                self.data_offset,
+            )),
+        }
+    }
+}
+
+/// A reader for the code metadata of a single function.
+pub struct CodeMetadataReader<'a> {
+    reader: BinaryReader<'a>,
+    count: u32,
+}
+
+impl<'a> CodeMetadataReader<'a> {
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn original_position(&self) -> usize {
+        self.reader.original_position()
+    }
+
+    fn skip(reader: &mut BinaryReader, count: u32) -> Result<()> {
+        for _ in 0..count {
+            reader.read_var_u32()?;
+            let len = reader.read_var_u32()?;
+            reader.read_bytes(len as usize)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<'b>(&mut self) -> Result<CodeMetadata<'b>>
+    where
+        'a: 'b,
+    {
+        let offset = self.reader.read_var_u32()?;
+        let len = self.reader.read_var_u32()?;
+        let data_offset = self.reader.original_position();
+        let data = self.reader.read_bytes(len as usize)?;
+        Ok(CodeMetadata {
+            offset,
+            data,
+            data_offset,
+        })
+    }
+}
+
+impl<'a> IntoIterator for CodeMetadataReader<'a> {
+    type Item = Result<CodeMetadata<'a>>;
+    type IntoIter = CodeMetadataIterator<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        let count = self.count;
+        CodeMetadataIterator {
+            reader: self,
+            left: count,
+            err: false,
+        }
+    }
+}
+
+pub struct CodeMetadataIterator<'a> {
+    reader: CodeMetadataReader<'a>,
+    left: u32,
+    err: bool,
+}
+
+impl<'a> Iterator for CodeMetadataIterator<'a> {
+    type Item = Result<CodeMetadata<'a>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.err || self.left == 0 {
+            return None;
+        }
+        let result = self.reader.read();
+        self.err = result.is_err();
+        self.left -= 1;
+        Some(result)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.reader.get_count() as usize;
+        (count, Some(count))
+    }
+}
+
+/// The code metadata of a single function in a `metadata.code.*` section.
+#[derive(Debug, Copy, Clone)]
+pub struct FunctionCodeMetadata<'a> {
+    /// The index of the function this metadata is for.
+    pub func: u32,
+    count: u32,
+    data: &'a [u8],
+    data_offset: usize,
+}
+
+impl<'a> FunctionCodeMetadata<'a> {
+    pub fn get_code_metadata_reader<'b>(&self) -> CodeMetadataReader<'b>
+    where
+        'a: 'b,
+    {
+        CodeMetadataReader {
+            reader: BinaryReader::new_with_offset(self.data, self.data_offset),
+            count: self.count,
+        }
+    }
+}
+
+/// A reader for custom sections in the `metadata.code.*` family, such as
+/// `metadata.code.branch_hint`, which attach metadata to instructions.
+///
+/// See the [branch hinting proposal] for the format of these sections.
+///
+/// [branch hinting proposal]: https://github.com/WebAssembly/branch-hinting
+pub struct CodeMetadataSectionReader<'a> {
+    reader: BinaryReader<'a>,
+    count: u32,
+}
+
+impl<'a> CodeMetadataSectionReader<'a> {
+    /// Creates a reader for a `metadata.code.*` section.
+    ///
+    /// # Examples
+    /// ```
+    /// # let data: &[u8] = &[0x01, 0x02, 0x02, 0x05, 0x01, 0x01, 0x09, 0x01, 0x00];
+    /// use wasmparser::CodeMetadataSectionReader;
+    /// let mut reader = CodeMetadataSectionReader::new(data, 0).expect("code metadata reader");
+    /// let func = reader.read().expect("function code metadata");
+    /// assert_eq!(func.func, 2);
+    /// let hints = func
+    ///     .get_code_metadata_reader()
+    ///     .into_iter()
+    ///     .map(|m| {
+    ///         let m = m?;
+    ///         Ok((m.offset, m.branch_hint()?))
+    ///     })
+    ///     .collect::<wasmparser::Result<Vec<_>>>()
+    ///     .expect("branch hints");
+    /// assert_eq!(hints, [(5, true), (9, false)]);
+    /// ```
+    pub fn new(data: &'a [u8], offset: usize) -> Result<CodeMetadataSectionReader<'a>> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        let count = reader.read_var_u32()?;
+        Ok(CodeMetadataSectionReader { reader, count })
+    }
+
+    pub fn original_position(&self) -> usize {
+        self.reader.original_position()
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn read<'b>(&mut self) -> Result<FunctionCodeMetadata<'b>>
+    where
+        'a: 'b,
+    {
+        let func = self.reader.read_var_u32()?;
+        let count = self.reader.read_var_u32()?;
+        let start = self.reader.position;
+        CodeMetadataReader::skip(&mut self.reader, count)?;
+        let end = self.reader.position;
+        Ok(FunctionCodeMetadata {
+            func,
+            count,
+            data: &self.reader.buffer[start..end],
+            data_offset: self.reader.original_offset + start,
+        })
+    }
+}
+
+impl<'a> SectionReader for CodeMetadataSectionReader<'a> {
+    type Item = FunctionCodeMetadata<'a>;
+    fn read(&mut self) -> Result<Self::Item> {
+        CodeMetadataSectionReader::read(self)
+    }
+    fn eof(&self) -> bool {
+        self.reader.eof()
+    }
+    fn original_position(&self) -> usize {
+        CodeMetadataSectionReader::original_position(self)
+    }
+    fn range(&self) -> Range {
+        self.reader.range()
+    }
+}
+
+impl<'a> SectionWithLimitedItems for CodeMetadataSectionReader<'a> {
+    fn get_count(&self) -> u32 {
+        CodeMetadataSectionReader::get_count(self)
+    }
+}
+
+impl<'a> IntoIterator for CodeMetadataSectionReader<'a> {
+    type Item = Result<FunctionCodeMetadata<'a>>;
+    type IntoIter = SectionIteratorLimited<CodeMetadataSectionReader<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SectionIteratorLimited::new(self)
+    }
+}
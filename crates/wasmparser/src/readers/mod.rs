@@ -15,10 +15,11 @@
 
 use super::{
     BinaryReader, BinaryReaderError, ExternalKind, GlobalType, LinkingType, MemoryType, NameType,
-    Naming, Operator, Range, RelocType, Result, SectionCode, TableType, TagType, Type,
+    Naming, Operator, Range, RelocType, Result, TableType, TagType, Type,
 };
 
 pub use self::alias_section::*;
+pub use self::code_metadata_section::*;
 pub use self::code_section::*;
 pub use self::data_section::*;
 pub use self::element_section::*;
@@ -41,6 +42,7 @@ pub use self::tag_section::*;
 pub use self::type_section::*;
 
 mod alias_section;
+mod code_metadata_section;
 mod code_section;
 mod data_section;
 mod element_section;
@@ -14,6 +14,7 @@
  */
 
 use super::{BinaryReader, BinaryReaderError, Operator, Result};
+use crate::VisitOperator;
 
 #[derive(Clone)]
 pub struct OperatorsReader<'a> {
@@ -59,6 +60,15 @@ impl<'a> OperatorsReader<'a> {
         self.reader.read_operator()
     }
 
+    /// Reads the next operator and passes it to `visitor`, as with
+    /// [`BinaryReader::visit_operator`].
+    pub fn visit_operator<T>(&mut self, visitor: &mut T) -> Result<T::Output>
+    where
+        T: VisitOperator<'a>,
+    {
+        self.reader.visit_operator(visitor)
+    }
+
     pub fn into_iter_with_offsets<'b>(self) -> OperatorsIteratorWithOffsets<'b>
     where
         'a: 'b,
@@ -14,7 +14,7 @@
  */
 
 use super::{
-    BinaryReader, Range, RelocType, Result, SectionCode, SectionIteratorLimited, SectionReader,
+    BinaryReader, Range, RelocType, Result, SectionIteratorLimited, SectionReader,
     SectionWithLimitedItems,
 };
 
@@ -23,12 +23,12 @@ pub struct Reloc {
     pub ty: RelocType,
     pub offset: u32,
     pub index: u32,
-    pub addend: Option<u32>,
+    pub addend: Option<i64>,
 }
 
 pub struct RelocSectionReader<'a> {
     reader: BinaryReader<'a>,
-    section_code: SectionCode<'a>,
+    section_index: u32,
     count: u32,
 }
 
@@ -36,14 +36,11 @@ impl<'a> RelocSectionReader<'a> {
     pub fn new(data: &'a [u8], offset: usize) -> Result<RelocSectionReader<'a>> {
         let mut reader = BinaryReader::new_with_offset(data, offset);
 
-        let section_id_position = reader.position;
-        let section_id = reader.read_var_u7()?;
-        let section_code = reader.read_section_code(section_id, section_id_position)?;
-
+        let section_index = reader.read_var_u32()?;
         let count = reader.read_var_u32()?;
         Ok(RelocSectionReader {
             reader,
-            section_code,
+            section_index,
             count,
         })
     }
@@ -52,11 +49,10 @@ impl<'a> RelocSectionReader<'a> {
         self.count
     }
 
-    pub fn get_section_code<'b>(&self) -> SectionCode<'b>
-    where
-        'a: 'b,
-    {
-        self.section_code
+    /// Returns the index, counting from zero, of the section within the
+    /// module that these relocations apply to.
+    pub fn get_section_index(&self) -> u32 {
+        self.section_index
     }
 
     pub fn original_position(&self) -> usize {
@@ -74,7 +70,7 @@ impl<'a> RelocSectionReader<'a> {
             | RelocType::TypeIndexLEB
             | RelocType::GlobalIndexLEB => None,
             RelocType::GlobalAddrLEB | RelocType::GlobalAddrSLEB | RelocType::GlobalAddrI32 => {
-                Some(self.reader.read_var_u32()?)
+                Some(self.reader.read_var_i64()?)
             }
         };
         Ok(Reloc {
@@ -15,15 +15,22 @@
 
 use crate::limits::*;
 use crate::operators_validator::OperatorValidator;
+use crate::prelude::*;
 use crate::WasmModuleResources;
 use crate::{Alias, ExternalKind, Import, ImportSectionEntryType};
-use crate::{BinaryReaderError, GlobalType, MemoryType, Range, Result, TableType, TagType, Type};
+use crate::{
+    BinaryReaderError, DeterministicProfile, GlobalType, MemoryType, Range, Result, TableType,
+    TagType, Type,
+};
 use crate::{DataKind, ElementItem, ElementKind, InitExpr, Instance, Operator};
 use crate::{FuncType, SectionReader, SectionWithLimitedItems};
 use crate::{FunctionBody, Parser, Payload};
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap as HashMap, BTreeSet as HashSet};
+use alloc::sync::Arc;
+use core::mem;
+#[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
-use std::mem;
-use std::sync::Arc;
 
 /// Test whether the given buffer contains a valid WebAssembly module,
 /// analogous to [`WebAssembly.validate`][js] in the JS API.
@@ -47,7 +54,9 @@ fn test_validate() {
 }
 
 mod func;
+mod stream;
 pub use func::FuncValidator;
+pub use stream::{StreamStatus, StreamingValidator};
 
 /// Validator for a WebAssembly binary module.
 ///
@@ -91,6 +100,13 @@ pub struct Validator {
     /// isn't.
     features: WasmFeatures,
 
+    /// Implementation limits, dictating how large of a module is accepted.
+    limits: ValidatorLimits,
+
+    /// The deterministic profile which function bodies must also conform
+    /// to, if any.
+    profile: Option<DeterministicProfile>,
+
     /// The current byte-level offset in the wasm binary. This is updated to
     /// produce error messages in `create_error`.
     offset: usize,
@@ -208,6 +224,80 @@ impl Default for WasmFeatures {
     }
 }
 
+/// Implementation limits imposed on modules by a [`Validator`].
+///
+/// These limits aren't part of the WebAssembly specification, so exceeding
+/// them results in an error for which
+/// [`BinaryReaderError::is_limit_exceeded`] returns `true`. The defaults are
+/// agreed upon with other engines for consistency.
+///
+/// The limits on function parameters and results, imports, and exports can
+/// only be made stricter than the defaults since they're also imposed while
+/// parsing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ValidatorLimits {
+    /// The maximum number of types in a module.
+    pub max_types: usize,
+    /// The maximum number of functions, including imports, in a module.
+    pub max_functions: usize,
+    /// The maximum number of imports in an import section.
+    pub max_imports: usize,
+    /// The maximum number of exports in a module.
+    pub max_exports: usize,
+    /// The maximum number of globals, including imports, in a module.
+    pub max_globals: usize,
+    /// The maximum number of data segments in a module.
+    pub max_data_segments: usize,
+    /// The maximum size, in bytes, of a function body (unlimited by default).
+    pub max_function_size: usize,
+    /// The maximum number of locals, including parameters, in a function.
+    pub max_function_locals: usize,
+    /// The maximum number of parameters of a function type.
+    pub max_function_params: usize,
+    /// The maximum number of results of a function type.
+    pub max_function_returns: usize,
+    /// The maximum number of items in an element segment and minimum size of
+    /// a table.
+    pub max_table_entries: usize,
+    /// The maximum number of tables, including imports, in a module.
+    pub max_tables: usize,
+    /// The maximum number of memories, including imports, in a module.
+    pub max_memories: usize,
+    /// The maximum number of nested modules in a module.
+    pub max_modules: usize,
+    /// The maximum number of instances in a module.
+    pub max_instances: usize,
+    /// The maximum number of tags, including imports, in a module.
+    pub max_tags: usize,
+    /// The maximum number of targets, not including the default, of a
+    /// `br_table` instruction.
+    pub max_br_table_size: usize,
+}
+
+impl Default for ValidatorLimits {
+    fn default() -> ValidatorLimits {
+        ValidatorLimits {
+            max_types: MAX_WASM_TYPES,
+            max_functions: MAX_WASM_FUNCTIONS,
+            max_imports: MAX_WASM_IMPORTS,
+            max_exports: MAX_WASM_EXPORTS,
+            max_globals: MAX_WASM_GLOBALS,
+            max_data_segments: MAX_WASM_DATA_SEGMENTS,
+            max_function_size: usize::MAX,
+            max_function_locals: MAX_WASM_FUNCTION_LOCALS,
+            max_function_params: MAX_WASM_FUNCTION_PARAMS,
+            max_function_returns: MAX_WASM_FUNCTION_RETURNS,
+            max_table_entries: MAX_WASM_TABLE_ENTRIES,
+            max_tables: MAX_WASM_TABLES,
+            max_memories: MAX_WASM_MEMORIES,
+            max_modules: MAX_WASM_MODULES,
+            max_instances: MAX_WASM_INSTANCES,
+            max_tags: MAX_WASM_TAGS,
+            max_br_table_size: MAX_WASM_BR_TABLE_SIZE as usize,
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Debug)]
 enum Order {
     Initial,
@@ -316,6 +406,26 @@ impl Validator {
         self
     }
 
+    /// Configures the implementation limits for this `Validator`.
+    pub fn limits(&mut self, limits: ValidatorLimits) -> &mut Validator {
+        self.limits = limits;
+        self
+    }
+
+    /// Configures the [`DeterministicProfile`] which function bodies must
+    /// conform to, or `None` (the default) to allow any operator which is
+    /// valid with the enabled features.
+    ///
+    /// The profile is only checked by [`FuncValidator::validate`], and thus
+    /// by [`Validator::validate_all`].
+    pub fn deterministic_profile(
+        &mut self,
+        profile: Option<DeterministicProfile>,
+    ) -> &mut Validator {
+        self.profile = profile;
+        self
+    }
+
     /// Validates an entire in-memory module with this validator.
     ///
     /// This function will internally create a [`Parser`] to parse the `bytes`
@@ -398,6 +508,10 @@ impl Validator {
         Err(BinaryReaderError::new(msg.into(), self.offset))
     }
 
+    fn create_limit_error<T>(&self, msg: impl Into<String>) -> Result<T> {
+        Err(BinaryReaderError::limit_exceeded(msg.into(), self.offset))
+    }
+
     /// Validates [`Payload::Version`](crate::Payload)
     pub fn version(&mut self, num: u32, range: &Range) -> Result<()> {
         self.offset = range.start;
@@ -522,16 +636,25 @@ impl Validator {
         }
     }
 
-    fn check_max(&self, cur_len: usize, amt_added: u32, max: usize, desc: &str) -> Result<()> {
+    /// Checks that adding `amt_added` items to `cur_len` stays within `max`,
+    /// where a `max` of `None` means that the enabled features only allow one
+    /// item.
+    fn check_max(
+        &self,
+        cur_len: usize,
+        amt_added: u32,
+        max: Option<usize>,
+        desc: &str,
+    ) -> Result<()> {
         let overflow = max
+            .unwrap_or(1)
             .checked_sub(cur_len)
             .and_then(|amt| amt.checked_sub(amt_added as usize))
             .is_none();
         if overflow {
-            return if max == 1 {
-                self.create_error(format!("multiple {}", desc))
-            } else {
-                self.create_error(format!("{} count is out of bounds", desc))
+            return match max {
+                Some(_) => self.create_limit_error(format!("{} count is out of bounds", desc)),
+                None => self.create_error(format!("multiple {}", desc)),
             };
         }
         Ok(())
@@ -566,7 +689,7 @@ impl Validator {
         self.check_max(
             self.cur.state.types.len(),
             section.get_count(),
-            MAX_WASM_TYPES,
+            Some(self.limits.max_types),
             "types",
         )?;
         self.section(order, section, |me, item| me.type_def(item))
@@ -578,6 +701,12 @@ impl Validator {
                 for ty in t.params.iter().chain(t.returns.iter()) {
                     self.value_type(*ty)?;
                 }
+                if t.params.len() > self.limits.max_function_params {
+                    return self.create_limit_error("function params size is out of bound");
+                }
+                if t.returns.len() > self.limits.max_function_returns {
+                    return self.create_limit_error("function returns size is out of bound");
+                }
                 if t.returns.len() > 1 && !self.features.multi_value {
                     return self
                         .create_error("invalid result arity: func type returns multiple values");
@@ -688,15 +817,15 @@ impl Validator {
             }
             _ => return self.create_error("element is not reference type"),
         }
-        self.limits(ty.initial, ty.maximum)?;
-        if ty.initial > MAX_WASM_TABLE_ENTRIES as u32 {
-            return self.create_error("minimum table size is out of bounds");
+        self.check_limits(ty.initial, ty.maximum)?;
+        if ty.initial as usize > self.limits.max_table_entries {
+            return self.create_limit_error("minimum table size is out of bounds");
         }
         Ok(())
     }
 
     fn memory_type(&self, ty: &MemoryType) -> Result<()> {
-        self.limits(ty.initial, ty.maximum)?;
+        self.check_limits(ty.initial, ty.maximum)?;
         let (true_maximum, err) = if ty.memory64 {
             if !self.features.memory64 {
                 return self.create_error("memory64 must be enabled for 64-bit memories");
@@ -745,7 +874,7 @@ impl Validator {
         self.value_type(ty.content_type)
     }
 
-    fn limits<T>(&self, initial: T, maximum: Option<T>) -> Result<()>
+    fn check_limits<T>(&self, initial: T, maximum: Option<T>) -> Result<()>
     where
         T: Into<u64>,
     {
@@ -760,12 +889,18 @@ impl Validator {
     /// Validates [`Payload::ImportSection`](crate::Payload)
     pub fn import_section(&mut self, section: &crate::ImportSectionReader<'_>) -> Result<()> {
         let order = self.header_order(Order::Import);
+        self.check_max(
+            0,
+            section.get_count(),
+            Some(self.limits.max_imports),
+            "imports",
+        )?;
         self.section(order, section, |me, item| me.import(item))?;
 
         // Clear the list of implicit imports after the import section is
         // finished since later import sections cannot append further to the
         // pseudo-instances defined in this import section.
-        self.cur.state.assert_mut().imports.implicit.drain();
+        self.cur.state.assert_mut().imports.implicit.clear();
         Ok(())
     }
 
@@ -803,7 +938,11 @@ impl Validator {
             ImportSectionEntryType::Function(type_index) => {
                 let ty = state.types[type_index as usize];
                 state.func_types.push(ty);
-                (state.func_types.len(), MAX_WASM_FUNCTIONS, "funcs")
+                (
+                    state.func_types.len(),
+                    Some(self.limits.max_functions),
+                    "funcs",
+                )
             }
             ImportSectionEntryType::Table(ty) => {
                 state.tables.push(ty);
@@ -816,22 +955,34 @@ impl Validator {
             ImportSectionEntryType::Tag(ty) => {
                 let ty = state.types[ty.type_index as usize];
                 state.tags.push(ty);
-                (state.tags.len(), MAX_WASM_TAGS, "tags")
+                (state.tags.len(), Some(self.limits.max_tags), "tags")
             }
             ImportSectionEntryType::Global(ty) => {
                 state.globals.push(ty);
                 state.num_imported_globals += 1;
-                (state.globals.len(), MAX_WASM_GLOBALS, "globals")
+                (
+                    state.globals.len(),
+                    Some(self.limits.max_globals),
+                    "globals",
+                )
             }
             ImportSectionEntryType::Instance(type_idx) => {
                 let index = state.types[type_idx as usize];
                 state.instances.push(index);
-                (state.instances.len(), MAX_WASM_INSTANCES, "instances")
+                (
+                    state.instances.len(),
+                    Some(self.limits.max_instances),
+                    "instances",
+                )
             }
             ImportSectionEntryType::Module(type_index) => {
                 let index = state.types[type_index as usize];
                 state.submodules.push(index);
-                (state.submodules.len(), MAX_WASM_MODULES, "modules")
+                (
+                    state.submodules.len(),
+                    Some(self.limits.max_modules),
+                    "modules",
+                )
             }
         };
         self.check_max(len, 0, max, desc)?;
@@ -848,7 +999,7 @@ impl Validator {
         self.check_max(
             self.cur.state.submodules.len(),
             count,
-            MAX_WASM_MODULES,
+            Some(self.limits.max_modules),
             "modules",
         )?;
         Ok(())
@@ -975,7 +1126,7 @@ impl Validator {
         self.check_max(
             self.cur.state.instances.len(),
             section.get_count(),
-            MAX_WASM_INSTANCES,
+            Some(self.limits.max_instances),
             "instances",
         )?;
         self.section(Order::ModuleLinkingHeader, section, |me, i| me.instance(i))
@@ -1135,7 +1286,7 @@ impl Validator {
         self.check_max(
             self.cur.state.func_types.len(),
             section.get_count(),
-            MAX_WASM_FUNCTIONS,
+            Some(self.limits.max_functions),
             "funcs",
         )?;
         // Assert that each type index is indeed a function type, and otherwise
@@ -1149,11 +1300,11 @@ impl Validator {
         })
     }
 
-    fn max_tables(&self) -> usize {
+    fn max_tables(&self) -> Option<usize> {
         if self.features.reference_types || self.features.module_linking {
-            MAX_WASM_TABLES
+            Some(self.limits.max_tables)
         } else {
-            1
+            None
         }
     }
 
@@ -1172,11 +1323,11 @@ impl Validator {
         })
     }
 
-    fn max_memories(&self) -> usize {
+    fn max_memories(&self) -> Option<usize> {
         if self.features.multi_memory {
-            MAX_WASM_MEMORIES
+            Some(self.limits.max_memories)
         } else {
-            1
+            None
         }
     }
 
@@ -1201,7 +1352,7 @@ impl Validator {
         self.check_max(
             self.cur.state.tags.len(),
             section.get_count(),
-            MAX_WASM_TAGS,
+            Some(self.limits.max_tags),
             "tags",
         )?;
         self.section(Order::Tag, section, |me, ty| {
@@ -1217,7 +1368,7 @@ impl Validator {
         self.check_max(
             self.cur.state.globals.len(),
             section.get_count(),
-            MAX_WASM_GLOBALS,
+            Some(self.limits.max_globals),
             "globals",
         )?;
         self.section(Order::Global, section, |me, g| {
@@ -1320,6 +1471,12 @@ impl Validator {
 
     /// Validates [`Payload::ExportSection`](crate::Payload)
     pub fn export_section(&mut self, section: &crate::ExportSectionReader<'_>) -> Result<()> {
+        self.check_max(
+            0,
+            section.get_count(),
+            Some(self.limits.max_exports),
+            "exports",
+        )?;
         self.section(Order::Export, section, |me, e| {
             if let ExternalKind::Type = e.kind {
                 return me.create_error("cannot export types");
@@ -1430,8 +1587,8 @@ impl Validator {
                 }
             }
             let mut items = e.items.get_items_reader()?;
-            if items.get_count() > MAX_WASM_TABLE_ENTRIES as u32 {
-                return me.create_error("num_elements is out of bounds");
+            if items.get_count() as usize > me.limits.max_table_entries {
+                return me.create_limit_error("num_elements is out of bounds");
             }
             for _ in 0..items.get_count() {
                 me.offset = items.original_position();
@@ -1460,8 +1617,8 @@ impl Validator {
         self.offset = range.start;
         self.update_order(Order::DataCount)?;
         self.cur.state.assert_mut().data_count = Some(count);
-        if count > MAX_WASM_DATA_SEGMENTS as u32 {
-            return self.create_error("data count section specifies too many data segments");
+        if count as usize > self.limits.max_data_segments {
+            return self.create_limit_error("data count section specifies too many data segments");
         }
         Ok(())
     }
@@ -1509,13 +1666,21 @@ impl Validator {
         let ty = self.cur.state.code_type_indexes[self.cur.code_section_index];
         self.cur.code_section_index += 1;
         let resources = ValidatorResources(self.cur.state.arc().clone());
-        Ok(FuncValidator::new(ty, 0, resources, &self.features).unwrap())
+        let mut validator =
+            FuncValidator::with_limits(ty, 0, resources, &self.features, &self.limits).unwrap();
+        validator.deterministic_profile(self.profile);
+        Ok(validator)
     }
 
     /// Validates [`Payload::DataSection`](crate::Payload).
     pub fn data_section(&mut self, section: &crate::DataSectionReader<'_>) -> Result<()> {
         self.cur.data_found = section.get_count();
-        self.check_max(0, section.get_count(), MAX_WASM_DATA_SEGMENTS, "segments")?;
+        self.check_max(
+            0,
+            section.get_count(),
+            Some(self.limits.max_data_segments),
+            "segments",
+        )?;
         let mut section = section.clone();
         section.forbid_bulk_memory(!self.features.bulk_memory);
         self.section(Order::Data, &section, |me, d| {
@@ -1777,8 +1942,8 @@ impl WasmModuleResources for ValidatorResources {
 }
 
 mod arc {
-    use std::ops::Deref;
-    use std::sync::Arc;
+    use alloc::sync::Arc;
+    use core::ops::Deref;
 
     pub struct MaybeOwned<T> {
         owned: bool,
@@ -2056,7 +2221,7 @@ impl<T> SnapshotList<T> {
     }
 }
 
-impl<T> std::ops::Index<usize> for SnapshotList<T> {
+impl<T> core::ops::Index<usize> for SnapshotList<T> {
     type Output = T;
 
     fn index(&self, index: usize) -> &T {
@@ -2064,7 +2229,7 @@ impl<T> std::ops::Index<usize> for SnapshotList<T> {
     }
 }
 
-impl<T> std::ops::IndexMut<usize> for SnapshotList<T> {
+impl<T> core::ops::IndexMut<usize> for SnapshotList<T> {
     fn index_mut(&mut self, index: usize) -> &mut T {
         self.get_mut(index).unwrap()
     }
@@ -2079,3 +2244,80 @@ impl<T> Default for SnapshotList<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate_with(limits: ValidatorLimits, wat: &str) -> Result<()> {
+        let wasm = wat::parse_str(wat).unwrap();
+        Validator::new().limits(limits).validate_all(&wasm)
+    }
+
+    #[test]
+    fn default_limits() {
+        let limits = ValidatorLimits::default();
+        assert_eq!(limits.max_function_locals, MAX_WASM_FUNCTION_LOCALS);
+        assert_eq!(limits.max_table_entries, MAX_WASM_TABLE_ENTRIES);
+        assert_eq!(limits.max_function_size, usize::MAX);
+    }
+
+    #[test]
+    fn stricter_limits() {
+        let limits = ValidatorLimits {
+            max_functions: 1,
+            max_function_locals: 2,
+            max_function_params: 1,
+            max_table_entries: 3,
+            max_br_table_size: 1,
+            max_function_size: 16,
+            ..ValidatorLimits::default()
+        };
+        let cases = [
+            ("(module (func) (func))", "funcs count is out of bounds"),
+            (
+                "(module (func (param i32) (local i32 i32)))",
+                "locals exceed maximum",
+            ),
+            (
+                "(module (func (param i32 i32)))",
+                "function params size is out of bound",
+            ),
+            ("(module (table 4 funcref))", "minimum table size"),
+            (
+                "(module (func block block i32.const 0 br_table 0 1 1 end end))",
+                "br_table size is out of bound",
+            ),
+            (
+                "(module (func i32.const 1 drop i32.const 2 drop i32.const 3 drop i32.const 4 drop i32.const 5 drop i32.const 6 drop))",
+                "function body size is out of bounds",
+            ),
+        ];
+        for (wat, msg) in cases.iter() {
+            let err = validate_with(limits, wat).unwrap_err();
+            assert!(err.message().contains(msg), "{}: {}", wat, err);
+            assert!(err.is_limit_exceeded(), "{}", err);
+            validate_with(ValidatorLimits::default(), wat).unwrap();
+        }
+    }
+
+    #[test]
+    fn looser_limits() {
+        let wat = "(module (table 11000000 funcref))";
+        let err = validate_with(ValidatorLimits::default(), wat).unwrap_err();
+        assert!(err.is_limit_exceeded());
+        let limits = ValidatorLimits {
+            max_table_entries: 20_000_000,
+            ..ValidatorLimits::default()
+        };
+        validate_with(limits, wat).unwrap();
+    }
+
+    #[test]
+    fn invalid_modules_are_not_limit_errors() {
+        let wat = "(module (memory 1) (memory 1))";
+        let err = validate_with(ValidatorLimits::default(), wat).unwrap_err();
+        assert!(err.message().contains("multiple memories"), "{}", err);
+        assert!(!err.is_limit_exceeded());
+    }
+}
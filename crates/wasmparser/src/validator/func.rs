@@ -1,6 +1,8 @@
-use crate::operators_validator::OperatorValidator;
-use crate::{BinaryReader, Result, Type};
-use crate::{FunctionBody, Operator, WasmFeatures, WasmModuleResources};
+use crate::operators_validator::{Frame, OperatorValidator, ValidatingVisitor};
+use crate::prelude::*;
+use crate::ValidatorLimits;
+use crate::{BinaryReader, DeterministicProfile, Result, Type};
+use crate::{BinaryReaderError, FunctionBody, Operator, WasmFeatures, WasmModuleResources};
 
 /// Validation context for a WebAssembly function.
 ///
@@ -12,6 +14,7 @@ use crate::{FunctionBody, Operator, WasmFeatures, WasmModuleResources};
 pub struct FuncValidator<T> {
     validator: OperatorValidator,
     resources: T,
+    profile: Option<DeterministicProfile>,
 }
 
 impl<T: WasmModuleResources> FuncValidator<T> {
@@ -29,13 +32,40 @@ impl<T: WasmModuleResources> FuncValidator<T> {
         offset: usize,
         resources: T,
         features: &WasmFeatures,
+    ) -> Result<FuncValidator<T>> {
+        FuncValidator::with_limits(ty, offset, resources, features, &Default::default())
+    }
+
+    /// Same as [`FuncValidator::new`], except that the function is validated
+    /// against the implementation `limits` specified instead of the default
+    /// ones.
+    pub fn with_limits(
+        ty: u32,
+        offset: usize,
+        resources: T,
+        features: &WasmFeatures,
+        limits: &ValidatorLimits,
     ) -> Result<FuncValidator<T>> {
         Ok(FuncValidator {
-            validator: OperatorValidator::new_func(ty, offset, features, &resources)?,
+            validator: OperatorValidator::new_func(ty, offset, features, limits, &resources)?,
             resources,
+            profile: None,
         })
     }
 
+    /// Configures the [`DeterministicProfile`] which the function must
+    /// conform to, or `None` (the default) to not check any profile.
+    ///
+    /// The profile is only checked by [`FuncValidator::validate`], which
+    /// rejects the first operator that isn't allowed by it.
+    pub fn deterministic_profile(
+        &mut self,
+        profile: Option<DeterministicProfile>,
+    ) -> &mut FuncValidator<T> {
+        self.profile = profile;
+        self
+    }
+
     /// Get the current height of the operand stack.
     ///
     /// This returns the height of the whole operand stack for this function,
@@ -44,20 +74,96 @@ impl<T: WasmModuleResources> FuncValidator<T> {
         self.validator.operands.len() as u32
     }
 
+    /// Returns the type of the operand `depth` entries from the top of the
+    /// operand stack, where a `depth` of 0 is the top of the stack.
+    ///
+    /// Returns `None` if `depth` is beyond the bottom of the stack and
+    /// `Some(None)` if the operand's type is unknown, which happens for
+    /// operands pushed by polymorphic instructions in unreachable code.
+    pub fn get_operand_type(&self, depth: usize) -> Option<Option<Type>> {
+        let operands = &self.validator.operands;
+        operands.len().checked_sub(depth + 1).map(|i| operands[i])
+    }
+
+    /// Get the current height of the control stack.
+    ///
+    /// This includes the frame of the function body itself, so it's 0 only
+    /// once the function's final `end` has been validated.
+    pub fn control_stack_height(&self) -> u32 {
+        self.validator.control.len() as u32
+    }
+
+    /// Returns the control frame `depth` entries from the top of the control
+    /// stack, where a `depth` of 0 is the innermost frame, or `None` if
+    /// `depth` is beyond the bottom of the stack.
+    ///
+    /// The innermost frame's [`Frame::unreachable`] indicates whether the
+    /// code currently being validated is unreachable.
+    pub fn get_control_frame(&self, depth: usize) -> Option<&Frame> {
+        let control = &self.validator.control;
+        control.len().checked_sub(depth + 1).map(|i| &control[i])
+    }
+
+    /// Returns the number of locals of this function, including parameters,
+    /// which have been defined so far.
+    pub fn len_locals(&self) -> u32 {
+        self.validator.num_locals()
+    }
+
+    /// Returns the type of the local at `index`, where parameters come first,
+    /// or `None` if it's out of bounds.
+    pub fn get_local_type(&self, index: u32) -> Option<Type> {
+        self.validator.local(index).ok()
+    }
+
+    /// Returns the operand type that was expected when the last call to
+    /// [`FuncValidator::op`] failed because an operand was missing or of the
+    /// wrong type.
+    ///
+    /// Returns `None` if the last operator was valid or was rejected for
+    /// another reason, and `Some(None)` if an operand of any type would have
+    /// been accepted. After such a failure the operand stack is left as it
+    /// was just before the missing or mismatched operand was popped.
+    pub fn mismatched_operand(&self) -> Option<Option<Type>> {
+        self.validator.mismatch
+    }
+
     /// Convenience function to validate an entire function's body.
     ///
     /// You may not end up using this in final implementations because you'll
-    /// often want to interleave validation with parsing.
+    /// often want to interleave validation with parsing. This is also the only
+    /// place that the size of the function body is checked against
+    /// [`ValidatorLimits::max_function_size`], and that the function is
+    /// checked against the configured
+    /// [`deterministic_profile`](FuncValidator::deterministic_profile).
     pub fn validate(&mut self, body: &FunctionBody<'_>) -> Result<()> {
+        let range = body.range();
+        if range.end - range.start > self.validator.limits.max_function_size {
+            return Err(BinaryReaderError::limit_exceeded(
+                "function body size is out of bounds",
+                range.start,
+            ));
+        }
         let mut reader = body.get_binary_reader();
         self.read_locals(&mut reader)?;
         reader.allow_memarg64(self.validator.features.memory64);
+        let mut visitor = ValidatingVisitor {
+            validator: &mut self.validator,
+            resources: &self.resources,
+        };
         while !reader.eof() {
             let pos = reader.original_position();
-            let op = reader.read_operator()?;
-            self.op(pos, &op)?;
+            reader
+                .visit_operator(&mut visitor)?
+                .map_err(|e| e.set_offset(pos))?;
         }
-        self.finish(reader.original_position())
+        self.finish(reader.original_position())?;
+        if let Some(profile) = &self.profile {
+            if let Some(v) = profile.check_function_body(body)?.first() {
+                return Err(BinaryReaderError::new(v.reason.to_string(), v.offset));
+            }
+        }
+        Ok(())
     }
 
     /// Reads the local defintions from the given `BinaryReader`, often sourced
@@ -90,6 +196,7 @@ impl<T: WasmModuleResources> FuncValidator<T> {
     /// the operator itself are passed to this function to provide more useful
     /// error messages.
     pub fn op(&mut self, offset: usize, operator: &Operator<'_>) -> Result<()> {
+        self.validator.mismatch = None;
         self.validator
             .process_operator(operator, &self.resources)
             .map_err(|e| e.set_offset(offset))?;
@@ -118,7 +225,7 @@ impl<T: WasmModuleResources> FuncValidator<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::WasmFuncType;
+    use crate::{FrameKind, WasmFuncType};
 
     struct EmptyResources;
 
@@ -200,4 +307,38 @@ mod tests {
         assert!(v.op(2, &Operator::I32Const { value: 99 }).is_ok());
         assert_eq!(v.operand_stack_height(), 2);
     }
+
+    #[test]
+    fn control_frames_and_locals() {
+        let mut v = FuncValidator::new(0, 0, &EmptyResources, &Default::default()).unwrap();
+        v.define_locals(0, 2, Type::I64).unwrap();
+        assert_eq!(v.len_locals(), 2);
+        assert_eq!(v.get_local_type(1), Some(Type::I64));
+        assert_eq!(v.get_local_type(2), None);
+
+        // The function body itself is the outermost frame.
+        assert_eq!(v.control_stack_height(), 1);
+        let frame = v.get_control_frame(0).unwrap();
+        assert_eq!(frame.kind, FrameKind::Block);
+        assert!(!frame.unreachable);
+
+        let ty = crate::TypeOrFuncType::Type(Type::I32);
+        v.op(1, &Operator::Loop { ty }).unwrap();
+        v.op(2, &Operator::Unreachable).unwrap();
+        v.op(3, &Operator::Drop).unwrap();
+        assert_eq!(v.control_stack_height(), 2);
+        let frame = v.get_control_frame(0).unwrap();
+        assert_eq!(frame.kind, FrameKind::Loop);
+        assert!(frame.unreachable);
+        assert_eq!(v.get_control_frame(1).unwrap().kind, FrameKind::Block);
+        assert!(v.get_control_frame(2).is_none());
+
+        // Operators in unreachable code have an unknown type.
+        v.op(4, &Operator::Select).unwrap();
+        assert_eq!(v.get_operand_type(0), Some(None));
+        v.op(5, &Operator::LocalGet { local_index: 0 }).unwrap();
+        assert_eq!(v.get_operand_type(0), Some(Some(Type::I64)));
+        assert_eq!(v.get_operand_type(1), Some(None));
+        assert_eq!(v.get_operand_type(2), None);
+    }
 }
@@ -0,0 +1,277 @@
+use super::{ValidPayload, Validator};
+use crate::prelude::*;
+use crate::{BinaryReaderError, Chunk, Parser, Payload, Result, ValidatorLimits, WasmFeatures};
+use core::mem;
+
+/// A driver which validates a WebAssembly module as its bytes arrive, such as
+/// from a network stream.
+///
+/// Bytes are fed in arbitrarily-sized chunks with
+/// [`StreamingValidator::push`], and [`StreamingValidator::validate`]
+/// validates everything that can be parsed from the bytes received so far.
+/// Sections are validated once they've been received in full, and function
+/// bodies are validated one at a time as each is completed, so the bulk of
+/// validation overlaps with the download of the code section.
+///
+/// All state lives in the `StreamingValidator` itself, so validation can be
+/// paused between any two calls and resumed later. Additionally
+/// [`StreamingValidator::validate_some`] bounds how many function bodies are
+/// validated per call, for callers which need to yield periodically.
+///
+/// # Examples
+///
+/// ```
+/// use wasmparser::{StreamStatus, StreamingValidator};
+///
+/// # fn foo() -> wasmparser::Result<()> {
+/// let wasm = b"\0asm\x01\0\0\0\x01\x04\x01\x60\0\0\x03\x02\x01\0\x0a\x04\x01\x02\0\x0b";
+/// let mut validator = StreamingValidator::new();
+/// for chunk in wasm.chunks(3) {
+///     validator.push(chunk);
+///     assert!(matches!(validator.validate()?, StreamStatus::NeedMoreData(_)));
+/// }
+/// validator.end_of_input();
+/// assert_eq!(validator.validate()?, StreamStatus::Done);
+/// assert_eq!(validator.functions_validated(), 1);
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+pub struct StreamingValidator {
+    validator: Validator,
+    parser: Parser,
+    /// Parsers of the enclosing modules while a nested module is validated.
+    stack: Vec<Parser>,
+    /// Bytes received but not yet consumed by `parser`, starting at `pos`.
+    buffer: Vec<u8>,
+    pos: usize,
+    eof: bool,
+    done: bool,
+    /// The error validation failed with, which is returned again from any
+    /// subsequent calls.
+    error: Option<BinaryReaderError>,
+    functions_validated: usize,
+}
+
+/// The result of a successful call to [`StreamingValidator::validate`] or
+/// [`StreamingValidator::validate_some`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StreamStatus {
+    /// Everything received so far is valid and more bytes are needed to
+    /// continue. The value is a hint of how many more bytes are needed.
+    NeedMoreData(u64),
+    /// Validation stopped because the maximum number of function bodies to
+    /// validate in one call was reached. Validation resumes with the next
+    /// call.
+    Paused,
+    /// The entire module was received and is valid.
+    Done,
+}
+
+impl StreamingValidator {
+    /// Creates a new `StreamingValidator` with the default set of enabled
+    /// WebAssembly features.
+    pub fn new() -> StreamingValidator {
+        StreamingValidator {
+            validator: Validator::new(),
+            parser: Parser::new(0),
+            stack: Vec::new(),
+            buffer: Vec::new(),
+            pos: 0,
+            eof: false,
+            done: false,
+            error: None,
+            functions_validated: 0,
+        }
+    }
+
+    /// Configures the enabled WebAssembly features, which should happen
+    /// before anything is validated.
+    pub fn wasm_features(&mut self, features: WasmFeatures) -> &mut StreamingValidator {
+        self.validator.wasm_features(features);
+        self
+    }
+
+    /// Configures the implementation limits, which should happen before
+    /// anything is validated.
+    pub fn limits(&mut self, limits: ValidatorLimits) -> &mut StreamingValidator {
+        self.validator.limits(limits);
+        self
+    }
+
+    /// Appends `data` to the bytes received so far.
+    ///
+    /// No validation happens until [`StreamingValidator::validate`] is
+    /// called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`StreamingValidator::end_of_input`] was already called.
+    pub fn push(&mut self, data: &[u8]) {
+        assert!(!self.eof, "data pushed after the end of input");
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Signals that all of the module's bytes have been pushed.
+    pub fn end_of_input(&mut self) {
+        self.eof = true;
+    }
+
+    /// Validates as much as possible of the bytes received so far.
+    ///
+    /// Errors are terminal: once an error is returned the same error is
+    /// returned from all future calls.
+    pub fn validate(&mut self) -> Result<StreamStatus> {
+        self.validate_some(usize::MAX)
+    }
+
+    /// Same as [`StreamingValidator::validate`], except that at most
+    /// `max_functions` function bodies are validated before returning
+    /// [`StreamStatus::Paused`].
+    pub fn validate_some(&mut self, max_functions: usize) -> Result<StreamStatus> {
+        if let Some(e) = &self.error {
+            return Err(e.clone());
+        }
+        let result = self.run(max_functions);
+        if let Err(e) = &result {
+            self.error = Some(e.clone());
+        }
+        result
+    }
+
+    fn run(&mut self, max_functions: usize) -> Result<StreamStatus> {
+        let mut validated = 0;
+        loop {
+            if self.done {
+                return Ok(StreamStatus::Done);
+            }
+            if validated == max_functions {
+                return Ok(StreamStatus::Paused);
+            }
+            let (payload, consumed) = match self.parser.parse(&self.buffer[self.pos..], self.eof)? {
+                Chunk::NeedMoreData(hint) => {
+                    // Drop the bytes which have been consumed so the buffer
+                    // only ever holds the current, partially received item.
+                    self.buffer.drain(..self.pos);
+                    self.pos = 0;
+                    return Ok(StreamStatus::NeedMoreData(hint));
+                }
+                Chunk::Parsed { payload, consumed } => (payload, consumed),
+            };
+            match self.validator.payload(&payload)? {
+                ValidPayload::Ok => {}
+                ValidPayload::Submodule(parser) => {
+                    self.stack.push(mem::replace(&mut self.parser, parser));
+                }
+                ValidPayload::Func(mut func, body) => {
+                    func.validate(&body)?;
+                    validated += 1;
+                    self.functions_validated += 1;
+                }
+            }
+            if let Payload::End = payload {
+                match self.stack.pop() {
+                    Some(parser) => self.parser = parser,
+                    None => self.done = true,
+                }
+            }
+            self.pos += consumed;
+        }
+    }
+
+    /// Returns the number of function bodies validated so far, including
+    /// those of nested modules.
+    pub fn functions_validated(&self) -> usize {
+        self.functions_validated
+    }
+
+    /// Returns the number of bytes received which haven't been validated yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.pos
+    }
+
+    /// Returns the underlying [`Validator`], which has the context of
+    /// everything validated so far.
+    pub fn validator(&self) -> &Validator {
+        &self.validator
+    }
+}
+
+impl Default for StreamingValidator {
+    fn default() -> StreamingValidator {
+        StreamingValidator::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wasm() -> Vec<u8> {
+        wat::parse_str(
+            r#"
+            (module
+                (func (export "a") (result i32) i32.const 1)
+                (func (param i32) (result i32) local.get 0 call 0 i32.add)
+                (func)
+                (data "hello"))
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn any_chunk_size() {
+        let wasm = wasm();
+        for size in 1..wasm.len() {
+            let mut validator = StreamingValidator::new();
+            for chunk in wasm.chunks(size) {
+                validator.push(chunk);
+                match validator.validate().unwrap() {
+                    StreamStatus::NeedMoreData(_) => {}
+                    status => panic!("unexpected status {:?}", status),
+                }
+            }
+            validator.end_of_input();
+            assert_eq!(validator.validate().unwrap(), StreamStatus::Done);
+            assert_eq!(validator.functions_validated(), 3);
+            assert_eq!(validator.buffered(), 0);
+        }
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let mut validator = StreamingValidator::new();
+        validator.push(&wasm());
+        validator.end_of_input();
+        assert_eq!(validator.validate_some(2).unwrap(), StreamStatus::Paused);
+        assert_eq!(validator.functions_validated(), 2);
+        assert_eq!(validator.validate_some(2).unwrap(), StreamStatus::Done);
+        assert_eq!(validator.functions_validated(), 3);
+        assert_eq!(validator.validate().unwrap(), StreamStatus::Done);
+    }
+
+    #[test]
+    fn errors_are_terminal() {
+        let wasm = wat::parse_str(r#"(module (func i32.const 0) (func))"#).unwrap();
+        let mut validator = StreamingValidator::new();
+        // The invalid body is reported as soon as it has been received, before
+        // the rest of the module.
+        let end = wasm.len() - 3;
+        validator.push(&wasm[..end]);
+        let err = validator.validate().unwrap_err();
+        assert!(err.message().contains("type mismatch"), "{}", err);
+        validator.push(&wasm[end..]);
+        validator.end_of_input();
+        assert_eq!(validator.validate().unwrap_err().offset(), err.offset());
+    }
+
+    #[test]
+    fn truncated() {
+        let wasm = wasm();
+        let mut validator = StreamingValidator::new();
+        validator.push(&wasm[..wasm.len() - 1]);
+        validator.end_of_input();
+        assert!(validator.validate().is_err());
+    }
+}
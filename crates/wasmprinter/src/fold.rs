@@ -0,0 +1,935 @@
+//! Support for printing function bodies as folded S-expressions.
+//!
+//! The binary format only has a flat sequence of instructions, so to print
+//! something like `(i32.add (local.get 0) (i32.const 1))` the operand tree has
+//! to be reconstructed from the stack effects of each instruction. This is done
+//! greedily: whenever an instruction consumes `n` values and the previous `n`
+//! expressions in the same block each produce exactly one value, those
+//! expressions are folded in as its operands. Otherwise the instruction is left
+//! flat and its operands stay as separate expressions preceding it.
+//!
+//! Folding is purely syntactic (`(op a b)` is defined as `a b op`) so the
+//! printed text always encodes back to the same bytes. The arity information
+//! only decides how much gets folded, which is why unknown arities (e.g. a call
+//! to a function whose type can't be determined) simply disable folding for
+//! that instruction.
+
+use crate::Printer;
+use anyhow::Result;
+use std::fmt::Write;
+use wasmparser::{FunctionBody, Operator, Type, TypeOrFuncType};
+
+/// A single, possibly folded, expression within a function body.
+struct Expr<'a> {
+    op: Operator<'a>,
+    /// The offset of `op` in the original binary.
+    offset: usize,
+    /// The number of blocks enclosing this expression, used to print label
+    /// comments for branches and blocks.
+    depth: u32,
+    /// For block-like instructions this is the index of the label they
+    /// introduce, in the order the labels appear in the function.
+    label: u32,
+    /// How many values this expression leaves on the stack, if known.
+    results: Option<u32>,
+    /// Expressions folded as the operands of `op`.
+    operands: Vec<Expr<'a>>,
+    /// For block-like instructions, the arms of the block. The first arm is
+    /// the body itself (or the `then`/`do` arm) and further arms are started
+    /// by `else`, `catch`, or `catch_all`.
+    arms: Vec<Arm<'a>>,
+    /// For `try` blocks, the `delegate` instruction which terminates it.
+    delegate: Option<Operator<'a>>,
+}
+
+struct Arm<'a> {
+    op: Option<Operator<'a>>,
+    body: Vec<Expr<'a>>,
+}
+
+/// A block which is currently open while folding a function body.
+struct Frame<'a> {
+    /// The block instruction that opened this frame, or `None` for the
+    /// function body itself.
+    block: Option<Expr<'a>>,
+    /// The expressions of the arm currently being parsed.
+    exprs: Vec<Expr<'a>>,
+    /// Number of values a branch to this frame's label carries.
+    label_arity: Option<u32>,
+    /// Number of values this block produces once it ends.
+    results: Option<u32>,
+}
+
+impl Printer {
+    /// Attempts to print the instructions of `body` in folded form.
+    ///
+    /// Returns `Ok(false)` without printing anything if the body isn't
+    /// well-nested enough to be folded, in which case the caller should print
+    /// it flat instead.
+    pub(crate) fn print_folded_body(&mut self, body: &FunctionBody<'_>, ty: u32) -> Result<bool> {
+        let func_results = match self.state.types.get(ty as usize) {
+            Some(Some(ty)) => Some(ty.returns.len() as u32),
+            _ => None,
+        };
+        let exprs = match self.fold_body(body, func_results)? {
+            Some(exprs) => exprs,
+            None => return Ok(false),
+        };
+        self.state.label = 0;
+        self.print_folded_seq(&exprs)?;
+        Ok(true)
+    }
+
+    fn fold_body<'a>(
+        &self,
+        body: &FunctionBody<'a>,
+        func_results: Option<u32>,
+    ) -> Result<Option<Vec<Expr<'a>>>> {
+        let mut reader = body.get_operators_reader()?;
+        reader.allow_memarg64(true);
+        let mut frames = vec![Frame {
+            block: None,
+            exprs: Vec::new(),
+            label_arity: func_results,
+            results: func_results,
+        }];
+        let mut label = 0;
+        while !reader.eof() {
+            let offset = reader.original_position();
+            let op = reader.read()?;
+            let depth = frames.len() as u32 - 1;
+            match op {
+                Operator::Block { ty }
+                | Operator::Loop { ty }
+                | Operator::If { ty }
+                | Operator::Try { ty } => {
+                    let (params, results) = match self.blockty_arity(ty) {
+                        Some((p, r)) => (Some(p), Some(r)),
+                        None => (None, None),
+                    };
+                    let label_arity = match op {
+                        Operator::Loop { .. } => params,
+                        _ => results,
+                    };
+                    // Only the condition of an `if` can be folded into it, any
+                    // block parameters must be left as prior expressions.
+                    let pops = match op {
+                        Operator::If { .. } => 1,
+                        _ => 0,
+                    };
+                    let frame = frames.last_mut().unwrap();
+                    let operands = take_operands(&mut frame.exprs, pops);
+                    let block = Expr {
+                        op,
+                        offset,
+                        depth: depth + 1,
+                        label,
+                        results,
+                        operands,
+                        arms: Vec::new(),
+                        delegate: None,
+                    };
+                    label += 1;
+                    frames.push(Frame {
+                        block: Some(block),
+                        exprs: Vec::new(),
+                        label_arity,
+                        results,
+                    });
+                }
+
+                Operator::Else | Operator::Catch { .. } | Operator::CatchAll => {
+                    let frame = frames.last_mut().unwrap();
+                    let block = match &mut frame.block {
+                        Some(block) => block,
+                        None => return Ok(None),
+                    };
+                    let valid = match (&block.op, &op) {
+                        (Operator::If { .. }, Operator::Else) => block.arms.is_empty(),
+                        (Operator::Try { .. }, Operator::Catch { .. })
+                        | (Operator::Try { .. }, Operator::CatchAll) => match block.arms.last() {
+                            Some(arm) => !matches!(arm.op, Some(Operator::CatchAll)),
+                            None => true,
+                        },
+                        _ => false,
+                    };
+                    if !valid {
+                        return Ok(None);
+                    }
+                    let body = std::mem::take(&mut frame.exprs);
+                    if block.arms.is_empty() {
+                        block.arms.push(Arm { op: None, body });
+                    } else {
+                        block.arms.last_mut().unwrap().body = body;
+                    }
+                    block.arms.push(Arm {
+                        op: Some(op),
+                        body: Vec::new(),
+                    });
+                }
+
+                Operator::End | Operator::Delegate { .. } => {
+                    let mut frame = frames.pop().unwrap();
+                    let mut block = match frame.block.take() {
+                        Some(block) => block,
+                        None => {
+                            // This is the `end` of the function itself, which
+                            // must be the last instruction.
+                            if matches!(op, Operator::End) && reader.eof() {
+                                return Ok(Some(frame.exprs));
+                            }
+                            return Ok(None);
+                        }
+                    };
+                    if let Operator::Delegate { .. } = op {
+                        if !matches!(block.op, Operator::Try { .. }) || !block.arms.is_empty() {
+                            return Ok(None);
+                        }
+                        block.delegate = Some(op);
+                    }
+                    if block.arms.is_empty() {
+                        block.arms.push(Arm {
+                            op: None,
+                            body: frame.exprs,
+                        });
+                    } else {
+                        block.arms.last_mut().unwrap().body = frame.exprs;
+                    }
+                    frames.last_mut().unwrap().exprs.push(block);
+                }
+
+                op => {
+                    let (pops, results) = match self.operator_arity(&op, &frames) {
+                        Some((pops, pushes)) => (pops, Some(pushes)),
+                        None => (0, None),
+                    };
+                    let frame = frames.last_mut().unwrap();
+                    let operands = take_operands(&mut frame.exprs, pops);
+                    frame.exprs.push(Expr {
+                        op,
+                        offset,
+                        depth,
+                        label: 0,
+                        results,
+                        operands,
+                        arms: Vec::new(),
+                        delegate: None,
+                    });
+                }
+            }
+        }
+
+        // Running out of instructions before the function's final `end` means
+        // the body is malformed.
+        Ok(None)
+    }
+
+    /// Returns the number of parameters and results of a block type, if known.
+    fn blockty_arity(&self, ty: TypeOrFuncType) -> Option<(u32, u32)> {
+        match ty {
+            TypeOrFuncType::Type(Type::EmptyBlockType) => Some((0, 0)),
+            TypeOrFuncType::Type(_) => Some((0, 1)),
+            TypeOrFuncType::FuncType(idx) => self.functype_arity(idx),
+        }
+    }
+
+    fn functype_arity(&self, idx: u32) -> Option<(u32, u32)> {
+        match self.state.types.get(idx as usize) {
+            Some(Some(ty)) => Some((ty.params.len() as u32, ty.returns.len() as u32)),
+            _ => None,
+        }
+    }
+
+    fn func_arity(&self, func: u32) -> Option<(u32, u32)> {
+        match self.state.func_types.get(func as usize) {
+            Some(Some(ty)) => self.functype_arity(*ty),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of operands popped and values pushed by `op`, if
+    /// known.
+    ///
+    /// Block-like instructions are handled by the caller and are not expected
+    /// here.
+    fn operator_arity(&self, op: &Operator<'_>, frames: &[Frame<'_>]) -> Option<(u32, u32)> {
+        use Operator::*;
+        let label_arity = |depth: u32| {
+            let idx = frames.len().checked_sub(depth as usize + 1)?;
+            frames[idx].label_arity
+        };
+        Some(match op {
+            Block { .. }
+            | Loop { .. }
+            | If { .. }
+            | Else
+            | Try { .. }
+            | Catch { .. }
+            | CatchAll
+            | Delegate { .. }
+            | End => return None,
+
+            Unreachable | Nop | Rethrow { .. } => (0, 0),
+            Br { relative_depth } => (label_arity(*relative_depth)?, 0),
+            BrIf { relative_depth } => {
+                let n = label_arity(*relative_depth)?;
+                (n + 1, n)
+            }
+            BrTable { table } => (label_arity(table.default())? + 1, 0),
+            Return => (frames[0].results?, 0),
+            Call { function_index } => self.func_arity(*function_index)?,
+            CallIndirect { index, .. } => {
+                let (params, results) = self.functype_arity(*index)?;
+                (params + 1, results)
+            }
+            ReturnCall { function_index } => (self.func_arity(*function_index)?.0, 0),
+            ReturnCallIndirect { index, .. } => (self.functype_arity(*index)?.0 + 1, 0),
+            Throw { index } => match self.state.tag_types.get(*index as usize) {
+                Some(Some(ty)) => (self.functype_arity(*ty)?.0, 0),
+                _ => return None,
+            },
+
+            Drop => (1, 0),
+            Select | TypedSelect { .. } => (3, 1),
+
+            LocalGet { .. } | GlobalGet { .. } => (0, 1),
+            LocalSet { .. } | GlobalSet { .. } => (1, 0),
+            LocalTee { .. } => (1, 1),
+
+            I32Load { .. }
+            | I64Load { .. }
+            | F32Load { .. }
+            | F64Load { .. }
+            | I32Load8S { .. }
+            | I32Load8U { .. }
+            | I32Load16S { .. }
+            | I32Load16U { .. }
+            | I64Load8S { .. }
+            | I64Load8U { .. }
+            | I64Load16S { .. }
+            | I64Load16U { .. }
+            | I64Load32S { .. }
+            | I64Load32U { .. }
+            | I32AtomicLoad { .. }
+            | I64AtomicLoad { .. }
+            | I32AtomicLoad8U { .. }
+            | I32AtomicLoad16U { .. }
+            | I64AtomicLoad8U { .. }
+            | I64AtomicLoad16U { .. }
+            | I64AtomicLoad32U { .. }
+            | V128Load { .. }
+            | V128Load8x8S { .. }
+            | V128Load8x8U { .. }
+            | V128Load16x4S { .. }
+            | V128Load16x4U { .. }
+            | V128Load32x2S { .. }
+            | V128Load32x2U { .. }
+            | V128Load8Splat { .. }
+            | V128Load16Splat { .. }
+            | V128Load32Splat { .. }
+            | V128Load64Splat { .. }
+            | V128Load32Zero { .. }
+            | V128Load64Zero { .. } => (1, 1),
+
+            I32Store { .. }
+            | I64Store { .. }
+            | F32Store { .. }
+            | F64Store { .. }
+            | I32Store8 { .. }
+            | I32Store16 { .. }
+            | I64Store8 { .. }
+            | I64Store16 { .. }
+            | I64Store32 { .. }
+            | I32AtomicStore { .. }
+            | I64AtomicStore { .. }
+            | I32AtomicStore8 { .. }
+            | I32AtomicStore16 { .. }
+            | I64AtomicStore8 { .. }
+            | I64AtomicStore16 { .. }
+            | I64AtomicStore32 { .. }
+            | V128Store { .. }
+            | V128Store8Lane { .. }
+            | V128Store16Lane { .. }
+            | V128Store32Lane { .. }
+            | V128Store64Lane { .. } => (2, 0),
+
+            V128Load8Lane { .. }
+            | V128Load16Lane { .. }
+            | V128Load32Lane { .. }
+            | V128Load64Lane { .. } => (2, 1),
+
+            MemorySize { .. } | TableSize { .. } => (0, 1),
+            MemoryGrow { .. } | TableGet { .. } => (1, 1),
+            TableGrow { .. } => (2, 1),
+            TableSet { .. } => (2, 0),
+            MemoryInit { .. }
+            | MemoryCopy { .. }
+            | MemoryFill { .. }
+            | TableInit { .. }
+            | TableCopy { .. }
+            | TableFill { .. } => (3, 0),
+            DataDrop { .. } | ElemDrop { .. } | AtomicFence { .. } => (0, 0),
+
+            I32Const { .. }
+            | I64Const { .. }
+            | F32Const { .. }
+            | F64Const { .. }
+            | V128Const { .. }
+            | RefNull { .. }
+            | RefFunc { .. } => (0, 1),
+
+            RefIsNull
+            | I32Eqz
+            | I64Eqz
+            | I32Clz
+            | I32Ctz
+            | I32Popcnt
+            | I64Clz
+            | I64Ctz
+            | I64Popcnt
+            | F32Abs
+            | F32Neg
+            | F32Ceil
+            | F32Floor
+            | F32Trunc
+            | F32Nearest
+            | F32Sqrt
+            | F64Abs
+            | F64Neg
+            | F64Ceil
+            | F64Floor
+            | F64Trunc
+            | F64Nearest
+            | F64Sqrt
+            | I32WrapI64
+            | I32TruncF32S
+            | I32TruncF32U
+            | I32TruncF64S
+            | I32TruncF64U
+            | I64ExtendI32S
+            | I64ExtendI32U
+            | I64TruncF32S
+            | I64TruncF32U
+            | I64TruncF64S
+            | I64TruncF64U
+            | F32ConvertI32S
+            | F32ConvertI32U
+            | F32ConvertI64S
+            | F32ConvertI64U
+            | F32DemoteF64
+            | F64ConvertI32S
+            | F64ConvertI32U
+            | F64ConvertI64S
+            | F64ConvertI64U
+            | F64PromoteF32
+            | I32ReinterpretF32
+            | I64ReinterpretF64
+            | F32ReinterpretI32
+            | F64ReinterpretI64
+            | I32Extend8S
+            | I32Extend16S
+            | I64Extend8S
+            | I64Extend16S
+            | I64Extend32S
+            | I32TruncSatF32S
+            | I32TruncSatF32U
+            | I32TruncSatF64S
+            | I32TruncSatF64U
+            | I64TruncSatF32S
+            | I64TruncSatF32U
+            | I64TruncSatF64S
+            | I64TruncSatF64U
+            | I8x16Splat
+            | I16x8Splat
+            | I32x4Splat
+            | I64x2Splat
+            | F32x4Splat
+            | F64x2Splat
+            | I8x16ExtractLaneS { .. }
+            | I8x16ExtractLaneU { .. }
+            | I16x8ExtractLaneS { .. }
+            | I16x8ExtractLaneU { .. }
+            | I32x4ExtractLane { .. }
+            | I64x2ExtractLane { .. }
+            | F32x4ExtractLane { .. }
+            | F64x2ExtractLane { .. }
+            | V128Not
+            | V128AnyTrue
+            | I8x16Abs
+            | I8x16Neg
+            | I8x16AllTrue
+            | I8x16Bitmask
+            | I8x16Popcnt
+            | I16x8Abs
+            | I16x8Neg
+            | I16x8AllTrue
+            | I16x8Bitmask
+            | I32x4Abs
+            | I32x4Neg
+            | I32x4AllTrue
+            | I32x4Bitmask
+            | I64x2Abs
+            | I64x2Neg
+            | I64x2AllTrue
+            | I64x2Bitmask
+            | F32x4Ceil
+            | F32x4Floor
+            | F32x4Trunc
+            | F32x4Nearest
+            | F32x4Abs
+            | F32x4Neg
+            | F32x4Sqrt
+            | F64x2Ceil
+            | F64x2Floor
+            | F64x2Trunc
+            | F64x2Nearest
+            | F64x2Abs
+            | F64x2Neg
+            | F64x2Sqrt
+            | I32x4TruncSatF32x4S
+            | I32x4TruncSatF32x4U
+            | F32x4ConvertI32x4S
+            | F32x4ConvertI32x4U
+            | I16x8ExtendLowI8x16S
+            | I16x8ExtendHighI8x16S
+            | I16x8ExtendLowI8x16U
+            | I16x8ExtendHighI8x16U
+            | I32x4ExtendLowI16x8S
+            | I32x4ExtendHighI16x8S
+            | I32x4ExtendLowI16x8U
+            | I32x4ExtendHighI16x8U
+            | I64x2ExtendLowI32x4S
+            | I64x2ExtendHighI32x4S
+            | I64x2ExtendLowI32x4U
+            | I64x2ExtendHighI32x4U
+            | F32x4DemoteF64x2Zero
+            | F64x2PromoteLowF32x4
+            | F64x2ConvertLowI32x4S
+            | F64x2ConvertLowI32x4U
+            | I32x4TruncSatF64x2SZero
+            | I32x4TruncSatF64x2UZero
+            | I16x8ExtAddPairwiseI8x16S
+            | I16x8ExtAddPairwiseI8x16U
+            | I32x4ExtAddPairwiseI16x8S
+            | I32x4ExtAddPairwiseI16x8U
+            | I32x4RelaxedTruncSatF32x4S
+            | I32x4RelaxedTruncSatF32x4U
+            | I32x4RelaxedTruncSatF64x2SZero
+            | I32x4RelaxedTruncSatF64x2UZero => (1, 1),
+
+            I32Eq
+            | I32Ne
+            | I32LtS
+            | I32LtU
+            | I32GtS
+            | I32GtU
+            | I32LeS
+            | I32LeU
+            | I32GeS
+            | I32GeU
+            | I64Eq
+            | I64Ne
+            | I64LtS
+            | I64LtU
+            | I64GtS
+            | I64GtU
+            | I64LeS
+            | I64LeU
+            | I64GeS
+            | I64GeU
+            | F32Eq
+            | F32Ne
+            | F32Lt
+            | F32Gt
+            | F32Le
+            | F32Ge
+            | F64Eq
+            | F64Ne
+            | F64Lt
+            | F64Gt
+            | F64Le
+            | F64Ge
+            | I32Add
+            | I32Sub
+            | I32Mul
+            | I32DivS
+            | I32DivU
+            | I32RemS
+            | I32RemU
+            | I32And
+            | I32Or
+            | I32Xor
+            | I32Shl
+            | I32ShrS
+            | I32ShrU
+            | I32Rotl
+            | I32Rotr
+            | I64Add
+            | I64Sub
+            | I64Mul
+            | I64DivS
+            | I64DivU
+            | I64RemS
+            | I64RemU
+            | I64And
+            | I64Or
+            | I64Xor
+            | I64Shl
+            | I64ShrS
+            | I64ShrU
+            | I64Rotl
+            | I64Rotr
+            | F32Add
+            | F32Sub
+            | F32Mul
+            | F32Div
+            | F32Min
+            | F32Max
+            | F32Copysign
+            | F64Add
+            | F64Sub
+            | F64Mul
+            | F64Div
+            | F64Min
+            | F64Max
+            | F64Copysign
+            | MemoryAtomicNotify { .. }
+            | I32AtomicRmwAdd { .. }
+            | I64AtomicRmwAdd { .. }
+            | I32AtomicRmw8AddU { .. }
+            | I32AtomicRmw16AddU { .. }
+            | I64AtomicRmw8AddU { .. }
+            | I64AtomicRmw16AddU { .. }
+            | I64AtomicRmw32AddU { .. }
+            | I32AtomicRmwSub { .. }
+            | I64AtomicRmwSub { .. }
+            | I32AtomicRmw8SubU { .. }
+            | I32AtomicRmw16SubU { .. }
+            | I64AtomicRmw8SubU { .. }
+            | I64AtomicRmw16SubU { .. }
+            | I64AtomicRmw32SubU { .. }
+            | I32AtomicRmwAnd { .. }
+            | I64AtomicRmwAnd { .. }
+            | I32AtomicRmw8AndU { .. }
+            | I32AtomicRmw16AndU { .. }
+            | I64AtomicRmw8AndU { .. }
+            | I64AtomicRmw16AndU { .. }
+            | I64AtomicRmw32AndU { .. }
+            | I32AtomicRmwOr { .. }
+            | I64AtomicRmwOr { .. }
+            | I32AtomicRmw8OrU { .. }
+            | I32AtomicRmw16OrU { .. }
+            | I64AtomicRmw8OrU { .. }
+            | I64AtomicRmw16OrU { .. }
+            | I64AtomicRmw32OrU { .. }
+            | I32AtomicRmwXor { .. }
+            | I64AtomicRmwXor { .. }
+            | I32AtomicRmw8XorU { .. }
+            | I32AtomicRmw16XorU { .. }
+            | I64AtomicRmw8XorU { .. }
+            | I64AtomicRmw16XorU { .. }
+            | I64AtomicRmw32XorU { .. }
+            | I32AtomicRmwXchg { .. }
+            | I64AtomicRmwXchg { .. }
+            | I32AtomicRmw8XchgU { .. }
+            | I32AtomicRmw16XchgU { .. }
+            | I64AtomicRmw8XchgU { .. }
+            | I64AtomicRmw16XchgU { .. }
+            | I64AtomicRmw32XchgU { .. }
+            | I8x16ReplaceLane { .. }
+            | I16x8ReplaceLane { .. }
+            | I32x4ReplaceLane { .. }
+            | I64x2ReplaceLane { .. }
+            | F32x4ReplaceLane { .. }
+            | F64x2ReplaceLane { .. }
+            | I8x16Eq
+            | I8x16Ne
+            | I8x16LtS
+            | I8x16LtU
+            | I8x16GtS
+            | I8x16GtU
+            | I8x16LeS
+            | I8x16LeU
+            | I8x16GeS
+            | I8x16GeU
+            | I16x8Eq
+            | I16x8Ne
+            | I16x8LtS
+            | I16x8LtU
+            | I16x8GtS
+            | I16x8GtU
+            | I16x8LeS
+            | I16x8LeU
+            | I16x8GeS
+            | I16x8GeU
+            | I32x4Eq
+            | I32x4Ne
+            | I32x4LtS
+            | I32x4LtU
+            | I32x4GtS
+            | I32x4GtU
+            | I32x4LeS
+            | I32x4LeU
+            | I32x4GeS
+            | I32x4GeU
+            | I64x2Eq
+            | I64x2Ne
+            | I64x2LtS
+            | I64x2GtS
+            | I64x2LeS
+            | I64x2GeS
+            | F32x4Eq
+            | F32x4Ne
+            | F32x4Lt
+            | F32x4Gt
+            | F32x4Le
+            | F32x4Ge
+            | F64x2Eq
+            | F64x2Ne
+            | F64x2Lt
+            | F64x2Gt
+            | F64x2Le
+            | F64x2Ge
+            | V128And
+            | V128AndNot
+            | V128Or
+            | V128Xor
+            | I8x16Shl
+            | I8x16ShrU
+            | I8x16ShrS
+            | I8x16Add
+            | I8x16AddSatS
+            | I8x16AddSatU
+            | I8x16Sub
+            | I8x16SubSatS
+            | I8x16SubSatU
+            | I16x8Shl
+            | I16x8ShrU
+            | I16x8ShrS
+            | I16x8Add
+            | I16x8AddSatS
+            | I16x8AddSatU
+            | I16x8Sub
+            | I16x8SubSatS
+            | I16x8SubSatU
+            | I16x8Mul
+            | I32x4Shl
+            | I32x4ShrU
+            | I32x4ShrS
+            | I32x4Add
+            | I32x4Sub
+            | I32x4Mul
+            | I64x2Shl
+            | I64x2ShrU
+            | I64x2ShrS
+            | I64x2Add
+            | I64x2Sub
+            | I64x2Mul
+            | F32x4Add
+            | F32x4Sub
+            | F32x4Div
+            | F32x4Mul
+            | F32x4Min
+            | F32x4Max
+            | F32x4PMin
+            | F32x4PMax
+            | F64x2Add
+            | F64x2Sub
+            | F64x2Div
+            | F64x2Mul
+            | F64x2Min
+            | F64x2Max
+            | F64x2PMin
+            | F64x2PMax
+            | I8x16Swizzle
+            | I8x16Shuffle { .. }
+            | I8x16NarrowI16x8S
+            | I8x16NarrowI16x8U
+            | I16x8NarrowI32x4S
+            | I16x8NarrowI32x4U
+            | I16x8ExtMulLowI8x16S
+            | I16x8ExtMulHighI8x16S
+            | I16x8ExtMulLowI8x16U
+            | I16x8ExtMulHighI8x16U
+            | I32x4ExtMulLowI16x8S
+            | I32x4ExtMulHighI16x8S
+            | I32x4ExtMulLowI16x8U
+            | I32x4ExtMulHighI16x8U
+            | I64x2ExtMulLowI32x4S
+            | I64x2ExtMulHighI32x4S
+            | I64x2ExtMulLowI32x4U
+            | I64x2ExtMulHighI32x4U
+            | I16x8Q15MulrSatS
+            | I8x16RoundingAverageU
+            | I16x8RoundingAverageU
+            | I8x16MinS
+            | I8x16MinU
+            | I8x16MaxS
+            | I8x16MaxU
+            | I16x8MinS
+            | I16x8MinU
+            | I16x8MaxS
+            | I16x8MaxU
+            | I32x4MinS
+            | I32x4MinU
+            | I32x4MaxS
+            | I32x4MaxU
+            | I32x4DotI16x8S
+            | I8x16RelaxedSwizzle
+            | F32x4RelaxedMin
+            | F32x4RelaxedMax
+            | F64x2RelaxedMin
+            | F64x2RelaxedMax => (2, 1),
+
+            MemoryAtomicWait32 { .. }
+            | MemoryAtomicWait64 { .. }
+            | I32AtomicRmwCmpxchg { .. }
+            | I64AtomicRmwCmpxchg { .. }
+            | I32AtomicRmw8CmpxchgU { .. }
+            | I32AtomicRmw16CmpxchgU { .. }
+            | I64AtomicRmw8CmpxchgU { .. }
+            | I64AtomicRmw16CmpxchgU { .. }
+            | I64AtomicRmw32CmpxchgU { .. }
+            | V128Bitselect
+            | F32x4Fma
+            | F32x4Fms
+            | F64x2Fma
+            | F64x2Fms
+            | I8x16LaneSelect
+            | I16x8LaneSelect
+            | I32x4LaneSelect
+            | I64x2LaneSelect => (3, 1),
+        })
+    }
+
+    fn print_folded_seq(&mut self, exprs: &[Expr<'_>]) -> Result<()> {
+        for expr in exprs {
+            self.newline();
+            self.print_folded_expr(expr)?;
+        }
+        Ok(())
+    }
+
+    fn print_folded_expr(&mut self, expr: &Expr<'_>) -> Result<()> {
+        if expr.arms.is_empty() {
+            return self.print_folded_plain(expr);
+        }
+
+        // An empty `(else)` arm is elided by the text format parser so to
+        // preserve the original bytes such an `if` is printed flat instead.
+        let empty_else = expr
+            .arms
+            .iter()
+            .any(|arm| matches!(arm.op, Some(Operator::Else)) && arm.body.is_empty());
+        if empty_else {
+            for operand in expr.operands.iter() {
+                self.print_folded_expr(operand)?;
+                self.newline();
+            }
+            self.state.label = expr.label;
+            self.print_branch_hint(expr.offset)?;
+            self.print_folded_operator(expr)?;
+            for arm in expr.arms.iter() {
+                if let Some(op) = &arm.op {
+                    self.newline();
+                    self.print_operator(op, expr.depth)?;
+                }
+                self.nesting += 1;
+                self.print_folded_seq(&arm.body)?;
+                self.nesting -= 1;
+            }
+            self.newline();
+            self.result.push_str("end");
+            return Ok(());
+        }
+
+        self.print_branch_hint(expr.offset)?;
+        self.start_group("");
+        self.state.label = expr.label;
+        self.print_folded_operator(expr)?;
+        self.print_folded_seq(&expr.operands)?;
+        match expr.op {
+            Operator::If { .. } | Operator::Try { .. } => {
+                for arm in expr.arms.iter() {
+                    self.newline();
+                    match &arm.op {
+                        None if matches!(expr.op, Operator::If { .. }) => self.start_group("then"),
+                        None => self.start_group("do"),
+                        Some(Operator::Else) => self.start_group("else"),
+                        Some(op) => {
+                            self.start_group("");
+                            self.print_operator(op, expr.depth)?;
+                        }
+                    }
+                    self.print_folded_seq(&arm.body)?;
+                    self.end_group();
+                }
+                if let Some(delegate) = &expr.delegate {
+                    self.newline();
+                    self.start_group("");
+                    self.print_operator(delegate, expr.depth)?;
+                    self.end_group();
+                }
+            }
+            _ => {
+                let body = &expr.arms[0].body;
+                self.print_folded_seq(body)?;
+                // The block's header ends in a line comment, so make sure the
+                // closing paren isn't swallowed by it.
+                if body.is_empty() {
+                    self.nesting -= 1;
+                    self.newline();
+                    self.nesting += 1;
+                }
+            }
+        }
+        self.end_group();
+        Ok(())
+    }
+
+    fn print_folded_operator(&mut self, expr: &Expr<'_>) -> Result<()> {
+        self.print_operator(&expr.op, expr.depth)?;
+        if self.options.print_offsets {
+            write!(self.result, " (;@{:x};)", expr.offset)?;
+        }
+        Ok(())
+    }
+
+    fn print_folded_plain(&mut self, expr: &Expr<'_>) -> Result<()> {
+        self.print_branch_hint(expr.offset)?;
+        self.start_group("");
+        self.print_folded_operator(expr)?;
+        // Operands which are themselves small expressions are kept on the same
+        // line, but anything containing blocks gets a line of its own.
+        if expr.operands.iter().all(is_inline) {
+            for operand in expr.operands.iter() {
+                self.result.push_str(" ");
+                self.print_folded_expr(operand)?;
+            }
+        } else {
+            self.print_folded_seq(&expr.operands)?;
+        }
+        self.end_group();
+        Ok(())
+    }
+}
+
+fn is_inline(expr: &Expr<'_>) -> bool {
+    expr.arms.is_empty() && expr.operands.iter().all(is_inline)
+}
+
+/// Removes the trailing `n` expressions from `exprs` if they each produce a
+/// single value, which means they can be used as the operands of an
+/// instruction consuming `n` values.
+fn take_operands<'a>(exprs: &mut Vec<Expr<'a>>, n: u32) -> Vec<Expr<'a>> {
+    let n = n as usize;
+    if n == 0 || exprs.len() < n {
+        return Vec::new();
+    }
+    let start = exprs.len() - n;
+    if exprs[start..].iter().all(|e| e.results == Some(1)) {
+        exprs.split_off(start)
+    } else {
+        Vec::new()
+    }
+}
@@ -11,10 +11,13 @@
 use anyhow::{bail, Context, Result};
 use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Write};
+use std::io;
 use std::mem;
 use std::path::Path;
 use wasmparser::*;
 
+mod fold;
+
 const MAX_LOCALS: u32 = 50000;
 
 /// Reads a WebAssembly `file` from the filesystem and then prints it into an
@@ -44,6 +47,74 @@ pub struct Printer {
     result: String,
     state: ModuleState,
     nesting: u32,
+    options: PrintOptions,
+}
+
+/// Options which configure how a [`Printer`] prints a WebAssembly binary.
+///
+/// The default options print a module in a form which can be parsed back into
+/// the exact same binary, using names from the `name` section where present.
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    /// Whether to print a comment with the binary offset of each instruction
+    /// in function bodies (disabled by default).
+    ///
+    /// Offsets are printed in hexadecimal as trailing `;; @1a` comments, or as
+    /// `(;@1a;)` block comments when instructions are folded.
+    pub print_offsets: bool,
+    /// Whether to use names from the `name` custom section to refer to items
+    /// (enabled by default). When disabled all items are referred to by their
+    /// raw index.
+    pub name_section: bool,
+    /// The number of spaces to indent each level of nesting with (2 by
+    /// default).
+    pub indent: usize,
+    /// Whether exports of functions, tables, memories, globals, and tags are
+    /// printed inline in the definition of the item they export, as in
+    /// `(func (export "foo") ...)` (disabled by default). Exports of imported
+    /// items are only printed inline when `inline_imports` is also enabled.
+    ///
+    /// Note that with this option exports are encoded in the order of the
+    /// items they export when the output is parsed again, which may be
+    /// different from their original order.
+    pub inline_exports: bool,
+    /// Whether imports are printed inline in the definition of the item they
+    /// import, as in `(func (import "m" "f") ...)` (disabled by default).
+    pub inline_imports: bool,
+    /// Whether to print the contents of data segments as a hex dump with 16
+    /// bytes per line rather than as a single escaped string (disabled by
+    /// default).
+    pub data_hex_dump: bool,
+    /// Whether function bodies are skipped entirely, printing only the
+    /// signature of each function (disabled by default).
+    ///
+    /// This is useful to get an overview of the structure of very large
+    /// modules, but the output is not equivalent to the original binary.
+    pub skeleton: bool,
+    /// Whether function bodies are printed as folded S-expressions (disabled
+    /// by default).
+    ///
+    /// When enabled the operand tree of instructions is reconstructed from
+    /// their stack effects, so instead of a flat sequence of instructions
+    /// something like `(i32.add (local.get 0) (i32.const 1))` is printed.
+    /// Instructions whose operands can't be determined, and function bodies
+    /// which aren't well-nested, are printed in their flat form instead.
+    pub fold_instructions: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> PrintOptions {
+        PrintOptions {
+            print_offsets: false,
+            name_section: true,
+            indent: 2,
+            inline_exports: false,
+            inline_imports: false,
+            data_hex_dump: false,
+            skeleton: false,
+            fold_instructions: false,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -57,6 +128,8 @@ struct ModuleState {
     table: u32,
     label: u32,
     types: Vec<Option<FuncType>>,
+    func_types: Vec<Option<u32>>,
+    tag_types: Vec<Option<u32>>,
     function_names: HashMap<u32, Naming>,
     local_names: HashMap<(u32, u32), Naming>,
     label_names: HashMap<(u32, u32), Naming>,
@@ -68,6 +141,16 @@ struct ModuleState {
     data_names: HashMap<u32, Naming>,
     module_name: Option<Naming>,
     implicit_instances_seen: HashSet<String>,
+    inline_exports: HashMap<(u8, u32), Vec<String>>,
+    inline_import: Option<(String, String)>,
+    /// Whether an `(import ...)` clause is being printed, in which case
+    /// exports can't be printed inline and stay in the export section.
+    in_import: bool,
+    /// Payloads of the `metadata.code.branch_hint` section, keyed by
+    /// function index and offset relative to the function's body.
+    branch_hints: HashMap<(u32, usize), Vec<u8>>,
+    /// The offset in the binary of the body of the function being printed.
+    body_start: usize,
 }
 
 struct Naming {
@@ -105,7 +188,16 @@ impl Printer {
         self.printers.insert(section.to_string(), Box::new(printer));
     }
 
+    /// Configures the [`PrintOptions`] used by this `Printer`.
+    pub fn options(&mut self, options: PrintOptions) -> &mut Printer {
+        self.options = options;
+        self
+    }
+
     /// Gets the output result of this `Printer`, or where all output is going.
+    ///
+    /// Note that when printing with [`Printer::print_to`] this only contains
+    /// the output which hasn't been written to the destination yet.
     pub fn result_mut(&mut self) -> &mut String {
         &mut self.result
     }
@@ -116,16 +208,46 @@ impl Printer {
     /// the WebAssembly Text Format and return the result as a `String`.
     pub fn print(&mut self, mut wasm: &[u8]) -> Result<String> {
         self.start_group("module");
-        self.print_contents(Parser::new(0), &mut wasm, "")?;
+        self.print_contents(Parser::new(0), &mut wasm, "", None)?;
         self.end_group();
         Ok(mem::take(&mut self.result))
     }
 
+    /// Prints a WebAssembly binary into the `dst` writer.
+    ///
+    /// This is the same as [`Printer::print`] except that output is written
+    /// to `dst` incrementally as the module is printed, for example after
+    /// each function, instead of being buffered in memory in its entirety.
+    /// This makes printing large modules much cheaper in terms of memory and
+    /// means output is available before the whole module is printed.
+    ///
+    /// If an error happens then some output may have already been written to
+    /// `dst`.
+    pub fn print_to(&mut self, mut wasm: &[u8], mut dst: impl io::Write) -> Result<()> {
+        self.start_group("module");
+        self.print_contents(Parser::new(0), &mut wasm, "", Some(&mut dst))?;
+        self.end_group();
+        self.flush(Some(&mut dst))?;
+        dst.flush().context("failed to flush output")?;
+        Ok(())
+    }
+
+    /// Writes everything printed so far into `dst`, if present.
+    fn flush(&mut self, dst: Option<&mut (dyn io::Write + '_)>) -> Result<()> {
+        if let Some(dst) = dst {
+            dst.write_all(self.result.as_bytes())
+                .context("failed to write output")?;
+            self.result.clear();
+        }
+        Ok(())
+    }
+
     fn print_contents(
         &mut self,
         mut parser: Parser,
         wasm: &mut &[u8],
         module_ty: &str,
+        mut dst: Option<&mut (dyn io::Write + '_)>,
     ) -> Result<()> {
         // First up try to find the `name` subsection which we'll use to print
         // pretty names everywhere. Also look for the `code` section so we can
@@ -156,11 +278,24 @@ impl Printer {
                     data_offset,
                     data,
                     range: _,
-                } => {
+                } if self.options.name_section => {
                     let reader = NameSectionReader::new(data, data_offset)?;
                     // Ignore any error associated with the name section.
                     drop(self.register_names(reader));
                 }
+                Payload::CustomSection {
+                    name,
+                    data_offset,
+                    data,
+                    range: _,
+                } if name == "metadata.code.branch_hint" => {
+                    // Like the name section, malformed branch hints are
+                    // ignored rather than failing to print the module.
+                    drop(self.register_branch_hints(data, data_offset));
+                }
+                Payload::ExportSection(s) if self.options.inline_exports => {
+                    self.register_inline_exports(s)?;
+                }
                 Payload::End => break,
                 _ => {}
             }
@@ -173,6 +308,7 @@ impl Printer {
         }
         self.result.push_str(module_ty);
         let mut code_printed = false;
+        let mut code_seen = false;
         loop {
             let payload = match parser.parse(*wasm, true)? {
                 Chunk::NeedMoreData(_) => unreachable!(),
@@ -191,6 +327,22 @@ impl Printer {
                     let mut printers = mem::replace(&mut self.printers, HashMap::new());
                     if let Some(printer) = printers.get_mut(name) {
                         printer(self, data_offset, data)?;
+                    } else if name.starts_with("metadata.code.")
+                        && name != "metadata.code.branch_hint"
+                    {
+                        // Only branch hints are printed as annotations on
+                        // instructions, so other code metadata is kept as a
+                        // raw custom section.
+                        self.newline();
+                        self.start_group("@custom ");
+                        self.print_str(name)?;
+                        self.result.push_str(if code_seen {
+                            " (after code) "
+                        } else {
+                            " (before code) "
+                        });
+                        self.print_bytes(data)?;
+                        self.end_group();
                     }
                     self.printers = printers;
                 }
@@ -203,7 +355,7 @@ impl Printer {
                     if reader.get_count() == 0 {
                         continue;
                     }
-                    self.print_code(&code, reader)?;
+                    self.print_code(&code, reader, dst.as_deref_mut())?;
                 }
                 Payload::TableSection(s) => self.print_tables(s)?,
                 Payload::MemorySection(s) => self.print_memories(s)?,
@@ -221,12 +373,13 @@ impl Printer {
                 // printed with the `Function` or `Module` section, so we
                 // skip this section
                 Payload::CodeSectionStart { size, .. } => {
+                    code_seen = true;
                     *wasm = &wasm[size as usize..];
                     parser.skip_section();
                 }
                 Payload::CodeSectionEntry(_) => unreachable!(),
 
-                Payload::DataSection(s) => self.print_data(s)?,
+                Payload::DataSection(s) => self.print_data(s, dst.as_deref_mut())?,
                 Payload::AliasSection(s) => self.print_aliases(s)?,
                 Payload::InstanceSection(s) => self.print_instances(s)?,
 
@@ -234,7 +387,8 @@ impl Printer {
                 Payload::ModuleSectionEntry { parser, .. } => {
                     self.newline();
                     self.start_group("module");
-                    self.print_contents(parser, wasm, &format!(" (;{};)", self.state.module))?;
+                    let module_ty = format!(" (;{};)", self.state.module);
+                    self.print_contents(parser, wasm, &module_ty, dst.as_deref_mut())?;
                     self.end_group();
                     self.state.module += 1;
                 }
@@ -246,6 +400,7 @@ impl Printer {
 
                 Payload::UnknownSection { id, .. } => bail!("found unknown section `{}`", id),
             }
+            self.flush(dst.as_deref_mut())?;
         }
         self.state = prev;
         Ok(())
@@ -262,6 +417,34 @@ impl Printer {
         self.nesting -= 1;
     }
 
+    fn register_branch_hints(&mut self, data: &[u8], offset: usize) -> Result<()> {
+        for func in CodeMetadataSectionReader::new(data, offset)? {
+            let func = func?;
+            for hint in func.get_code_metadata_reader() {
+                let hint = hint?;
+                self.state
+                    .branch_hints
+                    .insert((func.func, hint.offset as usize), hint.data.to_vec());
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the branch hint, if any, of the instruction at `offset` in the
+    /// current function as an annotation, such as
+    /// `(@metadata.code.branch_hint "\01")`, followed by a space.
+    fn print_branch_hint(&mut self, offset: usize) -> Result<()> {
+        let key = (self.state.func, offset - self.state.body_start);
+        let data = match self.state.branch_hints.get(&key) {
+            Some(data) => data.clone(),
+            None => return Ok(()),
+        };
+        self.result.push_str("(@metadata.code.branch_hint ");
+        self.print_bytes(&data)?;
+        self.result.push_str(") ");
+        Ok(())
+    }
+
     fn register_names(&mut self, names: NameSectionReader<'_>) -> Result<()> {
         fn name_map(into: &mut HashMap<u32, Naming>, names: NameMap<'_>, name: &str) -> Result<()> {
             let mut used = HashSet::new();
@@ -318,6 +501,20 @@ impl Printer {
         Ok(())
     }
 
+    fn register_inline_exports(&mut self, exports: ExportSectionReader<'_>) -> Result<()> {
+        for export in exports {
+            let export = export?;
+            if inline_export_kind(export.kind) {
+                self.state
+                    .inline_exports
+                    .entry((export.kind as u8, export.index))
+                    .or_insert_with(Vec::new)
+                    .push(export.field.to_string());
+            }
+        }
+        Ok(())
+    }
+
     fn print_types(&mut self, parser: TypeSectionReader<'_>) -> Result<()> {
         for ty in parser {
             self.newline();
@@ -462,12 +659,18 @@ impl Printer {
             }
             self.print_import(&import, true)?;
             match import.ty {
-                ImportSectionEntryType::Function(_) => self.state.func += 1,
+                ImportSectionEntryType::Function(ty) => {
+                    self.state.func += 1;
+                    self.state.func_types.push(Some(ty));
+                }
                 ImportSectionEntryType::Module(_) => self.state.module += 1,
                 ImportSectionEntryType::Instance(_) => self.state.instance += 1,
                 ImportSectionEntryType::Table(_) => self.state.table += 1,
                 ImportSectionEntryType::Memory(_) => self.state.memory += 1,
-                ImportSectionEntryType::Tag(_) => self.state.tag += 1,
+                ImportSectionEntryType::Tag(ty) => {
+                    self.state.tag += 1;
+                    self.state.tag_types.push(Some(ty.type_index));
+                }
                 ImportSectionEntryType::Global(_) => self.state.global += 1,
             }
         }
@@ -476,6 +679,12 @@ impl Printer {
 
     fn print_import(&mut self, import: &Import<'_>, index: bool) -> Result<()> {
         self.newline();
+        if let Some(field) = import.field {
+            if index && self.options.inline_imports && inline_import_ty(&import.ty) {
+                self.state.inline_import = Some((import.module.to_string(), field.to_string()));
+                return self.print_import_ty(&import.ty, index);
+            }
+        }
         self.start_group("import ");
         self.print_str(import.module)?;
         if let Some(field) = import.field {
@@ -483,7 +692,10 @@ impl Printer {
             self.print_str(field)?;
         }
         self.result.push_str(" ");
-        self.print_import_ty(&import.ty, index)?;
+        self.state.in_import = true;
+        let result = self.print_import_ty(&import.ty, index);
+        self.state.in_import = false;
+        result?;
         self.end_group();
         Ok(())
     }
@@ -496,6 +708,7 @@ impl Printer {
                 if index {
                     self.result.push_str(" ");
                     self.print_cur_func_name()?;
+                    self.print_inline_decls(ExternalKind::Function, self.state.func)?;
                 }
                 self.print_type_ref(*f)?;
             }
@@ -526,6 +739,7 @@ impl Printer {
         self.start_group("table ");
         if index {
             self.print_cur_table_name()?;
+            self.print_inline_decls(ExternalKind::Table, self.state.table)?;
             self.result.push_str(" ");
         }
         self.print_limits(ty.initial, ty.maximum)?;
@@ -538,6 +752,7 @@ impl Printer {
         self.start_group("memory ");
         if index {
             self.print_cur_memory_name()?;
+            self.print_inline_decls(ExternalKind::Memory, self.state.memory)?;
             self.result.push_str(" ");
         }
         if ty.memory64 {
@@ -554,11 +769,35 @@ impl Printer {
         self.start_group("tag ");
         if index {
             write!(self.result, "(;{};)", self.state.tag)?;
+            self.print_inline_decls(ExternalKind::Tag, self.state.tag)?;
         }
         self.print_functype_idx(ty.type_index, true, None)?;
         Ok(())
     }
 
+    /// Prints the inline `(export ...)` and `(import ...)` clauses, if any,
+    /// for the item of `kind` at `index` which is currently being defined.
+    fn print_inline_decls(&mut self, kind: ExternalKind, index: u32) -> Result<()> {
+        if self.state.in_import {
+            return Ok(());
+        }
+        if let Some(names) = self.state.inline_exports.remove(&(kind as u8, index)) {
+            for name in names {
+                self.result.push_str(" (export ");
+                self.print_str(&name)?;
+                self.result.push_str(")");
+            }
+        }
+        if let Some((module, field)) = self.state.inline_import.take() {
+            self.result.push_str(" (import ");
+            self.print_str(&module)?;
+            self.result.push_str(" ");
+            self.print_str(&field)?;
+            self.result.push_str(")");
+        }
+        Ok(())
+    }
+
     fn print_limits<T>(&mut self, initial: T, maximum: Option<T>) -> Result<()>
     where
         T: fmt::Display,
@@ -574,6 +813,7 @@ impl Printer {
         self.start_group("global ");
         if index {
             self.print_cur_global_name()?;
+            self.print_inline_decls(ExternalKind::Global, self.state.global)?;
             self.result.push_str(" ");
         }
         if ty.mutable {
@@ -615,6 +855,7 @@ impl Printer {
             self.print_tag_type(&tag, true)?;
             self.end_group();
             self.state.tag += 1;
+            self.state.tag_types.push(Some(tag.type_index));
         }
         Ok(())
     }
@@ -636,19 +877,33 @@ impl Printer {
         &mut self,
         code: &[FunctionBody<'_>],
         mut funcs: FunctionSectionReader<'_>,
+        mut dst: Option<&mut (dyn io::Write + '_)>,
     ) -> Result<()> {
         if funcs.get_count() != code.len() as u32 {
             bail!("mismatch in function and code section counts");
         }
+        // Record the types of all functions up front since they're needed to
+        // fold calls to functions defined later on.
+        for ty in funcs.clone() {
+            self.state.func_types.push(Some(ty?));
+        }
         for body in code {
             let ty = funcs.read()?;
             self.newline();
             self.start_group("func ");
             self.print_cur_func_name()?;
+            self.print_inline_decls(ExternalKind::Function, self.state.func)?;
             let params = self
                 .print_functype_idx(ty, true, Some(self.state.func))?
                 .unwrap_or(0);
 
+            if self.options.skeleton {
+                self.end_group();
+                self.state.func += 1;
+                self.flush(dst.as_deref_mut())?;
+                continue;
+            }
+
             let mut first = true;
             let mut local_idx = 0;
             let mut locals = NamedLocalPrinter::new("local");
@@ -678,11 +933,20 @@ impl Printer {
             }
             locals.finish(&mut self.result);
 
+            self.state.body_start = body.range().start;
+            if self.options.fold_instructions && self.print_folded_body(body, ty)? {
+                self.end_group();
+                self.state.func += 1;
+                self.flush(dst.as_deref_mut())?;
+                continue;
+            }
+
             self.state.label = 0;
             let nesting_start = self.nesting;
             let mut reader = body.get_operators_reader()?;
             reader.allow_memarg64(true);
             while !reader.eof() {
+                let offset = reader.original_position();
                 let operator = reader.read()?;
                 match operator {
                     // The final `end` in a reader is not printed, it's implied
@@ -720,35 +984,40 @@ impl Printer {
                     // out in front.
                     _ => self.newline(),
                 }
-                self.print_operator(&operator, nesting_start)?;
+                self.print_branch_hint(offset)?;
+                self.print_operator(&operator, self.nesting - nesting_start)?;
+                if self.options.print_offsets {
+                    write!(self.result, "  ;; @{:x}", offset)?;
+                }
             }
 
             // If this was an invalid function body then the nesting may not
             // have reset back to normal. Fix that up here and forcibly insert
             // a newline as well in case the last instruction was something
             // like an `if` which has a comment after it which could interfere
-            // with the closing paren printed for the func.
-            if self.nesting != nesting_start {
+            // with the closing paren printed for the func. The same goes for
+            // the offset comments printed after each instruction.
+            if self.nesting != nesting_start || self.options.print_offsets {
                 self.nesting = nesting_start;
                 self.newline();
             }
             self.end_group();
 
             self.state.func += 1;
+            self.flush(dst.as_deref_mut())?;
         }
         Ok(())
     }
 
     fn newline(&mut self) {
         self.result.push_str("\n");
-        for _ in 0..self.nesting {
-            self.result.push_str("  ");
+        for _ in 0..self.nesting as usize * self.options.indent {
+            self.result.push_str(" ");
         }
     }
 
-    fn print_operator(&mut self, op: &Operator<'_>, nesting_start: u32) -> Result<()> {
+    fn print_operator(&mut self, op: &Operator<'_>, cur_depth: u32) -> Result<()> {
         use Operator::*;
-        let cur_depth = self.nesting - nesting_start;
         let label = |relative: u32| match cur_depth.checked_sub(relative) {
             Some(i) => format!("@{}", i),
             None => format!(" INVALID "),
@@ -1627,6 +1896,17 @@ impl Printer {
     fn print_exports(&mut self, data: ExportSectionReader) -> Result<()> {
         for export in data {
             let export = export?;
+            // Exports printed inline with the item they export have already
+            // been removed from the set of inline exports.
+            if self.options.inline_exports
+                && inline_export_kind(export.kind)
+                && !self
+                    .state
+                    .inline_exports
+                    .contains_key(&(export.kind as u8, export.index))
+            {
+                continue;
+            }
             self.newline();
             self.start_group("export ");
             self.print_str(export.field)?;
@@ -1841,13 +2121,16 @@ impl Printer {
         Ok(())
     }
 
-    fn print_data(&mut self, data: DataSectionReader) -> Result<()> {
+    fn print_data(
+        &mut self,
+        data: DataSectionReader,
+        mut dst: Option<&mut (dyn io::Write + '_)>,
+    ) -> Result<()> {
         for (i, data) in data.into_iter().enumerate() {
             let data = data?;
             self.newline();
             self.start_group("data ");
             self.print_data_name(i as u32)?;
-            self.result.push_str(" ");
             match &data.kind {
                 DataKind::Passive => {}
                 DataKind::Active {
@@ -1855,16 +2138,22 @@ impl Printer {
                     init_expr,
                 } => {
                     if *memory_index != 0 {
-                        self.result.push_str("(memory ");
+                        self.result.push_str(" (memory ");
                         self.print_memory_idx(*memory_index)?;
-                        self.result.push_str(") ");
+                        self.result.push_str(")");
                     }
-                    self.print_init_expr_sugar(&init_expr, "offset")?;
                     self.result.push_str(" ");
+                    self.print_init_expr_sugar(&init_expr, "offset")?;
                 }
             }
-            self.print_bytes(data.data)?;
+            if self.options.data_hex_dump {
+                self.print_hex_dump(data.data)?;
+            } else {
+                self.result.push_str(" ");
+                self.print_bytes(data.data)?;
+            }
             self.end_group();
+            self.flush(dst.as_deref_mut())?;
         }
         Ok(())
     }
@@ -1923,6 +2212,7 @@ impl Printer {
                         ExternalKind::Function => {
                             self.print_cur_func_name()?;
                             self.state.func += 1;
+                            self.state.func_types.push(None);
                         }
                         ExternalKind::Table => {
                             self.print_cur_table_name()?;
@@ -1935,6 +2225,7 @@ impl Printer {
                         ExternalKind::Tag => {
                             write!(self.result, "(;{};)", self.state.tag)?;
                             self.state.tag += 1;
+                            self.state.tag_types.push(None);
                         }
                         ExternalKind::Global => {
                             self.print_cur_global_name()?;
@@ -2001,10 +2292,10 @@ impl Printer {
                     if i == 1 {
                         self.result.push_str(explicit);
                         self.result.push_str(" ");
-                        self.print_operator(&first_op.take().unwrap(), self.nesting)?;
+                        self.print_operator(&first_op.take().unwrap(), 0)?;
                     }
                     self.result.push_str(" ");
-                    self.print_operator(&other, self.nesting)?;
+                    self.print_operator(&other, 0)?;
                 }
             }
         }
@@ -2013,7 +2304,7 @@ impl Printer {
         // an expression with `explicit` as the leading token, instead we can
         // print the single operator.
         if let Some(op) = first_op {
-            self.print_operator(&op, self.nesting)?;
+            self.print_operator(&op, 0)?;
         }
         self.end_group();
         Ok(())
@@ -2028,7 +2319,7 @@ impl Printer {
                     if i > 0 {
                         self.result.push_str(" ");
                     }
-                    self.print_operator(&other, self.nesting)?
+                    self.print_operator(&other, 0)?
                 }
             }
         }
@@ -2065,6 +2356,20 @@ impl Printer {
         return Ok(());
     }
 
+    /// Prints `bytes` as a sequence of strings each on their own line, with
+    /// every byte escaped and prefixed by a comment with its offset.
+    fn print_hex_dump(&mut self, bytes: &[u8]) -> Result<()> {
+        for (i, chunk) in bytes.chunks(16).enumerate() {
+            self.newline();
+            write!(self.result, "(;{:#06x};) \"", i * 16)?;
+            for byte in chunk {
+                self.hex_byte(*byte);
+            }
+            self.result.push_str("\"");
+        }
+        Ok(())
+    }
+
     fn hex_byte(&mut self, byte: u8) {
         fn to_hex(b: u8) -> char {
             if b < 10 {
@@ -2079,6 +2384,32 @@ impl Printer {
     }
 }
 
+/// Returns whether exports of `kind` can be printed inline with the definition
+/// of the item they export.
+fn inline_export_kind(kind: ExternalKind) -> bool {
+    match kind {
+        ExternalKind::Function
+        | ExternalKind::Table
+        | ExternalKind::Memory
+        | ExternalKind::Global
+        | ExternalKind::Tag => true,
+        ExternalKind::Type | ExternalKind::Module | ExternalKind::Instance => false,
+    }
+}
+
+/// Returns whether imports of `ty` can be printed inline with the definition
+/// of the item they import.
+fn inline_import_ty(ty: &ImportSectionEntryType) -> bool {
+    match ty {
+        ImportSectionEntryType::Function(_)
+        | ImportSectionEntryType::Table(_)
+        | ImportSectionEntryType::Memory(_)
+        | ImportSectionEntryType::Global(_)
+        | ImportSectionEntryType::Tag(_) => true,
+        ImportSectionEntryType::Module(_) | ImportSectionEntryType::Instance(_) => false,
+    }
+}
+
 struct NamedLocalPrinter {
     group_name: &'static str,
     in_group: bool,
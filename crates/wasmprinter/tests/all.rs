@@ -166,3 +166,264 @@ fn no_oom() {
     let wat = wasmprinter::print_bytes(&bytes).unwrap();
     assert!(wat.len() < 500_000_000);
 }
+
+#[test]
+fn fold_instructions() {
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (func (param i32) (result i32)
+                    local.get 0
+                    i32.const 1
+                    i32.add
+                    block (result i32)
+                        local.get 0
+                    end
+                    i32.mul
+                )
+            )
+        "#,
+    )
+    .unwrap();
+    let wat = wasmprinter::Printer::new()
+        .options(wasmprinter::PrintOptions {
+            fold_instructions: true,
+            ..Default::default()
+        })
+        .print(&bytes)
+        .unwrap();
+    assert!(
+        wat.contains("(i32.mul\n      (i32.add (local.get 0) (i32.const 1))\n      (block"),
+        "{}",
+        wat
+    );
+    assert_eq!(wat::parse_str(&wat).unwrap(), bytes);
+}
+
+#[test]
+fn fold_instructions_fallback() {
+    // Instructions that don't have enough operands available in the same
+    // block are printed without folding anything in.
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (func (param i32)
+                    local.get 0
+                    block (param i32)
+                        i32.const 1
+                        i32.add
+                        drop
+                    end
+                    local.get 0
+                    if
+                    else
+                    end
+                )
+                (func if)
+            )
+        "#,
+    )
+    .unwrap();
+    let wat = wasmprinter::Printer::new()
+        .options(wasmprinter::PrintOptions {
+            fold_instructions: true,
+            ..Default::default()
+        })
+        .print(&bytes)
+        .unwrap();
+    assert!(
+        wat.contains("(i32.const 1)\n      (drop (i32.add))"),
+        "{}",
+        wat
+    );
+    assert_eq!(wat::parse_str(&wat).unwrap(), bytes);
+}
+
+#[test]
+fn print_options() {
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (import "m" "f" (func $imp (param i32)))
+                (func $f (export "f") (param i32) (result i32)
+                    local.get 0
+                    call $imp
+                    i32.const 1)
+                (memory (export "mem") 1)
+                (data (i32.const 0) "0123456789abcdefg")
+            )
+        "#,
+    )
+    .unwrap();
+    let print = |options| {
+        wasmprinter::Printer::new()
+            .options(options)
+            .print(&bytes)
+            .unwrap()
+    };
+
+    let wat = print(wasmprinter::PrintOptions {
+        name_section: false,
+        indent: 4,
+        print_offsets: true,
+        ..Default::default()
+    });
+    assert!(wat.contains("\n    (func (;1;) (type 1)"), "{}", wat);
+    assert!(wat.contains("\n        call 0  ;; @"), "{}", wat);
+    assert!(!wat.contains("$imp"), "{}", wat);
+    wat::parse_str(&wat).unwrap();
+
+    let wat = print(wasmprinter::PrintOptions {
+        inline_exports: true,
+        inline_imports: true,
+        ..Default::default()
+    });
+    assert!(
+        wat.contains("(func $imp (import \"m\" \"f\") (type 0)"),
+        "{}",
+        wat
+    );
+    assert!(wat.contains("(func $f (export \"f\") (type 1)"), "{}", wat);
+    assert!(wat.contains("(memory (;0;) (export \"mem\") 1)"), "{}", wat);
+    assert!(!wat.contains("(export \"f\" (func"), "{}", wat);
+    assert_eq!(wat::parse_str(&wat).unwrap(), bytes);
+
+    let wat = print(wasmprinter::PrintOptions {
+        data_hex_dump: true,
+        skeleton: true,
+        ..Default::default()
+    });
+    assert!(wat.contains("\n    (;0x0010;) \"\\67\")"), "{}", wat);
+    assert!(
+        wat.contains("(func $f (type 1) (param i32) (result i32))"),
+        "{}",
+        wat
+    );
+}
+
+#[test]
+fn inline_exports_of_imports() {
+    // Exports of imported items can only be printed inline along with the
+    // import itself, so without `inline_imports` they stay in the export
+    // section.
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (import "a" "f" (func $f))
+                (import "a" "m" (memory 1))
+                (import "a" "g" (global i32))
+                (func $h (export "h"))
+                (export "f" (func $f))
+                (export "m" (memory 0))
+                (export "g" (global 0))
+            )
+        "#,
+    )
+    .unwrap();
+    for inline_imports in [false, true] {
+        let wat = wasmprinter::Printer::new()
+            .options(wasmprinter::PrintOptions {
+                inline_exports: true,
+                inline_imports,
+                ..Default::default()
+            })
+            .print(&bytes)
+            .unwrap();
+        assert!(wat.contains("(func $h (export \"h\")"), "{}", wat);
+        assert_eq!(
+            wat.contains("(export \"f\" (func $f))"),
+            !inline_imports,
+            "{}",
+            wat
+        );
+        // Inline exports may be reordered, so compare the printed items
+        // regardless of their order.
+        let reparsed = wat::parse_str(&wat).unwrap();
+        wasmparser::validate(&reparsed).unwrap();
+        let items = |bytes: &[u8]| {
+            let mut lines = wasmprinter::print_bytes(bytes)
+                .unwrap()
+                .lines()
+                .map(|l| l.trim_end_matches(')').to_string())
+                .collect::<Vec<_>>();
+            lines.sort();
+            lines
+        };
+        assert_eq!(items(&reparsed), items(&bytes));
+    }
+}
+
+#[test]
+fn code_metadata() {
+    // Branch hints are printed as annotations on instructions, while other
+    // code metadata sections are printed as raw custom sections.
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (@custom "metadata.code.other" (before code) "\00\01\02")
+                (func (param i32)
+                    (@metadata.code.branch_hint "\01")
+                    (if (local.get 0) (then nop))
+                )
+            )
+        "#,
+    )
+    .unwrap();
+    let wat = wasmprinter::print_bytes(&bytes).unwrap();
+    assert!(
+        wat.contains(r#"(@custom "metadata.code.other" (before code) "\00\01\02")"#),
+        "{}",
+        wat
+    );
+    assert!(
+        wat.contains(r#"(@metadata.code.branch_hint "\01") if"#),
+        "{}",
+        wat
+    );
+    assert!(!wat.contains("(@metadata.code.other"), "{}", wat);
+    assert_eq!(wat::parse_str(&wat).unwrap(), bytes);
+}
+
+#[test]
+fn print_to() {
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (func $a (result i32) i32.const 1)
+                (func $b (result i32) i32.const 2)
+                (memory 1)
+                (data (i32.const 0) "hello")
+            )
+        "#,
+    )
+    .unwrap();
+
+    struct Writes<'a> {
+        dst: &'a mut Vec<u8>,
+        writes: usize,
+    }
+
+    impl std::io::Write for Writes<'_> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes += 1;
+            self.dst.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut dst = Vec::new();
+    let mut writes = Writes {
+        dst: &mut dst,
+        writes: 0,
+    };
+    wasmprinter::Printer::new()
+        .print_to(&bytes, &mut writes)
+        .unwrap();
+    assert!(writes.writes > 2);
+    let expected = wasmprinter::print_bytes(&bytes).unwrap();
+    assert_eq!(String::from_utf8(dst).unwrap(), expected);
+}
@@ -1,4 +1,4 @@
-use crate::ast::{self, kw, HeapType};
+use crate::ast::{self, annotation, kw, HeapType};
 use crate::parser::{Cursor, Parse, Parser, Result};
 use std::mem;
 
@@ -8,17 +8,56 @@ use std::mem;
 /// list of instructions for emission later on. The implicit `end` instruction
 /// at the end of an expression is not included in the `instrs` field.
 #[derive(Debug)]
-#[allow(missing_docs)]
 pub struct Expression<'a> {
+    /// The flat list of instructions in this expression.
     pub instrs: Box<[Instruction<'a>]>,
+    /// See [`Expression::instr_spans`].
+    pub(crate) instr_spans: Option<Box<[ast::Span]>>,
+    /// Hints from `(@metadata.code.branch_hint "...")` annotations, in the
+    /// order of the instructions they apply to.
+    pub branch_hints: Vec<BranchHint>,
+}
+
+/// A hint from the [branch hinting proposal] of whether a branch is likely
+/// to be taken.
+///
+/// In the text format this is written as a `(@metadata.code.branch_hint
+/// "\00")` or `(@metadata.code.branch_hint "\01")` annotation immediately
+/// before the instruction it applies to, for unlikely and likely branches
+/// respectively. In folded instructions the annotation precedes the whole
+/// s-expression, as in `(@metadata.code.branch_hint "\01") (if ...)`.
+///
+/// [branch hinting proposal]: https://github.com/WebAssembly/branch-hinting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchHint {
+    /// The index within `Expression::instrs` of the instruction this hint
+    /// applies to.
+    pub instr_index: usize,
+    /// The value of the hint: 0 if the branch is unlikely to be taken, or 1
+    /// if it is likely to be taken.
+    pub value: u32,
+}
+
+impl Expression<'_> {
+    /// Returns the location in the source of each instruction in `instrs`, if
+    /// known.
+    ///
+    /// This is `None` for expressions synthesized during expansion of the text
+    /// format's shorthands, and otherwise has the same length as `instrs`.
+    pub fn instr_spans(&self) -> Option<&[ast::Span]> {
+        self.instr_spans.as_deref()
+    }
 }
 
 impl<'a> Parse<'a> for Expression<'a> {
     fn parse(parser: Parser<'a>) -> Result<Self> {
+        let _r = parser.register_annotation("metadata.code.branch_hint");
         let mut exprs = ExpressionParser::default();
         exprs.parse(parser)?;
         Ok(Expression {
             instrs: exprs.instrs.into(),
+            instr_spans: Some(exprs.spans.into()),
+            branch_hints: exprs.branch_hints,
         })
     }
 }
@@ -35,9 +74,24 @@ struct ExpressionParser<'a> {
     /// eventually become the final `Expression`.
     instrs: Vec<Instruction<'a>>,
 
+    /// The span of each instruction in `instrs`.
+    spans: Vec<ast::Span>,
+
     /// Descriptor of all our nested s-expr blocks. This only happens when
     /// instructions themselves are nested.
     stack: Vec<Level<'a>>,
+
+    /// Branch hints parsed so far, for instructions already in `instrs`.
+    branch_hints: Vec<BranchHint>,
+
+    /// The value of a branch hint which applies to the next instruction
+    /// parsed.
+    pending_hint: Option<u32>,
+
+    /// Branch hints for folded instructions which are pushed onto `instrs`
+    /// only once their operands are parsed, as the depth in `stack` of the
+    /// instruction's `Level` and the hint's value.
+    folded_hints: Vec<(usize, u32)>,
 }
 
 enum Paren {
@@ -50,7 +104,7 @@ enum Paren {
 enum Level<'a> {
     /// This is a normal `block` or `loop` or similar, where the instruction
     /// payload here is pushed when the block is exited.
-    EndWith(Instruction<'a>),
+    EndWith(Instruction<'a>, ast::Span),
 
     /// This is a pretty special variant which means that we're parsing an `if`
     /// statement, and the state of the `if` parsing is tracked internally in
@@ -74,9 +128,9 @@ enum Level<'a> {
 enum If<'a> {
     /// Only the `if` has been parsed, next thing to parse is the clause, if
     /// any, of the `if` instruction.
-    Clause(Instruction<'a>),
+    Clause(Instruction<'a>, ast::Span),
     /// Next thing to parse is the `then` block
-    Then(Instruction<'a>),
+    Then(Instruction<'a>, ast::Span),
     /// Next thing to parse is the `else` block
     Else,
     /// This `if` statement has finished parsing and if anything remains it's a
@@ -87,7 +141,7 @@ enum If<'a> {
 /// Possible state of "what should be parsed next?" in a `try` expression.
 enum Try<'a> {
     /// Next thing to parse is the `do` block.
-    Do(Instruction<'a>),
+    Do(Instruction<'a>, ast::Span),
     /// Next thing to parse is `catch`/`catch_all`, or `delegate`.
     CatchOrDelegate,
     /// Next thing to parse is a `catch` block or `catch_all`.
@@ -100,6 +154,68 @@ enum Try<'a> {
 }
 
 impl<'a> ExpressionParser<'a> {
+    fn push(&mut self, instr: Instruction<'a>, span: ast::Span) {
+        self.instrs.push(instr);
+        self.spans.push(span);
+    }
+
+    /// Pushes an instruction that was just parsed, to which any pending
+    /// branch hint applies.
+    fn push_hinted(&mut self, instr: Instruction<'a>, span: ast::Span) {
+        if let Some(value) = self.pending_hint.take() {
+            self.branch_hints.push(BranchHint {
+                instr_index: self.instrs.len(),
+                value,
+            });
+        }
+        self.push(instr, span);
+    }
+
+    /// Defers any pending branch hint to the folded instruction whose `Level`
+    /// was just pushed onto `stack`.
+    fn defer_hint(&mut self) {
+        if let Some(value) = self.pending_hint.take() {
+            self.folded_hints.push((self.stack.len(), value));
+        }
+    }
+
+    /// Records the deferred branch hint, if any, for the folded instruction
+    /// at `depth` in `stack` that is about to be pushed onto `instrs`.
+    ///
+    /// This takes individual fields so it can be called while a `Level` in
+    /// `stack` is borrowed.
+    fn take_folded_hint(
+        folded_hints: &mut Vec<(usize, u32)>,
+        branch_hints: &mut Vec<BranchHint>,
+        instrs: &[Instruction<'a>],
+        depth: usize,
+    ) {
+        if let Some(&(d, value)) = folded_hints.last() {
+            if d == depth {
+                folded_hints.pop();
+                branch_hints.push(BranchHint {
+                    instr_index: instrs.len(),
+                    value,
+                });
+            }
+        }
+    }
+
+    /// Parses the contents of a `(@metadata.code.branch_hint ...)`
+    /// annotation, after its opening `(`.
+    fn parse_branch_hint(&mut self, parser: Parser<'a>) -> Result<()> {
+        parser.parse::<annotation::metadata_code_branch_hint>()?;
+        let value = match parser.parse::<&[u8]>()? {
+            [0] => 0,
+            [1] => 1,
+            _ => return Err(parser.error("invalid value for branch hint")),
+        };
+        if self.pending_hint.replace(value).is_some() {
+            return Err(parser.error("multiple branch hints for one instruction"));
+        }
+        Ok(())
+    }
+
     fn parse(&mut self, parser: Parser<'a>) -> Result<()> {
         // Here we parse instructions in a loop, and we do not recursively
         // invoke this parse function to avoid blowing the stack on
@@ -119,10 +235,14 @@ impl<'a> ExpressionParser<'a> {
                 }
             }
 
+            let span = parser.cur_span();
             match self.paren(parser)? {
                 // No parenthesis seen? Then we just parse the next instruction
                 // and move on.
-                Paren::None => self.instrs.push(parser.parse()?),
+                Paren::None => {
+                    let instr = parser.parse()?;
+                    self.push_hinted(instr, span);
+                }
 
                 // If we see a left-parenthesis then things are a little
                 // special. We handle block-like instructions specially
@@ -133,7 +253,19 @@ impl<'a> ExpressionParser<'a> {
                 // In all cases here we push something onto the `stack` to get
                 // popped when the `)` character is seen.
                 Paren::Left => {
-                    // First up is handling `if` parsing, which is funky in a
+                    // Branch hints are annotations which apply to the
+                    // instruction after them, so they're recorded and then
+                    // parsing continues after the closing `)`.
+                    if parser.peek::<annotation::metadata_code_branch_hint>() {
+                        self.parse_branch_hint(parser)?;
+                        parser.step(|cursor| match cursor.rparen() {
+                            Some(rest) => Ok(((), rest)),
+                            None => Err(cursor.error("expected `)`")),
+                        })?;
+                        continue;
+                    }
+
+                    // Next up is handling `if` parsing, which is funky in a
                     // whole bunch of ways. See the method internally for more
                     // information.
                     if self.handle_if_lparen(parser)? {
@@ -144,6 +276,7 @@ impl<'a> ExpressionParser<'a> {
                     if self.handle_try_lparen(parser)? {
                         continue;
                     }
+                    let span = parser.cur_span();
                     match parser.parse()? {
                         // If block/loop show up then we just need to be sure to
                         // push an `end` instruction whenever the `)` token is
@@ -151,27 +284,33 @@ impl<'a> ExpressionParser<'a> {
                         i @ Instruction::Block(_)
                         | i @ Instruction::Loop(_)
                         | i @ Instruction::Let(_) => {
-                            self.instrs.push(i);
-                            self.stack.push(Level::EndWith(Instruction::End(None)));
+                            self.push_hinted(i, span);
+                            self.stack
+                                .push(Level::EndWith(Instruction::End(None), span));
                         }
 
                         // Parsing an `if` instruction is super tricky, so we
                         // push an `If` scope and we let all our scope-based
                         // parsing handle the remaining items.
                         i @ Instruction::If(_) => {
-                            self.stack.push(Level::If(If::Clause(i)));
+                            self.stack.push(Level::If(If::Clause(i, span)));
+                            self.defer_hint();
                         }
 
                         // Parsing a `try` is easier than `if` but we also push
                         // a `Try` scope to handle the required nested blocks.
                         i @ Instruction::Try(_) => {
-                            self.stack.push(Level::Try(Try::Do(i)));
+                            self.stack.push(Level::Try(Try::Do(i, span)));
+                            self.defer_hint();
                         }
 
                         // Anything else means that we're parsing a nested form
                         // such as `(i32.add ...)` which means that the
                         // instruction we parsed will be coming at the end.
-                        other => self.stack.push(Level::EndWith(other)),
+                        other => {
+                            self.stack.push(Level::EndWith(other, span));
+                            self.defer_hint();
+                        }
                     }
                 }
 
@@ -179,7 +318,18 @@ impl<'a> ExpressionParser<'a> {
                 // guaranteed there's an item in the `stack` stack for us to
                 // pop. We peel that off and take a look at what it says to do.
                 Paren::Right => match self.stack.pop().unwrap() {
-                    Level::EndWith(i) => self.instrs.push(i),
+                    Level::EndWith(Instruction::End(None), _) => {
+                        self.push(Instruction::End(None), span)
+                    }
+                    Level::EndWith(i, span) => {
+                        Self::take_folded_hint(
+                            &mut self.folded_hints,
+                            &mut self.branch_hints,
+                            &self.instrs,
+                            self.stack.len() + 1,
+                        );
+                        self.push(i, span);
+                    }
                     Level::IfArm => {}
                     Level::TryArm => {}
 
@@ -187,30 +337,33 @@ impl<'a> ExpressionParser<'a> {
                     // block, then that's an error because there weren't enough
                     // items in the `if` statement. Otherwise we're just careful
                     // to terminate with an `end` instruction.
-                    Level::If(If::Clause(_)) => {
+                    Level::If(If::Clause(..)) => {
                         return Err(parser.error("previous `if` had no clause"));
                     }
-                    Level::If(If::Then(_)) => {
+                    Level::If(If::Then(..)) => {
                         return Err(parser.error("previous `if` had no `then`"));
                     }
                     Level::If(_) => {
-                        self.instrs.push(Instruction::End(None));
+                        self.push(Instruction::End(None), span);
                     }
 
                     // The `do` clause is required in a `try` statement, so
                     // we will signal that error here. Otherwise, terminate with
                     // an `end` or `delegate` instruction.
-                    Level::Try(Try::Do(_)) => {
+                    Level::Try(Try::Do(..)) => {
                         return Err(parser.error("previous `try` had no `do`"));
                     }
                     Level::Try(Try::Delegate) => {}
                     Level::Try(_) => {
-                        self.instrs.push(Instruction::End(None));
+                        self.push(Instruction::End(None), span);
                     }
                 },
             }
         }
 
+        if self.pending_hint.is_some() {
+            return Err(parser.error("branch hint must be followed by an instruction"));
+        }
         Ok(())
     }
 
@@ -250,6 +403,8 @@ impl<'a> ExpressionParser<'a> {
     /// `false` if we should parse the next item as an instruction (because we
     /// didn't handle the lparen here).
     fn handle_if_lparen(&mut self, parser: Parser<'a>) -> Result<bool> {
+        let depth = self.stack.len();
+
         // Only execute the code below if there's an `If` listed last.
         let i = match self.stack.last_mut() {
             Some(Level::If(i)) => i,
@@ -259,9 +414,9 @@ impl<'a> ExpressionParser<'a> {
         // The first thing parsed in an `if` statement is the clause. If the
         // clause starts with `then`, however, then we know to skip the clause
         // and fall through to below.
-        if let If::Clause(if_instr) = i {
+        if let If::Clause(if_instr, span) = i {
             let instr = mem::replace(if_instr, Instruction::End(None));
-            *i = If::Then(instr);
+            *i = If::Then(instr, *span);
             if !parser.peek::<kw::then>() {
                 return Ok(false);
             }
@@ -274,9 +429,16 @@ impl<'a> ExpressionParser<'a> {
         //
         // Note that when we see the `then`, that's when we actually add the
         // original `if` instruction to the stream.
-        if let If::Then(if_instr) = i {
+        if let If::Then(if_instr, span) = i {
             let instr = mem::replace(if_instr, Instruction::End(None));
+            Self::take_folded_hint(
+                &mut self.folded_hints,
+                &mut self.branch_hints,
+                &self.instrs,
+                depth,
+            );
             self.instrs.push(instr);
+            self.spans.push(*span);
             *i = If::Else;
             if parser.parse::<Option<kw::then>>()?.is_some() {
                 self.stack.push(Level::IfArm);
@@ -288,9 +450,11 @@ impl<'a> ExpressionParser<'a> {
         // effectively the same as the `then` parsing above
         if let If::Else = i {
             self.instrs.push(Instruction::Else(None));
+            self.spans.push(parser.cur_span());
             if parser.parse::<Option<kw::r#else>>()?.is_some() {
                 if parser.is_empty() {
                     self.instrs.pop();
+                    self.spans.pop();
                 }
                 self.stack.push(Level::IfArm);
                 return Ok(true);
@@ -319,6 +483,8 @@ impl<'a> ExpressionParser<'a> {
     /// `false` if we should parse the next item as an instruction (because we
     /// didn't handle the lparen here).
     fn handle_try_lparen(&mut self, parser: Parser<'a>) -> Result<bool> {
+        let depth = self.stack.len();
+
         // Only execute the code below if there's a `Try` listed last.
         let i = match self.stack.last_mut() {
             Some(Level::Try(i)) => i,
@@ -326,9 +492,16 @@ impl<'a> ExpressionParser<'a> {
         };
 
         // Try statements must start with a `do` block.
-        if let Try::Do(try_instr) = i {
+        if let Try::Do(try_instr, span) = i {
             let instr = mem::replace(try_instr, Instruction::End(None));
+            Self::take_folded_hint(
+                &mut self.folded_hints,
+                &mut self.branch_hints,
+                &self.instrs,
+                depth,
+            );
             self.instrs.push(instr);
+            self.spans.push(*span);
             if parser.parse::<Option<kw::r#do>>()?.is_some() {
                 // The state is advanced here only if the parse succeeds in
                 // order to strictly require the keyword.
@@ -345,9 +518,11 @@ impl<'a> ExpressionParser<'a> {
         // After a try's `do`, there are several possible kinds of handlers.
         if let Try::CatchOrDelegate = i {
             // `catch` may be followed by more `catch`s or `catch_all`.
+            let span = parser.cur_span();
             if parser.parse::<Option<kw::catch>>()?.is_some() {
                 let evt = parser.parse::<ast::Index<'a>>()?;
                 self.instrs.push(Instruction::Catch(evt));
+                self.spans.push(span);
                 *i = Try::Catch;
                 self.stack.push(Level::TryArm);
                 return Ok(true);
@@ -355,6 +530,7 @@ impl<'a> ExpressionParser<'a> {
             // `catch_all` can only come at the end and has no argument.
             if parser.parse::<Option<kw::catch_all>>()?.is_some() {
                 self.instrs.push(Instruction::CatchAll);
+                self.spans.push(span);
                 *i = Try::End;
                 self.stack.push(Level::TryArm);
                 return Ok(true);
@@ -363,6 +539,7 @@ impl<'a> ExpressionParser<'a> {
             if parser.parse::<Option<kw::delegate>>()?.is_some() {
                 let depth = parser.parse::<ast::Index<'a>>()?;
                 self.instrs.push(Instruction::Delegate(depth));
+                self.spans.push(span);
                 *i = Try::Delegate;
                 match self.paren(parser)? {
                     Paren::Left | Paren::None => return Ok(false),
@@ -373,15 +550,18 @@ impl<'a> ExpressionParser<'a> {
         }
 
         if let Try::Catch = i {
+            let span = parser.cur_span();
             if parser.parse::<Option<kw::catch>>()?.is_some() {
                 let evt = parser.parse::<ast::Index<'a>>()?;
                 self.instrs.push(Instruction::Catch(evt));
+                self.spans.push(span);
                 *i = Try::Catch;
                 self.stack.push(Level::TryArm);
                 return Ok(true);
             }
             if parser.parse::<Option<kw::catch_all>>()?.is_some() {
                 self.instrs.push(Instruction::CatchAll);
+                self.spans.push(span);
                 *i = Try::End;
                 self.stack.push(Level::TryArm);
                 return Ok(true);
@@ -165,10 +165,13 @@ impl<'a> Parse<'a> for Data<'a> {
                     // This is all that the spec allows, which is that if
                     // `offset` isn't present then this is "sugar" for a
                     // single-instruction expression.
+                    let span = parser.cur_span();
                     let insn = parser.parse()?;
                     if parser.is_empty() {
                         return Ok(ast::Expression {
                             instrs: [insn].into(),
+                            instr_spans: Some([span].into()),
+                            branch_hints: Vec::new(),
                         });
                     }
 
@@ -186,8 +189,15 @@ impl<'a> Parse<'a> for Data<'a> {
                     let expr: ast::Expression = parser.parse()?;
                     let mut instrs = Vec::from(expr.instrs);
                     instrs.push(insn);
+                    let instr_spans = expr.instr_spans.map(|spans| {
+                        let mut spans = Vec::from(spans);
+                        spans.push(span);
+                        spans.into()
+                    });
                     Ok(ast::Expression {
                         instrs: instrs.into(),
+                        instr_spans,
+                        branch_hints: expr.branch_hints,
                     })
                 }
             })?;
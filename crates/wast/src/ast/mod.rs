@@ -426,5 +426,6 @@ pub mod kw {
 /// Common annotations used to parse WebAssembly text files.
 pub mod annotation {
     annotation!(custom);
+    annotation!(metadata_code_branch_hint = "metadata.code.branch_hint");
     annotation!(name);
 }
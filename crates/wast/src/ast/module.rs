@@ -49,6 +49,67 @@ pub struct Module<'a> {
     pub kind: ModuleKind<'a>,
 }
 
+/// Options to configure the output of [`Module::encode_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// Whether to name as many items as possible in the `name` section.
+    ///
+    /// By default only items with an `$id` or `@name` annotation are named.
+    /// When enabled, items without one are additionally named after their
+    /// first export or, failing that, the field they're imported from.
+    /// Function parameters without names also inherit the names of the
+    /// parameters of the function's type definition, and any remaining
+    /// locals and labels are named `local{N}` and `label{N}` after their
+    /// index.
+    pub complete_names: bool,
+
+    /// Whether to build a [`SourceMap`] for the instructions in function
+    /// bodies of the module.
+    pub source_map: bool,
+}
+
+/// The result of [`Module::encode_with_options`].
+#[derive(Debug)]
+pub struct EncodedModule {
+    /// The binary encoding of the module.
+    pub wasm: Vec<u8>,
+    /// The source map for function bodies, if one was requested through
+    /// [`EncodeOptions::source_map`].
+    pub source_map: Option<SourceMap>,
+}
+
+/// A mapping from offsets of instructions in a binary module back to the
+/// location in the text format that they were defined at.
+///
+/// Only instructions within function bodies of the outermost module are
+/// mapped, excluding the implicit `end` terminating each body.
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    pub(crate) entries: Vec<(usize, ast::Span)>,
+}
+
+impl SourceMap {
+    /// Returns all `(offset, span)` entries of this map, sorted by the offset
+    /// of the instruction in the binary.
+    pub fn entries(&self) -> &[(usize, ast::Span)] {
+        &self.entries
+    }
+
+    /// Returns the span of the last instruction which starts at or before
+    /// the binary `offset`.
+    ///
+    /// This is intended for mapping offsets reported by engines, for example
+    /// in traps or validation errors, back to the text format.
+    pub fn lookup(&self, offset: usize) -> Option<ast::Span> {
+        let i = match self.entries.binary_search_by_key(&offset, |e| e.0) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        Some(self.entries[i].1)
+    }
+}
+
 /// The different kinds of ways to define a module.
 #[derive(Debug)]
 pub enum ModuleKind<'a> {
@@ -87,6 +148,20 @@ impl<'a> Module<'a> {
         crate::resolve::resolve(self)
     }
 
+    /// Same as [`Module::resolve`], except that resolution continues after an
+    /// error to report as many errors as possible.
+    ///
+    /// Resolution resumes with the next module field after an error, so
+    /// errors are reported for all fields of the module rather than only the
+    /// first one to fail.
+    ///
+    /// # Errors
+    ///
+    /// Returns all the errors found, in order, if resolution failed.
+    pub fn resolve_recovering(&mut self) -> std::result::Result<Names<'a>, Vec<crate::Error>> {
+        crate::resolve::resolve_with(self, true)
+    }
+
     /// Encodes this [`Module`] to its binary form.
     ///
     /// This function will take the textual representation in [`Module`] and
@@ -113,7 +188,26 @@ impl<'a> Module<'a> {
     /// expansion-related errors.
     pub fn encode(&mut self) -> std::result::Result<Vec<u8>, crate::Error> {
         self.resolve()?;
-        Ok(crate::binary::encode(self))
+        Ok(crate::binary::encode(self, &EncodeOptions::default()).wasm)
+    }
+
+    /// Same as [`Module::encode`], but with configuration of what's emitted
+    /// through `options`.
+    ///
+    /// This can be used to emit a more complete `name` section than the
+    /// default, as well as to get a [`SourceMap`] from instructions in the
+    /// binary back to where they were defined in the text format.
+    ///
+    /// # Errors
+    ///
+    /// This function can return an error for name resolution errors and other
+    /// expansion-related errors.
+    pub fn encode_with_options(
+        &mut self,
+        options: &EncodeOptions,
+    ) -> std::result::Result<EncodedModule, crate::Error> {
+        self.resolve()?;
+        Ok(crate::binary::encode(self, options))
     }
 
     fn validate(&self, parser: Parser<'_>) -> Result<()> {
@@ -183,7 +277,10 @@ impl<'a> ModuleField<'a> {
     fn parse_remaining(parser: Parser<'a>) -> Result<Vec<ModuleField>> {
         let mut fields = Vec::new();
         while !parser.is_empty() {
-            fields.push(parser.parens(ModuleField::parse)?);
+            match parser.parens(ModuleField::parse) {
+                Ok(field) => fields.push(field),
+                Err(e) => parser.recover(e)?,
+            }
         }
         Ok(fields)
     }
@@ -213,9 +213,12 @@ impl<'a> ElemPayload<'a> {
                 } else {
                     // Without `item` this is "sugar" for a single-instruction
                     // expression.
+                    let span = parser.cur_span();
                     let insn = parser.parse()?;
                     Ok(ast::Expression {
                         instrs: [insn].into(),
+                        instr_spans: Some([span].into()),
+                        branch_hints: Vec::new(),
                     })
                 }
             })?;
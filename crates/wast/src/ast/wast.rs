@@ -21,7 +21,10 @@ impl<'a> Parse<'a> for Wast<'a> {
         // bunch of directives, otherwise assume this is an inline module.
         if parser.peek2::<WastDirectiveToken>() {
             while !parser.is_empty() {
-                directives.push(parser.parens(|p| p.parse())?);
+                match parser.parens(|p| p.parse()) {
+                    Ok(directive) => directives.push(directive),
+                    Err(e) => parser.recover(e)?,
+                }
             }
         } else {
             let module = parser.parse::<ast::Wat>()?.module;
@@ -1,9 +1,18 @@
 use crate::ast::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
 
-pub fn encode(module: &Module<'_>) -> Vec<u8> {
+pub fn encode(module: &Module<'_>, options: &EncodeOptions) -> EncodedModule {
     match &module.kind {
-        ModuleKind::Text(fields) => encode_fields(&module.id, &module.name, fields),
-        ModuleKind::Binary(bytes) => bytes.iter().flat_map(|b| b.iter().cloned()).collect(),
+        ModuleKind::Text(fields) => encode_fields(&module.id, &module.name, fields, options),
+        ModuleKind::Binary(bytes) => EncodedModule {
+            wasm: bytes.iter().flat_map(|b| b.iter().cloned()).collect(),
+            source_map: if options.source_map {
+                Some(SourceMap::default())
+            } else {
+                None
+            },
+        },
     }
 }
 
@@ -11,7 +20,8 @@ fn encode_fields(
     module_id: &Option<Id<'_>>,
     module_name: &Option<NameAnnotation<'_>>,
     fields: &[ModuleField<'_>],
-) -> Vec<u8> {
+    options: &EncodeOptions,
+) -> EncodedModule {
     use crate::ast::CustomPlace::*;
     use crate::ast::CustomPlaceAnchor::*;
 
@@ -118,16 +128,38 @@ fn encode_fields(
     if contains_bulk_memory(&funcs) {
         e.section(12, &data.len());
     }
-    e.section_list(10, Code, &funcs);
+    let num_func_imports = fields
+        .iter()
+        .filter(|f| match f {
+            ModuleField::Import(i) => matches!(i.item.kind, ItemKind::Func(_)),
+            ModuleField::Alias(a) => a.kind == ExportKind::Func,
+            _ => false,
+        })
+        .count();
+    let branch_hints = BranchHints::new(&funcs, num_func_imports);
+    if !branch_hints.0.is_empty() {
+        e.section(0, &("metadata.code.branch_hint", branch_hints));
+    }
+    let source_map = if options.source_map {
+        let mut map = SourceMap::default();
+        e.code_section_with_source_map(&funcs, &mut map);
+        Some(map)
+    } else {
+        e.section_list(10, Code, &funcs);
+        None
+    };
     e.section_list(11, Data, &data);
 
-    let names = find_names(module_id, module_name, fields);
+    let names = find_names(module_id, module_name, fields, options.complete_names);
     if !names.is_empty() {
         e.section(0, &("name", names));
     }
     e.custom_sections(AfterLast);
 
-    return e.wasm;
+    return EncodedModule {
+        wasm: e.wasm,
+        source_map,
+    };
 
     fn contains_bulk_memory(funcs: &[&crate::ast::Func<'_>]) -> bool {
         funcs
@@ -173,6 +205,30 @@ impl Encoder<'_> {
         }
         self.custom_sections(CustomPlace::After(anchor));
     }
+
+    /// Same as `section_list` for the code section, but additionally records
+    /// the final offset of each instruction into `map`.
+    fn code_section_with_source_map(&mut self, funcs: &[&Func<'_>], map: &mut SourceMap) {
+        self.custom_sections(CustomPlace::Before(CustomPlaceAnchor::Code));
+        if !funcs.is_empty() {
+            let mut offsets = Vec::new();
+            self.tmp.truncate(0);
+            funcs.len().encode(&mut self.tmp);
+            for func in funcs {
+                func.encode_body(&mut self.tmp, Some(&mut offsets));
+            }
+            self.wasm.push(10);
+            self.tmp.len().encode(&mut self.wasm);
+            let base = self.wasm.len();
+            self.wasm.extend_from_slice(&self.tmp);
+            map.entries.extend(
+                offsets
+                    .into_iter()
+                    .map(|(offset, span)| (base + offset, span)),
+            );
+        }
+        self.custom_sections(CustomPlace::After(CustomPlaceAnchor::Code));
+    }
 }
 
 pub(crate) trait Encode {
@@ -207,6 +263,12 @@ impl Encode for str {
     }
 }
 
+impl Encode for Cow<'_, str> {
+    fn encode(&self, e: &mut Vec<u8>) {
+        (**self).encode(e)
+    }
+}
+
 impl Encode for usize {
     fn encode(&self, e: &mut Vec<u8>) {
         assert!(*self <= u32::max_value() as usize);
@@ -755,18 +817,91 @@ impl Encode for Data<'_> {
 
 impl Encode for Func<'_> {
     fn encode(&self, e: &mut Vec<u8>) {
-        assert!(self.exports.names.is_empty());
+        self.encode_body(e, None)
+    }
+}
+
+impl Func<'_> {
+    /// Encodes this function's body into `e`, optionally recording the offset
+    /// within `e` of each instruction, along with its span, into `offsets`.
+    fn encode_body(&self, e: &mut Vec<u8>, offsets: Option<&mut Vec<(usize, Span)>>) {
         let mut tmp = Vec::new();
+        let (expr, instr_offsets) = self.encode_contents(&mut tmp);
+
+        tmp.len().encode(e);
+        if let (Some(offsets), Some(spans)) = (offsets, &expr.instr_spans) {
+            let base = e.len();
+            offsets.extend(
+                instr_offsets
+                    .iter()
+                    .zip(spans.iter())
+                    .map(|(offset, span)| (base + offset, *span)),
+            );
+        }
+        e.extend_from_slice(&tmp);
+    }
+
+    /// Encodes this function's locals and instructions, without the leading
+    /// size of the body, into `e`. Returns the function's expression and the
+    /// offset of each of its instructions relative to the start of the body.
+    fn encode_contents(&self, e: &mut Vec<u8>) -> (&Expression<'_>, Vec<usize>) {
+        assert!(self.exports.names.is_empty());
         let (expr, locals) = match &self.kind {
             FuncKind::Inline { expression, locals } => (expression, locals),
             _ => panic!("should only have inline functions in emission"),
         };
 
-        locals.encode(&mut tmp);
-        expr.encode(&mut tmp);
+        locals.encode(e);
+        let mut instr_offsets = Vec::new();
+        for instr in expr.instrs.iter() {
+            instr_offsets.push(e.len());
+            instr.encode(e);
+        }
+        e.push(0x0b);
+        (expr, instr_offsets)
+    }
+}
 
-        tmp.len().encode(e);
-        e.extend_from_slice(&tmp);
+/// The contents of the `metadata.code.branch_hint` custom section: the index
+/// of each function with branch hints, and the offset within its body and
+/// value of each hint.
+struct BranchHints(Vec<(usize, Vec<(usize, u32)>)>);
+
+impl BranchHints {
+    fn new(funcs: &[&Func<'_>], num_func_imports: usize) -> BranchHints {
+        let mut ret = Vec::new();
+        for (i, func) in funcs.iter().enumerate() {
+            let hints = match &func.kind {
+                FuncKind::Inline { expression, .. } => &expression.branch_hints,
+                _ => continue,
+            };
+            if hints.is_empty() {
+                continue;
+            }
+            let (_, offsets) = func.encode_contents(&mut Vec::new());
+            let hints = hints
+                .iter()
+                .map(|hint| (offsets[hint.instr_index], hint.value))
+                .collect();
+            ret.push((num_func_imports + i, hints));
+        }
+        BranchHints(ret)
+    }
+}
+
+impl Encode for BranchHints {
+    fn encode(&self, e: &mut Vec<u8>) {
+        self.0.len().encode(e);
+        for (func, hints) in self.0.iter() {
+            func.encode(e);
+            hints.len().encode(e);
+            for (offset, value) in hints {
+                offset.encode(e);
+                // Each hint's value is a single byte.
+                1u32.encode(e);
+                e.push(*value as u8);
+            }
+        }
     }
 }
 
@@ -936,8 +1071,8 @@ struct Names<'a> {
     module: Option<&'a str>,
     funcs: Vec<(u32, &'a str)>,
     func_idx: u32,
-    locals: Vec<(u32, Vec<(u32, &'a str)>)>,
-    labels: Vec<(u32, Vec<(u32, &'a str)>)>,
+    locals: Vec<(u32, Vec<(u32, Cow<'a, str>)>)>,
+    labels: Vec<(u32, Vec<(u32, Cow<'a, str>)>)>,
     globals: Vec<(u32, &'a str)>,
     global_idx: u32,
     memories: Vec<(u32, &'a str)>,
@@ -962,6 +1097,7 @@ fn find_names<'a>(
     module_id: &Option<Id<'a>>,
     module_name: &Option<NameAnnotation<'a>>,
     fields: &[ModuleField<'a>],
+    complete: bool,
 ) -> Names<'a> {
     fn get_name<'a>(id: &Option<Id<'a>>, name: &Option<NameAnnotation<'a>>) -> Option<&'a str> {
         name.as_ref().map(|n| n.name).or(id.and_then(|id| {
@@ -986,6 +1122,34 @@ fn find_names<'a>(
         Data,
     }
 
+    // Parameters of function type definitions, indexed by type index, which
+    // is used to find the number of parameters of functions that don't list
+    // them inline, and in `complete` mode their names as well.
+    let mut type_params = Vec::new();
+    // The first export name of each item, used as the fallback name of items
+    // in `complete` mode.
+    let mut export_names = HashMap::new();
+    for field in fields {
+        match field {
+            ModuleField::Type(t) => type_params.push(match &t.def {
+                TypeDef::Func(f) => Some(&f.params[..]),
+                _ => None,
+            }),
+            ModuleField::Alias(a) if a.kind == ExportKind::Type => type_params.push(None),
+            ModuleField::Export(e) if complete => {
+                if let ItemRef::Item {
+                    kind,
+                    idx: Index::Num(idx, _),
+                    ..
+                } = &e.index
+                {
+                    export_names.entry((*kind, *idx)).or_insert(e.name);
+                }
+            }
+            _ => {}
+        }
+    }
+
     let mut ret = Names::default();
     ret.module = get_name(module_id, module_name);
     for field in fields {
@@ -1044,12 +1208,40 @@ fn find_names<'a>(
             Name::Elem => (&mut ret.elems, &mut ret.elem_idx),
             Name::Data => (&mut ret.data, &mut ret.data_idx),
         };
-        if let Some(name) = get_name(id, name) {
+        let mut name = get_name(id, name);
+        if name.is_none() && complete {
+            let export_kind = match kind {
+                Name::Func => Some(ExportKind::Func),
+                Name::Table => Some(ExportKind::Table),
+                Name::Memory => Some(ExportKind::Memory),
+                Name::Global => Some(ExportKind::Global),
+                Name::Module => Some(ExportKind::Module),
+                Name::Instance => Some(ExportKind::Instance),
+                Name::Tag => Some(ExportKind::Tag),
+                Name::Type => Some(ExportKind::Type),
+                Name::Elem | Name::Data => None,
+            };
+            name = export_kind
+                .and_then(|kind| export_names.get(&(kind, *idx)).copied())
+                .or(match field {
+                    ModuleField::Import(i) => i.field,
+                    _ => None,
+                });
+        }
+        if let Some(name) = name {
             list.push((*idx, name));
         }
 
         // Handle module locals separately from above
         if let ModuleField::Func(f) = field {
+            // Locals and labels have nothing else to fall back on, so in
+            // `complete` mode they're named after their kind and index.
+            let push_name =
+                |names: &mut Vec<_>, prefix: &str, idx: u32, name: Option<&'a str>| match name {
+                    Some(name) => names.push((idx, Cow::Borrowed(name))),
+                    None if complete => names.push((idx, Cow::Owned(format!("{}{}", prefix, idx)))),
+                    None => {}
+                };
             let mut local_names = Vec::new();
             let mut label_names = Vec::new();
             let mut local_idx = 0;
@@ -1058,23 +1250,37 @@ fn find_names<'a>(
             // Consult the inline type listed for local names of parameters.
             // This is specifically preserved during the name resolution
             // pass, but only for functions, so here we can look at the
-            // original source's names.
-            if let Some(ty) = &f.ty.inline {
-                for (id, name, _) in ty.params.iter() {
-                    if let Some(name) = get_name(id, name) {
-                        local_names.push((local_idx, name));
-                    }
-                    local_idx += 1;
+            // original source's names. Otherwise the parameters come from
+            // the type definition that the function refers to.
+            let inline_params = f.ty.inline.as_ref().map(|ty| &ty.params[..]);
+            let def_params = match &f.ty.index {
+                Some(ItemRef::Item {
+                    idx: Index::Num(n, _),
+                    ..
+                }) => type_params.get(*n as usize).copied().flatten(),
+                _ => None,
+            };
+            let nparams = inline_params.or(def_params).map_or(0, |p| p.len());
+            for i in 0..nparams {
+                let param_name = |params: Option<&[(Option<Id<'a>>, _, _)]>| {
+                    params
+                        .and_then(|p| p.get(i))
+                        .and_then(|(id, name, _)| get_name(id, name))
+                };
+                let mut name = param_name(inline_params);
+                if name.is_none() && complete {
+                    name = param_name(def_params);
                 }
+                push_name(&mut local_names, "local", local_idx, name);
+                local_idx += 1;
             }
             if let FuncKind::Inline {
                 locals, expression, ..
             } = &f.kind
             {
                 for local in locals {
-                    if let Some(name) = get_name(&local.id, &local.name) {
-                        local_names.push((local_idx, name));
-                    }
+                    let name = get_name(&local.id, &local.name);
+                    push_name(&mut local_names, "local", local_idx, name);
                     local_idx += 1;
                 }
 
@@ -1085,9 +1291,8 @@ fn find_names<'a>(
                         | Instruction::Loop(block)
                         | Instruction::Try(block)
                         | Instruction::Let(LetType { block, .. }) => {
-                            if let Some(name) = get_name(&block.label, &block.label_name) {
-                                label_names.push((label_idx, name));
-                            }
+                            let name = get_name(&block.label, &block.label_name);
+                            push_name(&mut label_names, "label", label_idx, name);
                             label_idx += 1;
                         }
                         _ => {}
@@ -1252,7 +1457,9 @@ impl Encode for NestedModule<'_> {
             _ => panic!("should only have inline modules in emission"),
         };
 
-        encode_fields(&self.id, &self.name, fields).encode(e);
+        encode_fields(&self.id, &self.name, fields, &EncodeOptions::default())
+            .wasm
+            .encode(e);
     }
 }
 
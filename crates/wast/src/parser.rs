@@ -113,6 +113,45 @@ pub fn parse<'a, T: Parse<'a>>(buf: &'a ParseBuffer<'a>) -> Result<T> {
     }
 }
 
+/// Same as [`parse`], except that parsing attempts to recover from errors to
+/// report as many of them as possible.
+///
+/// When an error is found in a module field, such as a `func` or `global`,
+/// or in a directive of a `*.wast` file, the error is recorded and the rest
+/// of the parenthesized item is skipped. Parsing then resumes with the next
+/// item, so the result contains everything that did parse successfully.
+///
+/// Returns the parsed `T` along with all errors encountered, in the order
+/// they were found. The `T` is `None` if an error couldn't be recovered from.
+///
+/// # Examples
+///
+/// ```
+/// use wast::Wat;
+/// use wast::parser::{self, ParseBuffer};
+///
+/// # fn foo() -> Result<(), wast::Error> {
+/// let wat = "(module (func i32.nope) (global i32) (func))";
+/// let buf = ParseBuffer::new(wat)?;
+/// let (module, errors) = parser::parse_recovering::<Wat>(&buf);
+/// assert!(module.is_some());
+/// assert_eq!(errors.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_recovering<'a, T: Parse<'a>>(buf: &'a ParseBuffer<'a>) -> (Option<T>, Vec<Error>) {
+    *buf.recovered.borrow_mut() = Some(Vec::new());
+    let result = parse(buf);
+    let mut errors = buf.recovered.borrow_mut().take().unwrap_or_default();
+    match result {
+        Ok(result) => (Some(result), errors),
+        Err(e) => {
+            errors.push(e);
+            (None, errors)
+        }
+    }
+}
+
 /// A trait for parsing a fragment of syntax in a recursive descent fashion.
 ///
 /// The [`Parse`] trait is main abstraction you'll be working with when defining
@@ -284,6 +323,8 @@ pub struct ParseBuffer<'a> {
     cur: Cell<usize>,
     known_annotations: RefCell<HashMap<String, usize>>,
     depth: Cell<usize>,
+    // errors recovered from so far, or `None` if not recovering from errors
+    recovered: RefCell<Option<Vec<Error>>>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -356,6 +397,7 @@ impl ParseBuffer<'_> {
             depth: Cell::new(0),
             input,
             known_annotations: Default::default(),
+            recovered: Default::default(),
         };
         ret.validate_annotations()?;
         Ok(ret)
@@ -439,6 +481,33 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Handles an `err` produced while parsing the parenthesized item at the
+    /// current position.
+    ///
+    /// When recovering from errors, see [`parse_recovering`], the error is
+    /// recorded and the item is skipped so parsing can continue with whatever
+    /// comes next. Otherwise `err` is returned.
+    pub(crate) fn recover(self, err: Error) -> Result<()> {
+        match self.buf.recovered.borrow_mut().as_mut() {
+            Some(errors) => errors.push(err),
+            None => return Err(err),
+        }
+        self.step(|mut cursor| {
+            let mut depth = 0;
+            while let Some(token) = cursor.advance_token() {
+                match token {
+                    Token::LParen(_) => depth += 1,
+                    Token::RParen(_) => depth -= 1,
+                    _ => {}
+                }
+                if depth <= 0 {
+                    break;
+                }
+            }
+            Ok(((), cursor))
+        })
+    }
+
     pub(crate) fn has_meaningful_tokens(self) -> bool {
         self.buf.tokens[self.cursor().cur..]
             .iter()
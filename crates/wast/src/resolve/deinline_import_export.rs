@@ -87,6 +87,8 @@ pub fn run(fields: &mut Vec<ModuleField>) {
                                     } else {
                                         Instruction::I64Const(0)
                                     }]),
+                                    instr_spans: None,
+                                    branch_hints: Vec::new(),
                                 },
                             },
                             data,
@@ -142,6 +144,8 @@ pub fn run(fields: &mut Vec<ModuleField>) {
                                 table: item_ref(kw::table(t.span), id),
                                 offset: Expression {
                                     instrs: Box::new([Instruction::I32Const(0)]),
+                                    instr_spans: None,
+                                    branch_hints: Vec::new(),
                                 },
                             },
                             payload,
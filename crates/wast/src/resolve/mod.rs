@@ -35,6 +35,12 @@ impl Ns {
 }
 
 pub fn resolve<'a>(module: &mut Module<'a>) -> Result<Names<'a>, Error> {
+    resolve_with(module, false).map_err(|mut errors| errors.remove(0))
+}
+
+/// Same as `resolve`, except that if `recover` is set then resolution
+/// continues after errors to find as many as possible.
+pub fn resolve_with<'a>(module: &mut Module<'a>, recover: bool) -> Result<Names<'a>, Vec<Error>> {
     let fields = match &mut module.kind {
         ModuleKind::Text(fields) => fields,
         _ => return Ok(Default::default()),
@@ -55,12 +61,16 @@ pub fn resolve<'a>(module: &mut Module<'a>) -> Result<Names<'a>, Error> {
 
     // With a canonical form of imports make sure that imports are all listed
     // first.
+    let mut errors = Vec::new();
     let mut last = None;
     for field in fields.iter() {
         match field {
             ModuleField::Import(i) => {
                 if let Some(name) = last {
-                    return Err(Error::new(i.span, format!("import after {}", name)));
+                    errors.push(Error::new(i.span, format!("import after {}", name)));
+                    if !recover {
+                        return Err(errors);
+                    }
                 }
             }
             ModuleField::Memory(_) => last = Some("memory"),
@@ -77,8 +87,14 @@ pub fn resolve<'a>(module: &mut Module<'a>) -> Result<Names<'a>, Error> {
 
     // Perform name resolution over all `Index` items to resolve them all to
     // indices instead of symbolic names.
-    let resolver = names::resolve(module.id, fields)?;
-    Ok(Names { resolver })
+    match names::resolve(module.id, fields, recover) {
+        Ok(resolver) if errors.is_empty() => Ok(Names { resolver }),
+        Ok(_) => Err(errors),
+        Err(e) => {
+            errors.extend(e);
+            Err(errors)
+        }
+    }
 }
 
 /// Representation of the results of name resolution for a module.
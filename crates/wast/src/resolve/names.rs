@@ -1,12 +1,14 @@
 use crate::ast::*;
 use crate::resolve::Ns;
 use crate::Error;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 
 pub fn resolve<'a>(
     id: Option<Id<'a>>,
     fields: &mut Vec<ModuleField<'a>>,
-) -> Result<Resolver<'a>, Error> {
+    recover: bool,
+) -> Result<Resolver<'a>, Vec<Error>> {
     let mut names = HashMap::new();
     let mut parents = Parents {
         prev: None,
@@ -14,9 +16,19 @@ pub fn resolve<'a>(
         depth: 0,
         names: &mut names,
     };
-    let mut resolver = Resolver::default();
-    resolver.process(&mut parents, fields)?;
-    Ok(resolver)
+    let mut resolver = Resolver {
+        recover,
+        ..Resolver::default()
+    };
+    if let Err(e) = resolver.process(&mut parents, fields) {
+        resolver.errors.get_mut().push(e);
+    }
+    let errors = resolver.errors.take();
+    if errors.is_empty() {
+        Ok(resolver)
+    } else {
+        Err(errors)
+    }
 }
 
 /// Context structure used to perform name resolution.
@@ -39,6 +51,10 @@ pub struct Resolver<'a> {
     fields: Namespace<'a>,
     type_info: Vec<TypeInfo<'a>>,
     implicit_instances: HashSet<&'a str>,
+    // If `true` then errors for a field are recorded in `errors` and
+    // resolution continues with the next field.
+    recover: bool,
+    errors: RefCell<Vec<Error>>,
 }
 
 impl<'a> Resolver<'a> {
@@ -50,17 +66,29 @@ impl<'a> Resolver<'a> {
         // Number everything in the module, recording what names correspond to
         // what indices.
         for field in fields.iter_mut() {
-            self.register(field)?;
+            let result = self.register(field);
+            self.recover(result)?;
         }
 
         // Then we can replace all our `Index::Id` instances with `Index::Num`
         // in the AST. Note that this also recurses into nested modules.
         for field in fields.iter_mut() {
-            self.resolve_field(field, parents)?;
+            let result = self.resolve_field(field, parents);
+            self.recover(result)?;
         }
         Ok(())
     }
 
+    fn recover(&mut self, result: Result<(), Error>) -> Result<(), Error> {
+        match result {
+            Err(e) if self.recover => {
+                self.errors.get_mut().push(e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
     fn register(&mut self, item: &ModuleField<'a>) -> Result<(), Error> {
         match item {
             ModuleField::Import(i) => {
@@ -296,8 +324,13 @@ impl<'a> Resolver<'a> {
                     NestedModuleKind::Inline { fields } => fields,
                     NestedModuleKind::Import { .. } => panic!("should only be inline"),
                 };
-                Resolver::default().process(&mut parents.push(self, m.id), fields)?;
-                Ok(())
+                let mut resolver = Resolver {
+                    recover: self.recover,
+                    ..Resolver::default()
+                };
+                let result = resolver.process(&mut parents.push(self, m.id), fields);
+                self.errors.borrow_mut().extend(resolver.errors.take());
+                result
             }
 
             ModuleField::Table(t) => {
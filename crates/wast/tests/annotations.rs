@@ -161,3 +161,40 @@ fn custom_section_order() -> anyhow::Result<()> {
     assert_matches!(wasm[17], Payload::End);
     Ok(())
 }
+
+#[test]
+fn branch_hint_annotations() -> anyhow::Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "" "" (func))
+            (func (param i32)
+                (@metadata.code.branch_hint "\01")
+                (if (local.get 0) (then))
+                local.get 0
+                (@metadata.code.branch_hint "\00") br_if 0))
+        "#,
+    )?;
+    let mut hints = Vec::new();
+    for payload in Parser::new(0).parse_all(&wasm) {
+        if let Payload::CustomSection {
+            name: "metadata.code.branch_hint",
+            data,
+            data_offset,
+            ..
+        } = payload?
+        {
+            for func in CodeMetadataSectionReader::new(data, data_offset)? {
+                let func = func?;
+                for hint in func.get_code_metadata_reader() {
+                    let hint = hint?;
+                    hints.push((func.func, hint.offset, hint.branch_hint()?));
+                }
+            }
+        }
+    }
+    // The body starts with a byte for its locals, and the hinted `if` comes
+    // after a two-byte `local.get`.
+    assert_eq!(hints, [(1, 3, true), (1, 8, false)]);
+    Ok(())
+}
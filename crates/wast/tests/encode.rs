@@ -0,0 +1,205 @@
+use wasmparser::*;
+use wast::parser::{self, ParseBuffer};
+use wast::{EncodeOptions, EncodedModule, Wat};
+
+fn encode(wat: &str, options: &EncodeOptions) -> anyhow::Result<EncodedModule> {
+    let buf = ParseBuffer::new(wat)?;
+    let mut wat = parser::parse::<Wat>(&buf)?;
+    Ok(wat.module.encode_with_options(options)?)
+}
+
+fn names(wasm: &[u8]) -> anyhow::Result<Vec<(&'static str, u32, String)>> {
+    let mut ret = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CustomSection {
+            name: "name",
+            data,
+            data_offset,
+            ..
+        } = payload?
+        {
+            for name in NameSectionReader::new(data, data_offset)? {
+                let (kind, map) = match name? {
+                    Name::Function(n) => ("func", n),
+                    Name::Table(n) => ("table", n),
+                    Name::Memory(n) => ("memory", n),
+                    Name::Global(n) => ("global", n),
+                    Name::Local(n) => {
+                        indirect_names(&mut ret, "local", n)?;
+                        continue;
+                    }
+                    Name::Label(n) => {
+                        indirect_names(&mut ret, "label", n)?;
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let mut map = map.get_map()?;
+                for _ in 0..map.get_count() {
+                    let naming = map.read()?;
+                    ret.push((kind, naming.index, naming.name.to_string()));
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+fn indirect_names(
+    ret: &mut Vec<(&'static str, u32, String)>,
+    kind: &'static str,
+    names: IndirectNameMap<'_>,
+) -> anyhow::Result<()> {
+    let mut reader = names.get_indirect_map()?;
+    for _ in 0..reader.get_indirect_count() {
+        let mut map = reader.read()?.get_map()?;
+        for _ in 0..map.get_count() {
+            let naming = map.read()?;
+            ret.push((kind, naming.index, naming.name.to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn complete_names() -> anyhow::Result<()> {
+    let wat = r#"
+        (module
+            (type $t (func (param $a i32) (param $b i32)))
+            (import "env" "f" (func))
+            (import "env" "m" (memory 1))
+            (func (export "g") (type $t) (local $c i32))
+            (table (export "t") 1 funcref)
+            (global $named (export "x") i32 (i32.const 0))
+        )
+    "#;
+
+    // By default only explicitly named items get a name, and local names
+    // account for the parameters of the referenced type.
+    let wasm = encode(wat, &EncodeOptions::default())?.wasm;
+    assert_eq!(
+        names(&wasm)?,
+        [
+            ("local", 2, "c".to_string()),
+            ("global", 0, "named".to_string())
+        ]
+    );
+
+    let options = EncodeOptions {
+        complete_names: true,
+        ..EncodeOptions::default()
+    };
+    let wasm = encode(wat, &options)?.wasm;
+    assert_eq!(
+        names(&wasm)?,
+        [
+            ("func", 0, "f".to_string()),
+            ("func", 1, "g".to_string()),
+            ("local", 0, "a".to_string()),
+            ("local", 1, "b".to_string()),
+            ("local", 2, "c".to_string()),
+            ("table", 0, "t".to_string()),
+            ("memory", 0, "m".to_string()),
+            ("global", 0, "named".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn complete_local_and_label_names() -> anyhow::Result<()> {
+    let wat = r#"
+        (module
+            (func (param i32) (local $named i64) (local f32)
+                block $outer
+                    loop
+                        br $outer
+                    end
+                end)
+        )
+    "#;
+
+    let wasm = encode(wat, &EncodeOptions::default())?.wasm;
+    assert_eq!(
+        names(&wasm)?,
+        [
+            ("local", 1, "named".to_string()),
+            ("label", 0, "outer".to_string()),
+        ]
+    );
+
+    let options = EncodeOptions {
+        complete_names: true,
+        ..EncodeOptions::default()
+    };
+    let wasm = encode(wat, &options)?.wasm;
+    assert_eq!(
+        names(&wasm)?,
+        [
+            ("local", 0, "local0".to_string()),
+            ("local", 1, "named".to_string()),
+            ("local", 2, "local2".to_string()),
+            ("label", 0, "outer".to_string()),
+            ("label", 1, "label1".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn source_map() -> anyhow::Result<()> {
+    let wat = r#"(module
+  (func (result i32)
+    (i32.add
+      (i32.const 1)
+      (i32.const 2)))
+  (func
+    block
+      unreachable
+    end))
+"#;
+    let options = EncodeOptions {
+        source_map: true,
+        ..EncodeOptions::default()
+    };
+    let encoded = encode(wat, &options)?;
+    let map = encoded.source_map.unwrap();
+
+    let mut actual = Vec::new();
+    for payload in Parser::new(0).parse_all(&encoded.wasm) {
+        if let Payload::CodeSectionEntry(body) = payload? {
+            let mut reader = body.get_operators_reader()?;
+            while !reader.eof() {
+                let (op, offset) = reader.read_with_offset()?;
+                if let Operator::End = op {
+                    if reader.eof() {
+                        assert!(map.entries().iter().all(|e| e.0 != offset));
+                        continue;
+                    }
+                }
+                actual.push(map.lookup(offset).unwrap().linecol_in(wat));
+            }
+        }
+    }
+    // `i32.const 1`, `i32.const 2`, `i32.add`, `block`, `unreachable`, `end`
+    assert_eq!(actual, [(3, 7), (4, 7), (2, 5), (6, 4), (7, 6), (8, 4)]);
+    assert_eq!(map.entries().len(), actual.len());
+    assert!(map.lookup(0).is_none());
+    Ok(())
+}
+
+#[test]
+fn expression_spans() -> anyhow::Result<()> {
+    let src = "i32.const 1 (i32.eqz (i32.const 2))";
+    let buf = ParseBuffer::new(src)?;
+    let expr = parser::parse::<wast::Expression>(&buf)?;
+    let spans = expr.instr_spans().unwrap();
+    assert_eq!(spans.len(), expr.instrs.len());
+    let cols = spans
+        .iter()
+        .map(|span| span.linecol_in(src).1)
+        .collect::<Vec<_>>();
+    // `i32.const 1`, `i32.const 2`, `i32.eqz`
+    assert_eq!(cols, [0, 22, 13]);
+    Ok(())
+}
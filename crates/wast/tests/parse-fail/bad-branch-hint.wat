@@ -0,0 +1 @@
+(module (func (@metadata.code.branch_hint "\02") (block (br_if 0 (i32.const 0)))))
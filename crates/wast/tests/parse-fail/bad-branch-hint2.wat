@@ -0,0 +1 @@
+(module (func (block (i32.const 0) (@metadata.code.branch_hint "\01"))))
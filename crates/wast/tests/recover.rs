@@ -0,0 +1,75 @@
+use wast::parser::{self, ParseBuffer};
+use wast::{Wast, Wat};
+
+fn linecols(text: &str, errors: &[wast::Error]) -> Vec<(usize, usize)> {
+    errors.iter().map(|e| e.span().linecol_in(text)).collect()
+}
+
+#[test]
+fn module_fields() -> anyhow::Result<()> {
+    let text = r#"(module
+  (func $a i32.nope)
+  (global $g i32 (i32.const 0))
+  (memory)
+  (func $b (call $a) (global.get $g))
+)"#;
+    let buf = ParseBuffer::new(text)?;
+    let (wat, errors) = parser::parse_recovering::<Wat>(&buf);
+    assert_eq!(linecols(text, &errors), [(1, 11), (3, 9)]);
+
+    // Only the fields that parsed successfully are in the module, and name
+    // resolution reports `$a` as missing since it failed to parse.
+    let mut wat = wat.unwrap();
+    let errors = wat.module.resolve_recovering().err().unwrap();
+    assert_eq!(linecols(text, &errors), [(4, 17)]);
+    Ok(())
+}
+
+#[test]
+fn resolution() -> anyhow::Result<()> {
+    let text = r#"(module
+  (func call $x)
+  (func $f)
+  (func $f)
+  (func call $f global.get $y)
+)"#;
+    let buf = ParseBuffer::new(text)?;
+    let (wat, errors) = parser::parse_recovering::<Wat>(&buf);
+    assert!(errors.is_empty());
+    let errors = wat.unwrap().module.resolve_recovering().err().unwrap();
+    let messages = errors.iter().map(|e| e.message()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "duplicate func identifier",
+            "failed to find func named `$x`",
+            "failed to find global named `$y`",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn wast_directives() -> anyhow::Result<()> {
+    let text = r#"
+(module (func i32.nope))
+(assert_return (invoke "f" (i32.nope)))
+(assert_trap (invoke "f") "unreachable")
+"#;
+    let buf = ParseBuffer::new(text)?;
+    let (wast, errors) = parser::parse_recovering::<Wast>(&buf);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(wast.unwrap().directives.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn unrecoverable() -> anyhow::Result<()> {
+    let text = "(module (func i32.nope) (func)";
+    let buf = ParseBuffer::new(text)?;
+    let (wat, errors) = parser::parse_recovering::<Wat>(&buf);
+    assert!(wat.is_none());
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].message(), "expected `)`");
+    Ok(())
+}
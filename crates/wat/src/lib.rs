@@ -221,6 +221,104 @@ fn _parse_str(wat: &str) -> Result<Vec<u8>> {
     Ok(ast.module.encode().map_err(|e| Error::cvt(e, wat))?)
 }
 
+/// Same as [`parse_file`], except that all errors in the file are returned
+/// instead of just the first one.
+///
+/// Parsing recovers from syntax errors in module fields by skipping the
+/// offending field, and name resolution continues past unresolved names, to
+/// report as many errors as possible at once. This is intended for editors
+/// and other tools presenting diagnostics to users.
+///
+/// # Errors
+///
+/// Returns a non-empty list of all the errors found, in the order they appear
+/// in the file.
+///
+/// # Examples
+///
+/// ```
+/// # fn foo() {
+/// if let Err(errors) = wat::parse_file_with_diagnostics("./foo.wat") {
+///     for error in errors {
+///         eprintln!("{}", error);
+///     }
+/// }
+/// # }
+/// ```
+pub fn parse_file_with_diagnostics(
+    file: impl AsRef<Path>,
+) -> std::result::Result<Vec<u8>, Vec<Error>> {
+    let file = file.as_ref();
+    let contents = std::fs::read(file).map_err(|err| {
+        vec![Error {
+            kind: Box::new(ErrorKind::Io {
+                err,
+                file: Some(file.to_owned()),
+            }),
+        }]
+    })?;
+    let result = if contents.starts_with(b"\0asm") {
+        Ok(contents)
+    } else {
+        match str::from_utf8(&contents) {
+            Ok(s) => parse_str_with_diagnostics(s),
+            Err(_) => Err(vec![Error {
+                kind: Box::new(ErrorKind::Custom {
+                    msg: "input bytes aren't valid utf-8".to_string(),
+                    file: None,
+                }),
+            }]),
+        }
+    };
+    result.map_err(|mut errors| {
+        for e in errors.iter_mut() {
+            e.set_path(file);
+        }
+        errors
+    })
+}
+
+/// Same as [`parse_str`], except that all errors in the text are returned
+/// instead of just the first one.
+///
+/// See [`parse_file_with_diagnostics`] for more information.
+///
+/// # Examples
+///
+/// ```
+/// let errors = wat::parse_str_with_diagnostics(r#"
+///     (module
+///         (func (result i32) i32.nope)
+///         (func call $missing)
+///     )
+/// "#).unwrap_err();
+/// assert_eq!(errors.len(), 2);
+/// ```
+pub fn parse_str_with_diagnostics(
+    wat: impl AsRef<str>,
+) -> std::result::Result<Vec<u8>, Vec<Error>> {
+    let wat = wat.as_ref();
+    // Most inputs are valid, so take the fast path first and only go looking
+    // for all the errors if there are any.
+    let err = match _parse_str(wat) {
+        Ok(wasm) => return Ok(wasm),
+        Err(e) => e,
+    };
+    let buf = ParseBuffer::new(wat).map_err(|e| vec![Error::cvt(e, wat)])?;
+    let (ast, mut errors) = parser::parse_recovering::<wast::Wat>(&buf);
+    if let Some(mut ast) = ast {
+        // Names are resolved even if there were syntax errors to report
+        // errors for the fields that did parse.
+        if let Err(e) = ast.module.resolve_recovering() {
+            errors.extend(e);
+        }
+    }
+    if errors.is_empty() {
+        return Err(vec![err]);
+    }
+    Err(errors.into_iter().map(|e| Error::cvt(e, wat)).collect())
+}
+
 /// A convenience type definition for `Result` where the error is [`Error`]
 pub type Result<T> = std::result::Result<T, Error>;
 
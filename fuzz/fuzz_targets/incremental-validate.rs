@@ -0,0 +1,44 @@
+#![no_main]
+
+use libfuzzer_sys::*;
+use wasmparser::{StreamStatus, StreamingValidator, Validator};
+
+// Simulate receiving chunks of data by fuzzing over a `Vec<Vec<u8>>` where each
+// element of the outer vec is a chunk of data we received.
+//
+// The assertion here is that validating everything in one go should always
+// produce the same result as validating incrementally, pausing after every
+// function body.
+fuzz_target!(|data: Vec<Vec<u8>>| {
+    drop(env_logger::try_init());
+
+    let everything = data.iter().flat_map(|a| a).copied().collect::<Vec<_>>();
+    let expected = Validator::new().validate_all(&everything);
+    if log::log_enabled!(log::Level::Debug) {
+        std::fs::write("input.wasm", &everything).unwrap();
+    }
+
+    let mut validator = StreamingValidator::new();
+    let mut data = data.into_iter();
+    let actual = loop {
+        match validator.validate_some(1) {
+            Ok(StreamStatus::NeedMoreData(_)) => match data.next() {
+                Some(chunk) => validator.push(&chunk),
+                None => validator.end_of_input(),
+            },
+            Ok(StreamStatus::Paused) => {}
+            Ok(StreamStatus::Done) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+
+    match (expected, actual) {
+        (Ok(()), Ok(())) => {}
+        // Function bodies are validated as they're received rather than at the
+        // end, so the two may fail with different errors, but they must agree
+        // that the module is invalid.
+        (Err(_), Err(_)) => {}
+        (Ok(()), Err(e)) => panic!("incremental validation failed: {}", e),
+        (Err(e), Ok(())) => panic!("incremental validation succeeded: {}", e),
+    }
+});
@@ -35,6 +35,7 @@ const CRATES_TO_PUBLISH: &[&str] = &[
     "wasm-smith",
     "wasm-mutate",
     "wasm-shrink",
+    "wasm-analysis",
     "wasm-tools",
 ];
 
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use std::fmt::Write;
+use std::path::PathBuf;
+use wasm_analysis::CallGraph;
+
+/// Print the call graph of a WebAssembly module.
+///
+/// Each function is a node, labeled with its name from the `name` section if
+/// it has one. Direct calls, possible targets of `call_indirect`, and
+/// references with `ref.func` are edges. Functions which aren't reachable from
+/// the module's exports, start function, or element segments are marked as
+/// unreachable.
+#[derive(clap::Parser)]
+pub struct Opts {
+    /// Input WebAssembly file, in the binary or text format.
+    input: PathBuf,
+
+    /// The format to print the call graph in.
+    #[clap(long, value_enum, default_value = "dot")]
+    format: Format,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    Dot,
+    Json,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = wat::parse_file(&self.input)?;
+        let graph = CallGraph::new(&input)?;
+        match self.format {
+            Format::Dot => print!("{}", dot(&graph)?),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&json(&graph))?),
+        }
+        Ok(())
+    }
+}
+
+fn dot(graph: &CallGraph) -> Result<String> {
+    let reachable = graph.reachable();
+    let mut dst = String::new();
+    writeln!(dst, "digraph callgraph {{")?;
+    for (i, func) in graph.functions().iter().enumerate() {
+        let i = i as u32;
+        let mut label = match &func.name {
+            Some(name) => format!("{}: {}", i, name),
+            None => format!("func {}", i),
+        };
+        if func.imported {
+            label.push_str(" (import)");
+        }
+        write!(dst, "  f{} [label={}", i, dot_string(&label))?;
+        if !reachable.contains(&i) {
+            write!(dst, ", style=dashed, color=gray")?;
+        }
+        writeln!(dst, "];")?;
+    }
+    for (i, func) in graph.functions().iter().enumerate() {
+        for callee in func.calls.iter() {
+            writeln!(dst, "  f{} -> f{};", i, callee)?;
+        }
+        for callee in func.indirect_calls.iter() {
+            writeln!(dst, "  f{} -> f{} [style=dashed];", i, callee)?;
+        }
+        for callee in func.references.iter() {
+            writeln!(dst, "  f{} -> f{} [style=dotted];", i, callee)?;
+        }
+    }
+    writeln!(dst, "}}")?;
+    Ok(dst)
+}
+
+/// Quotes `s` as a DOT string, in which only `"` and `\` need escaping.
+fn dot_string(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret.push('"');
+    ret
+}
+
+fn json(graph: &CallGraph) -> serde_json::Value {
+    let reachable = graph.reachable();
+    let roots = graph.roots();
+    let functions = graph
+        .functions()
+        .iter()
+        .enumerate()
+        .map(|(i, func)| {
+            serde_json::json!({
+                "index": i,
+                "name": func.name,
+                "type": func.ty,
+                "imported": func.imported,
+                "reachable": reachable.contains(&(i as u32)),
+                "calls": func.calls,
+                "indirect_calls": func.indirect_calls,
+                "references": func.references,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "functions": functions,
+        "roots": {
+            "exports": roots.exports,
+            "start": roots.start,
+            "elements": roots.elements,
+            "globals": roots.globals,
+        },
+    })
+}
@@ -9,12 +9,32 @@ use std::path::PathBuf;
 pub struct Opts {
     /// Input WebAssembly file to dump information about.
     input: PathBuf,
+
+    /// The format to print the dump in.
+    ///
+    /// `text` is an annotated hex listing meant for humans. `json` records
+    /// the byte range of every section and item along with each decoded
+    /// operator, its offset, and its immediates, for use by other tools.
+    #[clap(long, value_enum, default_value = "text")]
+    format: Format,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    Text,
+    Json,
 }
 
 impl Opts {
     pub fn run(&self) -> Result<()> {
         let input = wat::parse_file(&self.input)?;
-        println!("{}", wasmparser_dump::dump_wasm(&input)?);
+        match self.format {
+            Format::Text => println!("{}", wasmparser_dump::dump_wasm(&input)?),
+            Format::Json => {
+                let dump = wasmparser_dump::dump_wasm_structured(&input)?;
+                println!("{}", serde_json::to_string_pretty(&dump)?);
+            }
+        }
 
         Ok(())
     }
@@ -0,0 +1,89 @@
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::PathBuf;
+use wasm_analysis::gc::{Gc, Item};
+
+/// Remove unreachable items from a WebAssembly module.
+///
+/// Functions, tables, memories, globals, types, imports, and element and data
+/// segments which aren't reachable from the module's exports, its start
+/// function, or an item passed to `--retain` are removed. The remaining items
+/// are renumbered, and the `name` section is rewritten to match.
+///
+/// ## Example
+///
+/// $ wasm-tools gc input.wasm --retain func:3 -o output.wasm
+#[derive(clap::Parser)]
+pub struct Opts {
+    /// Input WebAssembly file, in the binary or text format.
+    input: PathBuf,
+
+    /// Where to write the output module.
+    ///
+    /// `stdout` is used if this argument is not supplied.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+
+    /// Keep an item even if it's unreachable, written as `KIND:INDEX`.
+    ///
+    /// The kind is one of `func`, `table`, `memory`, `global`, `tag`, `type`,
+    /// `elem`, or `data`, and the index refers to the input module. May be
+    /// passed multiple times.
+    #[clap(long, value_name = "KIND:INDEX", value_parser = parse_retain)]
+    retain: Vec<(Item, u32)>,
+
+    /// Keep every import, so that the module's imports don't change.
+    #[clap(long)]
+    keep_imports: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = wat::parse_file(&self.input)?;
+        let mut gc = Gc::new();
+        gc.keep_imports(self.keep_imports);
+        for (item, index) in &self.retain {
+            gc.retain(*item, *index);
+        }
+        let wasm = gc.run(&input)?;
+
+        let output = if self.wat {
+            wasmprinter::print_bytes(&wasm)?.into_bytes()
+        } else {
+            wasm
+        };
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, output).context(format!("failed to write: {:?}", path))?
+            }
+            None => std::io::stdout().write_all(&output)?,
+        }
+        Ok(())
+    }
+}
+
+fn parse_retain(s: &str) -> Result<(Item, u32)> {
+    let (kind, index) = match s.split_once(':') {
+        Some(pair) => pair,
+        None => bail!("expected `KIND:INDEX`, found `{}`", s),
+    };
+    let item = match kind {
+        "func" => Item::Function,
+        "table" => Item::Table,
+        "memory" => Item::Memory,
+        "global" => Item::Global,
+        "tag" => Item::Tag,
+        "type" => Item::Type,
+        "elem" => Item::Element,
+        "data" => Item::Data,
+        _ => bail!("unknown item kind `{}`", kind),
+    };
+    let index = index
+        .parse()
+        .with_context(|| format!("invalid index `{}`", index))?;
+    Ok((item, index))
+}
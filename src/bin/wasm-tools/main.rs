@@ -42,6 +42,9 @@ subcommands! {
     (mutate, "mutate")
     (dump, "dump")
     (objdump, "objdump")
+    (metadata, "metadata")
+    (callgraph, "callgraph")
+    (gc, "gc")
 }
 
 fn main() -> Result<()> {
@@ -0,0 +1,88 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+use wasm_encoder::Producers;
+
+/// Show or add entries in the `producers` section of a WebAssembly module.
+///
+/// Without any of the `--language`, `--processed-by`, or `--sdk` flags the
+/// languages, tools, and SDKs recorded in the module are printed. Otherwise
+/// the given entries are merged into the module's `producers` section, with
+/// any existing entry of the same name having its version replaced.
+#[derive(Parser)]
+pub struct Opts {
+    /// Input WebAssembly file, in the binary or text format.
+    input: PathBuf,
+
+    /// Add a source language, as `NAME` or `NAME=VERSION`.
+    #[clap(long, value_name = "NAME[=VERSION]")]
+    language: Vec<String>,
+
+    /// Add a tool which processed the module, as `NAME` or `NAME=VERSION`.
+    #[clap(long, value_name = "NAME[=VERSION]")]
+    processed_by: Vec<String>,
+
+    /// Add an SDK the module was built with, as `NAME` or `NAME=VERSION`.
+    #[clap(long, value_name = "NAME[=VERSION]")]
+    sdk: Vec<String>,
+
+    /// Where to write the edited binary when adding entries.
+    ///
+    /// If not specified then the input file is overwritten, which is only
+    /// allowed if it is a binary.
+    #[clap(short = 'o', long)]
+    output: Option<PathBuf>,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = std::fs::read(&self.input)
+            .context(format!("failed to read `{}`", self.input.display()))?;
+        let wasm = wat::parse_bytes(&input)?;
+
+        let mut added = Producers::new();
+        for (field, entries) in [
+            ("language", &self.language),
+            ("processed-by", &self.processed_by),
+            ("sdk", &self.sdk),
+        ] {
+            for entry in entries {
+                let (name, version) = match entry.split_once('=') {
+                    Some((name, version)) => (name, version),
+                    None => (&entry[..], ""),
+                };
+                if name.is_empty() {
+                    bail!("invalid `--{}` entry `{}`: missing name", field, entry);
+                }
+                added.add(field, name, version);
+            }
+        }
+
+        if added.is_empty() {
+            if self.output.is_some() {
+                bail!("no entries to add were specified");
+            }
+            let producers = Producers::from_wasm(&wasm)?;
+            for (field, values) in producers.fields() {
+                println!("{}:", field);
+                for (name, version) in values {
+                    if version.is_empty() {
+                        println!("  {}", name);
+                    } else {
+                        println!("  {} {}", name, version);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        let edited = added.add_to_module(&wasm)?;
+        let output = match &self.output {
+            Some(output) => output,
+            None if wasm[..] == input[..] => &self.input,
+            None => bail!("an output file must be specified when the input is text"),
+        };
+        std::fs::write(output, edited).context(format!("failed to write: {:?}", output))?;
+        Ok(())
+    }
+}
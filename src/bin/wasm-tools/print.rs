@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use clap::Parser;
+use std::io::{self, Write};
 use std::path::PathBuf;
 
 /// Print the textual form of a WebAssembly binary.
@@ -13,15 +14,66 @@ pub struct Opts {
     /// If not specified then the wasm file is printed to standard output.
     #[clap(short = 'o', long)]
     output: Option<PathBuf>,
+
+    /// Print function bodies as folded S-expressions, such as
+    /// `(i32.add (local.get 0) (i32.const 1))`, where possible.
+    #[clap(short = 'f', long)]
+    fold_instructions: bool,
+
+    /// Print the binary offset of each instruction as a comment.
+    #[clap(long)]
+    print_offsets: bool,
+
+    /// Refer to all items by their index instead of using names from the
+    /// `name` custom section.
+    #[clap(long)]
+    no_names: bool,
+
+    /// The number of spaces to indent each level of nesting with.
+    #[clap(long, default_value = "2")]
+    indent: usize,
+
+    /// Print exports inline with the definition of the item they export.
+    #[clap(long)]
+    inline_exports: bool,
+
+    /// Print imports inline with the definition of the item they import.
+    #[clap(long)]
+    inline_imports: bool,
+
+    /// Print the contents of data segments as a hex dump.
+    #[clap(long)]
+    data_hex_dump: bool,
+
+    /// Only print the signatures of functions, skipping their bodies.
+    #[clap(long)]
+    skeleton: bool,
 }
 
 impl Opts {
     pub fn run(&self) -> Result<()> {
-        let wat = wasmprinter::print_file(&self.input)?;
+        let wasm = std::fs::read(&self.input)
+            .context(format!("failed to read `{}`", self.input.display()))?;
+        let mut printer = wasmprinter::Printer::new();
+        printer.options(wasmprinter::PrintOptions {
+            print_offsets: self.print_offsets,
+            name_section: !self.no_names,
+            indent: self.indent,
+            inline_exports: self.inline_exports,
+            inline_imports: self.inline_imports,
+            data_hex_dump: self.data_hex_dump,
+            skeleton: self.skeleton,
+            fold_instructions: self.fold_instructions,
+        });
         if let Some(output) = &self.output {
-            std::fs::write(&output, wat).context(format!("failed to write {:?}", output))?;
+            let file = std::fs::File::create(&output)
+                .context(format!("failed to create {:?}", output))?;
+            printer.print_to(&wasm, io::BufWriter::new(file))?;
         } else {
-            println!("{}", wat);
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            printer.print_to(&wasm, &mut stdout)?;
+            writeln!(stdout)?;
         }
 
         Ok(())
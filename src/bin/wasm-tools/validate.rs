@@ -1,8 +1,8 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use rayon::prelude::*;
 use std::path::PathBuf;
 use std::time::Instant;
-use wasmparser::{Parser, ValidPayload, Validator, WasmFeatures};
+use wasmparser::{DeterministicProfile, Parser, ValidPayload, Validator, WasmFeatures};
 
 /// Validate a WebAssembly binary
 ///
@@ -22,6 +22,10 @@ use wasmparser::{Parser, ValidPayload, Validator, WasmFeatures};
 ///
 /// # Validate `mvp.wasm` without any Wasm feature proposals enabled.
 /// $ wasm-tools validate --features=-all mvp.wasm
+///
+/// # Validate `contract.wasm` and check that it executes deterministically,
+/// # including that every NaN is canonicalized.
+/// $ wasm-tools validate --deterministic --canonicalize-nans contract.wasm
 /// ```
 #[derive(clap::Parser)]
 pub struct Opts {
@@ -33,6 +37,20 @@ pub struct Opts {
     #[clap(long, short = 'f', parse(try_from_str = parse_features))]
     features: Option<WasmFeatures>,
 
+    /// Also reject operators whose results may differ between engines or
+    /// hardware, such as relaxed SIMD and atomic operators.
+    ///
+    /// Unlike the "deterministic" feature, floats are allowed. Every rejected
+    /// operator is reported along with its offset.
+    #[clap(long)]
+    deterministic: bool,
+
+    /// With `--deterministic`, also require every float operator which may
+    /// produce a NaN to be followed by a sequence which canonicalizes it, as
+    /// generated by `wasm-smith`.
+    #[clap(long, requires = "deterministic")]
+    canonicalize_nans: bool,
+
     /// Input WebAssembly file to validate.
     ///
     /// This can either be a WebAssembly binary (*.wasm) or a WebAssembly text
@@ -78,6 +96,20 @@ impl Opts {
             .into_par_iter()
             .try_for_each(|(mut validator, body)| validator.validate(&body))?;
         log::info!("functions validated in {:?}", start.elapsed());
+
+        if self.deterministic {
+            let profile = DeterministicProfile {
+                canonicalize_nans: self.canonicalize_nans,
+            };
+            let violations = profile.check_module(&wasm)?;
+            if !violations.is_empty() {
+                let mut msg = String::from("module is not deterministic:");
+                for violation in &violations {
+                    msg.push_str(&format!("\n  {}", violation));
+                }
+                bail!(msg);
+            }
+        }
         Ok(())
     }
 }
@@ -88,6 +120,7 @@ fn parse_features(arg: &str) -> Result<WasmFeatures> {
     const FEATURES: &[(&str, fn(&mut WasmFeatures) -> &mut bool)] = &[
         ("reference-types", |f| &mut f.reference_types),
         ("simd", |f| &mut f.simd),
+        ("relaxed-simd", |f| &mut f.relaxed_simd),
         ("threads", |f| &mut f.threads),
         ("bulk-memory", |f| &mut f.bulk_memory),
         ("multi-value", |f| &mut f.multi_value),
@@ -52,6 +52,7 @@ fn run_test(test: &Path, bless: bool) -> Result<()> {
     let assert = test.with_extension("wat.dump");
     let dump =
         wasmparser_dump::dump_wasm(&wasm).with_context(|| format!("failed to dump {:?}", test))?;
+    check_structured(&wasm).with_context(|| format!("failed structured dump of {:?}", test))?;
     if bless {
         std::fs::write(assert, &dump)?;
         return Ok(());
@@ -94,6 +95,37 @@ fn run_test(test: &Path, bless: bool) -> Result<()> {
     }
 }
 
+/// Asserts that the structured dump of `wasm` serializes and that every item
+/// and operator lies within the range of whatever contains it.
+fn check_structured(wasm: &[u8]) -> Result<()> {
+    fn check_module(module: &wasmparser_dump::ModuleDump<'_>) -> Result<()> {
+        for section in module.sections.iter() {
+            if section.range.start < module.range.start || section.range.end > module.range.end {
+                bail!("{} section outside of its module", section.kind);
+            }
+            for item in section.items.iter() {
+                if item.range.start < section.range.start || item.range.end > section.range.end {
+                    bail!("item outside of its {} section", section.kind);
+                }
+                for op in item.operators.iter() {
+                    if !(item.range.start..item.range.end).contains(&op.offset) {
+                        bail!("operator outside of its item in {} section", section.kind);
+                    }
+                }
+            }
+            for nested in section.modules.iter() {
+                check_module(nested)?;
+            }
+        }
+        Ok(())
+    }
+
+    let dump = wasmparser_dump::dump_wasm_structured(wasm)?;
+    check_module(&dump)?;
+    serde_json::to_string(&dump)?;
+    Ok(())
+}
+
 fn find_tests(path: &Path, tests: &mut Vec<PathBuf>) {
     for f in path.read_dir().unwrap() {
         let f = f.unwrap();
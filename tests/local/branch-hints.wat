@@ -0,0 +1,21 @@
+(module
+  (import "" "f" (func $f (param i32)))
+  (func (param i32) (result i32)
+    (@metadata.code.branch_hint "\00")
+    (if (local.get 0)
+      (then (call $f (i32.const 1))))
+    (block
+      local.get 0
+      (@metadata.code.branch_hint "\01") br_if 0
+      (@metadata.code.branch_hint "\00") (br_if 0 (i32.eqz (local.get 0))))
+    local.get 0
+    (@metadata.code.branch_hint "\01")
+    if (result i32)
+      i32.const 1
+    else
+      i32.const 2
+    end)
+  (func
+    (loop
+      (@metadata.code.branch_hint "\01")
+      (br_if 0 (i32.const 0)))))
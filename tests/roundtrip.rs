@@ -232,6 +232,21 @@ impl TestState {
             self.bump_ntests();
             self.binary_compare(&binary2, contents, false)
                 .context("failed to compare original `wat` with roundtrip `wat`")?;
+
+            // Also make sure that printing with folded instructions yields
+            // the same binary.
+            let string = wasmprinter::Printer::new()
+                .options(wasmprinter::PrintOptions {
+                    fold_instructions: true,
+                    ..Default::default()
+                })
+                .print(contents)
+                .context("failed to print wasm with folded instructions")?;
+            let binary3 = wat::parse_str(&string)
+                .context("failed to parse folded `wat` from `wasmprinter`")?;
+            self.bump_ntests();
+            self.binary_compare(&binary3, contents, false)
+                .context("failed to compare original `wat` with folded roundtrip `wat`")?;
         }
 
         Ok(())